@@ -0,0 +1,109 @@
+//! Simulator hot loop allocation benchmarks
+//! 
+//! Run with `cargo bench --bench hot_loop`. Drives 1M synthetic bars through
+//! `ExchangeSimulator::simulate_with_signals`, the full per-bar path (fills, trade table,
+//! position marks, equity), with a bracketed entry every `ENTRY_EVERY` bars.
+//! 
+//! A counting global allocator records how many allocations the run makes, printed as
+//! allocations per bar before the timings. Bars without signals reuse the simulator's
+//! buffers and share the interned symbol, so only entries and exits allocate (signal
+//! clones, fills, trade records). A figure near 1 or above means something in the loop
+//! allocates on every bar again.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rust_decimal_macros::dec;
+
+use backtest_engine::simulator::ExchangeSimulator;
+use backtest_engine::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+use backtest_engine::types::*;
+
+const BAR_COUNT: usize = 1_000_000;
+
+/// Enter every 1000 bars with a ±1% bracket and a 30-minute TTL
+const ENTRY_EVERY: usize = 1_000;
+
+/// Allocator that counts allocation calls
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn entry(bar: &Bar) -> StrategySignal {
+    StrategySignal {
+        side: TradeSide::Buy,
+        size: dec!(0.01),
+        entry_price: None,
+        take_profit: Some(bar.close * dec!(1.01)),
+        stop_loss: Some(bar.close * dec!(0.99)),
+        time_to_live: Some(TimeToLive::Duration(1_800_000)),
+        rule_tag: Some("bracket".to_string()),
+        tags: Vec::new(),
+        symbol: String::new(),
+    }
+}
+
+/// Simulate `data` on a simulator preallocated for it
+fn run(runtime: &tokio::runtime::Runtime, mut simulator: ExchangeSimulator, data: &MarketData) -> SimulationResult {
+    runtime.block_on(simulator.simulate_with_signals(
+        data,
+        |bar_idx, bar| if bar_idx % ENTRY_EVERY == 0 { vec![entry(bar)] } else { Vec::new() },
+        &IntrabarPolicy::OneSecondBars,
+        &SlippageMode::None,
+    )).unwrap()
+}
+
+fn bench_hot_loop(c: &mut Criterion) {
+    let config = SyntheticDataConfig {
+        bar_count: BAR_COUNT,
+        ..SyntheticDataConfig::default()
+    };
+    let data = SyntheticDataGenerator::new(config, 42).generate();
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+    let simulator = ExchangeSimulator::with_capacity(BAR_COUNT).unwrap();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let result = run(&runtime, simulator, &data);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!(
+        "simulator hot loop over {} bars: {} allocations, {:.4} per bar, {} fills",
+        BAR_COUNT,
+        allocations,
+        allocations as f64 / BAR_COUNT as f64,
+        result.trades.len(),
+    );
+
+    let mut group = c.benchmark_group("simulator_hot_loop_1m_bars");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(BAR_COUNT as u64));
+    group.bench_function("simulate_with_signals", |b| {
+        b.iter_batched(
+            || ExchangeSimulator::with_capacity(BAR_COUNT).unwrap(),
+            |simulator| run(&runtime, simulator, &data),
+            BatchSize::LargeInput,
+        );
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_hot_loop);
+criterion_main!(benches);
@@ -0,0 +1,119 @@
+//! Indicator throughput benchmarks
+//! 
+//! Run with `cargo bench --bench indicators`. Each case computes one indicator over
+//! 1M synthetic 1-minute bars with SIMD enabled and disabled, reporting bars/sec.
+//! 
+//! EMA, RSI and ATR have no SIMD path, so on/off should match within noise. SMA's SIMD
+//! path pays for Decimal <-> f64 conversion on every bar, which can leave it slower than
+//! the scalar one. Compare against a baseline run of the same machine rather than
+//! fixed figures.
+//! 
+//! `sma_aos_vs_soa` compares scalar SMA over `Vec<Bar>` against `BarColumns`. Decimal
+//! addition dominates the window sum, so the layout win only shows once the
+//! per-element arithmetic gets cheaper.
+//! 
+//! `precision` compares `Precision::Decimal` against the `Float64` fast path. ATR gains
+//! least because it converts three Decimal fields per bar on the way in.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+use backtest_engine::indicators::IndicatorRegistry;
+use backtest_engine::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+use backtest_engine::types::{BarColumns, EmaSeed, IndicatorParams, MarketData, Precision, SessionWarmup, VwapAnchor, ZeroVolumePolicy};
+
+const BAR_COUNT: usize = 1_000_000;
+
+fn market_data() -> MarketData {
+    let config = SyntheticDataConfig {
+        bar_count: BAR_COUNT,
+        ..SyntheticDataConfig::default()
+    };
+    SyntheticDataGenerator::new(config, 42).generate()
+}
+
+fn bench_indicators(c: &mut Criterion) {
+    let data = market_data();
+    
+    let mut group = c.benchmark_group("indicators_1m_bars");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(BAR_COUNT as u64));
+    
+    for indicator in ["ema", "sma", "rsi", "atr"] {
+        for enable_simd in [true, false] {
+            let label = if enable_simd { "simd" } else { "scalar" };
+            group.bench_with_input(BenchmarkId::new(indicator, label), &data, |b, data| {
+                // Fresh registry per iteration so the result cache never short-circuits
+                b.iter_batched(
+                    || IndicatorRegistry::new(enable_simd).unwrap(),
+                    |mut registry| registry.calculate(indicator, data).unwrap(),
+                    BatchSize::LargeInput,
+                );
+            });
+        }
+    }
+    
+    group.finish();
+}
+
+fn bench_sma_layout(c: &mut Criterion) {
+    let data = market_data();
+    let columns = BarColumns::from_bars(&data.bars);
+    
+    let mut group = c.benchmark_group("sma_aos_vs_soa_1m_bars");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(BAR_COUNT as u64));
+    
+    // Scalar path only: the SIMD path converts to f64 first, which hides the layout
+    group.bench_function("aos", |b| {
+        b.iter_batched(
+            || IndicatorRegistry::new(false).unwrap(),
+            |mut registry| registry.calculate("sma", &data).unwrap(),
+            BatchSize::LargeInput,
+        );
+    });
+    group.bench_function("soa", |b| {
+        b.iter_batched(
+            || IndicatorRegistry::new(false).unwrap(),
+            |mut registry| registry.calculate_columns("sma", &data.symbol, &columns).unwrap(),
+            BatchSize::LargeInput,
+        );
+    });
+    
+    group.finish();
+}
+
+fn bench_precision(c: &mut Criterion) {
+    let data = market_data();
+    
+    let mut group = c.benchmark_group("precision_1m_bars");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(BAR_COUNT as u64));
+    
+    for (indicator, period) in [("ema", 20), ("sma", 20), ("rsi", 14), ("atr", 14)] {
+        for precision in [Precision::Decimal, Precision::Float64] {
+            let params = IndicatorParams {
+                period,
+                alpha: None,
+                threshold: None,
+                compute_precision: precision,
+                ema_seed: EmaSeed::SmaSeed,
+                vwap_anchor: VwapAnchor::Cumulative,
+                session_warmup: SessionWarmup::Partial,
+                zero_volume: ZeroVolumePolicy::CarryForward,
+                price_precision: None,
+            };
+            group.bench_with_input(BenchmarkId::new(indicator, format!("{:?}", precision)), &data, |b, data| {
+                b.iter_batched(
+                    || IndicatorRegistry::new(false).unwrap(),
+                    |mut registry| registry.calculate_with_params(indicator, data, &params).unwrap(),
+                    BatchSize::LargeInput,
+                );
+            });
+        }
+    }
+    
+    group.finish();
+}
+
+criterion_group!(benches, bench_indicators, bench_sma_layout, bench_precision);
+criterion_main!(benches);
@@ -0,0 +1,126 @@
+//! Indicator pipeline memory benchmarks
+//! 
+//! Run with `cargo bench --bench pipeline`. Feeds 1M synthetic bars through EMA, RSI
+//! and ATR the two ways the engine can: materialized (every series computed up front,
+//! then looked up per bar as `ExchangeSimulator::simulate` does) and fused (an
+//! `IndicatorStream` advanced inside the bar loop, as `simulate_streaming` does). The
+//! simulator itself is the same on both paths and is left out.
+//! 
+//! A counting global allocator records the peak heap each path reaches, printed before
+//! the timings. The materialized path holds three full series, each also held by the
+//! registry cache, so its peak grows with the bar count. The fused path keeps only each
+//! indicator's window; its peak growing with the data is a regression. Throughput should
+//! be close on both paths, since the same Decimal arithmetic dominates either way.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::HashMap;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rust_decimal::Decimal;
+
+use backtest_engine::indicators::IndicatorRegistry;
+use backtest_engine::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+use backtest_engine::types::{IndicatorValue, MarketData};
+
+const BAR_COUNT: usize = 1_000_000;
+
+/// Allocator that tracks live and peak heap bytes
+struct CountingAllocator;
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let live = LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn indicator_names() -> Vec<String> {
+    vec!["ema".to_string(), "rsi".to_string(), "atr".to_string()]
+}
+
+/// Compute every series, then read each bar's values back out of them
+fn materialized(data: &MarketData) -> Decimal {
+    let mut registry = IndicatorRegistry::new(false).unwrap();
+    let series: Vec<Vec<IndicatorValue>> = indicator_names().iter()
+        .map(|name| registry.calculate(name, data).unwrap())
+        .collect();
+
+    let mut checksum = Decimal::ZERO;
+    for bar in &data.bars {
+        for values in &series {
+            if let Ok(i) = values.binary_search_by_key(&bar.timestamp, |v| v.timestamp) {
+                checksum += values[i].value;
+            }
+        }
+    }
+    checksum
+}
+
+/// Advance the indicators with the bar loop, holding only their current values
+fn fused(data: &MarketData) -> Decimal {
+    let names = indicator_names();
+    let registry = IndicatorRegistry::new(false).unwrap();
+    let mut stream = registry.stream(&names, &HashMap::new()).unwrap();
+
+    let mut checksum = Decimal::ZERO;
+    for bar in &data.bars {
+        stream.update(bar);
+        for name in &names {
+            if let Some(value) = stream.current()[name] {
+                checksum += value;
+            }
+        }
+    }
+    checksum
+}
+
+/// Peak heap above the current baseline while running `f`
+fn peak_bytes(f: impl FnOnce() -> Decimal) -> (usize, Decimal) {
+    let baseline = LIVE_BYTES.load(Ordering::Relaxed);
+    PEAK_BYTES.store(baseline, Ordering::Relaxed);
+    let checksum = f();
+    (PEAK_BYTES.load(Ordering::Relaxed) - baseline, checksum)
+}
+
+fn bench_pipeline(c: &mut Criterion) {
+    let config = SyntheticDataConfig {
+        bar_count: BAR_COUNT,
+        ..SyntheticDataConfig::default()
+    };
+    let data = SyntheticDataGenerator::new(config, 42).generate();
+
+    let (materialized_peak, materialized_checksum) = peak_bytes(|| materialized(&data));
+    let (fused_peak, fused_checksum) = peak_bytes(|| fused(&data));
+    assert_eq!(materialized_checksum, fused_checksum, "paths must see the same indicator values");
+    println!(
+        "indicator_pipeline peak heap over {} bars: materialized {} KB, fused {} KB",
+        BAR_COUNT,
+        materialized_peak / 1024,
+        fused_peak / 1024,
+    );
+
+    let mut group = c.benchmark_group("indicator_pipeline_1m_bars");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(BAR_COUNT as u64));
+    group.bench_function("materialized", |b| b.iter(|| black_box(materialized(&data))));
+    group.bench_function("fused", |b| b.iter(|| black_box(fused(&data))));
+    group.finish();
+}
+
+criterion_group!(benches, bench_pipeline);
+criterion_main!(benches);
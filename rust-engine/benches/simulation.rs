@@ -0,0 +1,77 @@
+//! Simulation loop benchmarks
+//! 
+//! Run with `cargo bench --bench simulation`. Drives 1M synthetic bars through the
+//! per-bar trade table loop (entries, TP/SL/timeout exits, equity/drawdown) that
+//! `ExchangeSimulator::simulate` runs for every bar. The WASM strategy call is left
+//! out so the number reflects engine cost only; signals come from a fixed schedule.
+//! 
+//! Per-bar cost must stay flat as closed trades accumulate; time growing faster than
+//! bar count (e.g. re-summing trade history each bar) is a regression.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rust_decimal_macros::dec;
+
+use backtest_engine::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+use backtest_engine::trade_table::TradeTableGenerator;
+use backtest_engine::types::*;
+
+const BAR_COUNT: usize = 1_000_000;
+
+/// Enter every 50 bars with a ±1% bracket and a 30-minute TTL
+const ENTRY_EVERY: usize = 50;
+
+fn bench_simulation(c: &mut Criterion) {
+    let config = SyntheticDataConfig {
+        bar_count: BAR_COUNT,
+        ..SyntheticDataConfig::default()
+    };
+    let data = SyntheticDataGenerator::new(config, 42).generate();
+    
+    let signals: Vec<Vec<StrategySignal>> = data.bars.iter().enumerate()
+        .map(|(i, bar)| {
+            if !i.is_multiple_of(ENTRY_EVERY) {
+                return Vec::new();
+            }
+            vec![StrategySignal {
+                side: if (i / ENTRY_EVERY).is_multiple_of(2) { TradeSide::Buy } else { TradeSide::Sell },
+                size: dec!(1000.0),
+                entry_price: Some(bar.close),
+                take_profit: Some(bar.close * dec!(1.01)),
+                stop_loss: Some(bar.close * dec!(0.99)),
+                time_to_live: Some(TimeToLive::Duration(1_800_000)),
+                rule_tag: None,
+                tags: Vec::new(),
+                symbol: data.symbol.clone(),
+            }]
+        })
+        .collect();
+    
+    let mut group = c.benchmark_group("simulation_1m_bars");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(BAR_COUNT as u64));
+    
+    group.bench_function("process_bar", |b| {
+        b.iter_batched(
+            TradeTableGenerator::new,
+            |mut generator| {
+                for (bar, bar_signals) in data.bars.iter().zip(signals.iter()) {
+                    generator.process_bar(
+                        &data.symbol,
+                        bar,
+                        bar_signals,
+                        &IntrabarPolicy::OneSecondBars,
+                        &SlippageMode::TradeSweep,
+                        &data.rules,
+                    ).unwrap();
+                }
+                generator.generate_result()
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    
+    group.finish();
+}
+
+criterion_group!(benches, bench_simulation);
+criterion_main!(benches);
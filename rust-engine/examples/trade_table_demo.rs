@@ -7,6 +7,7 @@ use rust_decimal_macros::dec;
 use anyhow::Result;
 use tracing::{info, debug};
 
+use backtest_engine::data::MarketDataBuilder;
 use backtest_engine::types::*;
 use backtest_engine::trade_table::TradeTableGenerator;
 use backtest_engine::export::{ExportConfig, ExportFormat, TradeTableExporter};
@@ -19,10 +20,7 @@ async fn main() -> Result<()> {
     info!("Starting Trade Table Generation Demo");
 
     // Create sample market data
-    let market_data = create_sample_market_data();
-    
-    // Create sample strategy signals
-    let signals = create_sample_signals();
+    let market_data = create_sample_market_data()?;
     
     // Create trade table generator
     let mut generator = TradeTableGenerator::new();
@@ -39,7 +37,10 @@ async fn main() -> Result<()> {
                 entry_price: Some(bar.close),
                 take_profit: Some(bar.close * dec!(1.05)), // 5% TP
                 stop_loss: Some(bar.close * dec!(0.95)),   // 5% SL
-                time_to_live: Some(3600000), // 1 hour TTL
+                time_to_live: Some(TimeToLive::Duration(3600000)), // 1 hour TTL
+                rule_tag: None,
+                tags: Vec::new(),
+                symbol: "BTCUSDT".to_string(),
             }]
         } else if i == 20 { // Exit signal at bar 20
             vec![StrategySignal {
@@ -49,6 +50,9 @@ async fn main() -> Result<()> {
                 take_profit: None,
                 stop_loss: None,
                 time_to_live: None,
+                rule_tag: None,
+                tags: Vec::new(),
+                symbol: "BTCUSDT".to_string(),
             }]
         } else {
             vec![]
@@ -56,6 +60,7 @@ async fn main() -> Result<()> {
         
         // Process the bar
         generator.process_bar(
+            &market_data.symbol,
             bar,
             &bar_signals,
             &IntrabarPolicy::ExactTrades,
@@ -64,6 +69,11 @@ async fn main() -> Result<()> {
         )?;
     }
     
+    // Close anything still open on the last bar
+    if let Some(last_bar) = market_data.bars.last() {
+        generator.finalize(last_bar, &SlippageMode::TradeSweep, &market_data.rules)?;
+    }
+    
     // Generate final result
     let result = generator.generate_result();
     
@@ -81,6 +91,8 @@ async fn main() -> Result<()> {
         clickhouse_url: None,
         clickhouse_database: None,
         clickhouse_table: None,
+        export_manifest_alongside_results: false,
+        equity_sampling: None,
     };
     
     let exporter = TradeTableExporter::new(export_config);
@@ -90,14 +102,14 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-fn create_sample_market_data() -> MarketData {
+fn create_sample_market_data() -> Result<MarketData> {
     let mut bars = Vec::new();
     let base_price = dec!(50000.0);
     
     // Create 30 bars of sample data
     for i in 0..30 {
         let timestamp = 1609459200000 + (i * 60000) as u64; // 1-minute bars starting from 2021-01-01
-        let price_change = Decimal::from(i as i32) * dec!(100.0); // $100 price change per bar
+        let price_change = Decimal::from(i) * dec!(100.0); // $100 price change per bar
         let price = base_price + price_change;
         
         bars.push(Bar {
@@ -111,26 +123,7 @@ fn create_sample_market_data() -> MarketData {
         });
     }
     
-    MarketData {
-        symbol: "BTCUSDT".to_string(),
-        timeframe: "1m".to_string(),
-        bars,
-        trades: Vec::new(),
-        rules: ExchangeRules::default(),
-    }
-}
-
-fn create_sample_signals() -> Vec<StrategySignal> {
-    vec![
-        StrategySignal {
-            side: TradeSide::Buy,
-            size: dec!(1000.0),
-            entry_price: Some(dec!(50000.0)),
-            take_profit: Some(dec!(52500.0)), // 5% TP
-            stop_loss: Some(dec!(47500.0)),   // 5% SL
-            time_to_live: Some(3600000), // 1 hour
-        }
-    ]
+    Ok(MarketDataBuilder::new("BTCUSDT").bars(bars).build()?)
 }
 
 fn print_summary(summary: &TradeSummary) {
@@ -143,9 +136,11 @@ fn print_summary(summary: &TradeSummary) {
     println!("Average Win: ${:.2}", summary.avg_win_usd);
     println!("Average Loss: ${:.2}", summary.avg_loss_usd);
     println!("Expectancy: ${:.2}", summary.expectancy);
-    println!("Max Drawdown: {:.2}%", summary.max_drawdown * dec!(100.0));
+    println!("Max Drawdown: {:.2}%", summary.max_drawdown);
     println!("Profit Factor: {:.2}", summary.profit_factor);
     println!("Avg Holding Time: {:.2} hours", summary.avg_holding_time_hours);
+    println!("Total Fees: ${:.2}", summary.total_fees_usd);
+    println!("Total Slippage: ${:.2}", summary.total_slippage_usd);
     println!("===================\n");
 }
 
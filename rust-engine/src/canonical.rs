@@ -0,0 +1,229 @@
+//! Canonical serialization for audit and golden-dataset hashes
+//! 
+//! `serde_json::to_string` is not a stable hash input: `HashMap` fields serialize in
+//! iteration order, which differs between processes, and equal decimals can print
+//! differently (`10.0` vs `10.00`). Everything that is hashed goes through
+//! [`to_canonical_json`] instead, which produces:
+//! 
+//! - compact JSON with no insignificant whitespace;
+//! - object keys sorted by byte order, at every depth;
+//! - decimal strings (`-?digits.digits`, as `rust_decimal` serializes) normalized by
+//!   stripping trailing fractional zeros, so `"10.50"` becomes `"10.5"`, `"10.00"`
+//!   becomes `"10"` and `"-0.0"` becomes `"0"`;
+//! - all other numbers and strings exactly as `serde_json` writes them.
+//! 
+//! Changing any of these rules changes every stored hash, so treat the format as frozen.
+//! 
+//! Key order does not matter (keys are sorted), but adding, removing or renaming a
+//! field of a hashed type does. Such changes are versioned by [`SCHEMA_VERSION`],
+//! recorded in every manifest. To change the schema on purpose:
+//! 
+//! 1. bump [`SCHEMA_VERSION`];
+//! 2. update the pinned fixture strings in this module's tests to the new output;
+//! 3. regenerate any golden hashes, noting the bump in the changelog, since hashes
+//!    from manifests of an older version are no longer reproducible.
+
+use std::collections::{BTreeMap, HashMap};
+use anyhow::Result;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+/// Version of the canonical layout of hashed types; see the module docs before bumping
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Serialize `value` to canonical JSON bytes suitable for hashing
+pub fn to_canonical_json<T: Serialize>(value: &T) -> Result<String> {
+    let value = serde_json::to_value(value)?;
+    let mut out = String::new();
+    write_canonical(&value, &mut out)?;
+    Ok(out)
+}
+
+/// `serialize_with` helper that writes a `HashMap` in sorted-key order
+/// 
+/// Keeps plain `serde_json` output (saved manifests, exports) stable across runs, not
+/// just the canonical hash input.
+pub fn sorted_map<S, K, V>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Ord + Serialize,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+fn write_canonical(value: &Value, out: &mut String) -> Result<()> {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::to_string(key)?);
+                out.push(':');
+                write_canonical(value, out)?;
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out)?;
+            }
+            out.push(']');
+        }
+        Value::String(s) => {
+            out.push_str(&serde_json::to_string(&normalize_decimal_string(s))?);
+        }
+        other => out.push_str(&serde_json::to_string(other)?),
+    }
+    
+    Ok(())
+}
+
+/// Strip trailing fractional zeros from a `-?digits.digits` string; leave anything else
+fn normalize_decimal_string(s: &str) -> std::borrow::Cow<'_, str> {
+    let unsigned = s.strip_prefix('-').unwrap_or(s);
+    let is_decimal = unsigned.split_once('.').is_some_and(|(int, frac)| {
+        !int.is_empty()
+            && !frac.is_empty()
+            && int.bytes().all(|b| b.is_ascii_digit())
+            && frac.bytes().all(|b| b.is_ascii_digit())
+    });
+    if !is_decimal {
+        return std::borrow::Cow::Borrowed(s);
+    }
+    
+    let trimmed = s.trim_end_matches('0').trim_end_matches('.');
+    if trimmed.trim_start_matches('-').bytes().all(|b| b == b'0') {
+        return std::borrow::Cow::Owned("0".to_string());
+    }
+    std::borrow::Cow::Owned(trimmed.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::types::{EquityPoint, ExitReason, HitTpSl, Percent, SimulationResult, TradeRecord, TradeType};
+    use crate::versioning::ResultSummary;
+
+    fn result_with_attribution(entries: &[(String, i64)]) -> SimulationResult {
+        let mut attribution = HashMap::new();
+        for (key, pnl) in entries {
+            attribution.insert(key.clone(), rust_decimal::Decimal::from(*pnl));
+        }
+
+        SimulationResult {
+            trades: Vec::new(),
+            positions: Vec::new(),
+            equity_curve: vec![EquityPoint {
+                timestamp: 1609459200000,
+                equity: dec!(10000.00),
+                realized_equity: dec!(10000.00),
+                unrealized_pnl: dec!(0.0),
+                drawdown: dec!(0.0),
+                exposure: dec!(0.0),
+                net_exposure: dec!(-0.0),
+            }],
+            max_drawdown: dec!(0.0),
+            exposure: dec!(0.0),
+            net_exposure: dec!(0.0),
+            attribution,
+        }
+    }
+
+    #[test]
+    fn test_canonical_json_ignores_map_insertion_order() {
+        let forward: Vec<(String, i64)> = (0..32).map(|i| (format!("rule_{}", i), i)).collect();
+        let reversed: Vec<(String, i64)> = forward.iter().rev().cloned().collect();
+
+        let a = to_canonical_json(&result_with_attribution(&forward)).unwrap();
+        let b = to_canonical_json(&result_with_attribution(&reversed)).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a, to_canonical_json(&result_with_attribution(&forward)).unwrap());
+    }
+
+    #[test]
+    fn test_sorted_map_serializes_in_key_order() {
+        let forward: Vec<(String, i64)> = (0..32).map(|i| (format!("rule_{:02}", i), i)).collect();
+        let json = serde_json::to_string(&result_with_attribution(&forward)).unwrap();
+
+        let positions: Vec<usize> = forward.iter()
+            .map(|(key, _)| json.find(&format!("\"{}\"", key)).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_canonical_json_normalizes_decimals_and_sorts_keys() {
+        let json = to_canonical_json(&serde_json::json!({
+            "b": "10.50",
+            "a": { "z": "-0.000", "y": "100" },
+            "c": ["3.0", "BTCUSDT", 1.5],
+        })).unwrap();
+        assert_eq!(json, r#"{"a":{"y":"100","z":"0"},"b":"10.5","c":["3","BTCUSDT",1.5]}"#);
+    }
+
+    /// Fails on any change to the hashed layout; follow the module docs to bump
+    #[test]
+    fn test_canonical_schema_is_pinned() {
+        assert_eq!(SCHEMA_VERSION, 1, "update the pinned fixtures below along with the version");
+
+        let trade = TradeRecord {
+            date: "2024-01-01".to_string(),
+            trade_type: TradeType::Long,
+            entry_price: dec!(42000.50),
+            entry_time_utc: "2024-01-01T00:00:00.000Z".to_string(),
+            exit_price: dec!(42100.25),
+            exit_time_utc: "2024-01-01T01:00:00.000Z".to_string(),
+            exit_reason: ExitReason::TakeProfit,
+            hit_tp_sl: HitTpSl::TakeProfit,
+            size_usd: dec!(1000),
+            qty: dec!(0.0238),
+            fees_usd: dec!(0.20),
+            slippage_usd: dec!(0),
+            pnl_usd: dec!(2.17),
+            pnl_pct: Percent::new(dec!(0.217)),
+            breakeven_price: dec!(42000.55),
+            fill_outside_bar: false,
+            symbol: "BTCUSDT".to_string(),
+            tags: Vec::new(),
+            rule_tag: None,
+        };
+        assert_eq!(
+            to_canonical_json(&trade).unwrap(),
+            concat!(
+                r#"{"breakeven_price":"42000.55","date":"2024-01-01","entry_price":"42000.5","#,
+                r#""entry_time_utc":"2024-01-01T00:00:00.000Z","exit_price":"42100.25","exit_reason":"TakeProfit","#,
+                r#""exit_time_utc":"2024-01-01T01:00:00.000Z","fees_usd":"0.2","hit_tp_sl":"TakeProfit","#,
+                r#""pnl_pct":"0.217","pnl_usd":"2.17","qty":"0.0238","size_usd":"1000","slippage_usd":"0","#,
+                r#""symbol":"BTCUSDT","trade_type":"Long"}"#,
+            )
+        );
+
+        let summary = ResultSummary {
+            total_trades: 1,
+            total_symbols: 1,
+            final_equity: dec!(10002.17),
+            sharpe_ratio: dec!(1.5),
+            bars_processed: 1440,
+            ..ResultSummary::default()
+        };
+        assert_eq!(
+            to_canonical_json(&summary).unwrap(),
+            concat!(
+                r#"{"bars_processed":1440,"calmar_ratio":"0","execution_time_ms":0,"final_equity":"10002.17","#,
+                r#""max_drawdown":"0","profit_factor":"0","sharpe_ratio":"1.5","sortino_ratio":"0","#,
+                r#""throughput_bars_per_sec":0.0,"total_symbols":1,"total_trades":1,"win_rate":"0"}"#,
+            )
+        );
+    }
+}
@@ -0,0 +1,748 @@
+//! Market data loading and transformation
+//! 
+//! Loaders for exchange trade ticks that drive the `ExactTrades` intrabar policy, bar
+//! resampling to coarser timeframes, and gap handling. Ticks are validated on the way
+//! in so downstream fill logic can assume they are time-ordered and sit inside the
+//! bars they belong to; missing bars are filled or reported according to a
+//! [`GapPolicy`], never silently. [`MarketDataBuilder`] assembles `MarketData` from
+//! bars, ticks and rules with the same checks applied to the bars.
+
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::error::{BacktestError, Result};
+use crate::timeframe::Timeframe;
+use crate::types::*;
+
+/// Load trade ticks from a `.csv` file (see [`parse_trade_ticks_csv`])
+pub fn load_trade_ticks(path: &Path) -> Result<Vec<Trade>> {
+    match path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()).as_deref() {
+        Some("csv") => {
+            let content = fs::read_to_string(path)
+                .map_err(|source| BacktestError::Io { path: path.display().to_string(), source })?;
+            let ticks = parse_trade_ticks_csv(&content)?;
+            info!("Loaded {} trade ticks from {}", ticks.len(), path.display());
+            Ok(ticks)
+        }
+        _ => Err(BacktestError::InvalidData(format!("Unsupported tick file format: {}", path.display()))),
+    }
+}
+
+/// Parse trade ticks from CSV
+/// 
+/// Requires a header with a timestamp column, `price`, `qty` (or `quantity`) and
+/// `side` (`buy`/`sell`) columns; an optional `trade_id` column is kept, otherwise the
+/// row number is used. The timestamp column's name gives its unit: `timestamp` (or
+/// `time`) is Unix milliseconds, `timestamp_us` microseconds and `timestamp_ns`
+/// nanoseconds. Timestamps must be non-decreasing at microsecond resolution and
+/// prices/quantities positive.
+pub fn parse_trade_ticks_csv(content: &str) -> Result<Vec<Trade>> {
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let header = lines.next()
+        .ok_or_else(|| BacktestError::InvalidData("Tick CSV is empty".to_string()))?;
+    let columns: Vec<String> = header.split(',').map(|c| c.trim().to_lowercase()).collect();
+    let column = |names: &[&str]| {
+        columns.iter().position(|c| names.contains(&c.as_str()))
+            .ok_or_else(|| BacktestError::InvalidData(format!("Tick CSV is missing a {} column", names[0])))
+    };
+    
+    let (timestamp_idx, timestamp_unit) = column(&["timestamp_us"]).map(|idx| (idx, TimestampUnit::Micros))
+        .or_else(|_| column(&["timestamp_ns"]).map(|idx| (idx, TimestampUnit::Nanos)))
+        .or_else(|_| column(&["timestamp", "time"]).map(|idx| (idx, TimestampUnit::Millis)))?;
+    let price_idx = column(&["price"])?;
+    let qty_idx = column(&["qty", "quantity"])?;
+    let side_idx = column(&["side"])?;
+    let trade_id_idx = column(&["trade_id", "id"]).ok();
+    
+    let mut ticks: Vec<Trade> = Vec::new();
+    
+    for (row, line) in lines.enumerate() {
+        let row = row + 2; // 1-based, after the header
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        let field = |idx: usize| fields.get(idx).copied()
+            .ok_or_else(|| BacktestError::InvalidData(format!("Row {}: missing column {}", row, columns[idx])));
+        
+        let timestamp: u64 = field(timestamp_idx)?.parse()
+            .map_err(|_| BacktestError::InvalidData(format!("Row {}: invalid timestamp", row)))?;
+        let timestamp = Timestamp::from_unit(timestamp, timestamp_unit);
+        let price: Decimal = field(price_idx)?.parse()
+            .map_err(|_| BacktestError::InvalidData(format!("Row {}: invalid price", row)))?;
+        let quantity: Decimal = field(qty_idx)?.parse()
+            .map_err(|_| BacktestError::InvalidData(format!("Row {}: invalid quantity", row)))?;
+        let side = match field(side_idx)?.to_ascii_lowercase().as_str() {
+            "buy" | "b" => TradeSide::Buy,
+            "sell" | "s" => TradeSide::Sell,
+            other => return Err(BacktestError::InvalidData(format!("Row {}: unknown side '{}'", row, other))),
+        };
+        
+        if price <= Decimal::ZERO || quantity <= Decimal::ZERO {
+            return Err(BacktestError::InvalidData(format!("Row {}: price and quantity must be positive", row)));
+        }
+        if let Some(prev) = ticks.last() {
+            if timestamp < prev.timestamp {
+                return Err(BacktestError::InvalidData(format!(
+                    "Row {}: timestamp {} is earlier than previous tick {}",
+                    row, timestamp, prev.timestamp
+                )));
+            }
+        }
+        
+        let trade_id = match trade_id_idx {
+            Some(idx) => field(idx)?.to_string(),
+            None => (row - 1).to_string(),
+        };
+        
+        ticks.push(Trade { timestamp, price, quantity, side, trade_id });
+    }
+    
+    Ok(ticks)
+}
+
+/// Group time-ordered ticks under the bars whose window `[timestamp, timestamp + bar_interval_ms)`
+/// contains them
+/// 
+/// Returns one index range into `trades` per bar (empty for bars without ticks). Fails
+/// if ticks are out of order or any tick falls outside every bar window.
+pub fn group_trades_by_bar(bars: &[Bar], trades: &[Trade], bar_interval_ms: u64) -> Result<Vec<Range<usize>>> {
+    let mut ranges = Vec::with_capacity(bars.len());
+    let mut next = 0;
+    
+    for bar in bars {
+        let window_start = Timestamp::from_millis(bar.timestamp);
+        let window_end = Timestamp::from_millis(bar.timestamp + bar_interval_ms);
+        
+        if let Some(tick) = trades.get(next) {
+            if tick.timestamp < window_start {
+                return Err(BacktestError::DataGap {
+                    trade_id: tick.trade_id.clone(),
+                    timestamp: tick.timestamp.as_millis(),
+                });
+            }
+        }
+        
+        let start = next;
+        while next < trades.len() && trades[next].timestamp < window_end {
+            if next > 0 && trades[next].timestamp < trades[next - 1].timestamp {
+                return Err(BacktestError::InvalidData(format!("Ticks are not in timestamp order at {}", trades[next].trade_id)));
+            }
+            next += 1;
+        }
+        ranges.push(start..next);
+    }
+    
+    if let Some(tick) = trades.get(next) {
+        return Err(BacktestError::DataGap {
+            trade_id: tick.trade_id.clone(),
+            timestamp: tick.timestamp.as_millis(),
+        });
+    }
+    
+    Ok(ranges)
+}
+
+/// Validate ticks against the bars and store them in `market_data.trades`
+pub fn attach_trades(market_data: &mut MarketData, trades: Vec<Trade>, bar_interval_ms: u64) -> Result<()> {
+    group_trades_by_bar(&market_data.bars, &trades, bar_interval_ms)?;
+    market_data.trades = trades;
+    Ok(())
+}
+
+/// Validating constructor for [`MarketData`]
+/// 
+/// [`MarketDataBuilder::build`] checks that bar timestamps strictly increase and that
+/// every bar's OHLC is consistent (`low <= open, close <= high`, volume not negative).
+/// Without an explicit timeframe it is inferred from the smallest spacing between bars,
+/// so gaps do not skew it; an explicit one must match that spacing. Ticks, if any, are
+/// checked as [`attach_trades`] does.
+#[derive(Debug, Clone, Default)]
+pub struct MarketDataBuilder {
+    symbol: String,
+    timeframe: Option<String>,
+    bars: Vec<Bar>,
+    trades: Vec<Trade>,
+    rules: ExchangeRules,
+    timestamp_convention: TimestampConvention,
+}
+
+impl MarketDataBuilder {
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self { symbol: symbol.into(), ..Self::default() }
+    }
+    
+    pub fn timeframe(mut self, timeframe: impl Into<String>) -> Self {
+        self.timeframe = Some(timeframe.into());
+        self
+    }
+    
+    pub fn bars(mut self, bars: Vec<Bar>) -> Self {
+        self.bars = bars;
+        self
+    }
+    
+    pub fn trades(mut self, trades: Vec<Trade>) -> Self {
+        self.trades = trades;
+        self
+    }
+    
+    pub fn rules(mut self, rules: ExchangeRules) -> Self {
+        self.rules = rules;
+        self
+    }
+    
+    pub fn timestamp_convention(mut self, timestamp_convention: TimestampConvention) -> Self {
+        self.timestamp_convention = timestamp_convention;
+        self
+    }
+    
+    /// Validate the parts and assemble the `MarketData`
+    pub fn build(self) -> Result<MarketData> {
+        for pair in self.bars.windows(2) {
+            if pair[1].timestamp <= pair[0].timestamp {
+                return Err(BacktestError::InvalidData(format!(
+                    "{} bars are not in timestamp order: {} follows {}", self.symbol, pair[1].timestamp, pair[0].timestamp
+                )));
+            }
+        }
+        if let Some(bar) = self.bars.iter().find(|bar| !ohlc_consistent(bar)) {
+            return Err(BacktestError::InvalidData(format!(
+                "{} bar at {} has inconsistent OHLCV: open {} high {} low {} close {} volume {}",
+                self.symbol, bar.timestamp, bar.open, bar.high, bar.low, bar.close, bar.volume
+            )));
+        }
+        
+        let timeframe = match self.timeframe {
+            Some(timeframe) => {
+                let timeframe = timeframe.parse::<Timeframe>().map_err(|e| BacktestError::InvalidData(e.to_string()))?;
+                if let Some(spacing) = min_spacing(&self.bars).filter(|spacing| *spacing != timeframe.as_millis()) {
+                    return Err(BacktestError::InvalidData(format!(
+                        "{} bars are {}ms apart but the timeframe is {}", self.symbol, spacing, timeframe
+                    )));
+                }
+                timeframe
+            }
+            None => infer_timeframe(&self.bars).ok_or_else(|| BacktestError::InvalidData(format!(
+                "Cannot infer the timeframe of {} from {} bars; set it explicitly", self.symbol, self.bars.len()
+            )))?,
+        };
+        
+        let mut market_data = MarketData {
+            symbol: self.symbol,
+            timeframe: timeframe.to_string(),
+            bars: self.bars,
+            trades: Vec::new(),
+            rules: self.rules,
+            timestamp_convention: self.timestamp_convention,
+        };
+        if !self.trades.is_empty() {
+            // Ticks are matched to open-time windows
+            let interval_ms = timeframe.as_millis();
+            match market_data.timestamp_convention {
+                TimestampConvention::OpenTime => group_trades_by_bar(&market_data.bars, &self.trades, interval_ms)?,
+                TimestampConvention::CloseTime => {
+                    let mut normalized = market_data.clone();
+                    normalize_timestamps(&mut normalized)?;
+                    group_trades_by_bar(&normalized.bars, &self.trades, interval_ms)?
+                }
+            };
+            market_data.trades = self.trades;
+        }
+        
+        Ok(market_data)
+    }
+}
+
+fn ohlc_consistent(bar: &Bar) -> bool {
+    bar.low <= bar.open.min(bar.close)
+        && bar.high >= bar.open.max(bar.close)
+        && bar.volume >= Decimal::ZERO
+}
+
+/// Timeframe of the smallest spacing between consecutive bars, `None` with fewer than two
+fn infer_timeframe(bars: &[Bar]) -> Option<Timeframe> {
+    Timeframe::from_millis(min_spacing(bars)?)
+}
+
+/// Smallest gap between consecutive bar timestamps, `None` with fewer than two bars
+fn min_spacing(bars: &[Bar]) -> Option<u64> {
+    bars.windows(2).map(|pair| pair[1].timestamp - pair[0].timestamp).min()
+}
+
+/// Restamp bars to open time, the convention the rest of the engine assumes
+/// 
+/// Close-time bars are shifted back by one interval of `market_data.timeframe`; data
+/// already in open time is left alone. Ticks carry their own exact timestamps and are
+/// not touched.
+pub fn normalize_timestamps(market_data: &mut MarketData) -> Result<()> {
+    if market_data.timestamp_convention == TimestampConvention::OpenTime {
+        return Ok(());
+    }
+    
+    let interval_ms = market_data.timeframe.parse::<Timeframe>()
+        .map_err(|e| BacktestError::InvalidData(e.to_string()))?
+        .as_millis();
+    for bar in &mut market_data.bars {
+        bar.timestamp = bar.timestamp.checked_sub(interval_ms).ok_or_else(|| BacktestError::InvalidData(
+            format!("Close-time bar at {} is earlier than one {} interval", bar.timestamp, market_data.timeframe)
+        ))?;
+    }
+    market_data.timestamp_convention = TimestampConvention::OpenTime;
+    
+    Ok(())
+}
+
+/// Check that `trade_count` is consistent with volume so it can drive slippage
+/// 
+/// A bar reporting trades must report volume. A count of zero on a bar with volume
+/// means the source does not report trades, which is fine for a whole series but not
+/// for a few bars of a series that otherwise does.
+pub fn validate_trade_counts(bars: &[Bar]) -> Result<()> {
+    if let Some(bar) = bars.iter().find(|bar| bar.trade_count > 0 && bar.volume <= Decimal::ZERO) {
+        return Err(BacktestError::InvalidData(format!(
+            "Bar at {} reports {} trades but volume {}", bar.timestamp, bar.trade_count, bar.volume
+        )));
+    }
+    
+    let reports_trades = bars.iter().any(|bar| bar.trade_count > 0);
+    if reports_trades {
+        if let Some(bar) = bars.iter().find(|bar| bar.trade_count == 0 && bar.volume > Decimal::ZERO) {
+            return Err(BacktestError::InvalidData(format!(
+                "Bar at {} has volume {} but no trade count, while other bars report trades", bar.timestamp, bar.volume
+            )));
+        }
+    }
+    
+    Ok(())
+}
+
+/// Drop the last bar if it was still forming at `as_of` (Unix milliseconds)
+/// 
+/// A bar is partial when its open time plus one interval of `market_data.timeframe`
+/// is later than `as_of`, as with the live candle of freshly pulled data. Bars must
+/// be stamped with open time. Returns the open time of the dropped bar.
+pub fn exclude_partial_last_bar(market_data: &mut MarketData, as_of: u64) -> Result<Option<u64>> {
+    let interval_ms = market_data.timeframe.parse::<Timeframe>()
+        .map_err(|e| BacktestError::InvalidData(e.to_string()))?
+        .as_millis();
+    
+    match market_data.bars.last() {
+        Some(last) if last.timestamp + interval_ms > as_of => {
+            let timestamp = last.timestamp;
+            market_data.bars.pop();
+            info!("Dropped partial {} bar at {} (as of {})", market_data.symbol, timestamp, as_of);
+            Ok(Some(timestamp))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// What to do with bars missing from a series
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GapPolicy {
+    /// Insert flat bars at the previous close with zero volume
+    Fill,
+    /// Leave the series as is and only report the gaps
+    #[default]
+    Skip,
+    /// Refuse to run on data with gaps
+    Error,
+}
+
+/// Run of consecutive missing bars
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BarGap {
+    /// Open time of the first missing bar
+    pub start: u64,
+    pub missing_bars: u64,
+}
+
+/// Audit record of the gaps found in one symbol's bars and what was done about them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataQualityReport {
+    pub symbol: String,
+    pub policy: GapPolicy,
+    pub gaps: Vec<BarGap>,
+    /// Open times of the bars inserted under [`GapPolicy::Fill`]
+    pub synthesized: Vec<u64>,
+    /// Open time of the still-forming last bar dropped by [`exclude_partial_last_bar`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partial_bar: Option<u64>,
+}
+
+/// Find missing bars in `market_data` and handle them according to `policy`
+/// 
+/// A gap is any step between consecutive bars longer than one interval of
+/// `market_data.timeframe`. Synthesized bars open, high, low and close at the previous
+/// bar's close with zero volume and trade count, so they move no indicator by price
+/// and are listed in the returned report.
+pub fn fill_gaps(market_data: &mut MarketData, policy: GapPolicy) -> Result<DataQualityReport> {
+    let interval_ms = market_data.timeframe.parse::<Timeframe>()
+        .map_err(|e| BacktestError::InvalidData(e.to_string()))?
+        .as_millis();
+    if market_data.bars.windows(2).any(|w| w[1].timestamp <= w[0].timestamp) {
+        return Err(BacktestError::InvalidData("Bars must be in strictly increasing timestamp order".to_string()));
+    }
+    
+    let mut report = DataQualityReport {
+        symbol: market_data.symbol.clone(),
+        policy,
+        gaps: Vec::new(),
+        synthesized: Vec::new(),
+        partial_bar: None,
+    };
+    let mut bars = Vec::with_capacity(market_data.bars.len());
+    
+    for bar in market_data.bars.drain(..) {
+        if let Some((prev_timestamp, prev_close)) = bars.last().map(|b: &Bar| (b.timestamp, b.close)) {
+            let missing_bars = (bar.timestamp - prev_timestamp - 1) / interval_ms;
+            if missing_bars > 0 {
+                let start = prev_timestamp + interval_ms;
+                report.gaps.push(BarGap { start, missing_bars });
+                
+                match policy {
+                    GapPolicy::Error => return Err(BacktestError::InvalidData(format!(
+                        "{} is missing {} bars starting at {}", report.symbol, missing_bars, start
+                    ))),
+                    GapPolicy::Skip => {}
+                    GapPolicy::Fill => {
+                        for i in 0..missing_bars {
+                            let timestamp = start + i * interval_ms;
+                            bars.push(Bar {
+                                timestamp,
+                                open: prev_close,
+                                high: prev_close,
+                                low: prev_close,
+                                close: prev_close,
+                                volume: Decimal::ZERO,
+                                trade_count: 0,
+                            });
+                            report.synthesized.push(timestamp);
+                        }
+                    }
+                }
+            }
+        }
+        bars.push(bar);
+    }
+    market_data.bars = bars;
+    
+    if !report.gaps.is_empty() {
+        warn!(
+            "{}: {} gaps in bar data, {} bars synthesized ({:?})",
+            report.symbol, report.gaps.len(), report.synthesized.len(), policy
+        );
+    }
+    Ok(report)
+}
+
+/// Aggregate bars into a coarser timeframe
+/// 
+/// Buckets are aligned to multiples of the target interval since the Unix epoch (so
+/// `1d` buckets start at UTC midnight) and stamped with the bucket's open time. Each
+/// bucket takes the first open, max high, min low, last close, and summed volume and
+/// trade count. Partial buckets at either end of the series (data starting or ending
+/// mid-bucket) are dropped; buckets thinned by gaps inside the series are kept.
+pub fn resample(bars: &[Bar], from: &str, to: &str) -> Result<Vec<Bar>> {
+    let parse = |timeframe: &str| timeframe.parse::<Timeframe>()
+        .map_err(|e| BacktestError::InvalidData(e.to_string()));
+    let from = parse(from)?;
+    let to = parse(to)?;
+    
+    if !from.divides(&to) {
+        return Err(BacktestError::InvalidData(format!("Cannot resample {} to {}: target must be a multiple of source", from, to)));
+    }
+    let (from_ms, to_ms) = (from.as_millis(), to.as_millis());
+    if bars.windows(2).any(|w| w[1].timestamp <= w[0].timestamp) {
+        return Err(BacktestError::InvalidData("Bars must be in strictly increasing timestamp order".to_string()));
+    }
+    
+    let bars_per_bucket = (to_ms / from_ms) as usize;
+    let mut buckets: Vec<(Bar, usize)> = Vec::with_capacity(bars.len() / bars_per_bucket + 1);
+    
+    for bar in bars {
+        let bucket_start = bar.timestamp - bar.timestamp % to_ms;
+        
+        match buckets.last_mut() {
+            Some((current, count)) if current.timestamp == bucket_start => {
+                current.high = current.high.max(bar.high);
+                current.low = current.low.min(bar.low);
+                current.close = bar.close;
+                current.volume += bar.volume;
+                current.trade_count += bar.trade_count;
+                *count += 1;
+            }
+            _ => buckets.push((Bar { timestamp: bucket_start, ..bar.clone() }, 1)),
+        }
+    }
+    
+    let is_partial = |bucket: &(Bar, usize)| bucket.1 < bars_per_bucket;
+    if buckets.last().is_some_and(is_partial) {
+        buckets.pop();
+    }
+    if buckets.first().is_some_and(is_partial) {
+        buckets.remove(0);
+    }
+    
+    Ok(buckets.into_iter().map(|(bar, _)| bar).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    const TICKS_CSV: &str = "timestamp,price,qty,side,trade_id\n\
+                             1609459200100,100.5,0.2,buy,t1\n\
+                             1609459230000,100.7,0.1,sell,t2\n\
+                             1609459320500,101.0,0.3,buy,t3\n\
+                             1609459379999,100.9,0.05,sell,t4\n";
+
+    fn bars(count: u64) -> Vec<Bar> {
+        (0..count).map(|i| Bar {
+            timestamp: 1609459200000 + i * 60_000,
+            open: dec!(100.0),
+            high: dec!(101.5),
+            low: dec!(99.5),
+            close: dec!(101.0),
+            volume: dec!(10.0),
+            trade_count: 2,
+        }).collect()
+    }
+
+    #[test]
+    fn test_load_ticks_and_group_under_bars() {
+        let path = std::env::temp_dir().join(format!("backtest_ticks_{}.csv", std::process::id()));
+        fs::write(&path, TICKS_CSV).unwrap();
+        let ticks = load_trade_ticks(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(ticks.len(), 4);
+        assert_eq!(ticks[2].price, dec!(101.0));
+        assert!(matches!(ticks[1].side, TradeSide::Sell));
+
+        let groups = group_trades_by_bar(&bars(3), &ticks, 60_000).unwrap();
+        assert_eq!(groups, vec![0..2, 2..2, 2..4]);
+    }
+
+    #[test]
+    fn test_rejects_out_of_order_and_out_of_window_ticks() {
+        let unordered = "timestamp,price,qty,side\n1609459230000,100.7,0.1,sell\n1609459200100,100.5,0.2,buy\n";
+        assert!(parse_trade_ticks_csv(unordered).is_err());
+
+        // Last tick belongs to a fourth bar that does not exist
+        let ticks = parse_trade_ticks_csv(TICKS_CSV).unwrap();
+        assert!(matches!(
+            group_trades_by_bar(&bars(2), &ticks, 60_000),
+            Err(BacktestError::DataGap { .. })
+        ));
+
+        let mut market_data = MarketDataBuilder::new("BTCUSDT").bars(bars(3)).build().unwrap();
+        attach_trades(&mut market_data, ticks, 60_000).unwrap();
+        assert_eq!(market_data.trades.len(), 4);
+    }
+
+    #[test]
+    fn test_sub_millisecond_ticks_keep_their_order() {
+        // Two ticks inside the same millisecond, 250us apart
+        let in_order = "timestamp_us,price,qty,side,trade_id\n\
+                        1609459200100250,100.5,0.2,buy,t1\n\
+                        1609459200100500,100.6,0.1,sell,t2\n";
+        let ticks = parse_trade_ticks_csv(in_order).unwrap();
+        assert_eq!(ticks[0].timestamp.as_millis(), ticks[1].timestamp.as_millis());
+        assert!(ticks[0].timestamp < ticks[1].timestamp);
+        assert_eq!(ticks[1].timestamp, Timestamp::from_micros(1609459200100500));
+        assert_eq!(group_trades_by_bar(&bars(1), &ticks, 60_000).unwrap(), vec![0..2]);
+
+        // Swapped, they no longer tie at millisecond resolution and are caught
+        let swapped = "timestamp_us,price,qty,side\n1609459200100500,100.6,0.1,sell\n1609459200100250,100.5,0.2,buy\n";
+        assert!(parse_trade_ticks_csv(swapped).is_err());
+
+        // Nanosecond feeds truncate to the microsecond
+        let nanos = parse_trade_ticks_csv("timestamp_ns,price,qty,side\n1609459200100250999,100.5,0.2,buy\n").unwrap();
+        assert_eq!(nanos[0].timestamp, ticks[0].timestamp);
+    }
+
+    #[test]
+    fn test_gap_policy_fill_lists_synthesized_bars() {
+        // Minutes 0, 1, 4, 5: bars at minutes 2 and 3 are missing
+        let mut gapped = bars(6);
+        gapped.drain(2..4);
+        let data = |bars: Vec<Bar>| MarketDataBuilder::new("BTCUSDT").timeframe("1m").bars(bars).build().unwrap();
+
+        let mut filled = data(gapped.clone());
+        let report = fill_gaps(&mut filled, GapPolicy::Fill).unwrap();
+        assert_eq!(report.gaps, vec![BarGap { start: 1609459320000, missing_bars: 2 }]);
+        assert_eq!(report.synthesized, vec![1609459320000, 1609459380000]);
+        assert_eq!(filled.bars.len(), 6);
+        assert!(filled.bars.windows(2).all(|w| w[1].timestamp - w[0].timestamp == 60_000));
+        assert_eq!(filled.bars[2].close, filled.bars[1].close);
+        assert_eq!(filled.bars[2].volume, Decimal::ZERO);
+
+        // Skip reports the same gap without touching the series
+        let mut skipped = data(gapped.clone());
+        let report = fill_gaps(&mut skipped, GapPolicy::Skip).unwrap();
+        assert_eq!(report.gaps.len(), 1);
+        assert!(report.synthesized.is_empty());
+        assert_eq!(skipped.bars.len(), 4);
+
+        assert!(fill_gaps(&mut data(gapped), GapPolicy::Error).is_err());
+        assert!(fill_gaps(&mut data(bars(6)), GapPolicy::Error).unwrap().gaps.is_empty());
+    }
+
+    #[test]
+    fn test_builder_validates_bars_and_infers_timeframe() {
+        // Minutes 0, 1, 4: the gap does not change the inferred spacing
+        let mut gapped = bars(5);
+        gapped.drain(2..4);
+        let data = MarketDataBuilder::new("BTCUSDT").bars(gapped).build().unwrap();
+        assert_eq!(data.timeframe, "1m");
+        assert_eq!(data.timestamp_convention, TimestampConvention::OpenTime);
+
+        let mut out_of_order = bars(3);
+        out_of_order.swap(1, 2);
+        let err = MarketDataBuilder::new("BTCUSDT").bars(out_of_order).build().unwrap_err();
+        assert!(err.to_string().contains("not in timestamp order"), "{}", err);
+        let mut duplicated = bars(2);
+        duplicated[1].timestamp = duplicated[0].timestamp;
+        assert!(MarketDataBuilder::new("BTCUSDT").bars(duplicated).build().is_err());
+
+        let mut inverted = bars(3);
+        inverted[1].high = dec!(99.0);
+        let err = MarketDataBuilder::new("BTCUSDT").bars(inverted).build().unwrap_err();
+        assert!(err.to_string().contains("inconsistent OHLCV"), "{}", err);
+
+        // One bar has no spacing to infer from
+        assert!(MarketDataBuilder::new("BTCUSDT").bars(bars(1)).build().is_err());
+        assert_eq!(MarketDataBuilder::new("BTCUSDT").timeframe("5m").bars(bars(1)).build().unwrap().timeframe, "5m");
+
+        // An explicit timeframe must match the bars' spacing, however it is spelled
+        let err = MarketDataBuilder::new("BTCUSDT").timeframe("5m").bars(bars(3)).build().unwrap_err();
+        assert!(err.to_string().contains("60000ms apart but the timeframe is 5m"), "{}", err);
+        assert!(MarketDataBuilder::new("BTCUSDT").timeframe("60s").bars(bars(3)).build().is_ok());
+    }
+
+    #[test]
+    fn test_trade_counts_must_match_volume() {
+        let mut series = minute_bars(&[100, 101, 102]);
+        assert!(validate_trade_counts(&series).is_ok());
+
+        // Zero-volume filler bars carry no trades either
+        series[1].volume = Decimal::ZERO;
+        series[1].trade_count = 0;
+        assert!(validate_trade_counts(&series).is_ok());
+
+        series[1].trade_count = 5;
+        assert!(validate_trade_counts(&series).is_err());
+
+        // A source without trade counts is fine, a partial one is not
+        let mut unreported = minute_bars(&[100, 101, 102]);
+        unreported.iter_mut().for_each(|bar| bar.trade_count = 0);
+        assert!(validate_trade_counts(&unreported).is_ok());
+        unreported[2].trade_count = 4;
+        assert!(validate_trade_counts(&unreported).is_err());
+    }
+
+    fn minute_bars(closes: &[i64]) -> Vec<Bar> {
+        closes.iter().enumerate().map(|(i, close)| {
+            let close = Decimal::from(*close);
+            Bar {
+                timestamp: 1609459200000 + i as u64 * 60_000,
+                open: close - dec!(1),
+                high: close + dec!(2),
+                low: close - dec!(2),
+                close,
+                volume: dec!(10),
+                trade_count: 3,
+            }
+        }).collect()
+    }
+
+    #[test]
+    fn test_resample_1m_to_5m() {
+        // Twelve minutes: two full 5m buckets and a trailing partial one
+        let bars = minute_bars(&[100, 101, 105, 99, 102, 103, 104, 90, 95, 96, 97, 98]);
+        let resampled = resample(&bars, "1m", "5m").unwrap();
+
+        assert_eq!(resampled.len(), 2);
+        let first = &resampled[0];
+        assert_eq!(first.timestamp, 1609459200000);
+        assert_eq!(first.open, dec!(99));
+        assert_eq!(first.high, dec!(107));
+        assert_eq!(first.low, dec!(97));
+        assert_eq!(first.close, dec!(102));
+        assert_eq!(first.volume, dec!(50));
+        assert_eq!(first.trade_count, 15);
+
+        let second = &resampled[1];
+        assert_eq!(second.timestamp, 1609459500000);
+        assert_eq!(second.low, dec!(88));
+        assert_eq!(second.close, dec!(96));
+    }
+
+    #[test]
+    fn test_resample_drops_leading_partial_and_rejects_non_multiples() {
+        let bars = minute_bars(&[100, 101, 102, 103, 104, 105, 106, 107, 108, 109]);
+        // Start two minutes into the first bucket
+        let resampled = resample(&bars[2..], "1m", "5m").unwrap();
+        assert_eq!(resampled.len(), 1);
+        assert_eq!(resampled[0].timestamp, 1609459500000);
+
+        assert!(resample(&bars, "2m", "5m").is_err());
+        assert!(resample(&bars, "5m", "1m").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_close_time_bars_trade_like_open_time_bars() {
+        use crate::simulator::ExchangeSimulator;
+        use crate::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+
+        let open_time = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 120,
+            ..SyntheticDataConfig::default()
+        }, 5).generate();
+        let mut close_time = open_time.clone();
+        close_time.timestamp_convention = TimestampConvention::CloseTime;
+        for bar in &mut close_time.bars {
+            bar.timestamp += 60_000;
+        }
+
+        // Unnormalized close-time bars are refused rather than silently misaligned
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        assert!(simulator.simulate_with_signals(&close_time, |_, _| Vec::new(), &IntrabarPolicy::OneSecondBars, &SlippageMode::None).await.is_err());
+
+        normalize_timestamps(&mut close_time).unwrap();
+        assert_eq!(close_time.timestamp_convention, TimestampConvention::OpenTime);
+        assert_eq!(close_time.bars[0].timestamp, open_time.bars[0].timestamp);
+
+        let signals = |bar_idx: usize, bar: &Bar| if bar_idx.is_multiple_of(20) {
+            vec![StrategySignal {
+                side: TradeSide::Buy,
+                size: dec!(0.01),
+                entry_price: None,
+                take_profit: Some(bar.close * dec!(1.002)),
+                stop_loss: Some(bar.close * dec!(0.998)),
+                time_to_live: None,
+                rule_tag: None,
+                tags: Vec::new(),
+                symbol: String::new(),
+            }]
+        } else {
+            Vec::new()
+        };
+        let mut trades = Vec::new();
+        for data in [&open_time, &close_time] {
+            let mut simulator = ExchangeSimulator::new().unwrap();
+            simulator.simulate_with_signals(data, signals, &IntrabarPolicy::OneSecondBars, &SlippageMode::None).await.unwrap();
+            trades.push(simulator.get_trade_table_result().trades);
+        }
+        assert!(!trades[0].is_empty());
+        assert_eq!(
+            crate::canonical::to_canonical_json(&trades[0]).unwrap(),
+            crate::canonical::to_canonical_json(&trades[1]).unwrap()
+        );
+    }
+
+}
@@ -0,0 +1,374 @@
+//! Trade table comparison
+//! 
+//! Diffs two [`TradeTableResult`]s trade by trade, e.g. to confirm that an engine
+//! change is behavior-preserving or to see exactly which trades it moved. Rejections
+//! and per-tag summaries are compared too, so two tables only diff as empty when
+//! every part of them matches.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt::Debug;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::types::*;
+
+/// One field that differs between the two sides
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldDelta {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+    /// `after - before` for numeric fields
+    pub delta: Option<Decimal>,
+}
+
+/// Trade present on both sides with at least one differing field
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeChange {
+    pub symbol: String,
+    pub entry_time_utc: String,
+    pub fields: Vec<FieldDelta>,
+}
+
+/// Differences between two trade tables
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeTableDiff {
+    /// Trades only in the second table
+    pub added: Vec<TradeRecord>,
+    /// Trades only in the first table
+    pub removed: Vec<TradeRecord>,
+    /// Trades in both tables whose fields differ
+    pub changed: Vec<TradeChange>,
+    /// Summary metrics that differ
+    pub summary: Vec<FieldDelta>,
+    /// Rejections only in the second table
+    pub rejections_added: Vec<RejectedTrade>,
+    /// Rejections only in the first table
+    pub rejections_removed: Vec<RejectedTrade>,
+    /// Per-tag summaries that differ, by tag; a tag missing on one side compares
+    /// against a summary of no trades
+    pub tag_summaries: Vec<TagSummaryChange>,
+}
+
+/// Summary of one tag's trades that differs between the two sides
+#[derive(Debug, Clone, Serialize)]
+pub struct TagSummaryChange {
+    pub tag: String,
+    pub fields: Vec<FieldDelta>,
+}
+
+impl TradeTableDiff {
+    /// Whether the two tables are identical
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty() && self.summary.is_empty()
+            && self.rejections_added.is_empty() && self.rejections_removed.is_empty()
+            && self.tag_summaries.is_empty()
+    }
+}
+
+/// Compare two trade tables, matching trades by (symbol, entry time)
+///
+/// Trades sharing a key (e.g. both legs in hedge mode) are paired in table order.
+/// Removed and changed trades follow the order of `a`, added trades the order of `b`.
+pub fn diff_trade_tables(a: &TradeTableResult, b: &TradeTableResult) -> TradeTableDiff {
+    let mut unmatched: HashMap<(&str, &str), VecDeque<&TradeRecord>> = HashMap::new();
+    for trade in &b.trades {
+        unmatched.entry(trade_key(trade)).or_default().push_back(trade);
+    }
+
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    for before in &a.trades {
+        match unmatched.get_mut(&trade_key(before)).and_then(|queue| queue.pop_front()) {
+            Some(after) => {
+                let fields = trade_deltas(before, after);
+                if !fields.is_empty() {
+                    changed.push(TradeChange {
+                        symbol: before.symbol.clone(),
+                        entry_time_utc: before.entry_time_utc.clone(),
+                        fields,
+                    });
+                }
+            }
+            None => removed.push(before.clone()),
+        }
+    }
+
+    // Whatever is left in `b` had no counterpart in `a`; walk `b` again to keep its order
+    let mut added = Vec::new();
+    for trade in &b.trades {
+        if let Some(queue) = unmatched.get_mut(&trade_key(trade)) {
+            if queue.front().is_some_and(|left| std::ptr::eq(*left, trade)) {
+                added.push(trade.clone());
+                queue.pop_front();
+            }
+        }
+    }
+
+    let (rejections_added, rejections_removed) = rejection_deltas(&a.rejected_trades, &b.rejected_trades);
+
+    TradeTableDiff {
+        added,
+        removed,
+        changed,
+        summary: summary_deltas(&a.summary, &b.summary),
+        rejections_added,
+        rejections_removed,
+        tag_summaries: tag_summary_deltas(&a.summary_by_tag, &b.summary_by_tag),
+    }
+}
+
+/// Rejections in `b` but not `a`, and in `a` but not `b`, matching identical rejections in order
+fn rejection_deltas(a: &[RejectedTrade], b: &[RejectedTrade]) -> (Vec<RejectedTrade>, Vec<RejectedTrade>) {
+    let mut unmatched: HashMap<_, usize> = HashMap::new();
+    for rejected in b {
+        *unmatched.entry(rejection_key(rejected)).or_default() += 1;
+    }
+
+    let mut removed = Vec::new();
+    for rejected in a {
+        match unmatched.get_mut(&rejection_key(rejected)) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => removed.push(rejected.clone()),
+        }
+    }
+
+    // Leftover counts belong to the last matching rejections of `b`
+    let mut added = Vec::new();
+    for rejected in b.iter().rev() {
+        if let Some(count) = unmatched.get_mut(&rejection_key(rejected)) {
+            if *count > 0 {
+                *count -= 1;
+                added.push(rejected.clone());
+            }
+        }
+    }
+    added.reverse();
+    (added, removed)
+}
+
+fn rejection_key(rejected: &RejectedTrade) -> (u64, &str, bool, ReasonCode, Decimal) {
+    (rejected.timestamp, rejected.symbol.as_str(), matches!(rejected.side, TradeSide::Buy), rejected.reason, rejected.notional)
+}
+
+fn tag_summary_deltas(a: &BTreeMap<String, TradeSummary>, b: &BTreeMap<String, TradeSummary>) -> Vec<TagSummaryChange> {
+    let tags: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    let none = empty_summary();
+    tags.into_iter()
+        .filter_map(|tag| {
+            let fields = summary_deltas(a.get(tag).unwrap_or(&none), b.get(tag).unwrap_or(&none));
+            (!fields.is_empty()).then(|| TagSummaryChange { tag: tag.clone(), fields })
+        })
+        .collect()
+}
+
+/// Summary of a side that has no trades for a tag
+fn empty_summary() -> TradeSummary {
+    TradeSummary {
+        total_trades: 0,
+        wins: 0,
+        losses: 0,
+        scratches: 0,
+        win_rate: Percent::ZERO,
+        net_pnl_usd: Decimal::ZERO,
+        avg_win_usd: Decimal::ZERO,
+        avg_loss_usd: Decimal::ZERO,
+        expectancy: Decimal::ZERO,
+        max_drawdown: Percent::ZERO,
+        profit_factor: Decimal::ZERO,
+        avg_holding_time_hours: Decimal::ZERO,
+        total_fees_usd: Decimal::ZERO,
+        total_slippage_usd: Decimal::ZERO,
+    }
+}
+
+fn trade_key(trade: &TradeRecord) -> (&str, &str) {
+    (trade.symbol.as_str(), trade.entry_time_utc.as_str())
+}
+
+fn trade_deltas(a: &TradeRecord, b: &TradeRecord) -> Vec<FieldDelta> {
+    let mut fields = Vec::new();
+    push_value(&mut fields, "date", &a.date, &b.date);
+    push_value(&mut fields, "trade_type", &a.trade_type, &b.trade_type);
+    push_decimal(&mut fields, "entry_price", a.entry_price, b.entry_price);
+    push_decimal(&mut fields, "exit_price", a.exit_price, b.exit_price);
+    push_value(&mut fields, "exit_time_utc", &a.exit_time_utc, &b.exit_time_utc);
+    push_value(&mut fields, "exit_reason", &a.exit_reason, &b.exit_reason);
+    push_value(&mut fields, "hit_tp_sl", &a.hit_tp_sl, &b.hit_tp_sl);
+    push_decimal(&mut fields, "size_usd", a.size_usd, b.size_usd);
+    push_decimal(&mut fields, "qty", a.qty, b.qty);
+    push_decimal(&mut fields, "fees_usd", a.fees_usd, b.fees_usd);
+    push_decimal(&mut fields, "slippage_usd", a.slippage_usd, b.slippage_usd);
+    push_decimal(&mut fields, "pnl_usd", a.pnl_usd, b.pnl_usd);
+    push_decimal(&mut fields, "pnl_pct", a.pnl_pct.value(), b.pnl_pct.value());
+    push_decimal(&mut fields, "breakeven_price", a.breakeven_price, b.breakeven_price);
+    push_value(&mut fields, "fill_outside_bar", &a.fill_outside_bar, &b.fill_outside_bar);
+    push_value(&mut fields, "tags", &a.tags, &b.tags);
+    fields
+}
+
+fn summary_deltas(a: &TradeSummary, b: &TradeSummary) -> Vec<FieldDelta> {
+    let mut fields = Vec::new();
+    push_decimal(&mut fields, "total_trades", a.total_trades.into(), b.total_trades.into());
+    push_decimal(&mut fields, "wins", a.wins.into(), b.wins.into());
+    push_decimal(&mut fields, "losses", a.losses.into(), b.losses.into());
+    push_decimal(&mut fields, "scratches", a.scratches.into(), b.scratches.into());
+    push_decimal(&mut fields, "win_rate", a.win_rate.value(), b.win_rate.value());
+    push_decimal(&mut fields, "net_pnl_usd", a.net_pnl_usd, b.net_pnl_usd);
+    push_decimal(&mut fields, "avg_win_usd", a.avg_win_usd, b.avg_win_usd);
+    push_decimal(&mut fields, "avg_loss_usd", a.avg_loss_usd, b.avg_loss_usd);
+    push_decimal(&mut fields, "expectancy", a.expectancy, b.expectancy);
+    push_decimal(&mut fields, "max_drawdown", a.max_drawdown.value(), b.max_drawdown.value());
+    push_decimal(&mut fields, "profit_factor", a.profit_factor, b.profit_factor);
+    push_decimal(&mut fields, "avg_holding_time_hours", a.avg_holding_time_hours, b.avg_holding_time_hours);
+    push_decimal(&mut fields, "total_fees_usd", a.total_fees_usd, b.total_fees_usd);
+    push_decimal(&mut fields, "total_slippage_usd", a.total_slippage_usd, b.total_slippage_usd);
+    fields
+}
+
+fn push_decimal(fields: &mut Vec<FieldDelta>, field: &'static str, before: Decimal, after: Decimal) {
+    if before != after {
+        fields.push(FieldDelta {
+            field,
+            before: before.to_string(),
+            after: after.to_string(),
+            delta: Some(after - before),
+        });
+    }
+}
+
+fn push_value<T: PartialEq + Debug>(fields: &mut Vec<FieldDelta>, field: &'static str, before: &T, after: &T) {
+    if before != after {
+        fields.push(FieldDelta {
+            field,
+            before: format!("{:?}", before),
+            after: format!("{:?}", after),
+            delta: None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::trade_table::TradeTableGenerator;
+
+    fn trade(symbol: &str, entry_time: &str, exit_price: Decimal) -> TradeRecord {
+        TradeRecord {
+            date: "2024-01-01".to_string(),
+            trade_type: TradeType::Long,
+            entry_price: dec!(100),
+            entry_time_utc: entry_time.to_string(),
+            exit_price,
+            exit_time_utc: "2024-01-01T12:00:00Z".to_string(),
+            exit_reason: ExitReason::Timeout,
+            hit_tp_sl: HitTpSl::None,
+            size_usd: dec!(1000),
+            qty: dec!(10),
+            fees_usd: dec!(0),
+            slippage_usd: dec!(0),
+            pnl_usd: (exit_price - dec!(100)) * dec!(10),
+            pnl_pct: Percent::new(exit_price - dec!(100)),
+            breakeven_price: dec!(100),
+            fill_outside_bar: false,
+            symbol: symbol.to_string(),
+            tags: Vec::new(),
+            rule_tag: None,
+        }
+    }
+
+    fn table(trades: Vec<TradeRecord>) -> TradeTableResult {
+        TradeTableResult { trades, ..TradeTableGenerator::new().generate_result() }
+    }
+
+    #[test]
+    fn test_diff_isolates_changed_exit_price() {
+        let before = table(vec![
+            trade("BTCUSDT", "2024-01-01T00:00:00Z", dec!(101)),
+            trade("BTCUSDT", "2024-01-01T01:00:00Z", dec!(102)),
+            trade("ETHUSDT", "2024-01-01T00:00:00Z", dec!(99)),
+        ]);
+        assert!(diff_trade_tables(&before, &before).is_empty());
+
+        let mut after = before.clone();
+        after.trades[1].exit_price = dec!(102.5);
+        let diff = diff_trade_tables(&before, &after);
+
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert!(diff.summary.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].symbol, "BTCUSDT");
+        assert_eq!(diff.changed[0].entry_time_utc, "2024-01-01T01:00:00Z");
+        assert_eq!(diff.changed[0].fields, vec![FieldDelta {
+            field: "exit_price",
+            before: "102".to_string(),
+            after: "102.5".to_string(),
+            delta: Some(dec!(0.5)),
+        }]);
+    }
+
+    #[test]
+    fn test_diff_reports_added_and_removed_trades() {
+        let before = table(vec![
+            trade("BTCUSDT", "2024-01-01T00:00:00Z", dec!(101)),
+            trade("BTCUSDT", "2024-01-01T01:00:00Z", dec!(102)),
+        ]);
+        let mut after = table(vec![
+            trade("BTCUSDT", "2024-01-01T00:00:00Z", dec!(101)),
+            trade("BTCUSDT", "2024-01-01T02:00:00Z", dec!(103)),
+        ]);
+        after.summary.net_pnl_usd = dec!(40);
+
+        let diff = diff_trade_tables(&before, &after);
+        assert!(diff.changed.is_empty());
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].entry_time_utc, "2024-01-01T01:00:00Z");
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].entry_time_utc, "2024-01-01T02:00:00Z");
+        assert_eq!(diff.summary.len(), 1);
+        assert_eq!(diff.summary[0].field, "net_pnl_usd");
+        assert_eq!(diff.summary[0].delta, Some(dec!(40)));
+    }
+
+    #[test]
+    fn test_diff_compares_tags_rejections_and_tag_summaries() {
+        let before = table(vec![trade("BTCUSDT", "2024-01-01T00:00:00Z", dec!(101))]);
+
+        let mut retagged = before.clone();
+        retagged.trades[0].tags = vec![("setup".to_string(), "breakout".to_string())];
+        let diff = diff_trade_tables(&before, &retagged);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].fields[0].field, "tags");
+
+        let mut flagged = before.clone();
+        flagged.trades[0].fill_outside_bar = true;
+        let diff = diff_trade_tables(&before, &flagged);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].fields[0].field, "fill_outside_bar");
+
+        let mut rejected = before.clone();
+        rejected.rejected_trades.push(RejectedTrade {
+            timestamp: 1704067200000,
+            symbol: "BTCUSDT".to_string(),
+            side: TradeSide::Buy,
+            reason: ReasonCode::Cooldown,
+            notional: dec!(1000),
+        });
+        let diff = diff_trade_tables(&before, &rejected);
+        assert_eq!(diff.rejections_added.len(), 1);
+        assert!(diff.rejections_removed.is_empty());
+        assert!(!diff.is_empty());
+        let diff = diff_trade_tables(&rejected, &before);
+        assert_eq!(diff.rejections_removed.len(), 1);
+
+        let mut by_tag = before.clone();
+        by_tag.summary_by_tag.insert("setup=breakout".to_string(), TradeSummary { total_trades: 1, ..empty_summary() });
+        let diff = diff_trade_tables(&before, &by_tag);
+        assert_eq!(diff.tag_summaries.len(), 1);
+        assert_eq!(diff.tag_summaries[0].tag, "setup=breakout");
+        assert_eq!(diff.tag_summaries[0].fields[0].field, "total_trades");
+        assert!(diff_trade_tables(&by_tag, &by_tag).is_empty());
+    }
+}
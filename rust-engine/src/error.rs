@@ -0,0 +1,70 @@
+//! Engine error types
+//! 
+//! Library functions return [`BacktestError`] so callers can match on the failure
+//! kind. Top-level orchestration (`BacktestEngine`, binaries) keeps using `anyhow`,
+//! which wraps these errors through `?`.
+
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use crate::wasm::StrategyValidationError;
+
+/// Result alias defaulting to [`BacktestError`]
+pub type Result<T, E = BacktestError> = std::result::Result<T, E>;
+
+/// Major failure classes of a backtest
+#[derive(Debug, Error)]
+pub enum BacktestError {
+    /// Indicator name not registered with the engine
+    #[error("Unknown indicator: {0}")]
+    UnknownIndicator(String),
+    
+    /// Indicator exists but cannot be computed from the given input layout
+    #[error("Indicator not supported for {input} input: {indicator}")]
+    UnsupportedInput { indicator: String, input: &'static str },
+    
+    /// Malformed or inconsistent input data
+    #[error("{0}")]
+    InvalidData(String),
+    
+    /// Fewer bars than the longest indicator warmup needs
+    #[error("Insufficient data for {symbol}: {indicator} needs {required} bars, {available} available")]
+    InsufficientData {
+        symbol: String,
+        indicator: String,
+        required: usize,
+        available: usize,
+    },
+    
+    /// Data point not covered by any bar window
+    #[error("Tick {trade_id} at {timestamp} falls outside every bar window")]
+    DataGap { trade_id: String, timestamp: u64 },
+    
+    /// Order notional below the exchange minimum
+    #[error("Notional value {notional} below minimum {min_notional}")]
+    MinNotional { notional: Decimal, min_notional: Decimal },
+    
+    /// Strategy module failed while executing
+    #[error("WASM strategy trapped: {0}")]
+    WasmTrap(String),
+    
+    /// Strategy declared indicators or parameters the engine cannot honor
+    #[error(transparent)]
+    StrategyValidation(#[from] StrategyValidationError),
+    
+    /// Two runs of the same symbol over the same inputs disagreed
+    #[error("Nondeterministic result for {symbol}: {detail}")]
+    DeterminismViolation { symbol: String, detail: String },
+    
+    /// Code path that exists in the API but is not built yet
+    #[error("{0} not yet implemented")]
+    NotImplemented(&'static str),
+    
+    /// Failed to read an input file
+    #[error("Failed to read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
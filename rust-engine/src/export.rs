@@ -1,13 +1,21 @@
 //! Export system for trade table results
 //! 
-//! Provides CSV, Parquet, and ClickHouse export functionality for trade table results.
+//! Provides CSV, Parquet, Arrow IPC, and ClickHouse export functionality for trade table results.
+//! Arrow IPC needs the `arrow-ipc` feature.
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use rust_decimal::Decimal;
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use tracing::{info, debug, error};
 
+use crate::canonical::to_canonical_json;
 use crate::types::*;
+use crate::versioning::RunManifest;
+
+#[cfg(feature = "arrow-ipc")]
+pub mod arrow_ipc;
 
 /// Export format enumeration
 #[derive(Debug, Clone)]
@@ -15,6 +23,8 @@ pub enum ExportFormat {
     Csv,
     Parquet,
     ClickHouse,
+    /// Arrow IPC stream with a typed schema (see `export::arrow_ipc`)
+    ArrowIpc,
 }
 
 /// Export configuration
@@ -25,6 +35,122 @@ pub struct ExportConfig {
     pub clickhouse_url: Option<String>,
     pub clickhouse_database: Option<String>,
     pub clickhouse_table: Option<String>,
+    /// Write the producing run's [`ExportMetadata`] next to the results
+    pub export_manifest_alongside_results: bool,
+    /// Decimate exported equity curves; `None` writes every point
+    /// 
+    /// Only `export_equity_curve` applies it, and that exists with the `arrow-ipc`
+    /// feature alone; without the feature this setting has no effect beyond what
+    /// `sampled_equity_curve` returns.
+    pub equity_sampling: Option<Sampling>,
+}
+
+/// Milliseconds in a UTC day, for `Sampling::Daily`
+const MS_PER_DAY: u64 = 86_400_000;
+
+/// How an exported equity curve is thinned out
+/// 
+/// Points are kept, never averaged, so every exported row is one the run produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sampling {
+    /// Every `n`th point, starting with the first, plus the last
+    Every(usize),
+    /// The last point of each UTC day: end-of-day equity
+    Daily,
+    /// From each run of this many points: the highest and lowest equity and the
+    /// deepest drawdown, which is not the lowest equity when the bucket sets a new peak
+    /// 
+    /// Peaks and troughs survive, so plotted drawdowns keep their depth. The curve's
+    /// first and last points are always kept, so the sampled curve spans the whole run.
+    MinMaxPerBucket(usize),
+}
+
+impl Sampling {
+    /// The points of `points` this sampling keeps, in their original order
+    pub fn apply(&self, points: &[EquityPoint]) -> Vec<EquityPoint> {
+        let mut kept = Vec::new();
+        match *self {
+            Sampling::Every(n) => {
+                let step = n.max(1);
+                kept.extend(points.iter().step_by(step).cloned());
+                // Keep the final equity even when the step passes over it
+                if let Some(last) = points.last() {
+                    if !(points.len() - 1).is_multiple_of(step) {
+                        kept.push(last.clone());
+                    }
+                }
+            }
+            Sampling::Daily => {
+                for (i, point) in points.iter().enumerate() {
+                    let day_ends = points.get(i + 1)
+                        .is_none_or(|next| next.timestamp / MS_PER_DAY != point.timestamp / MS_PER_DAY);
+                    if day_ends {
+                        kept.push(point.clone());
+                    }
+                }
+            }
+            Sampling::MinMaxPerBucket(size) => {
+                let bucket_count = points.len().div_ceil(size.max(1));
+                for (b, bucket) in points.chunks(size.max(1)).enumerate() {
+                    let mut high = 0;
+                    let mut low = 0;
+                    let mut deepest = 0;
+                    for (i, point) in bucket.iter().enumerate() {
+                        if point.equity > bucket[high].equity {
+                            high = i;
+                        }
+                        if point.equity < bucket[low].equity {
+                            low = i;
+                        }
+                        if point.drawdown > bucket[deepest].drawdown {
+                            deepest = i;
+                        }
+                    }
+                    let mut indices = vec![high, low, deepest];
+                    if b == 0 {
+                        indices.push(0);
+                    }
+                    if b + 1 == bucket_count {
+                        indices.push(bucket.len() - 1);
+                    }
+                    indices.sort_unstable();
+                    let mut previous = None;
+                    for i in indices {
+                        if previous != Some(i) {
+                            kept.push(bucket[i].clone());
+                            previous = Some(i);
+                        }
+                    }
+                }
+            }
+        }
+        kept
+    }
+}
+
+/// Link from an exported file back to the [`RunManifest`] that produced it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportMetadata {
+    pub run_id: String,
+    pub engine_version: String,
+    pub strategy_hash: String,
+    pub config_hash: String,
+}
+
+impl From<&RunManifest> for ExportMetadata {
+    fn from(manifest: &RunManifest) -> Self {
+        Self {
+            run_id: manifest.run_id.clone(),
+            engine_version: manifest.engine_version.version.clone(),
+            strategy_hash: manifest.audit_chain.strategy_hash.clone(),
+            config_hash: manifest.audit_chain.config_hash.clone(),
+        }
+    }
+}
+
+/// Sidecar file holding the [`ExportMetadata`] for a CSV export at `output_path`
+pub fn manifest_sidecar_path(output_path: &str) -> String {
+    format!("{}.manifest.json", output_path)
 }
 
 impl Default for ExportConfig {
@@ -35,6 +161,8 @@ impl Default for ExportConfig {
             clickhouse_url: None,
             clickhouse_database: None,
             clickhouse_table: Some("trades".to_string()),
+            export_manifest_alongside_results: false,
+            equity_sampling: None,
         }
     }
 }
@@ -42,12 +170,29 @@ impl Default for ExportConfig {
 /// Trade table exporter
 pub struct TradeTableExporter {
     config: ExportConfig,
+    metadata: Option<ExportMetadata>,
 }
 
 impl TradeTableExporter {
     /// Create a new exporter with configuration
     pub fn new(config: ExportConfig) -> Self {
-        Self { config }
+        Self { config, metadata: None }
+    }
+
+    /// Attach the manifest of the run being exported
+    pub fn with_manifest(mut self, manifest: &RunManifest) -> Self {
+        self.metadata = Some(ExportMetadata::from(manifest));
+        self
+    }
+
+    /// Metadata to write with the results, if the config asks for it
+    fn metadata_to_export(&self) -> Result<Option<&ExportMetadata>> {
+        if !self.config.export_manifest_alongside_results {
+            return Ok(None);
+        }
+        self.metadata.as_ref()
+            .map(Some)
+            .ok_or_else(|| anyhow::anyhow!("export_manifest_alongside_results is set but no manifest was attached"))
     }
 
     /// Export trade table result
@@ -56,6 +201,7 @@ impl TradeTableExporter {
             ExportFormat::Csv => self.export_csv(result).await,
             ExportFormat::Parquet => self.export_parquet(result).await,
             ExportFormat::ClickHouse => self.export_clickhouse(result).await,
+            ExportFormat::ArrowIpc => self.export_arrow_ipc(result).await,
         }
     }
 
@@ -64,6 +210,8 @@ impl TradeTableExporter {
         let output_path = self.config.output_path.as_ref()
             .ok_or_else(|| anyhow::anyhow!("Output path not specified for CSV export"))?;
 
+        let metadata = self.metadata_to_export()?;
+
         info!("Exporting trade table to CSV: {}", output_path);
 
         let mut csv_content = String::new();
@@ -76,26 +224,13 @@ impl TradeTableExporter {
             let line = format!(
                 "{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
                 trade.date,
-                match trade.trade_type {
-                    TradeType::Long => "Long",
-                    TradeType::Short => "Short",
-                },
+                trade_type_label(&trade.trade_type),
                 trade.entry_price,
                 trade.entry_time_utc,
                 trade.exit_price,
                 trade.exit_time_utc,
-                match trade.exit_reason {
-                    ExitReason::TakeProfit => "TP",
-                    ExitReason::StopLoss => "SL",
-                    ExitReason::StrategyExit => "StrategyExit",
-                    ExitReason::Liquidation => "Liquidation",
-                    ExitReason::Timeout => "Timeout",
-                },
-                match trade.hit_tp_sl {
-                    HitTpSl::TakeProfit => "TP",
-                    HitTpSl::StopLoss => "SL",
-                    HitTpSl::None => "None",
-                },
+                exit_reason_label(&trade.exit_reason),
+                hit_tp_sl_label(&trade.hit_tp_sl),
                 trade.size_usd,
                 trade.qty,
                 trade.fees_usd,
@@ -111,6 +246,7 @@ impl TradeTableExporter {
         csv_content.push_str(&format!("total_trades,{}\n", result.summary.total_trades));
         csv_content.push_str(&format!("wins,{}\n", result.summary.wins));
         csv_content.push_str(&format!("losses,{}\n", result.summary.losses));
+        csv_content.push_str(&format!("scratches,{}\n", result.summary.scratches));
         csv_content.push_str(&format!("win_rate,{}\n", result.summary.win_rate));
         csv_content.push_str(&format!("net_pnl_usd,{}\n", result.summary.net_pnl_usd));
         csv_content.push_str(&format!("avg_win_usd,{}\n", result.summary.avg_win_usd));
@@ -141,6 +277,13 @@ impl TradeTableExporter {
 
         // Write to file
         tokio::fs::write(output_path, csv_content).await?;
+
+        // CSV has no metadata section, so the manifest link goes in a sidecar
+        if let Some(metadata) = metadata {
+            let sidecar_path = manifest_sidecar_path(output_path);
+            tokio::fs::write(&sidecar_path, to_canonical_json(metadata)?).await?;
+            debug!("Wrote manifest sidecar for run {}: {}", metadata.run_id, sidecar_path);
+        }
         
         info!("CSV export completed: {} trades, {} rejected", 
               result.trades.len(), result.rejected_trades.len());
@@ -152,10 +295,51 @@ impl TradeTableExporter {
     async fn export_parquet(&self, _result: &TradeTableResult) -> Result<()> {
         // TODO: Implement Parquet export using arrow-rs
         // This would require adding arrow dependencies to Cargo.toml
+        // ExportMetadata belongs in the file's key-value metadata once this exists
         error!("Parquet export not yet implemented");
         Err(anyhow::anyhow!("Parquet export not yet implemented"))
     }
 
+    /// Export to an Arrow IPC stream
+    #[cfg(feature = "arrow-ipc")]
+    async fn export_arrow_ipc(&self, result: &TradeTableResult) -> Result<()> {
+        let output_path = self.config.output_path.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Output path not specified for Arrow IPC export"))?;
+
+        info!("Exporting trade table to Arrow IPC: {}", output_path);
+
+        let bytes = arrow_ipc::trades_to_ipc(&result.trades, self.metadata_to_export()?)?;
+        tokio::fs::write(output_path, bytes).await?;
+
+        info!("Arrow IPC export completed: {} trades", result.trades.len());
+        Ok(())
+    }
+
+    #[cfg(not(feature = "arrow-ipc"))]
+    async fn export_arrow_ipc(&self, _result: &TradeTableResult) -> Result<()> {
+        error!("Arrow IPC export requires the arrow-ipc feature");
+        Err(anyhow::anyhow!("Arrow IPC export requires the arrow-ipc feature"))
+    }
+
+    /// Write an equity curve as an Arrow IPC stream to `path`, thinned by `equity_sampling`
+    #[cfg(feature = "arrow-ipc")]
+    pub async fn export_equity_curve(&self, equity_curve: &[EquityPoint], path: &str) -> Result<()> {
+        let points = self.sampled_equity_curve(equity_curve);
+        let bytes = arrow_ipc::equity_curve_to_ipc(&points, self.metadata_to_export()?)?;
+        tokio::fs::write(path, bytes).await?;
+
+        info!("Equity curve export completed: {} of {} points to {}", points.len(), equity_curve.len(), path);
+        Ok(())
+    }
+
+    /// The points of `equity_curve` that `equity_sampling` exports
+    pub fn sampled_equity_curve<'a>(&self, equity_curve: &'a [EquityPoint]) -> Cow<'a, [EquityPoint]> {
+        match &self.config.equity_sampling {
+            Some(sampling) => Cow::Owned(sampling.apply(equity_curve)),
+            None => Cow::Borrowed(equity_curve),
+        }
+    }
+
     /// Export to ClickHouse
     async fn export_clickhouse(&self, result: &TradeTableResult) -> Result<()> {
         let clickhouse_url = self.config.clickhouse_url.as_ref()
@@ -189,16 +373,23 @@ impl TradeTableExporter {
             total_trades: 0,
             wins: 0,
             losses: 0,
-            win_rate: Decimal::ZERO,
+            scratches: 0,
+            win_rate: Percent::ZERO,
             net_pnl_usd: Decimal::ZERO,
             avg_win_usd: Decimal::ZERO,
             avg_loss_usd: Decimal::ZERO,
             expectancy: Decimal::ZERO,
-            max_drawdown: Decimal::ZERO,
+            max_drawdown: Percent::ZERO,
             profit_factor: Decimal::ZERO,
             avg_holding_time_hours: Decimal::ZERO,
+            total_fees_usd: Decimal::ZERO,
+            total_slippage_usd: Decimal::ZERO,
         };
 
+        // Per-symbol averages weighted back into totals, so each symbol's breakeven band holds
+        let mut total_win_usd = Decimal::ZERO;
+        let mut total_loss_usd = Decimal::ZERO;
+
         for symbol_result in symbol_results {
             if let Some(trade_table) = &symbol_result.trade_table {
                 all_trades.extend(trade_table.trades.clone());
@@ -208,7 +399,12 @@ impl TradeTableExporter {
                 combined_summary.total_trades += trade_table.summary.total_trades;
                 combined_summary.wins += trade_table.summary.wins;
                 combined_summary.losses += trade_table.summary.losses;
+                combined_summary.scratches += trade_table.summary.scratches;
                 combined_summary.net_pnl_usd += trade_table.summary.net_pnl_usd;
+                combined_summary.total_fees_usd += trade_table.summary.total_fees_usd;
+                combined_summary.total_slippage_usd += trade_table.summary.total_slippage_usd;
+                total_win_usd += trade_table.summary.avg_win_usd * Decimal::from(trade_table.summary.wins);
+                total_loss_usd += trade_table.summary.avg_loss_usd * Decimal::from(trade_table.summary.losses);
                 
                 // Update max drawdown to the maximum across all symbols
                 if trade_table.summary.max_drawdown > combined_summary.max_drawdown {
@@ -219,42 +415,34 @@ impl TradeTableExporter {
 
         // Recalculate combined statistics
         if combined_summary.total_trades > 0 {
-            combined_summary.win_rate = Decimal::from(combined_summary.wins) / 
-                Decimal::from(combined_summary.total_trades) * dec!(100.0);
-            
-            let winning_trades: Vec<&TradeRecord> = all_trades.iter()
-                .filter(|trade| trade.pnl_usd > Decimal::ZERO)
-                .collect();
-            
-            let losing_trades: Vec<&TradeRecord> = all_trades.iter()
-                .filter(|trade| trade.pnl_usd <= Decimal::ZERO)
-                .collect();
+            let decided = combined_summary.wins + combined_summary.losses;
+            if decided > 0 {
+                combined_summary.win_rate = Percent::from_ratio(
+                    Decimal::from(combined_summary.wins) / Decimal::from(decided)
+                );
+            }
 
             combined_summary.avg_win_usd = if combined_summary.wins > 0 {
-                winning_trades.iter()
-                    .map(|trade| trade.pnl_usd)
-                    .sum::<Decimal>() / Decimal::from(combined_summary.wins)
+                total_win_usd / Decimal::from(combined_summary.wins)
             } else {
                 Decimal::ZERO
             };
 
             combined_summary.avg_loss_usd = if combined_summary.losses > 0 {
-                losing_trades.iter()
-                    .map(|trade| trade.pnl_usd)
-                    .sum::<Decimal>() / Decimal::from(combined_summary.losses)
+                total_loss_usd / Decimal::from(combined_summary.losses)
             } else {
                 Decimal::ZERO
             };
 
-            combined_summary.expectancy = (combined_summary.win_rate / dec!(100.0)) * combined_summary.avg_win_usd + 
-                (dec!(1.0) - combined_summary.win_rate / dec!(100.0)) * combined_summary.avg_loss_usd;
+            combined_summary.expectancy = combined_summary.net_pnl_usd / 
+                Decimal::from(combined_summary.total_trades);
 
-            let gross_profit: Decimal = winning_trades.iter()
-                .map(|trade| trade.pnl_usd)
+            let gross_profit: Decimal = all_trades.iter()
+                .map(|trade| trade.pnl_usd.max(Decimal::ZERO))
                 .sum();
             
-            let gross_loss: Decimal = losing_trades.iter()
-                .map(|trade| trade.pnl_usd.abs())
+            let gross_loss: Decimal = all_trades.iter()
+                .map(|trade| (-trade.pnl_usd).max(Decimal::ZERO))
                 .sum();
 
             combined_summary.profit_factor = if gross_loss > Decimal::ZERO {
@@ -271,12 +459,44 @@ impl TradeTableExporter {
             trades: all_trades,
             summary: combined_summary,
             rejected_trades: all_rejected,
+            // Tag summaries stay per symbol; each classifies scratches with its own band
+            summary_by_tag: BTreeMap::new(),
         };
 
         self.export(&combined_result).await
     }
 }
 
+/// Trade type label used in exported files
+fn trade_type_label(trade_type: &TradeType) -> &'static str {
+    match trade_type {
+        TradeType::Long => "Long",
+        TradeType::Short => "Short",
+    }
+}
+
+/// Exit reason label used in exported files
+fn exit_reason_label(exit_reason: &ExitReason) -> &'static str {
+    match exit_reason {
+        ExitReason::TakeProfit => "TP",
+        ExitReason::StopLoss => "SL",
+        ExitReason::StrategyExit => "StrategyExit",
+        ExitReason::Liquidation => "Liquidation",
+        ExitReason::Timeout => "Timeout",
+        ExitReason::EndOfData => "EndOfData",
+        ExitReason::Flatten => "Flatten",
+    }
+}
+
+/// TP/SL hit label used in exported files
+fn hit_tp_sl_label(hit_tp_sl: &HitTpSl) -> &'static str {
+    match hit_tp_sl {
+        HitTpSl::TakeProfit => "TP",
+        HitTpSl::StopLoss => "SL",
+        HitTpSl::None => "None",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -287,14 +507,93 @@ mod tests {
         let config = ExportConfig::default();
         assert!(matches!(config.format, ExportFormat::Csv));
         assert_eq!(config.output_path, Some("trade_table.csv".to_string()));
+        assert!(!config.export_manifest_alongside_results);
     }
 
     #[test]
     fn test_trade_table_exporter_creation() {
         let config = ExportConfig::default();
-        let exporter = TradeTableExporter::new(config);
+        let _exporter = TradeTableExporter::new(config);
         // Test passes if creation doesn't panic
     }
+
+    #[test]
+    fn test_min_max_sampling_keeps_the_max_drawdown_point() {
+        // A wave with a slow upward drift, and one dip deeper than the rest at point 3217
+        let mut peak = Decimal::ZERO;
+        let curve: Vec<EquityPoint> = (0..10_000u64).map(|i| {
+            let wave = Decimal::from((i * 37) % 101) - dec!(50);
+            let dip = if i == 3217 { dec!(400) } else { Decimal::ZERO };
+            let equity = dec!(10000) + Decimal::from(i) / dec!(10) + wave - dip;
+            peak = peak.max(equity);
+            EquityPoint {
+                timestamp: 1609459200000 + i * 60_000,
+                equity,
+                realized_equity: equity,
+                unrealized_pnl: Decimal::ZERO,
+                drawdown: (peak - equity) / peak,
+                exposure: Decimal::ZERO,
+                net_exposure: Decimal::ZERO,
+            }
+        }).collect();
+        let deepest = curve.iter().max_by_key(|p| p.drawdown).unwrap();
+        assert_eq!(deepest.timestamp, curve[3217].timestamp);
+
+        let exporter = TradeTableExporter::new(ExportConfig {
+            equity_sampling: Some(Sampling::MinMaxPerBucket(250)),
+            ..ExportConfig::default()
+        });
+        let sampled = exporter.sampled_equity_curve(&curve);
+
+        assert!(sampled.len() <= 3 * curve.len() / 250 + 2);
+        assert!(sampled.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+        assert_eq!(sampled.first().unwrap().timestamp, curve[0].timestamp);
+        assert_eq!(sampled.last().unwrap().timestamp, curve[curve.len() - 1].timestamp);
+        let sampled_deepest = sampled.iter().max_by_key(|p| p.drawdown).unwrap();
+        assert_eq!(sampled_deepest.timestamp, deepest.timestamp);
+        assert_eq!(sampled_deepest.drawdown, deepest.drawdown);
+        assert_eq!(sampled.iter().map(|p| p.equity).max(), curve.iter().map(|p| p.equity).max());
+        assert_eq!(sampled.iter().map(|p| p.equity).min(), curve.iter().map(|p| p.equity).min());
+    }
+
+    #[test]
+    fn test_every_and_daily_sampling() {
+        let point = |timestamp: u64, equity| EquityPoint {
+            timestamp,
+            equity,
+            realized_equity: equity,
+            unrealized_pnl: Decimal::ZERO,
+            drawdown: Decimal::ZERO,
+            exposure: Decimal::ZERO,
+            net_exposure: Decimal::ZERO,
+        };
+        // Six hourly points each day for three days
+        let curve: Vec<EquityPoint> = (0..18u64)
+            .map(|i| point((i / 6) * 86_400_000 + (i % 6) * 3_600_000, Decimal::from(i)))
+            .collect();
+
+        let every: Vec<Decimal> = Sampling::Every(4).apply(&curve).iter().map(|p| p.equity).collect();
+        assert_eq!(every, vec![dec!(0), dec!(4), dec!(8), dec!(12), dec!(16), dec!(17)]);
+
+        let daily: Vec<Decimal> = Sampling::Daily.apply(&curve).iter().map(|p| p.equity).collect();
+        assert_eq!(daily, vec![dec!(5), dec!(11), dec!(17)]);
+
+        assert!(Sampling::Daily.apply(&[]).is_empty());
+        assert_eq!(Sampling::Every(0).apply(&curve).len(), curve.len());
+
+        // Neither end is an extreme, yet both are kept
+        let mut peak = Decimal::ZERO;
+        let swings: Vec<EquityPoint> = [5, 9, 1, 7, 8, 6].iter().enumerate()
+            .map(|(i, equity)| {
+                let equity = Decimal::from(*equity);
+                peak = peak.max(equity);
+                EquityPoint { drawdown: (peak - equity) / peak, ..point(i as u64, equity) }
+            })
+            .collect();
+        let min_max: Vec<Decimal> = Sampling::MinMaxPerBucket(6).apply(&swings).iter().map(|p| p.equity).collect();
+        assert_eq!(min_max, vec![dec!(5), dec!(9), dec!(1), dec!(6)]);
+        assert!(Sampling::MinMaxPerBucket(6).apply(&[]).is_empty());
+    }
 }
 
 
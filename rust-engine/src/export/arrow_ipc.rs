@@ -0,0 +1,207 @@
+//! Arrow IPC stream encoding for trade tables and equity curves
+//! 
+//! Decimals are written as `Decimal128(38, 8)`; values with more than eight decimal
+//! places are rounded to eight. Timestamps stay as epoch milliseconds (`UInt64`) and
+//! the ISO time columns as strings, matching the CSV export.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use anyhow::Result;
+use arrow_array::{ArrayRef, Decimal128Array, RecordBatch, StringArray, UInt64Array};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field, Schema};
+use rust_decimal::Decimal;
+
+use super::{exit_reason_label, hit_tp_sl_label, trade_type_label, ExportMetadata};
+use crate::types::*;
+
+/// Decimal128 precision of every decimal column
+pub const DECIMAL_PRECISION: u8 = 38;
+/// Decimal128 scale of every decimal column
+pub const DECIMAL_SCALE: i8 = 8;
+
+fn decimal_field(name: &str) -> Field {
+    Field::new(name, DataType::Decimal128(DECIMAL_PRECISION, DECIMAL_SCALE), false)
+}
+
+fn string_field(name: &str) -> Field {
+    Field::new(name, DataType::Utf8, false)
+}
+
+/// Schema of the trade table stream, in CSV column order
+pub fn trade_schema() -> Schema {
+    Schema::new(vec![
+        string_field("date"),
+        string_field("type"),
+        decimal_field("entry_price"),
+        string_field("entry_time_utc"),
+        decimal_field("exit_price"),
+        string_field("exit_time_utc"),
+        string_field("exit_reason"),
+        string_field("hit_tp_sl"),
+        decimal_field("size_usd"),
+        decimal_field("qty"),
+        decimal_field("fees_usd"),
+        decimal_field("pnl_usd"),
+        decimal_field("pnl_pct"),
+        string_field("symbol"),
+    ])
+}
+
+/// Schema of the equity curve stream
+pub fn equity_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("timestamp", DataType::UInt64, false),
+        decimal_field("equity"),
+        decimal_field("realized_equity"),
+        decimal_field("unrealized_pnl"),
+        decimal_field("drawdown"),
+        decimal_field("exposure"),
+        decimal_field("net_exposure"),
+    ])
+}
+
+/// Encode trade records as an Arrow IPC stream
+/// 
+/// `metadata`, when given, is stored in the schema's key-value metadata under
+/// `backtest.*` keys so the stream links back to its run manifest.
+pub fn trades_to_ipc(trades: &[TradeRecord], metadata: Option<&ExportMetadata>) -> Result<Vec<u8>> {
+    let schema = with_metadata(trade_schema(), metadata);
+    let strings = |value: fn(&TradeRecord) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from_iter_values(trades.iter().map(value)))
+    };
+
+    let columns: Vec<ArrayRef> = vec![
+        strings(|t| t.date.as_str()),
+        strings(|t| trade_type_label(&t.trade_type)),
+        decimal_column(trades.iter().map(|t| t.entry_price))?,
+        strings(|t| t.entry_time_utc.as_str()),
+        decimal_column(trades.iter().map(|t| t.exit_price))?,
+        strings(|t| t.exit_time_utc.as_str()),
+        strings(|t| exit_reason_label(&t.exit_reason)),
+        strings(|t| hit_tp_sl_label(&t.hit_tp_sl)),
+        decimal_column(trades.iter().map(|t| t.size_usd))?,
+        decimal_column(trades.iter().map(|t| t.qty))?,
+        decimal_column(trades.iter().map(|t| t.fees_usd))?,
+        decimal_column(trades.iter().map(|t| t.pnl_usd))?,
+        decimal_column(trades.iter().map(|t| t.pnl_pct.value()))?,
+        strings(|t| t.symbol.as_str()),
+    ];
+
+    write_stream(schema, columns)
+}
+
+/// Encode an equity curve as an Arrow IPC stream
+pub fn equity_curve_to_ipc(points: &[EquityPoint], metadata: Option<&ExportMetadata>) -> Result<Vec<u8>> {
+    let schema = with_metadata(equity_schema(), metadata);
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from_iter_values(points.iter().map(|p| p.timestamp))),
+        decimal_column(points.iter().map(|p| p.equity))?,
+        decimal_column(points.iter().map(|p| p.realized_equity))?,
+        decimal_column(points.iter().map(|p| p.unrealized_pnl))?,
+        decimal_column(points.iter().map(|p| p.drawdown))?,
+        decimal_column(points.iter().map(|p| p.exposure))?,
+        decimal_column(points.iter().map(|p| p.net_exposure))?,
+    ];
+
+    write_stream(schema, columns)
+}
+
+fn with_metadata(schema: Schema, metadata: Option<&ExportMetadata>) -> Schema {
+    match metadata {
+        Some(metadata) => schema.with_metadata(HashMap::from([
+            ("backtest.run_id".to_string(), metadata.run_id.clone()),
+            ("backtest.engine_version".to_string(), metadata.engine_version.clone()),
+            ("backtest.strategy_hash".to_string(), metadata.strategy_hash.clone()),
+            ("backtest.config_hash".to_string(), metadata.config_hash.clone()),
+        ])),
+        None => schema,
+    }
+}
+
+/// Rescale each value to [`DECIMAL_SCALE`] and collect the mantissas
+fn decimal_column(values: impl Iterator<Item = Decimal>) -> Result<ArrayRef> {
+    let mantissas: Vec<i128> = values
+        .map(|value| {
+            let mut value = value;
+            value.rescale(DECIMAL_SCALE as u32);
+            value.mantissa()
+        })
+        .collect();
+    let array = Decimal128Array::from(mantissas).with_precision_and_scale(DECIMAL_PRECISION, DECIMAL_SCALE)?;
+    Ok(Arc::new(array))
+}
+
+fn write_stream(schema: Schema, columns: Vec<ArrayRef>) -> Result<Vec<u8>> {
+    let schema = Arc::new(schema);
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+
+    let mut writer = StreamWriter::try_new(Vec::new(), &schema)?;
+    writer.write(&batch)?;
+    writer.finish()?;
+    Ok(writer.into_inner()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use arrow_array::Array;
+    use arrow_ipc::reader::StreamReader;
+    use rust_decimal_macros::dec;
+
+    fn trade(pnl_usd: Decimal) -> TradeRecord {
+        TradeRecord {
+            date: "2024-01-01".to_string(),
+            trade_type: TradeType::Long,
+            entry_price: dec!(42000.5),
+            entry_time_utc: "2024-01-01T00:00:00.000Z".to_string(),
+            exit_price: dec!(42100.25),
+            exit_time_utc: "2024-01-01T01:00:00.000Z".to_string(),
+            exit_reason: ExitReason::TakeProfit,
+            hit_tp_sl: HitTpSl::TakeProfit,
+            size_usd: dec!(1000),
+            qty: dec!(0.0238),
+            fees_usd: dec!(0.2),
+            slippage_usd: dec!(0),
+            pnl_usd,
+            pnl_pct: Percent::from_ratio(pnl_usd / dec!(1000)),
+            breakeven_price: dec!(42000.55),
+            fill_outside_bar: false,
+            symbol: "BTCUSDT".to_string(),
+            tags: Vec::new(),
+            rule_tag: None,
+        }
+    }
+
+    #[test]
+    fn test_trade_table_round_trips_through_ipc_stream() {
+        let trades = vec![trade(dec!(2.17)), trade(dec!(-1.123456789))];
+        let metadata = ExportMetadata {
+            run_id: "run-1".to_string(),
+            engine_version: "0.1.0".to_string(),
+            strategy_hash: "strategy".to_string(),
+            config_hash: "config".to_string(),
+        };
+        let bytes = trades_to_ipc(&trades, Some(&metadata)).unwrap();
+
+        let reader = StreamReader::try_new(Cursor::new(bytes), None).unwrap();
+        let schema = reader.schema();
+        assert_eq!(schema.fields(), trade_schema().fields());
+        assert_eq!(schema.metadata().get("backtest.run_id").map(String::as_str), Some("run-1"));
+
+        let batches: Vec<RecordBatch> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), trades.len());
+
+        let pnl = batches[0]
+            .column(schema.index_of("pnl_usd").unwrap())
+            .as_any()
+            .downcast_ref::<Decimal128Array>()
+            .unwrap();
+        assert_eq!(pnl.len(), 2);
+        assert_eq!(Decimal::from_i128_with_scale(pnl.value(0), DECIMAL_SCALE as u32), dec!(2.17));
+        // Rounded to the fixed scale
+        assert_eq!(Decimal::from_i128_with_scale(pnl.value(1), DECIMAL_SCALE as u32), dec!(-1.12345679));
+    }
+}
@@ -0,0 +1,250 @@
+//! Persistent indicator cache
+//! 
+//! Stores computed indicator series on disk so parameter sweeps over the same data can
+//! reuse them across processes. Entries are bincode-encoded (decimals as their raw
+//! 16-byte form, since bincode cannot decode Decimal's string serde), zstd-compressed, and keyed
+//! by indicator name, parameters, and a content hash of the input bars, so any change
+//! to the data or parameters misses the cache instead of returning stale values. The
+//! key also carries the engine version and [`INDICATOR_VERSION`], so values computed by an
+//! older build are never served to a newer one.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use rust_decimal::Decimal;
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::error::{BacktestError, Result};
+use crate::types::{Bar, IndicatorParams, IndicatorValue};
+
+/// File extension of cache entries
+const ENTRY_EXTENSION: &str = "bin.zst";
+
+/// Version of the indicator implementations; bump it whenever any indicator's output changes
+pub const INDICATOR_VERSION: u32 = 2;
+
+/// On-disk cache configuration
+#[derive(Debug, Clone)]
+pub struct DiskCacheConfig {
+    /// Directory holding the cache entries
+    pub dir: PathBuf,
+    /// Total size the entries may occupy; least recently used entries are evicted beyond it
+    pub max_bytes: u64,
+    /// zstd compression level
+    pub compression_level: i32,
+}
+
+impl Default for DiskCacheConfig {
+    fn default() -> Self {
+        Self {
+            dir: std::env::temp_dir().join("backtest_indicator_cache"),
+            max_bytes: 1 << 30, // 1 GiB
+            compression_level: 3,
+        }
+    }
+}
+
+/// Indicator series cache shared across processes through the filesystem
+#[derive(Debug, Clone)]
+pub struct IndicatorDiskCache {
+    config: DiskCacheConfig,
+}
+
+impl IndicatorDiskCache {
+    /// Open (creating if needed) the cache directory
+    pub fn new(config: DiskCacheConfig) -> Result<Self> {
+        fs::create_dir_all(&config.dir).map_err(|source| io_error(&config.dir, source))?;
+        Ok(Self { config })
+    }
+
+    /// Cache key for `indicator_name` with `params` over `bars`, as computed by this build
+    pub fn key(indicator_name: &str, params: &IndicatorParams, bars: &[Bar]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+        hasher.update([0]);
+        hasher.update(INDICATOR_VERSION.to_le_bytes());
+        hasher.update(indicator_name.as_bytes());
+        hasher.update([0]);
+        hasher.update(format!("{:?}", params).as_bytes());
+        hasher.update([0]);
+        hasher.update(bars_content_hash(bars));
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Load a cached series, or `None` on a miss
+    /// 
+    /// Unreadable entries are treated as misses and removed. A hit refreshes the entry's
+    /// modification time, which is what eviction orders by.
+    pub fn load(&self, key: &str) -> Option<Vec<IndicatorValue>> {
+        let path = self.entry_path(key);
+        let compressed = fs::read(&path).ok()?;
+
+        match decode(&compressed) {
+            Ok(values) => {
+                if let Err(e) = fs::File::options().write(true).open(&path).and_then(|f| f.set_modified(SystemTime::now())) {
+                    debug!("Could not refresh cache entry {}: {}", path.display(), e);
+                }
+                Some(values)
+            }
+            Err(e) => {
+                warn!("Discarding unreadable cache entry {}: {}", path.display(), e);
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// Store a series under `key`, then evict down to `max_bytes`
+    pub fn store(&self, key: &str, values: &[IndicatorValue]) -> Result<()> {
+        let raw: Vec<(u64, [u8; 16], bool)> = values.iter().map(|v| (v.timestamp, v.value.serialize(), v.warm)).collect();
+        let encoded = bincode::serialize(&raw)
+            .map_err(|e| BacktestError::InvalidData(format!("Failed to encode indicator cache entry: {}", e)))?;
+        let compressed = zstd::encode_all(encoded.as_slice(), self.config.compression_level)
+            .map_err(|source| io_error(&self.config.dir, source))?;
+
+        // Write then rename so a concurrent reader never sees a partial entry
+        let path = self.entry_path(key);
+        let tmp_path = self.config.dir.join(format!("{}.{}.tmp", key, std::process::id()));
+        fs::write(&tmp_path, compressed).map_err(|source| io_error(&tmp_path, source))?;
+        fs::rename(&tmp_path, &path).map_err(|source| io_error(&path, source))?;
+
+        self.evict()
+    }
+
+    /// Remove every cache entry
+    pub fn clear(&self) -> Result<()> {
+        for (path, _, _) in self.entries()? {
+            fs::remove_file(&path).map_err(|source| io_error(&path, source))?;
+        }
+        Ok(())
+    }
+
+    /// Total size of the cache entries in bytes
+    pub fn size_bytes(&self) -> Result<u64> {
+        Ok(self.entries()?.iter().map(|(_, size, _)| size).sum())
+    }
+
+    /// Drop least recently used entries until the cache fits in `max_bytes`
+    fn evict(&self) -> Result<()> {
+        let mut entries = self.entries()?;
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        if total <= self.config.max_bytes {
+            return Ok(());
+        }
+
+        entries.sort_by(|a, b| a.2.cmp(&b.2).then_with(|| a.0.cmp(&b.0)));
+        for (path, size, _) in entries {
+            if total <= self.config.max_bytes {
+                break;
+            }
+            debug!("Evicting indicator cache entry {}", path.display());
+            fs::remove_file(&path).map_err(|source| io_error(&path, source))?;
+            total -= size;
+        }
+
+        Ok(())
+    }
+
+    /// (path, size, modified) of every entry in the cache directory
+    fn entries(&self) -> Result<Vec<(PathBuf, u64, SystemTime)>> {
+        let dir = fs::read_dir(&self.config.dir).map_err(|source| io_error(&self.config.dir, source))?;
+
+        let mut entries = Vec::new();
+        for entry in dir {
+            let entry = entry.map_err(|source| io_error(&self.config.dir, source))?;
+            let path = entry.path();
+            if !path.to_string_lossy().ends_with(ENTRY_EXTENSION) {
+                continue;
+            }
+            let metadata = entry.metadata().map_err(|source| io_error(&path, source))?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push((path, metadata.len(), modified));
+        }
+
+        Ok(entries)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.config.dir.join(format!("{}.{}", key, ENTRY_EXTENSION))
+    }
+}
+
+/// SHA-256 over every bar field, in order
+pub fn bars_content_hash(bars: &[Bar]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for bar in bars {
+        hasher.update(bar.timestamp.to_le_bytes());
+        for value in [bar.open, bar.high, bar.low, bar.close, bar.volume] {
+            hasher.update(value.serialize());
+        }
+        hasher.update(bar.trade_count.to_le_bytes());
+    }
+    hasher.finalize().into()
+}
+
+fn decode(compressed: &[u8]) -> std::result::Result<Vec<IndicatorValue>, String> {
+    let encoded = zstd::decode_all(compressed).map_err(|e| e.to_string())?;
+    let raw: Vec<(u64, [u8; 16], bool)> = bincode::deserialize(&encoded).map_err(|e| e.to_string())?;
+    Ok(raw.into_iter()
+        .map(|(timestamp, value, warm)| IndicatorValue { timestamp, value: Decimal::deserialize(value), warm })
+        .collect())
+}
+
+fn io_error(path: &Path, source: std::io::Error) -> BacktestError {
+    BacktestError::Io {
+        path: path.display().to_string(),
+        source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("backtest_indicator_cache_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn series(len: u64) -> Vec<IndicatorValue> {
+        (0..len).map(|i| IndicatorValue { timestamp: i * 60_000, value: Decimal::from(i) * dec!(1.5), warm: true }).collect()
+    }
+
+    #[test]
+    fn test_eviction_keeps_cache_under_budget() {
+        let dir = cache_dir("evict");
+        let cache = IndicatorDiskCache::new(DiskCacheConfig { dir: dir.clone(), ..DiskCacheConfig::default() }).unwrap();
+        cache.store("first", &series(500)).unwrap();
+        let entry_size = cache.size_bytes().unwrap();
+
+        // Room for one entry only: storing a second evicts the older one
+        let cache = IndicatorDiskCache::new(DiskCacheConfig {
+            dir: dir.clone(),
+            max_bytes: entry_size + entry_size / 2,
+            ..DiskCacheConfig::default()
+        }).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        cache.store("second", &series(500)).unwrap();
+        assert!(cache.load("first").is_none());
+        assert_eq!(cache.load("second").unwrap().len(), 500);
+        assert!(cache.size_bytes().unwrap() <= entry_size + entry_size / 2);
+
+        cache.clear().unwrap();
+        assert_eq!(cache.size_bytes().unwrap(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_entry_is_a_miss() {
+        let dir = cache_dir("corrupt");
+        let cache = IndicatorDiskCache::new(DiskCacheConfig { dir: dir.clone(), ..DiskCacheConfig::default() }).unwrap();
+        fs::write(cache.entry_path("broken"), b"not zstd").unwrap();
+
+        assert!(cache.load("broken").is_none());
+        assert!(!cache.entry_path("broken").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -3,20 +3,34 @@
 //! High-performance indicator calculations using SIMD instructions for vectorized operations.
 //! All calculations use deterministic floating-point arithmetic with Decimal128 precision.
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
 // use portable_simd::*; // Temporarily disabled
-use anyhow::Result;
 use tracing::{debug, warn};
 
-use crate::types::{Bar, IndicatorValue, IndicatorParams};
+use crate::error::{BacktestError, Result};
+use crate::indicator_cache::IndicatorDiskCache;
+use crate::precision::{decimal_sqrt, round_to_precision, RoundingMode};
+use crate::streaming::{IndicatorStream, StreamingIndicator};
+use crate::types::{Bar, BarColumns, ChannelValue, DivergenceEvent, DivergenceKind, EmaSeed, IndicatorValue, IndicatorParams, Precision, SessionWarmup, SuperTrendValue, SwingPoint, VwapAnchor, ZeroVolumePolicy};
 
 /// Registry for managing indicator calculations
 pub struct IndicatorRegistry {
     enable_simd: bool,
     cache: HashMap<String, Vec<IndicatorValue>>,
+    disk_cache: Option<IndicatorDiskCache>,
+    stats: CacheStats,
+}
+
+/// Where [`IndicatorRegistry::calculate_with_params`] found each series
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub memory_hits: u64,
+    pub disk_hits: u64,
+    pub computed: u64,
 }
 
 impl IndicatorRegistry {
@@ -24,20 +38,159 @@ impl IndicatorRegistry {
         Ok(Self {
             enable_simd,
             cache: HashMap::new(),
+            disk_cache: None,
+            stats: CacheStats::default(),
         })
     }
     
+    /// Back the in-memory cache with a persistent one, consulted on memory misses
+    pub fn with_disk_cache(mut self, disk_cache: IndicatorDiskCache) -> Self {
+        self.disk_cache = Some(disk_cache);
+        self
+    }
+    
+    /// Drop every in-memory cached series so the next request recomputes or hits disk
+    pub fn clear_memory_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Remove every entry from the persistent cache, if one is attached
+    pub fn clear_disk_cache(&self) -> Result<()> {
+        match &self.disk_cache {
+            Some(disk_cache) => disk_cache.clear(),
+            None => Ok(()),
+        }
+    }
+    
+    /// Cache hit and computation counts so far
+    pub fn cache_stats(&self) -> CacheStats {
+        self.stats
+    }
+    
+    /// Whether `indicator_name` can be calculated by this registry
+    pub fn is_supported(&self, indicator_name: &str) -> bool {
+        default_params(indicator_name).is_some()
+    }
+    
+    /// Parameters [`IndicatorRegistry::calculate`] uses for `indicator_name`
+    pub fn default_params(&self, indicator_name: &str) -> Option<IndicatorParams> {
+        default_params(indicator_name)
+    }
+    
+    /// Fail fast when `market_data` cannot produce a value for every indicator
+    /// 
+    /// The requirement is the longest warmup among `indicator_names` at their default
+    /// parameters, and at least one bar even with no indicators, so an empty or too-short
+    /// series is an [`BacktestError::InsufficientData`] rather than a silent zero-trade run.
+    pub fn check_sufficient_data(
+        &self,
+        market_data: &crate::types::MarketData,
+        indicator_names: &[String],
+    ) -> Result<()> {
+        self.check_sufficient_data_with_params(market_data, indicator_names, &HashMap::new())
+    }
+    
+    /// [`IndicatorRegistry::check_sufficient_data`] with `params` in place of the
+    /// defaults for the indicators it names
+    pub fn check_sufficient_data_with_params(
+        &self,
+        market_data: &crate::types::MarketData,
+        indicator_names: &[String],
+        params: &HashMap<String, IndicatorParams>,
+    ) -> Result<()> {
+        let (required, limiting) = match longest_warmup(indicator_names, params)? {
+            Some((bars, name)) if bars > 1 => (bars, name),
+            _ => (1, "bars".to_string()),
+        };
+        
+        let available = market_data.bars.len();
+        if available < required {
+            return Err(BacktestError::InsufficientData {
+                symbol: market_data.symbol.clone(),
+                indicator: limiting,
+                required,
+                available,
+            });
+        }
+        
+        Ok(())
+    }
+    
+    /// Bars before every indicator in `indicator_names` has a value
+    /// 
+    /// The longest warmup, with `params` in place of the defaults where given; 0 for
+    /// no indicators. Used as the default burn-in before a strategy may enter.
+    pub fn warmup_bars(
+        &self,
+        indicator_names: &[String],
+        params: &HashMap<String, IndicatorParams>,
+    ) -> Result<usize> {
+        Ok(longest_warmup(indicator_names, params)?.map_or(0, |(bars, _)| bars))
+    }
+    
+    /// Streaming state for `indicator_names`, with `params` in place of the defaults
+    /// where given
+    /// 
+    /// `None` if any of them has no streaming form or would be calculated on the f64
+    /// SIMD path, since the stream must reproduce [`IndicatorRegistry::calculate_with_params`]
+    /// exactly.
+    pub fn stream(
+        &self,
+        indicator_names: &[String],
+        params: &HashMap<String, IndicatorParams>,
+    ) -> Option<IndicatorStream> {
+        let mut indicators = Vec::with_capacity(indicator_names.len());
+        for name in indicator_names {
+            let params = match params.get(name) {
+                Some(params) => params.clone(),
+                None => default_params(name)?,
+            };
+            if name == "sma" && self.enable_simd && params.period >= 8 {
+                return None;
+            }
+            indicators.push((name.clone(), StreamingIndicator::new(name, &params)?));
+        }
+        
+        Some(IndicatorStream::new(indicators))
+    }
+    
     /// Calculate indicator values for given market data
     pub fn calculate(
         &mut self,
         indicator_name: &str,
         market_data: &crate::types::MarketData,
     ) -> Result<Vec<IndicatorValue>> {
-        debug!("Calculating indicator: {}", indicator_name);
+        let params = default_params(indicator_name)
+            .ok_or_else(|| BacktestError::UnknownIndicator(indicator_name.to_string()))?;
+        
+        self.calculate_with_params(indicator_name, market_data, &params)
+    }
+    
+    /// Calculate indicator values with explicit parameters
+    /// 
+    /// `params.compute_precision` selects Decimal (default) or the f64 fast path for
+    /// `ema`, `sma`, `rsi`, `atr` and `vwap`. `hh`/`ll` only compare values and are
+    /// always exact. `supertrend` (line) and `supertrend_dir` (+1/-1) always use Decimal,
+    /// with the ATR multiplier taken from `params.threshold`.
+    /// 
+    /// `macd` is the MACD line, EMA(fast) - EMA(`params.period`), with the fast period
+    /// taken from `params.threshold` (default 12); it always uses Decimal.
+    /// 
+    /// Channels are exposed one band per name: `keltner`/`donchian` return the midline and
+    /// the `_upper`/`_lower` suffixes return the bands. Registry Donchian channels exclude
+    /// the current bar so `close > donchian_upper` is a usable breakout test.
+    pub fn calculate_with_params(
+        &mut self,
+        indicator_name: &str,
+        market_data: &crate::types::MarketData,
+        params: &IndicatorParams,
+    ) -> Result<Vec<IndicatorValue>> {
+        debug!("Calculating indicator: {} ({:?})", indicator_name, params.compute_precision);
         
         // Check cache first
-        let cache_key = format!("{}_{}", indicator_name, market_data.symbol);
+        let cache_key = format!("{}_{}_{:?}", indicator_name, market_data.symbol, params);
         if let Some(cached) = self.cache.get(&cache_key) {
+            self.stats.memory_hits += 1;
             return Ok(cached.clone());
         }
         
@@ -46,18 +199,103 @@ impl IndicatorRegistry {
             return Ok(Vec::new());
         }
         
-        let values = match indicator_name {
-            "ema" => self.calculate_ema(bars, &IndicatorParams { period: 20, alpha: None, threshold: None })?,
-            "sma" => self.calculate_sma(bars, &IndicatorParams { period: 20, alpha: None, threshold: None })?,
-            "rsi" => self.calculate_rsi(bars, &IndicatorParams { period: 14, alpha: None, threshold: None })?,
-            "atr" => self.calculate_atr(bars, &IndicatorParams { period: 14, alpha: None, threshold: None })?,
-            "vwap" => self.calculate_vwap(bars, &IndicatorParams { period: 0, alpha: None, threshold: None })?,
-            "hh" => self.calculate_highest_high(bars, &IndicatorParams { period: 20, alpha: None, threshold: None })?,
-            "ll" => self.calculate_lowest_low(bars, &IndicatorParams { period: 20, alpha: None, threshold: None })?,
-            _ => return Err(anyhow::anyhow!("Unknown indicator: {}", indicator_name)),
+        // Then the persistent cache, keyed by content rather than symbol
+        let disk_key = self.disk_cache.as_ref().map(|_| IndicatorDiskCache::key(indicator_name, params, bars));
+        if let (Some(disk_cache), Some(disk_key)) = (&self.disk_cache, &disk_key) {
+            if let Some(values) = disk_cache.load(disk_key) {
+                self.stats.disk_hits += 1;
+                self.cache.insert(cache_key, values.clone());
+                return Ok(values);
+            }
+        }
+        
+        let values = match (indicator_name, params.compute_precision) {
+            ("ema", Precision::Decimal) => self.calculate_ema(bars, params)?,
+            ("ema", Precision::Float64) => self.calculate_ema_f64(bars, params)?,
+            ("sma", Precision::Decimal) => self.calculate_sma(bars, params)?,
+            ("sma", Precision::Float64) => self.calculate_sma_f64(bars, params)?,
+            ("rsi", Precision::Decimal) => self.calculate_rsi(bars, params)?,
+            ("rsi", Precision::Float64) => self.calculate_rsi_f64(bars, params)?,
+            ("atr", Precision::Decimal) => self.calculate_atr(bars, params)?,
+            ("atr", Precision::Float64) => self.calculate_atr_f64(bars, params)?,
+            ("vwap", Precision::Decimal) => self.calculate_vwap(bars, params)?,
+            ("vwap", Precision::Float64) => self.calculate_vwap_f64(bars, params)?,
+            ("supertrend", _) => self.calculate_supertrend(bars, params.period, multiplier(params, indicator_name))?
+                .into_iter()
+                .map(|st| IndicatorValue { timestamp: st.timestamp, value: st.value, warm: true })
+                .collect(),
+            ("supertrend_dir", _) => self.calculate_supertrend(bars, params.period, multiplier(params, indicator_name))?
+                .into_iter()
+                .map(|st| IndicatorValue { timestamp: st.timestamp, value: Decimal::from(st.direction), warm: true })
+                .collect(),
+            ("keltner" | "keltner_upper" | "keltner_lower", _) => {
+                let channel = self.calculate_keltner(bars, params.period, multiplier(params, indicator_name))?;
+                channel_band(indicator_name, channel)
+            }
+            ("donchian" | "donchian_upper" | "donchian_lower", _) => {
+                let channel = self.calculate_donchian(bars, params.period, true)?;
+                channel_band(indicator_name, channel)
+            }
+            ("macd", _) => self.calculate_macd(bars, params)?,
+            ("cci", _) => self.calculate_cci(bars, params)?,
+            ("mfi", _) => self.calculate_mfi(bars, params)?,
+            ("stddev", _) => self.calculate_rolling_std(bars, params)?,
+            ("variance", _) => self.calculate_rolling_variance(bars, params)?,
+            ("hh", _) => self.calculate_highest_high(bars, params)?,
+            ("ll", _) => self.calculate_lowest_low(bars, params)?,
+            _ => return Err(BacktestError::UnknownIndicator(indicator_name.to_string())),
         };
+        let values = round_prices(indicator_name, params, values);
         
         // Cache the result
+        self.stats.computed += 1;
+        if let (Some(disk_cache), Some(disk_key)) = (&self.disk_cache, &disk_key) {
+            if let Err(e) = disk_cache.store(disk_key, &values) {
+                warn!("Failed to persist {} to the indicator cache: {}", indicator_name, e);
+            }
+        }
+        self.cache.insert(cache_key, values.clone());
+        
+        Ok(values)
+    }
+    
+    /// Calculate indicator values from column-oriented bars
+    /// 
+    /// Supports the close-only indicators (`ema`, `sma`) and produces the same values
+    /// as [`IndicatorRegistry::calculate`] on the equivalent `Vec<Bar>`.
+    pub fn calculate_columns(
+        &mut self,
+        indicator_name: &str,
+        symbol: &str,
+        columns: &BarColumns,
+    ) -> Result<Vec<IndicatorValue>> {
+        debug!("Calculating columnar indicator: {}", indicator_name);
+        
+        let cache_key = format!("{}_{}", indicator_name, symbol);
+        if let Some(cached) = self.cache.get(&cache_key) {
+            return Ok(cached.clone());
+        }
+        
+        if columns.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let values = match indicator_name {
+            "ema" | "sma" => {
+                let params = default_params(indicator_name)
+                    .ok_or_else(|| BacktestError::UnknownIndicator(indicator_name.to_string()))?;
+                if indicator_name == "ema" {
+                    self.calculate_ema_columns(columns, &params)?
+                } else {
+                    self.calculate_sma_columns(columns, &params)?
+                }
+            }
+            _ => return Err(BacktestError::UnsupportedInput {
+                indicator: indicator_name.to_string(),
+                input: "columnar",
+            }),
+        };
+        
         self.cache.insert(cache_key, values.clone());
         
         Ok(values)
@@ -68,34 +306,124 @@ impl IndicatorRegistry {
         let period = params.period;
         let alpha = params.alpha.unwrap_or(dec!(2.0) / Decimal::from(period + 1));
         
-        if bars.len() < period {
+        let warmup = ema_warmup(params);
+        if warmup == 0 || bars.len() < warmup {
             return Ok(Vec::new());
         }
         
         let mut values = Vec::with_capacity(bars.len());
         
-        // Initialize with SMA for the first value
-        let mut ema = bars[0..period].iter()
+        // Seed with the SMA of the warmup window (a single close for FirstValueSeed)
+        let mut ema = bars[0..warmup].iter()
             .map(|b| b.close)
-            .sum::<Decimal>() / Decimal::from(period);
+            .sum::<Decimal>() / Decimal::from(warmup);
         
         values.push(IndicatorValue {
-            timestamp: bars[period - 1].timestamp,
+            timestamp: bars[warmup - 1].timestamp,
             value: ema,
+            warm: true,
         });
         
         // Calculate EMA for remaining bars
-        for bar in bars.iter().skip(period) {
+        for bar in bars.iter().skip(warmup) {
             ema = alpha * bar.close + (dec!(1.0) - alpha) * ema;
             values.push(IndicatorValue {
                 timestamp: bar.timestamp,
                 value: ema,
+                warm: true,
+            });
+        }
+        
+        Ok(values)
+    }
+    
+    /// Calculate EMA over the close column
+    fn calculate_ema_columns(&self, columns: &BarColumns, params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
+        let period = params.period;
+        let alpha = params.alpha.unwrap_or(dec!(2.0) / Decimal::from(period + 1));
+        let closes = &columns.close;
+        let warmup = ema_warmup(params);
+        
+        if warmup == 0 || closes.len() < warmup {
+            return Ok(Vec::new());
+        }
+        
+        let mut values = Vec::with_capacity(closes.len());
+        
+        let mut ema = closes[0..warmup].iter().sum::<Decimal>() / Decimal::from(warmup);
+        
+        values.push(IndicatorValue {
+            timestamp: columns.timestamp[warmup - 1],
+            value: ema,
+            warm: true,
+        });
+        
+        for (close, timestamp) in closes.iter().zip(&columns.timestamp).skip(warmup) {
+            ema = alpha * close + (dec!(1.0) - alpha) * ema;
+            values.push(IndicatorValue {
+                timestamp: *timestamp,
+                value: ema,
+                warm: true,
             });
         }
         
         Ok(values)
     }
     
+    /// Calculate SMA over the close column
+    fn calculate_sma_columns(&self, columns: &BarColumns, params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
+        let period = params.period;
+        let closes = &columns.close;
+        
+        if closes.len() < period {
+            return Ok(Vec::new());
+        }
+        
+        let mut values = Vec::with_capacity(closes.len() - period + 1);
+        
+        if self.enable_simd && period >= 8 {
+            let closes_f64: Vec<f64> = closes.iter()
+                .map(|c| c.to_f64().unwrap_or(0.0))
+                .collect();
+            self.sma_f64(&closes_f64, |i| columns.timestamp[i], period, &mut values);
+        } else {
+            for (i, window) in closes.windows(period).enumerate() {
+                let sma = window.iter().sum::<Decimal>() / Decimal::from(period);
+                
+                values.push(IndicatorValue {
+                    timestamp: columns.timestamp[i + period - 1],
+                    value: sma,
+                    warm: true,
+                });
+            }
+        }
+        
+        Ok(values)
+    }
+    
+    /// Calculate the MACD line, EMA(fast) - EMA(slow)
+    /// 
+    /// Values start once both EMAs exist and are stamped like the bars they end on.
+    fn calculate_macd(&self, bars: &[Bar], params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
+        let slow = self.calculate_ema(bars, &IndicatorParams { alpha: None, ..params.clone() })?;
+        let fast = self.calculate_ema(bars, &IndicatorParams {
+            period: macd_fast_period(params),
+            alpha: None,
+            ..params.clone()
+        })?;
+        
+        // Both series end on the last bar, so align them from the end
+        let len = slow.len().min(fast.len());
+        Ok(slow[slow.len() - len..].iter()
+            .zip(&fast[fast.len() - len..])
+            .map(|(slow, fast)| IndicatorValue {
+                timestamp: slow.timestamp,
+                value: fast.value - slow.value,
+                warm: true,
+            })
+            .collect())
+    }
+    
     /// Calculate Simple Moving Average (SMA) with SIMD optimization
     fn calculate_sma(&self, bars: &[Bar], params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
         let period = params.period;
@@ -120,6 +448,7 @@ impl IndicatorRegistry {
                 values.push(IndicatorValue {
                     timestamp: bars[i + period - 1].timestamp,
                     value: sma,
+                    warm: true,
                 });
             }
         }
@@ -134,6 +463,13 @@ impl IndicatorRegistry {
             .map(|b| b.close.to_f64().unwrap_or(0.0))
             .collect();
         
+        self.sma_f64(&closes, |i| bars[i].timestamp, period, values);
+        
+        Ok(())
+    }
+    
+    /// Rolling-window SMA over f64 closes, shared by the bar and columnar SIMD paths
+    fn sma_f64<F: Fn(usize) -> u64>(&self, closes: &[f64], timestamp_at: F, period: usize, values: &mut Vec<IndicatorValue>) {
         for i in 0..=closes.len() - period {
             let slice = &closes[i..i + period];
             
@@ -147,12 +483,11 @@ impl IndicatorRegistry {
             let sma = sum / period as f64;
             
             values.push(IndicatorValue {
-                timestamp: bars[i + period - 1].timestamp,
+                timestamp: timestamp_at(i + period - 1),
                 value: Decimal::from_f64(sma).unwrap_or(dec!(0.0)),
+                warm: true,
             });
         }
-        
-        Ok(())
     }
     
     /// SIMD sum for f64 arrays
@@ -227,12 +562,154 @@ impl IndicatorRegistry {
             values.push(IndicatorValue {
                 timestamp: bars[i + 1].timestamp,
                 value: rsi,
+                warm: true,
+            });
+        }
+        
+        Ok(values)
+    }
+    
+    /// Calculate Commodity Channel Index (CCI)
+    /// 
+    /// `(tp - SMA(tp)) / (0.015 * mean deviation)` over typical price `(h + l + c) / 3`.
+    /// A window with zero mean deviation has every typical price equal to its mean, so
+    /// the CCI is defined as 0 rather than dividing by zero.
+    fn calculate_cci(&self, bars: &[Bar], params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
+        let period = params.period;
+        
+        if period == 0 || bars.len() < period {
+            return Ok(Vec::new());
+        }
+        
+        // 3x typical price: exact sums, and the ratio is unaffected by the scale
+        let typical: Vec<Decimal> = bars.iter()
+            .map(|b| b.high + b.low + b.close)
+            .collect();
+        let mut values = Vec::with_capacity(bars.len() - period + 1);
+        
+        for i in period - 1..bars.len() {
+            let window = &typical[i + 1 - period..=i];
+            let mean = window.iter().sum::<Decimal>() / Decimal::from(period);
+            let mean_deviation = window.iter()
+                .map(|tp| (*tp - mean).abs())
+                .sum::<Decimal>() / Decimal::from(period);
+            
+            let cci = if mean_deviation == dec!(0.0) {
+                dec!(0.0)
+            } else {
+                (typical[i] - mean) / (dec!(0.015) * mean_deviation)
+            };
+            
+            values.push(IndicatorValue {
+                timestamp: bars[i].timestamp,
+                value: cci,
+                warm: true,
+            });
+        }
+        
+        Ok(values)
+    }
+    
+    /// Calculate Money Flow Index (MFI)
+    /// 
+    /// Volume-weighted RSI: typical price * volume is positive flow when typical price
+    /// rises and negative flow when it falls, summed over `period` bars. With no negative
+    /// flow the index clamps to 100; with no flow at all it is a neutral 50. Windows
+    /// without any volume follow `params.zero_volume`.
+    fn calculate_mfi(&self, bars: &[Bar], params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
+        let period = params.period;
+        
+        if period == 0 || bars.len() < period + 1 {
+            return Ok(Vec::new());
+        }
+        
+        // 3x typical price: exact comparisons, and the flow ratio is unaffected by the scale
+        let typical: Vec<Decimal> = bars.iter()
+            .map(|b| b.high + b.low + b.close)
+            .collect();
+        
+        // Signed flows for bars 1..n; unchanged typical price contributes to neither side
+        let mut positive = Vec::with_capacity(bars.len() - 1);
+        let mut negative = Vec::with_capacity(bars.len() - 1);
+        for i in 1..bars.len() {
+            let flow = typical[i] * bars[i].volume;
+            match typical[i].cmp(&typical[i - 1]) {
+                Ordering::Greater => {
+                    positive.push(flow);
+                    negative.push(dec!(0.0));
+                }
+                Ordering::Less => {
+                    positive.push(dec!(0.0));
+                    negative.push(flow);
+                }
+                Ordering::Equal => {
+                    positive.push(dec!(0.0));
+                    negative.push(dec!(0.0));
+                }
+            }
+        }
+        
+        let mut values = Vec::with_capacity(bars.len() - period);
+        let mut last = None;
+        
+        for i in period - 1..positive.len() {
+            // Flows i+1-period..=i belong to bars i+2-period..=i+1
+            if bars[i + 2 - period..=i + 1].iter().all(|b| b.volume.is_zero()) {
+                if let Some(value) = zero_volume_value(params.zero_volume, last) {
+                    values.push(IndicatorValue { timestamp: bars[i + 1].timestamp, value, warm: true });
+                }
+                continue;
+            }
+            
+            let positive_flow = positive[i + 1 - period..=i].iter().sum::<Decimal>();
+            let negative_flow = negative[i + 1 - period..=i].iter().sum::<Decimal>();
+            
+            let mfi = if negative_flow == dec!(0.0) {
+                if positive_flow == dec!(0.0) { dec!(50.0) } else { dec!(100.0) }
+            } else {
+                dec!(100.0) - dec!(100.0) / (dec!(1.0) + positive_flow / negative_flow)
+            };
+            
+            last = Some(mfi);
+            values.push(IndicatorValue {
+                timestamp: bars[i + 1].timestamp,
+                value: mfi,
+                warm: true,
             });
         }
         
         Ok(values)
     }
     
+    /// Calculate the rolling population standard deviation of closes
+    /// 
+    /// Square root of [`IndicatorRegistry::calculate_rolling_variance`]; values start at
+    /// bar `period - 1`.
+    pub fn calculate_rolling_std(&self, bars: &[Bar], params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
+        let period = params.period;
+        let closes: Vec<Decimal> = bars.iter().map(|b| b.close).collect();
+        
+        let values = rolling_std(&closes, period).into_iter()
+            .zip(bars.iter().skip(period.saturating_sub(1)))
+            .map(|(value, bar)| IndicatorValue { timestamp: bar.timestamp, value, warm: true })
+            .collect();
+        
+        Ok(values)
+    }
+    
+    /// Calculate the rolling population variance of closes (see [`rolling_variance`])
+    pub fn calculate_rolling_variance(&self, bars: &[Bar], params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
+        let period = params.period;
+        let closes: Vec<Decimal> = bars.iter().map(|b| b.close).collect();
+        
+        let values = rolling_variance(&closes, period).into_iter()
+            .zip(bars.iter().skip(period.saturating_sub(1)))
+            .map(|(value, bar)| IndicatorValue { timestamp: bar.timestamp, value, warm: true })
+            .collect();
+        
+        Ok(values)
+    }
+    
     /// Calculate Average True Range (ATR)
     fn calculate_atr(&self, bars: &[Bar], params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
         let period = params.period;
@@ -261,6 +738,7 @@ impl IndicatorRegistry {
         values.push(IndicatorValue {
             timestamp: bars[period].timestamp,
             value: atr,
+            warm: true,
         });
         
         for i in period..true_ranges.len() {
@@ -268,32 +746,212 @@ impl IndicatorRegistry {
             values.push(IndicatorValue {
                 timestamp: bars[i + 1].timestamp,
                 value: atr,
+                warm: true,
+            });
+        }
+        
+        Ok(values)
+    }
+    
+    /// Calculate SuperTrend on top of Wilder's ATR
+    /// 
+    /// Basic bands are `hl2 ± multiplier * ATR`. The final upper band only moves down
+    /// (and the final lower band only up) until the previous close crosses it, at which
+    /// point it resets to the basic band. The trend flips to down when the close falls
+    /// below the final lower band and back up when it rises above the final upper band.
+    /// The first value is an uptrend if the close is at or above `hl2`.
+    /// 
+    /// Values start where ATR does, at bar `period`.
+    pub fn calculate_supertrend(
+        &self,
+        bars: &[Bar],
+        period: usize,
+        multiplier: Decimal,
+    ) -> Result<Vec<SuperTrendValue>> {
+        let atr_params = IndicatorParams {
+            period,
+            alpha: None,
+            threshold: None,
+            compute_precision: Precision::Decimal,
+            ema_seed: EmaSeed::SmaSeed,
+            vwap_anchor: VwapAnchor::Cumulative,
+            session_warmup: SessionWarmup::Partial,
+            zero_volume: ZeroVolumePolicy::CarryForward,
+            price_precision: None,
+        };
+        let atr = self.calculate_atr(bars, &atr_params)?;
+        
+        let mut values = Vec::with_capacity(atr.len());
+        let mut final_upper = Decimal::ZERO;
+        let mut final_lower = Decimal::ZERO;
+        let mut direction = 0i8;
+        
+        for (k, atr_value) in atr.iter().enumerate() {
+            let i = period + k;
+            let bar = &bars[i];
+            let hl2 = (bar.high + bar.low) / dec!(2.0);
+            let basic_upper = hl2 + multiplier * atr_value.value;
+            let basic_lower = hl2 - multiplier * atr_value.value;
+            
+            if k == 0 {
+                final_upper = basic_upper;
+                final_lower = basic_lower;
+                direction = if bar.close >= hl2 { 1 } else { -1 };
+            } else {
+                let prev_close = bars[i - 1].close;
+                if basic_upper < final_upper || prev_close > final_upper {
+                    final_upper = basic_upper;
+                }
+                if basic_lower > final_lower || prev_close < final_lower {
+                    final_lower = basic_lower;
+                }
+                
+                direction = match direction {
+                    1 if bar.close < final_lower => -1,
+                    -1 if bar.close > final_upper => 1,
+                    unchanged => unchanged,
+                };
+            }
+            
+            values.push(SuperTrendValue {
+                timestamp: bar.timestamp,
+                value: if direction > 0 { final_lower } else { final_upper },
+                direction,
             });
         }
         
         Ok(values)
     }
     
+    /// Calculate Keltner channels: EMA midline ± `multiplier` * ATR
+    /// 
+    /// EMA and ATR share `period`; values start where both are defined.
+    pub fn calculate_keltner(
+        &self,
+        bars: &[Bar],
+        period: usize,
+        multiplier: Decimal,
+    ) -> Result<Vec<ChannelValue>> {
+        let params = IndicatorParams {
+            period,
+            alpha: None,
+            threshold: None,
+            compute_precision: Precision::Decimal,
+            ema_seed: EmaSeed::SmaSeed,
+            vwap_anchor: VwapAnchor::Cumulative,
+            session_warmup: SessionWarmup::Partial,
+            zero_volume: ZeroVolumePolicy::CarryForward,
+            price_precision: None,
+        };
+        let ema = self.calculate_ema(bars, &params)?;
+        let atr = self.calculate_atr(bars, &params)?;
+        
+        // Both series end on the last bar; drop the EMA's extra leading values
+        let skip = ema.len().saturating_sub(atr.len());
+        let values = ema[skip..].iter().zip(&atr)
+            .map(|(mid, atr)| {
+                let offset = multiplier * atr.value;
+                ChannelValue {
+                    timestamp: mid.timestamp,
+                    upper: mid.value + offset,
+                    middle: mid.value,
+                    lower: mid.value - offset,
+                }
+            })
+            .collect();
+        
+        Ok(values)
+    }
+    
+    /// Calculate Donchian channels from the highest high and lowest low over `period` bars
+    /// 
+    /// With `exclude_current`, the channel at bar i covers bars `i - period..i` so a
+    /// breakout above the upper band can actually occur on bar i; values then start at
+    /// bar `period`. Otherwise bar i is included and values start at bar `period - 1`.
+    pub fn calculate_donchian(
+        &self,
+        bars: &[Bar],
+        period: usize,
+        exclude_current: bool,
+    ) -> Result<Vec<ChannelValue>> {
+        let params = IndicatorParams {
+            period,
+            alpha: None,
+            threshold: None,
+            compute_precision: Precision::Decimal,
+            ema_seed: EmaSeed::SmaSeed,
+            vwap_anchor: VwapAnchor::Cumulative,
+            session_warmup: SessionWarmup::Partial,
+            zero_volume: ZeroVolumePolicy::CarryForward,
+            price_precision: None,
+        };
+        let highs = self.calculate_highest_high(bars, &params)?;
+        let lows = self.calculate_lowest_low(bars, &params)?;
+        
+        let channel = |(high, low): (&IndicatorValue, &IndicatorValue), timestamp: u64| ChannelValue {
+            timestamp,
+            upper: high.value,
+            middle: (high.value + low.value) / dec!(2.0),
+            lower: low.value,
+        };
+        
+        let values = if exclude_current {
+            // The window ending at bar i - 1 becomes the channel for bar i
+            highs.iter().zip(&lows)
+                .zip(bars.iter().skip(period))
+                .map(|(window, bar)| channel(window, bar.timestamp))
+                .collect()
+        } else {
+            highs.iter().zip(&lows)
+                .map(|window| channel(window, window.0.timestamp))
+                .collect()
+        };
+        
+        Ok(values)
+    }
+    
     /// Calculate Volume Weighted Average Price (VWAP)
-    fn calculate_vwap(&self, bars: &[Bar], _params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
+    /// 
+    /// Accumulates over `params.vwap_anchor` sessions; under [`SessionWarmup::Strict`]
+    /// the bars before the first complete session get no value, under
+    /// [`SessionWarmup::Partial`] their values are marked not `warm`. Until a session has
+    /// traded volume, `params.zero_volume` decides the output.
+    fn calculate_vwap(&self, bars: &[Bar], params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
         let mut values = Vec::with_capacity(bars.len());
         let mut cumulative_volume = dec!(0.0);
         let mut cumulative_volume_price = dec!(0.0);
+        let mut last = None;
+        let first_output = first_vwap_output(bars, params);
+        let first_warm = first_complete_session(bars, params.vwap_anchor);
         
-        for bar in bars {
+        for (i, bar) in bars.iter().enumerate() {
+            if i > 0 && vwap_session(&bars[i - 1], params.vwap_anchor) != vwap_session(bar, params.vwap_anchor) {
+                cumulative_volume = dec!(0.0);
+                cumulative_volume_price = dec!(0.0);
+            }
+            
             let typical_price = (bar.high + bar.low + bar.close) / dec!(3.0);
             cumulative_volume_price += typical_price * bar.volume;
             cumulative_volume += bar.volume;
             
             let vwap = if cumulative_volume > dec!(0.0) {
-                cumulative_volume_price / cumulative_volume
+                let vwap = cumulative_volume_price / cumulative_volume;
+                last = Some(vwap);
+                vwap
             } else {
-                dec!(0.0)
+                match zero_volume_value(params.zero_volume, last) {
+                    Some(carried) => carried,
+                    None => continue,
+                }
             };
             
+            if i < first_output {
+                continue;
+            }
             values.push(IndicatorValue {
                 timestamp: bar.timestamp,
                 value: vwap,
+                warm: i >= first_warm,
             });
         }
         
@@ -319,6 +977,7 @@ impl IndicatorRegistry {
             values.push(IndicatorValue {
                 timestamp: bars[i + period - 1].timestamp,
                 value: highest,
+                warm: true,
             });
         }
         
@@ -344,10 +1003,1127 @@ impl IndicatorRegistry {
             values.push(IndicatorValue {
                 timestamp: bars[i + period - 1].timestamp,
                 value: lowest,
+                warm: true,
             });
         }
         
         Ok(values)
     }
-}
-
+    
+    /// f64 fast-path EMA (see [`Precision::Float64`] for the determinism waiver)
+    fn calculate_ema_f64(&self, bars: &[Bar], params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
+        let period = params.period;
+        let alpha = params.alpha
+            .and_then(|a| a.to_f64())
+            .unwrap_or(2.0 / (period + 1) as f64);
+        
+        let warmup = ema_warmup(params);
+        if warmup == 0 || bars.len() < warmup {
+            return Ok(Vec::new());
+        }
+        
+        let closes = closes_f64(bars);
+        let mut values = Vec::with_capacity(bars.len());
+        
+        let mut ema = closes[0..warmup].iter().sum::<f64>() / warmup as f64;
+        values.push(f64_value(bars[warmup - 1].timestamp, ema));
+        
+        for (i, close) in closes.iter().enumerate().skip(warmup) {
+            ema = alpha * close + (1.0 - alpha) * ema;
+            values.push(f64_value(bars[i].timestamp, ema));
+        }
+        
+        Ok(values)
+    }
+    
+    /// f64 fast-path SMA using a running window sum
+    fn calculate_sma_f64(&self, bars: &[Bar], params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
+        let period = params.period;
+        
+        if period == 0 || bars.len() < period {
+            return Ok(Vec::new());
+        }
+        
+        let closes = closes_f64(bars);
+        let mut values = Vec::with_capacity(bars.len() - period + 1);
+        
+        let mut sum = closes[0..period].iter().sum::<f64>();
+        values.push(f64_value(bars[period - 1].timestamp, sum / period as f64));
+        
+        for i in period..closes.len() {
+            sum += closes[i] - closes[i - period];
+            values.push(f64_value(bars[i].timestamp, sum / period as f64));
+        }
+        
+        Ok(values)
+    }
+    
+    /// f64 fast-path RSI
+    fn calculate_rsi_f64(&self, bars: &[Bar], params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
+        let period = params.period;
+        
+        if bars.len() < period + 1 {
+            return Ok(Vec::new());
+        }
+        
+        let closes = closes_f64(bars);
+        let mut values = Vec::with_capacity(bars.len() - period);
+        
+        let changes: Vec<f64> = closes.windows(2).map(|w| w[1] - w[0]).collect();
+        let mut avg_gain = changes[0..period].iter().map(|c| c.max(0.0)).sum::<f64>() / period as f64;
+        let mut avg_loss = changes[0..period].iter().map(|c| (-c).max(0.0)).sum::<f64>() / period as f64;
+        
+        for (i, change) in changes.iter().enumerate().skip(period) {
+            avg_gain = (avg_gain * (period - 1) as f64 + change.max(0.0)) / period as f64;
+            avg_loss = (avg_loss * (period - 1) as f64 + (-change).max(0.0)) / period as f64;
+            
+            let rs = if avg_loss == 0.0 { 100.0 } else { avg_gain / avg_loss };
+            let rsi = 100.0 - (100.0 / (1.0 + rs));
+            
+            values.push(f64_value(bars[i + 1].timestamp, rsi));
+        }
+        
+        Ok(values)
+    }
+    
+    /// f64 fast-path ATR with Wilder's smoothing
+    fn calculate_atr_f64(&self, bars: &[Bar], params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
+        let period = params.period;
+        
+        if bars.len() < period + 1 {
+            return Ok(Vec::new());
+        }
+        
+        let mut values = Vec::with_capacity(bars.len() - period);
+        
+        let true_ranges: Vec<f64> = bars.windows(2)
+            .map(|w| {
+                let high = w[1].high.to_f64().unwrap_or(0.0);
+                let low = w[1].low.to_f64().unwrap_or(0.0);
+                let prev_close = w[0].close.to_f64().unwrap_or(0.0);
+                (high - low).max((high - prev_close).abs()).max((low - prev_close).abs())
+            })
+            .collect();
+        
+        let mut atr = true_ranges[0..period].iter().sum::<f64>() / period as f64;
+        values.push(f64_value(bars[period].timestamp, atr));
+        
+        for (i, tr) in true_ranges.iter().enumerate().skip(period) {
+            atr = (atr * (period - 1) as f64 + tr) / period as f64;
+            values.push(f64_value(bars[i + 1].timestamp, atr));
+        }
+        
+        Ok(values)
+    }
+    
+    /// f64 fast-path VWAP
+    fn calculate_vwap_f64(&self, bars: &[Bar], params: &IndicatorParams) -> Result<Vec<IndicatorValue>> {
+        let mut values = Vec::with_capacity(bars.len());
+        let mut cumulative_volume = 0.0;
+        let mut cumulative_volume_price = 0.0;
+        let mut last = None;
+        let first_output = first_vwap_output(bars, params);
+        let first_warm = first_complete_session(bars, params.vwap_anchor);
+        
+        for (i, bar) in bars.iter().enumerate() {
+            if i > 0 && vwap_session(&bars[i - 1], params.vwap_anchor) != vwap_session(bar, params.vwap_anchor) {
+                cumulative_volume = 0.0;
+                cumulative_volume_price = 0.0;
+            }
+            
+            let high = bar.high.to_f64().unwrap_or(0.0);
+            let low = bar.low.to_f64().unwrap_or(0.0);
+            let close = bar.close.to_f64().unwrap_or(0.0);
+            let volume = bar.volume.to_f64().unwrap_or(0.0);
+            
+            cumulative_volume_price += (high + low + close) / 3.0 * volume;
+            cumulative_volume += volume;
+            
+            let vwap = if cumulative_volume > 0.0 {
+                let vwap = cumulative_volume_price / cumulative_volume;
+                last = Some(vwap);
+                vwap
+            } else {
+                match zero_volume_value(params.zero_volume, last) {
+                    Some(carried) => carried,
+                    None => continue,
+                }
+            };
+            
+            if i < first_output {
+                continue;
+            }
+            values.push(IndicatorValue { warm: i >= first_warm, ..f64_value(bar.timestamp, vwap) });
+        }
+        
+        Ok(values)
+    }
+}
+
+
+/// Default parameters used by [`IndicatorRegistry::calculate`]
+fn default_params(indicator_name: &str) -> Option<IndicatorParams> {
+    let period = match indicator_name {
+        "ema" | "sma" | "hh" | "ll" => 20,
+        "rsi" | "atr" | "mfi" => 14,
+        "cci" | "stddev" | "variance" => 20,
+        "supertrend" | "supertrend_dir" => 10,
+        "keltner" | "keltner_upper" | "keltner_lower" => 20,
+        "donchian" | "donchian_upper" | "donchian_lower" => 20,
+        "macd" => 26,
+        "vwap" => 0,
+        _ => return None,
+    };
+    
+    Some(IndicatorParams {
+        period,
+        alpha: None,
+        threshold: default_multiplier(indicator_name),
+        compute_precision: Precision::Decimal,
+        ema_seed: EmaSeed::SmaSeed,
+        vwap_anchor: VwapAnchor::Cumulative,
+        session_warmup: SessionWarmup::Partial,
+        zero_volume: ZeroVolumePolicy::CarryForward,
+        price_precision: None,
+    })
+}
+
+/// Rolling population variance over `period` values
+/// 
+/// Sliding-window Welford: the mean and sum of squared deviations are updated as one
+/// value enters and another leaves, instead of `E[x²] - E[x]²`, which cancels
+/// catastrophically when the variance is small relative to the level. The first value
+/// covers `values[..period]`.
+pub fn rolling_variance(values: &[Decimal], period: usize) -> Vec<Decimal> {
+    if period == 0 || values.len() < period {
+        return Vec::new();
+    }
+    
+    let n = Decimal::from(period);
+    let mut variances = Vec::with_capacity(values.len() - period + 1);
+    let mut mean = dec!(0.0);
+    let mut m2 = dec!(0.0);
+    
+    // Standard Welford over the first window
+    for (k, value) in values[..period].iter().enumerate() {
+        let delta = *value - mean;
+        mean += delta / Decimal::from(k + 1);
+        m2 += delta * (*value - mean);
+    }
+    variances.push((m2 / n).max(dec!(0.0)));
+    
+    // Slide: replace the oldest value with the newest
+    for i in period..values.len() {
+        let incoming = values[i];
+        let outgoing = values[i - period];
+        let old_mean = mean;
+        mean += (incoming - outgoing) / n;
+        m2 += (incoming - outgoing) * (incoming - mean + outgoing - old_mean);
+        
+        // Rounding can leave a tiny negative residue on flat windows
+        variances.push((m2 / n).max(dec!(0.0)));
+    }
+    
+    variances
+}
+
+/// Rolling population standard deviation over `period` values, the square root of
+/// [`rolling_variance`]
+pub fn rolling_std(values: &[Decimal], period: usize) -> Vec<Decimal> {
+    rolling_variance(values, period).into_iter().map(decimal_sqrt).collect()
+}
+
+/// Default ATR multiplier for band indicators, carried in `IndicatorParams::threshold`
+fn default_multiplier(indicator_name: &str) -> Option<Decimal> {
+    match indicator_name {
+        "supertrend" | "supertrend_dir" => Some(dec!(3.0)),
+        "keltner" | "keltner_upper" | "keltner_lower" => Some(dec!(2.0)),
+        _ => None,
+    }
+}
+
+/// Fast EMA period of `macd`, carried in `params.threshold`
+fn macd_fast_period(params: &IndicatorParams) -> usize {
+    params.threshold
+        .and_then(|period| period.to_usize())
+        .unwrap_or(12)
+}
+
+fn multiplier(params: &IndicatorParams, indicator_name: &str) -> Decimal {
+    params.threshold
+        .or_else(|| default_multiplier(indicator_name))
+        .unwrap_or(dec!(1.0))
+}
+
+/// Whether `indicator_name` is quoted in price units, as opposed to an oscillator,
+/// a direction or a squared price
+pub(crate) fn is_price_denominated(indicator_name: &str) -> bool {
+    matches!(indicator_name,
+        "ema" | "sma" | "atr" | "vwap" | "supertrend" | "macd" | "stddev" | "hh" | "ll"
+        | "keltner" | "keltner_upper" | "keltner_lower"
+        | "donchian" | "donchian_upper" | "donchian_lower")
+}
+
+/// `value` rounded nearest-even to `precision` decimals, as exchange prices are
+pub(crate) fn round_price(value: Decimal, precision: u8) -> Decimal {
+    round_to_precision(value, precision, RoundingMode::NearestEven)
+}
+
+/// Round a price-denominated series to `params.price_precision`, if set
+fn round_prices(indicator_name: &str, params: &IndicatorParams, mut values: Vec<IndicatorValue>) -> Vec<IndicatorValue> {
+    if let Some(precision) = params.price_precision.filter(|_| is_price_denominated(indicator_name)) {
+        for value in &mut values {
+            value.value = round_price(value.value, precision);
+        }
+    }
+    values
+}
+
+/// Select one band of a channel by registry name suffix
+fn channel_band(indicator_name: &str, channel: Vec<ChannelValue>) -> Vec<IndicatorValue> {
+    channel.into_iter()
+        .map(|c| IndicatorValue {
+            timestamp: c.timestamp,
+            value: if indicator_name.ends_with("_upper") {
+                c.upper
+            } else if indicator_name.ends_with("_lower") {
+                c.lower
+            } else {
+                c.middle
+            },
+            warm: true,
+        })
+        .collect()
+}
+
+/// Largest [`min_bars`] among `indicator_names` and the indicator needing it
+fn longest_warmup(
+    indicator_names: &[String],
+    params: &HashMap<String, IndicatorParams>,
+) -> Result<Option<(usize, String)>> {
+    let mut longest: Option<(usize, String)> = None;
+    for name in indicator_names {
+        let params = params.get(name).cloned()
+            .or_else(|| default_params(name))
+            .ok_or_else(|| BacktestError::UnknownIndicator(name.clone()))?;
+        let bars = min_bars(name, &params);
+        if longest.as_ref().is_none_or(|(most, _)| bars > *most) {
+            longest = Some((bars, name.clone()));
+        }
+    }
+    Ok(longest)
+}
+
+/// Bars needed before `indicator_name` emits its first value
+fn min_bars(indicator_name: &str, params: &IndicatorParams) -> usize {
+    match indicator_name {
+        "ema" => ema_warmup(params),
+        "macd" => ema_warmup(&IndicatorParams {
+            period: params.period.max(macd_fast_period(params)),
+            ..params.clone()
+        }),
+        "vwap" => 1,
+        // Need a previous close (true range, price change) or exclude the current bar
+        "rsi" | "atr" | "mfi" | "supertrend" | "supertrend_dir"
+        | "keltner" | "keltner_upper" | "keltner_lower"
+        | "donchian" | "donchian_upper" | "donchian_lower" => params.period + 1,
+        _ => params.period,
+    }
+    .max(1)
+}
+
+/// Milliseconds in a day, the length of a [`VwapAnchor::Daily`] session
+const MS_PER_DAY: u64 = 86_400_000;
+
+/// Session `bar` belongs to under `anchor`
+fn vwap_session(bar: &Bar, anchor: VwapAnchor) -> u64 {
+    match anchor {
+        VwapAnchor::Cumulative => 0,
+        VwapAnchor::Daily => bar.timestamp / MS_PER_DAY,
+    }
+}
+
+/// Index of the first bar of the first session the data covers from its start
+/// 
+/// 0 when the data opens on a session boundary (or the anchor never resets);
+/// `bars.len()` when no session starts within the data.
+fn first_complete_session(bars: &[Bar], anchor: VwapAnchor) -> usize {
+    let Some(first) = bars.first() else {
+        return 0;
+    };
+    match anchor {
+        VwapAnchor::Cumulative => 0,
+        VwapAnchor::Daily if first.timestamp % MS_PER_DAY == 0 => 0,
+        VwapAnchor::Daily => bars.iter()
+            .position(|bar| vwap_session(bar, anchor) != vwap_session(first, anchor))
+            .unwrap_or(bars.len()),
+    }
+}
+
+/// Value a volume-weighted indicator emits for a window with no volume
+fn zero_volume_value<T>(policy: ZeroVolumePolicy, last: Option<T>) -> Option<T> {
+    match policy {
+        ZeroVolumePolicy::CarryForward => last,
+        ZeroVolumePolicy::Skip => None,
+    }
+}
+
+fn first_vwap_output(bars: &[Bar], params: &IndicatorParams) -> usize {
+    match params.session_warmup {
+        SessionWarmup::Partial => 0,
+        SessionWarmup::Strict => first_complete_session(bars, params.vwap_anchor),
+    }
+}
+
+/// Timestamp of the first bar whose session-anchored values cover a whole session
+/// 
+/// Values stamped earlier come from the partial session the data starts in and are
+/// warmup. `None` if no complete session starts within `bars`.
+pub fn session_warmup_end(bars: &[Bar], params: &IndicatorParams) -> Option<u64> {
+    bars.get(first_complete_session(bars, params.vwap_anchor)).map(|bar| bar.timestamp)
+}
+
+/// Number of closes averaged into the first EMA value
+fn ema_warmup(params: &IndicatorParams) -> usize {
+    match params.ema_seed {
+        EmaSeed::SmaSeed => params.period,
+        EmaSeed::FirstValueSeed => 1,
+    }
+}
+
+fn closes_f64(bars: &[Bar]) -> Vec<f64> {
+    bars.iter().map(|b| b.close.to_f64().unwrap_or(0.0)).collect()
+}
+
+/// Decimal places kept when converting f64 results back to Decimal
+const F64_RESULT_SCALE: u32 = 12;
+
+/// Convert an f64 result via a fixed-scale integer, which is far cheaper than
+/// `Decimal::from_f64`'s exact conversion and keeps the fast path fast
+fn f64_value(timestamp: u64, value: f64) -> IndicatorValue {
+    let scaled = (value * 10f64.powi(F64_RESULT_SCALE as i32)).round();
+    let value = if scaled.is_finite() && scaled.abs() < i64::MAX as f64 {
+        Decimal::new(scaled as i64, F64_RESULT_SCALE)
+    } else {
+        Decimal::from_f64(value).unwrap_or(dec!(0.0))
+    };
+    
+    IndicatorValue { timestamp, value, warm: true }
+}
+
+/// Detect regular and hidden divergences between price and an oscillator
+/// 
+/// Swing highs/lows are fractal pivots: a bar whose high (low) is strictly above
+/// (below) the `fractal_window` bars on each side. Consecutive swings at most
+/// `lookback` bars apart are compared against the oscillator value on the same bars.
+/// Bars without an oscillator value (e.g. during warmup) are skipped.
+/// 
+/// A swing is only known `fractal_window` bars after it prints; each event carries
+/// `confirmed_at` and must not be acted on before then.
+pub fn detect_divergence(
+    price: &[Bar],
+    osc: &[IndicatorValue],
+    lookback: usize,
+    fractal_window: usize,
+) -> Vec<DivergenceEvent> {
+    let osc_by_timestamp: HashMap<u64, Decimal> = osc.iter()
+        .map(|v| (v.timestamp, v.value))
+        .collect();
+    
+    // Align price and oscillator on shared timestamps
+    let aligned: Vec<(&Bar, Decimal)> = price.iter()
+        .filter_map(|bar| osc_by_timestamp.get(&bar.timestamp).map(|v| (bar, *v)))
+        .collect();
+    
+    let highs: Vec<Decimal> = aligned.iter().map(|(bar, _)| bar.high).collect();
+    let lows: Vec<Decimal> = aligned.iter().map(|(bar, _)| bar.low).collect();
+    
+    let mut events = Vec::new();
+    
+    for (pivots, is_high) in [
+        (pivot_indices(&highs, fractal_window, fractal_window, true), true),
+        (pivot_indices(&lows, fractal_window, fractal_window, false), false),
+    ] {
+        for pair in pivots.windows(2) {
+            let (prev, cur) = (pair[0], pair[1]);
+            if cur - prev > lookback {
+                continue;
+            }
+            
+            let (prev_bar, prev_osc) = aligned[prev];
+            let (bar, cur_osc) = aligned[cur];
+            let (prev_price, cur_price) = if is_high {
+                (prev_bar.high, bar.high)
+            } else {
+                (prev_bar.low, bar.low)
+            };
+            
+            let kind = match (is_high, cur_price.cmp(&prev_price), cur_osc.cmp(&prev_osc)) {
+                (true, Ordering::Greater, Ordering::Less) => DivergenceKind::RegularBearish,
+                (true, Ordering::Less, Ordering::Greater) => DivergenceKind::HiddenBearish,
+                (false, Ordering::Less, Ordering::Greater) => DivergenceKind::RegularBullish,
+                (false, Ordering::Greater, Ordering::Less) => DivergenceKind::HiddenBullish,
+                _ => continue,
+            };
+            
+            events.push(DivergenceEvent {
+                kind,
+                prev_timestamp: prev_bar.timestamp,
+                timestamp: bar.timestamp,
+                confirmed_at: aligned[cur + fractal_window].0.timestamp,
+                prev_price,
+                price: cur_price,
+                prev_osc,
+                osc: cur_osc,
+            });
+        }
+    }
+    
+    events.sort_by_key(|e| e.confirmed_at);
+    events
+}
+
+/// Find swing highs: bars whose high is strictly above the `left` bars before and
+/// the `right` bars after
+/// 
+/// A pivot is only known once its `right` following bars have closed, so it carries an
+/// inherent `right`-bar lag. Strategies must act on `confirmed_at`, never on
+/// `timestamp`, or they will be trading on future data.
+pub fn find_swing_highs(bars: &[Bar], left: usize, right: usize) -> Vec<SwingPoint> {
+    let highs: Vec<Decimal> = bars.iter().map(|b| b.high).collect();
+    swing_points(bars, &highs, left, right, true)
+}
+
+/// Find swing lows: bars whose low is strictly below the `left` bars before and the
+/// `right` bars after
+/// 
+/// Same `right`-bar confirmation lag as [`find_swing_highs`].
+pub fn find_swing_lows(bars: &[Bar], left: usize, right: usize) -> Vec<SwingPoint> {
+    let lows: Vec<Decimal> = bars.iter().map(|b| b.low).collect();
+    swing_points(bars, &lows, left, right, false)
+}
+
+fn swing_points(bars: &[Bar], values: &[Decimal], left: usize, right: usize, is_high: bool) -> Vec<SwingPoint> {
+    pivot_indices(values, left, right, is_high)
+        .into_iter()
+        .map(|i| SwingPoint {
+            timestamp: bars[i].timestamp,
+            price: values[i],
+            confirmed_at: bars[i + right].timestamp,
+        })
+        .collect()
+}
+
+/// Indices of pivots that strictly exceed `left` values before and `right` values after
+/// 
+/// `is_high` selects pivot highs (greater than neighbours) or lows (less than).
+/// Values within `right` of the end are never pivots, since they cannot be confirmed yet.
+fn pivot_indices(values: &[Decimal], left: usize, right: usize, is_high: bool) -> Vec<usize> {
+    if values.len() < left + right + 1 {
+        return Vec::new();
+    }
+    
+    (left..values.len() - right)
+        .filter(|&i| {
+            let beats = |other: &Decimal| if is_high { values[i] > *other } else { values[i] < *other };
+            values[i - left..i].iter().all(beats) && values[i + 1..=i + right].iter().all(beats)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+
+    fn market_data() -> crate::types::MarketData {
+        let config = SyntheticDataConfig {
+            bar_count: 500,
+            ..SyntheticDataConfig::default()
+        };
+        SyntheticDataGenerator::new(config, 7).generate()
+    }
+
+    #[test]
+    fn test_macd_is_fast_minus_slow_ema() {
+        let data = market_data();
+        let mut registry = IndicatorRegistry::new(false).unwrap();
+        let macd = registry.calculate("macd", &data).unwrap();
+        let ema = |registry: &mut IndicatorRegistry, period| registry.calculate_with_params("ema", &data, &IndicatorParams {
+            period,
+            ..default_params("ema").unwrap()
+        }).unwrap();
+        let fast = ema(&mut registry, 12);
+        let slow = ema(&mut registry, 26);
+
+        // Starts with the 26-period EMA, ends with the data
+        assert_eq!(macd.len(), slow.len());
+        assert_eq!(macd[0].timestamp, slow[0].timestamp);
+        let last = macd.last().unwrap();
+        assert_eq!(last.timestamp, data.bars.last().unwrap().timestamp);
+        assert_eq!(last.value, fast.last().unwrap().value - slow.last().unwrap().value);
+    }
+
+    #[test]
+    fn test_strict_daily_vwap_waits_for_first_full_session() {
+        // Hourly bars from 12:00 on Jan 1 to 23:00 on Jan 2
+        let start = 1609459200000 + 12 * 3_600_000;
+        let bars: Vec<Bar> = (0..36u64).map(|i| {
+            let price = Decimal::from(100 + i);
+            Bar {
+                timestamp: start + i * 3_600_000,
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume: dec!(1.0),
+                trade_count: 1,
+            }
+        }).collect();
+        let params = |session_warmup| IndicatorParams {
+            vwap_anchor: VwapAnchor::Daily,
+            session_warmup,
+            ..default_params("vwap").unwrap()
+        };
+        let registry = IndicatorRegistry::new(false).unwrap();
+
+        let partial = registry.calculate_vwap(&bars, &params(SessionWarmup::Partial)).unwrap();
+        assert_eq!(partial.len(), bars.len());
+        // Jan 1 values only cover half a session and are flagged as such
+        assert!(partial[..12].iter().all(|v| !v.warm));
+        assert!(partial[12..].iter().all(|v| v.warm));
+        assert_eq!(session_warmup_end(&bars, &params(SessionWarmup::Partial)), Some(bars[12].timestamp));
+
+        let strict = registry.calculate_vwap(&bars, &params(SessionWarmup::Strict)).unwrap();
+        assert_eq!(strict.len(), 24);
+        assert_eq!(strict[0].timestamp, 1609459200000 + 86_400_000);
+        // The reset leaves only Jan 2 bars in the first value
+        assert_eq!(strict[0].value, bars[12].close);
+        assert_eq!(strict[0].value, partial[12].value);
+
+        let strict_f64 = registry.calculate_vwap_f64(&bars, &params(SessionWarmup::Strict)).unwrap();
+        assert_eq!(strict_f64.len(), 24);
+        assert_eq!(strict_f64[0].timestamp, strict[0].timestamp);
+        assert!(strict.iter().chain(&strict_f64).all(|v| v.warm));
+        let partial_f64 = registry.calculate_vwap_f64(&bars, &params(SessionWarmup::Partial)).unwrap();
+        assert_eq!(partial_f64.iter().filter(|v| !v.warm).count(), 12);
+    }
+
+    #[test]
+    fn test_bar_columns_round_trip() {
+        let data = market_data();
+        let columns = BarColumns::from_bars(&data.bars);
+        assert_eq!(columns.len(), data.bars.len());
+
+        let bars = columns.to_bars();
+        for (original, restored) in data.bars.iter().zip(&bars) {
+            assert_eq!(original.timestamp, restored.timestamp);
+            assert_eq!(original.open, restored.open);
+            assert_eq!(original.high, restored.high);
+            assert_eq!(original.low, restored.low);
+            assert_eq!(original.close, restored.close);
+            assert_eq!(original.volume, restored.volume);
+            assert_eq!(original.trade_count, restored.trade_count);
+        }
+    }
+
+    #[test]
+    fn test_columnar_indicators_match_bar_indicators() {
+        let data = market_data();
+        let columns = BarColumns::from(data.bars.as_slice());
+
+        for indicator in ["ema", "sma"] {
+            for enable_simd in [true, false] {
+                let from_bars = IndicatorRegistry::new(enable_simd).unwrap()
+                    .calculate(indicator, &data).unwrap();
+                let from_columns = IndicatorRegistry::new(enable_simd).unwrap()
+                    .calculate_columns(indicator, &data.symbol, &columns).unwrap();
+
+                assert_eq!(from_bars.len(), from_columns.len());
+                for (a, b) in from_bars.iter().zip(&from_columns) {
+                    assert_eq!(a.timestamp, b.timestamp);
+                    assert_eq!(a.value, b.value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_float64_matches_decimal_within_tolerance() {
+        // Stated tolerance for the f64 fast path on a benign (GBM, ~100 price) series
+        const TOLERANCE: f64 = 1e-9;
+
+        let data = market_data();
+
+        for indicator in ["ema", "sma", "rsi", "atr", "vwap"] {
+            let mut params = default_params(indicator).unwrap();
+            let exact = IndicatorRegistry::new(false).unwrap()
+                .calculate_with_params(indicator, &data, &params).unwrap();
+            params.compute_precision = Precision::Float64;
+            let fast = IndicatorRegistry::new(false).unwrap()
+                .calculate_with_params(indicator, &data, &params).unwrap();
+
+            assert_eq!(exact.len(), fast.len(), "{}", indicator);
+            for (a, b) in exact.iter().zip(&fast) {
+                assert_eq!(a.timestamp, b.timestamp);
+                let a = a.value.to_f64().unwrap();
+                let b = b.value.to_f64().unwrap();
+                assert!(
+                    (a - b).abs() <= TOLERANCE * a.abs().max(1.0),
+                    "{}: decimal {} vs f64 {}", indicator, a, b
+                );
+            }
+        }
+    }
+
+    fn peak_bars(highs: &[Decimal]) -> Vec<Bar> {
+        highs.iter().enumerate().map(|(i, high)| Bar {
+            timestamp: 1609459200000 + i as u64 * 60_000,
+            open: *high - dec!(1.0),
+            high: *high,
+            low: *high - dec!(2.0),
+            close: *high - dec!(0.5),
+            volume: dec!(100.0),
+            trade_count: 10,
+        }).collect()
+    }
+
+    #[test]
+    fn test_detects_bearish_divergence() {
+        // Two price peaks, the second higher; the oscillator peaks lower the second time
+        let highs = [100, 102, 105, 103, 101, 102, 104, 108, 104, 102, 101]
+            .map(Decimal::from);
+        let rsi = [50, 60, 75, 65, 55, 58, 62, 68, 60, 55, 52]
+            .map(Decimal::from);
+        let bars = peak_bars(&highs);
+        let osc: Vec<IndicatorValue> = bars.iter().zip(rsi)
+            .map(|(bar, value)| IndicatorValue { timestamp: bar.timestamp, value, warm: true })
+            .collect();
+
+        let events = detect_divergence(&bars, &osc, 10, 2);
+
+        let bearish: Vec<_> = events.iter()
+            .filter(|e| e.kind == DivergenceKind::RegularBearish)
+            .collect();
+        assert_eq!(bearish.len(), 1);
+        let event = bearish[0];
+        assert_eq!(event.prev_timestamp, bars[2].timestamp);
+        assert_eq!(event.timestamp, bars[7].timestamp);
+        assert_eq!(event.confirmed_at, bars[9].timestamp);
+        assert_eq!(event.price, dec!(108));
+        assert_eq!(event.osc, dec!(68));
+
+        // Outside the lookback the peaks are not compared
+        assert!(detect_divergence(&bars, &osc, 4, 2).iter()
+            .all(|e| e.kind != DivergenceKind::RegularBearish));
+    }
+
+    #[test]
+    fn test_swing_high_confirmed_only_after_right_bars() {
+        let highs = [100, 101, 104, 102, 101, 103, 100].map(Decimal::from);
+        let bars = peak_bars(&highs);
+
+        // The peak at index 2 needs bars 3 and 4 before it exists
+        assert!(find_swing_highs(&bars[..4], 2, 2).is_empty());
+
+        let swings = find_swing_highs(&bars[..5], 2, 2);
+        assert_eq!(swings.len(), 1);
+        assert_eq!(swings[0].timestamp, bars[2].timestamp);
+        assert_eq!(swings[0].price, dec!(104));
+        assert_eq!(swings[0].confirmed_at, bars[4].timestamp);
+    }
+
+    #[test]
+    fn test_swing_points_ignore_data_beyond_right_window() {
+        let highs = [100, 101, 104, 102, 101, 103, 100].map(Decimal::from);
+        let bars = peak_bars(&highs);
+        let baseline = find_swing_highs(&bars, 2, 2);
+
+        // Rewriting everything after the confirmation window leaves the pivot untouched
+        let mut altered = bars.clone();
+        for bar in altered.iter_mut().skip(5) {
+            bar.high = dec!(500);
+            bar.low = dec!(1);
+        }
+        let swings = find_swing_highs(&altered, 2, 2);
+        assert_eq!(swings[0].timestamp, baseline[0].timestamp);
+        assert_eq!(swings[0].price, baseline[0].price);
+
+        let lows = find_swing_lows(&bars, 1, 1);
+        assert!(lows.iter().all(|s| s.confirmed_at == s.timestamp + 60_000));
+    }
+
+    #[test]
+    fn test_first_value_seed_matches_tradingview_ema() {
+        // EMA(3) in TradingView's convention: seeded with the first close, alpha = 0.5,
+        // plotted from the first bar
+        let closes = [10, 11, 12, 11, 13].map(Decimal::from);
+        let expected = [dec!(10), dec!(10.5), dec!(11.25), dec!(11.125), dec!(12.0625)];
+        let data = crate::types::MarketData {
+            symbol: "BTCUSDT".to_string(),
+            timeframe: "1m".to_string(),
+            bars: peak_bars(&closes).into_iter().zip(closes)
+                .map(|(bar, close)| Bar { close, ..bar })
+                .collect(),
+            trades: Vec::new(),
+            rules: crate::types::ExchangeRules::default(),
+            timestamp_convention: crate::types::TimestampConvention::OpenTime,
+        };
+
+        let mut params = default_params("ema").unwrap();
+        params.period = 3;
+        params.ema_seed = EmaSeed::FirstValueSeed;
+
+        for precision in [Precision::Decimal, Precision::Float64] {
+            params.compute_precision = precision;
+            let values = IndicatorRegistry::new(false).unwrap()
+                .calculate_with_params("ema", &data, &params).unwrap();
+
+            assert_eq!(values.len(), closes.len());
+            assert_eq!(values[0].timestamp, data.bars[0].timestamp);
+            for (value, expected) in values.iter().zip(expected) {
+                assert_eq!(value.value, expected);
+            }
+        }
+
+        // The default SMA seed starts at bar `period - 1` with the mean of the first closes
+        params.ema_seed = EmaSeed::SmaSeed;
+        params.compute_precision = Precision::Decimal;
+        let values = IndicatorRegistry::new(false).unwrap()
+            .calculate_with_params("ema", &data, &params).unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[0].value, dec!(11));
+    }
+
+    #[test]
+    fn test_supertrend_flips_once_at_reversal() {
+        // 30 bars rising by 2, then 30 bars falling by 2, with a constant 2-point range
+        let closes: Vec<Decimal> = (0..60)
+            .map(|i| if i < 30 { 100 + 2 * i } else { 158 - 2 * (i - 30) })
+            .map(Decimal::from)
+            .collect();
+        let bars = peak_bars(&closes.iter().map(|c| *c + dec!(0.5)).collect::<Vec<_>>());
+        let registry = IndicatorRegistry::new(false).unwrap();
+
+        let values = registry.calculate_supertrend(&bars, 10, dec!(3.0)).unwrap();
+        assert_eq!(values.len(), bars.len() - 10);
+        assert_eq!(values[0].direction, 1);
+
+        let flips: Vec<usize> = values.windows(2)
+            .enumerate()
+            .filter(|(_, w)| w[0].direction != w[1].direction)
+            .map(|(k, _)| k + 1)
+            .collect();
+        assert_eq!(flips.len(), 1);
+        let flip = &values[flips[0]];
+        assert_eq!(flip.direction, -1);
+        assert!(flip.timestamp > bars[29].timestamp && flip.timestamp <= bars[35].timestamp);
+
+        // The line is the lower band below price in the uptrend and the upper band above it after
+        for (value, bar) in values.iter().zip(&bars[10..]) {
+            if value.direction > 0 {
+                assert!(value.value < bar.close);
+            } else {
+                assert!(value.value > bar.close);
+            }
+        }
+
+        // Deterministic, and exposed through the registry as line and direction series
+        assert_eq!(values, registry.calculate_supertrend(&bars, 10, dec!(3.0)).unwrap());
+        let data = crate::types::MarketData {
+            symbol: "TEST".to_string(),
+            timeframe: "1m".to_string(),
+            bars: bars.clone(),
+            trades: Vec::new(),
+            rules: crate::types::ExchangeRules::default(),
+            timestamp_convention: crate::types::TimestampConvention::OpenTime,
+        };
+        let line = IndicatorRegistry::new(false).unwrap().calculate("supertrend", &data).unwrap();
+        let direction = IndicatorRegistry::new(false).unwrap().calculate("supertrend_dir", &data).unwrap();
+        assert_eq!(line.iter().map(|v| v.value).collect::<Vec<_>>(), values.iter().map(|v| v.value).collect::<Vec<_>>());
+        assert_eq!(direction[flips[0]].value, dec!(-1));
+    }
+
+    fn ranging_bars(count: usize) -> Vec<Bar> {
+        // Highs cycle 100, 102, 104, 106, 104, 102; lows are always 2 below
+        let cycle = [0, 2, 4, 6, 4, 2];
+        let highs: Vec<Decimal> = (0..count)
+            .map(|i| Decimal::from(100 + cycle[i % cycle.len()]))
+            .collect();
+        peak_bars(&highs)
+    }
+
+    #[test]
+    fn test_donchian_width_on_ranging_series() {
+        let mut bars = ranging_bars(30);
+        let registry = IndicatorRegistry::new(false).unwrap();
+
+        let inclusive = registry.calculate_donchian(&bars, 6, false).unwrap();
+        let exclusive = registry.calculate_donchian(&bars, 6, true).unwrap();
+        assert_eq!(inclusive.len(), 25);
+        assert_eq!(exclusive.len(), 24);
+        assert_eq!(inclusive[0].timestamp, bars[5].timestamp);
+        assert_eq!(exclusive[0].timestamp, bars[6].timestamp);
+
+        // Every 6-bar window spans a full cycle: 98..106
+        for channel in inclusive.iter().chain(&exclusive) {
+            assert_eq!(channel.upper - channel.lower, dec!(8));
+            assert_eq!(channel.middle, dec!(102));
+        }
+
+        // A breakout bar sets the inclusive band but only clears the offset band
+        let last = bars.last().unwrap().timestamp;
+        bars.extend(peak_bars(&[dec!(110)]).into_iter().map(|b| Bar { timestamp: last + 60_000, ..b }));
+        let inclusive = registry.calculate_donchian(&bars, 6, false).unwrap();
+        let exclusive = registry.calculate_donchian(&bars, 6, true).unwrap();
+        let breakout = bars.last().unwrap();
+        assert_eq!(inclusive.last().unwrap().upper, dec!(110));
+        assert_eq!(exclusive.last().unwrap().upper, dec!(106));
+        assert!(breakout.close > exclusive.last().unwrap().upper);
+    }
+
+    #[test]
+    fn test_keltner_width_tracks_atr() {
+        let bars = ranging_bars(60);
+        let data = crate::types::MarketData {
+            symbol: "TEST".to_string(),
+            timeframe: "1m".to_string(),
+            bars: bars.clone(),
+            trades: Vec::new(),
+            rules: crate::types::ExchangeRules::default(),
+            timestamp_convention: crate::types::TimestampConvention::OpenTime,
+        };
+        let mut registry = IndicatorRegistry::new(false).unwrap();
+        let mut params = default_params("atr").unwrap();
+        params.period = 10;
+        let atr = registry.calculate_with_params("atr", &data, &params).unwrap();
+
+        let channel = registry.calculate_keltner(&bars, 10, dec!(2.0)).unwrap();
+        assert_eq!(channel.len(), atr.len());
+        for (value, atr) in channel.iter().zip(&atr) {
+            assert_eq!(value.timestamp, atr.timestamp);
+            // Bands are added to a 28-digit midline, so allow last-digit rounding
+            let offset = dec!(2.0) * atr.value;
+            assert!((value.upper - value.middle - offset).abs() < dec!(0.000000001));
+            assert!((value.middle - value.lower - offset).abs() < dec!(0.000000001));
+        }
+
+        // Range-bound prices stay inside the bands once the ATR has settled
+        let settled = &channel[10..];
+        let closes = &bars[bars.len() - settled.len()..];
+        assert!(settled.iter().zip(closes).all(|(c, bar)| c.lower < bar.close && bar.close < c.upper));
+
+        let upper = registry.calculate("keltner_upper", &data).unwrap();
+        assert_eq!(upper.len(), bars.len() - 20);
+    }
+
+    #[test]
+    fn test_cci_typical_range_and_flat_series() {
+        let data = market_data();
+        let values = IndicatorRegistry::new(false).unwrap().calculate("cci", &data).unwrap();
+        assert_eq!(values.len(), data.bars.len() - 19);
+
+        // Lambert's 0.015 constant keeps the majority of readings inside ±100
+        let inside = values.iter()
+            .filter(|v| v.value.abs() <= dec!(100))
+            .count() as f64 / values.len() as f64;
+        assert!(inside > 0.5 && inside < 0.95, "{} inside ±100", inside);
+        assert!(values.iter().any(|v| v.value > dec!(100)));
+        assert!(values.iter().any(|v| v.value < dec!(-100)));
+
+        // Zero mean deviation is defined as 0, not a division error
+        let flat = peak_bars(&[dec!(100); 25]);
+        let params = default_params("cci").unwrap();
+        let values = IndicatorRegistry::new(false).unwrap().calculate_cci(&flat, &params).unwrap();
+        assert!(values.iter().all(|v| v.value == dec!(0)));
+    }
+
+    #[test]
+    fn test_mfi_stays_within_bounds() {
+        let data = market_data();
+        let values = IndicatorRegistry::new(false).unwrap().calculate("mfi", &data).unwrap();
+        assert_eq!(values.len(), data.bars.len() - 14);
+        assert!(values.iter().all(|v| v.value >= dec!(0) && v.value <= dec!(100)));
+
+        // No negative flow clamps to 100; no flow at all is neutral
+        let params = default_params("mfi").unwrap();
+        let registry = IndicatorRegistry::new(false).unwrap();
+        let rising: Vec<Decimal> = (0..20).map(|i| Decimal::from(100 + i)).collect();
+        let values = registry.calculate_mfi(&peak_bars(&rising), &params).unwrap();
+        assert!(values.iter().all(|v| v.value == dec!(100)));
+        let values = registry.calculate_mfi(&peak_bars(&[dec!(100); 20]), &params).unwrap();
+        assert!(values.iter().all(|v| v.value == dec!(50)));
+    }
+
+    #[test]
+    fn test_zero_volume_bar_carries_vwap_forward() {
+        // Daily VWAP where the second session opens with a zero-volume bar
+        let day = 86_400_000;
+        let bar = |timestamp: u64, price: Decimal, volume: Decimal| Bar {
+            timestamp,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume,
+            trade_count: 1,
+        };
+        let bars = vec![
+            bar(1609459200000, dec!(100), dec!(1)),
+            bar(1609459200000 + 60_000, dec!(102), dec!(1)),
+            bar(1609459200000 + day, dec!(110), dec!(0)),
+            bar(1609459200000 + day + 60_000, dec!(108), dec!(2)),
+        ];
+        let params = |zero_volume| IndicatorParams {
+            vwap_anchor: VwapAnchor::Daily,
+            zero_volume,
+            ..default_params("vwap").unwrap()
+        };
+        let registry = IndicatorRegistry::new(false).unwrap();
+        
+        let carried = registry.calculate_vwap(&bars, &params(ZeroVolumePolicy::CarryForward)).unwrap();
+        let values: Vec<Decimal> = carried.iter().map(|v| v.value).collect();
+        assert_eq!(values, vec![dec!(100), dec!(101), dec!(101), dec!(108)]);
+        let fast = registry.calculate_vwap_f64(&bars, &params(ZeroVolumePolicy::CarryForward)).unwrap();
+        assert_eq!(fast[2].value, dec!(101));
+        
+        let skipped = registry.calculate_vwap(&bars, &params(ZeroVolumePolicy::Skip)).unwrap();
+        let timestamps: Vec<u64> = skipped.iter().map(|v| v.timestamp).collect();
+        assert_eq!(timestamps, vec![bars[0].timestamp, bars[1].timestamp, bars[3].timestamp]);
+        
+        // Halted from bar 20: from bar 33 the 14-bar MFI window holds no volume and
+        // repeats the reading at bar 32 (MFI values start at bar 14)
+        let mut halted = peak_bars(&(0..40).map(|i| Decimal::from(100 + i % 4)).collect::<Vec<_>>());
+        for bar in &mut halted[20..] {
+            bar.volume = dec!(0);
+        }
+        let mfi = registry.calculate_mfi(&halted, &default_params("mfi").unwrap()).unwrap();
+        assert_eq!(mfi.len(), halted.len() - 14);
+        assert!(mfi[33 - 14..].iter().all(|v| v.value == mfi[32 - 14].value));
+        assert_ne!(mfi[32 - 14].value, dec!(50));
+    }
+
+    #[test]
+    fn test_rolling_variance_matches_two_pass() {
+        // Small moves on a large price level, where E[x²] - E[x]² would cancel
+        let mut data = market_data();
+        for bar in &mut data.bars {
+            bar.close += dec!(1000000);
+        }
+        let mut params = default_params("variance").unwrap();
+        params.period = 50;
+        let registry = IndicatorRegistry::new(false).unwrap();
+        let variance = registry.calculate_rolling_variance(&data.bars, &params).unwrap();
+        let std = registry.calculate_rolling_std(&data.bars, &params).unwrap();
+        assert_eq!(variance.len(), data.bars.len() - 49);
+
+        for (k, (var, sd)) in variance.iter().zip(&std).enumerate() {
+            let window = &data.bars[k..k + 50];
+            let mean = window.iter().map(|b| b.close).sum::<Decimal>() / dec!(50);
+            let two_pass = window.iter()
+                .map(|b| (b.close - mean) * (b.close - mean))
+                .sum::<Decimal>() / dec!(50);
+
+            assert!((var.value - two_pass).abs() < dec!(0.0000000001), "{} vs {}", var.value, two_pass);
+            assert!((sd.value - decimal_sqrt(two_pass)).abs() < dec!(0.0000000001));
+        }
+    }
+
+    #[test]
+    fn test_rolling_std_of_constant_series_is_zero() {
+        let bars = peak_bars(&[dec!(123.45); 40]);
+        let params = default_params("stddev").unwrap();
+        let values = IndicatorRegistry::new(false).unwrap().calculate_rolling_std(&bars, &params).unwrap();
+        assert_eq!(values.len(), 21);
+        assert!(values.iter().all(|v| v.value == dec!(0)));
+    }
+
+    #[test]
+    fn test_unknown_indicator_error_kind() {
+        let data = market_data();
+        let mut registry = IndicatorRegistry::new(false).unwrap();
+
+        match registry.calculate("ichimoku", &data) {
+            Err(BacktestError::UnknownIndicator(name)) => assert_eq!(name, "ichimoku"),
+            other => panic!("expected UnknownIndicator, got {:?}", other),
+        }
+
+        let columns = BarColumns::from(data.bars.as_slice());
+        assert!(matches!(
+            registry.calculate_columns("rsi", &data.symbol, &columns),
+            Err(BacktestError::UnsupportedInput { input: "columnar", .. })
+        ));
+    }
+
+    #[test]
+    fn test_insufficient_data_names_shortfall() {
+        let indicators = vec!["ema".to_string(), "rsi".to_string(), "sma".to_string()];
+        let registry = IndicatorRegistry::new(false).unwrap();
+
+        // Exactly the longest period (ema/sma at 20) is enough
+        let mut data = market_data();
+        data.bars.truncate(20);
+        assert!(registry.check_sufficient_data(&data, &indicators).is_ok());
+
+        // One bar short names the limiting indicator and the shortfall
+        data.bars.truncate(19);
+        match registry.check_sufficient_data(&data, &indicators) {
+            Err(err @ BacktestError::InsufficientData { .. }) => {
+                assert!(err.to_string().contains("ema needs 20 bars, 19 available"), "{}", err);
+            }
+            other => panic!("expected InsufficientData, got {:?}", other),
+        }
+
+        data.bars.clear();
+        assert!(matches!(
+            registry.check_sufficient_data(&data, &[]),
+            Err(BacktestError::InsufficientData { required: 1, available: 0, .. })
+        ));
+        assert!(matches!(
+            registry.check_sufficient_data(&data, &indicators),
+            Err(BacktestError::InsufficientData { required: 20, available: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_registry_rebuilt_after_drop_reads_indicator_from_disk() {
+        use crate::indicator_cache::{DiskCacheConfig, IndicatorDiskCache};
+
+        let dir = std::env::temp_dir().join(format!("backtest_registry_cache_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let open_registry = || IndicatorRegistry::new(false).unwrap().with_disk_cache(
+            IndicatorDiskCache::new(DiskCacheConfig { dir: dir.clone(), ..DiskCacheConfig::default() }).unwrap()
+        );
+        let data = market_data();
+
+        // First run computes and persists
+        let mut first = open_registry();
+        let computed = first.calculate("ema", &data).unwrap();
+        assert_eq!(first.cache_stats(), CacheStats { memory_hits: 0, disk_hits: 0, computed: 1 });
+
+        // Nothing survives in memory: a registry rebuilt over the same directory, as a
+        // new process would, loads instead of recomputing
+        drop(first);
+        let mut second = open_registry();
+        let loaded = second.calculate("ema", &data).unwrap();
+        assert_eq!(second.cache_stats(), CacheStats { memory_hits: 0, disk_hits: 1, computed: 0 });
+        assert_eq!(
+            crate::canonical::to_canonical_json(&loaded).unwrap(),
+            crate::canonical::to_canonical_json(&computed).unwrap()
+        );
+
+        // Different bars miss the cache
+        let mut shorter = data.clone();
+        shorter.bars.pop();
+        let mut changed = open_registry();
+        changed.calculate("ema", &shorter).unwrap();
+        assert_eq!(changed.cache_stats().computed, 1);
+
+        second.clear_disk_cache().unwrap();
+        let mut third = open_registry();
+        third.calculate("ema", &data).unwrap();
+        assert_eq!(third.cache_stats().computed, 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -5,18 +5,32 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use tracing::{info, warn, error};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
 
 pub mod indicators;
+pub mod indicator_cache;
+pub mod streaming;
+pub mod strategy;
 pub mod simulator;
 pub mod precision;
 pub mod wasm;
 pub mod types;
 pub mod trade_table;
 pub mod export;
+pub mod canonical;
+pub mod versioning;
+pub mod data;
+pub mod timeframe;
+pub mod error;
+pub mod session;
+pub mod diff;
+pub mod ratios;
+pub mod monitoring;
+pub mod test_support;
 
 use types::*;
 
@@ -33,6 +47,37 @@ pub struct EngineConfig {
     pub prealloc_size: usize,
     /// Deterministic random seed
     pub random_seed: u64,
+    /// Tick/lot/fee rules per symbol; unlisted symbols fall back to `ExchangeRules::default()`
+    #[serde(default)]
+    pub symbol_rules: HashMap<String, ExchangeRules>,
+    /// Run every symbol twice and fail the job if the two results differ
+    #[serde(default)]
+    pub verify_determinism: bool,
+    /// How missing bars are handled before indicators run
+    #[serde(default)]
+    pub gap_policy: data::GapPolicy,
+    /// Bars at the start of each symbol during which entries are rejected
+    /// 
+    /// `None` uses the longest warmup among the strategy's indicators, so the first,
+    /// thinly seeded values are never traded on.
+    #[serde(default)]
+    pub warmup_bars: Option<u64>,
+    /// Drop each symbol's last bar when it is still forming at the job's `as_of` time
+    #[serde(default)]
+    pub exclude_last_partial_bar: bool,
+    /// Advance indicators alongside the bar loop instead of materializing their series
+    /// 
+    /// Holds only each indicator's current value, for datasets too large to keep every
+    /// series in memory. Strategies needing an indicator without a streaming form fall
+    /// back to the materialized path.
+    #[serde(default)]
+    pub streaming_indicators: bool,
+    /// Round price-denominated indicator outputs to each symbol's price precision
+    /// 
+    /// Off by default so strategies see the same full-precision values as before; a
+    /// strategy declaring its own `price_precision` is rounded either way.
+    #[serde(default)]
+    pub round_indicator_prices: bool,
 }
 
 impl Default for EngineConfig {
@@ -43,6 +88,13 @@ impl Default for EngineConfig {
             cpu_affinity: None,
             prealloc_size: 1_000_000, // 1M bars
             random_seed: 42,
+            symbol_rules: HashMap::new(),
+            verify_determinism: false,
+            gap_policy: data::GapPolicy::default(),
+            warmup_bars: None,
+            exclude_last_partial_bar: false,
+            streaming_indicators: false,
+            round_indicator_prices: false,
         }
     }
 }
@@ -68,8 +120,38 @@ pub struct BacktestJob {
     pub slippage_mode: SlippageMode,
     /// Strategy WASM hash
     pub strategy_wasm_hash: String,
+    /// Parameters handed to the strategy's `strategy_init`, letting one module serve a sweep
+    #[serde(default)]
+    pub strategy_params: serde_json::Value,
     /// Data snapshot ID
     pub snapshot_id: String,
+    /// Days per year used to annualize Sharpe, Sortino and Calmar
+    #[serde(default)]
+    pub annualization: timeframe::AnnualizationConvention,
+    /// Annual risk-free rate (0.04 for 4%) that Sharpe and Sortino measure returns against
+    #[serde(default)]
+    pub risk_free_rate: Decimal,
+    /// Simple or log per-bar returns for Sharpe, Sortino, Calmar and volatility
+    #[serde(default)]
+    pub return_method: ratios::ReturnMethod,
+    /// When the data was pulled (Unix milliseconds), for `exclude_last_partial_bar`;
+    /// `None` uses the time the job starts, recorded in its result's manifest
+    #[serde(default)]
+    pub as_of: Option<u64>,
+    /// Positions already held when the run starts, for continuation runs and live handoff
+    /// 
+    /// Each is seeded into its symbol's run and exits on its own take profit, stop loss
+    /// or time to live, with PnL measured from its `entry_price`.
+    #[serde(default)]
+    pub initial_positions: Vec<ActivePosition>,
+    /// Whether entries fill on their signal's bar or at the following bar's open
+    #[serde(default)]
+    pub fill_model: FillModel,
+    /// Sizing, risk limits, sessions and the other trade table settings for the run
+    /// 
+    /// Its `fill_model` and `annualization` are replaced by the job's own.
+    #[serde(default)]
+    pub trade_table: trade_table::TradeTableConfig,
 }
 
 /// Intrabar simulation policies
@@ -83,6 +165,21 @@ pub enum IntrabarPolicy {
     LinearInterpolation,
 }
 
+/// When an entry signal fills, relative to the bar it was emitted on
+/// 
+/// Only entries move: take profit, stop loss and time to live are still checked
+/// against each bar's intrabar path once the position is open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FillModel {
+    /// Fill on the signal's bar, at the price its `IntrabarPolicy` gives
+    /// (the close under `ExactTrades`)
+    #[default]
+    SameBar,
+    /// Fill at the next bar's open, the signal having been computed on the close;
+    /// signals on a symbol's last bar never fill
+    NextBarOpen,
+}
+
 /// Slippage simulation modes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SlippageMode {
@@ -92,8 +189,158 @@ pub enum SlippageMode {
     TradeSweep,
     /// Synthetic order book (L2)
     SyntheticBook,
+    /// Fixed number of ticks against the fill, for tick-dominated instruments
+    FixedTicks(u32),
+    /// Random adverse slippage per fill, as a fraction of price (0.0005 for 5 bps)
+    /// 
+    /// Each fill draws from a normal distribution of `mean` and `stddev`, floored at
+    /// zero, seeded from `EngineConfig::random_seed` and the symbol: a seed always
+    /// reproduces the same draws for a symbol, whichever symbols the job ran before it,
+    /// and sweeping seeds gives Monte Carlo spreads of execution cost.
+    Stochastic {
+        mean: Decimal,
+        stddev: Decimal,
+    },
+}
+
+/// Fee schedules the engine can apply, by `BacktestJob::fee_version`
+pub const SUPPORTED_FEE_VERSIONS: &[&str] = &["v1"];
+
+/// Problem found by [`BacktestEngine::validate_job`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobProblem {
+    /// `end_time` is not after `start_time`
+    InvalidTimeRange { start_time: u64, end_time: u64 },
+    /// No symbols to backtest
+    NoSymbols,
+    /// Timeframe string does not parse
+    InvalidTimeframe(String),
+    /// No strategy module referenced
+    MissingStrategyHash,
+    /// No data snapshot referenced
+    MissingSnapshot,
+    /// Fee version not in [`SUPPORTED_FEE_VERSIONS`]
+    UnknownFeeVersion(String),
+    /// Strategy needs indicators or parameters the engine cannot provide
+    Strategy(wasm::StrategyValidationError),
+    /// Initial position on a symbol outside the job, or with no quantity or price
+    InvalidInitialPosition(String),
+}
+
+impl std::fmt::Display for JobProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobProblem::InvalidTimeRange { start_time, end_time } => {
+                write!(f, "end_time {} is not after start_time {}", end_time, start_time)
+            }
+            JobProblem::NoSymbols => write!(f, "no symbols"),
+            JobProblem::InvalidTimeframe(timeframe) => write!(f, "unknown timeframe '{}'", timeframe),
+            JobProblem::MissingStrategyHash => write!(f, "no strategy hash"),
+            JobProblem::MissingSnapshot => write!(f, "no snapshot"),
+            JobProblem::UnknownFeeVersion(version) => write!(f, "unknown fee version '{}'", version),
+            JobProblem::Strategy(err) => write!(f, "{}", err),
+            JobProblem::InvalidInitialPosition(symbol) => write!(f, "invalid initial position on '{}'", symbol),
+        }
+    }
+}
+
+/// Job rejected by [`BacktestJob::validate`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobValidationError {
+    pub job_id: String,
+    /// Every problem found, in check order
+    pub problems: Vec<JobProblem>,
+}
+
+impl std::fmt::Display for JobValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Job '{}' is invalid", self.job_id)?;
+        for (i, problem) in self.problems.iter().enumerate() {
+            write!(f, "{} {}", if i == 0 { ":" } else { ";" }, problem)?;
+        }
+        Ok(())
+    }
 }
 
+impl std::error::Error for JobValidationError {}
+
+impl BacktestJob {
+    /// Check the job's own fields, before any strategy or data is loaded
+    /// 
+    /// Collects every problem into one error rather than failing on the first.
+    /// [`BacktestEngine::validate_job`] adds the checks that need the strategy.
+    pub fn validate(&self) -> std::result::Result<(), JobValidationError> {
+        let mut problems = Vec::new();
+        
+        if self.end_time <= self.start_time {
+            problems.push(JobProblem::InvalidTimeRange {
+                start_time: self.start_time,
+                end_time: self.end_time,
+            });
+        }
+        if self.symbols.is_empty() {
+            problems.push(JobProblem::NoSymbols);
+        }
+        if self.timeframe.parse::<timeframe::Timeframe>().is_err() {
+            problems.push(JobProblem::InvalidTimeframe(self.timeframe.clone()));
+        }
+        if self.strategy_wasm_hash.trim().is_empty() {
+            problems.push(JobProblem::MissingStrategyHash);
+        }
+        // Only that one is named; whether it holds data is left to `validate_job`
+        if self.snapshot_id.trim().is_empty() {
+            problems.push(JobProblem::MissingSnapshot);
+        }
+        if !SUPPORTED_FEE_VERSIONS.contains(&self.fee_version.as_str()) {
+            problems.push(JobProblem::UnknownFeeVersion(self.fee_version.clone()));
+        }
+        for position in &self.initial_positions {
+            if !self.symbols.contains(&position.symbol)
+                || position.quantity <= Decimal::ZERO
+                || position.entry_price <= Decimal::ZERO
+            {
+                problems.push(JobProblem::InvalidInitialPosition(position.symbol.clone()));
+            }
+        }
+        
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(JobValidationError {
+                job_id: self.job_id.clone(),
+                problems,
+            })
+        }
+    }
+}
+
+/// Check [`BacktestEngine::validate_job`] could not carry out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UncheckedItem {
+    /// Whether the snapshot has data for the job's symbols and time range; there is no
+    /// data store to look it up in yet
+    SnapshotAvailability,
+}
+
+/// Outcome of a dry-run job validation
+#[derive(Debug, Clone)]
+pub struct ValidationReport {
+    pub job_id: String,
+    /// Every problem found, in check order; empty when the job can run
+    pub problems: Vec<JobProblem>,
+    /// Checks that were skipped, which `is_valid` says nothing about
+    pub unchecked: Vec<UncheckedItem>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Symbol of the market data [`BacktestEngine::run_on_bars`] builds around its bars
+pub const IN_MEMORY_SYMBOL: &str = "BARS";
+
 /// Main backtesting engine
 pub struct BacktestEngine {
     config: EngineConfig,
@@ -101,6 +348,9 @@ pub struct BacktestEngine {
     simulator: simulator::ExchangeSimulator,
     wasm_runtime: wasm::WasmRuntime,
     performance_metrics: PerformanceMetrics,
+    metrics: Option<Arc<monitoring::MetricsCollector>>,
+    /// Bars served in place of loading, by symbol
+    preloaded_bars: HashMap<String, Vec<Bar>>,
 }
 
 impl BacktestEngine {
@@ -115,7 +365,7 @@ impl BacktestEngine {
         
         // Initialize components
         let indicators = indicators::IndicatorRegistry::new(config.enable_simd)?;
-        let simulator = simulator::ExchangeSimulator::new()?;
+        let simulator = Self::job_simulator(&config, trade_table::TradeTableConfig::default())?;
         let wasm_runtime = wasm::WasmRuntime::new()?;
         
         Ok(Self {
@@ -124,30 +374,94 @@ impl BacktestEngine {
             simulator,
             wasm_runtime,
             performance_metrics: PerformanceMetrics::new(),
+            metrics: None,
+            preloaded_bars: HashMap::new(),
         })
     }
     
+    /// Simulator for one job, seeded from the engine config
+    fn job_simulator(config: &EngineConfig, trade_table: trade_table::TradeTableConfig) -> Result<simulator::ExchangeSimulator> {
+        let mut simulator = simulator::ExchangeSimulator::with_config(trade_table, config.prealloc_size)?;
+        simulator.set_random_seed(config.random_seed);
+        Ok(simulator)
+    }
+    
+    /// Start `job` on a fresh simulator, so nothing carries over from the last job
+    fn start_job(&mut self, job: &BacktestJob) -> Result<()> {
+        let trade_table = trade_table::TradeTableConfig {
+            annualization: job.annualization,
+            ..job.trade_table.clone()
+        };
+        self.simulator = Self::job_simulator(&self.config, trade_table)?;
+        Ok(())
+    }
+    
+    /// `job` with its data cutoff fixed, so every symbol drops the same partial bar
+    fn pin_as_of(&self, mut job: BacktestJob) -> BacktestJob {
+        if self.config.exclude_last_partial_bar && job.as_of.is_none() {
+            job.as_of = Some(chrono::Utc::now().timestamp_millis() as u64);
+        }
+        job
+    }
+    
+    /// Report to `metrics` (currently determinism violations)
+    pub fn with_metrics(mut self, metrics: Arc<monitoring::MetricsCollector>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+    
+    /// Run jobs on `bars` for `symbol` instead of loading its data
+    pub fn with_bars(mut self, symbol: &str, bars: Vec<Bar>) -> Self {
+        self.preloaded_bars.insert(symbol.to_string(), bars);
+        self
+    }
+    
+    /// Settings this engine runs jobs with
+    pub fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+    
     /// Execute a backtesting job
     pub async fn execute_job(&mut self, job: BacktestJob) -> Result<BacktestResult> {
         info!("Executing backtest job: {}", job.job_id);
         
+        job.validate()?;
+        let job = self.pin_as_of(job);
+        
         let start_time = std::time::Instant::now();
+        self.performance_metrics.phase_timings = PhaseTimings::default();
+        
+        self.start_job(&job)?;
         
         // Load strategy WASM
-        let strategy = self.wasm_runtime.load_strategy(&job.strategy_wasm_hash).await?;
+        let strategy = self.wasm_runtime.load_strategy(&job.strategy_wasm_hash, &job.strategy_params).await?;
         
-        // Execute backtest for each symbol
-        let mut symbol_results = Vec::new();
+        // Fail fast on unsupported indicators or out-of-bounds parameters
+        strategy.get_metadata()?.validate(&self.indicators)?;
+        
+        // One instance per symbol, as the symbols' bars interleave
+        let mut strategies = vec![strategy];
+        for _ in 1..job.symbols.len() {
+            strategies.push(self.wasm_runtime.load_strategy(&job.strategy_wasm_hash, &job.strategy_params).await?);
+        }
+        self.performance_metrics.phase_timings.wasm_ms += elapsed_ms(start_time);
+        
+        // Load every symbol's data, then backtest them together in time order
+        let phase_start = std::time::Instant::now();
+        let mut markets = Vec::with_capacity(job.symbols.len());
         for symbol in &job.symbols {
-            let symbol_result = self.execute_symbol_backtest(
-                symbol,
-                &job,
-                &strategy,
-            ).await?;
-            symbol_results.push(symbol_result);
+            info!("Backtesting symbol: {}", symbol);
+            markets.push(self.load_market_data(symbol, &job).await?);
         }
+        self.performance_metrics.phase_timings.data_load_ms += elapsed_ms(phase_start);
+        
+        let mut strategies: Vec<&mut dyn strategy::Strategy> = strategies.iter_mut()
+            .map(|strategy| strategy as &mut dyn strategy::Strategy)
+            .collect();
+        let symbol_results = self.run_symbols(markets, &job, &mut strategies).await?;
         
         let execution_time = start_time.elapsed();
+        self.performance_metrics.execution_time_ms = execution_time.as_millis() as u64;
         
         // Compile results
         let result = BacktestResult {
@@ -162,49 +476,309 @@ impl BacktestEngine {
         Ok(result)
     }
     
-    /// Execute backtest for a single symbol
-    async fn execute_symbol_backtest(
-        &mut self,
-        symbol: &str,
-        job: &BacktestJob,
-        strategy: &wasm::Strategy,
-    ) -> Result<SymbolResult> {
-        info!("Backtesting symbol: {}", symbol);
+    /// Check a job without loading bars or running the simulation
+    /// 
+    /// Collects every problem rather than stopping at the first, so a UI can show them
+    /// together. Errors are reserved for failures of the check itself (e.g. the strategy
+    /// module cannot be loaded). Checks it cannot carry out, such as whether the snapshot
+    /// has data, are listed in [`ValidationReport::unchecked`].
+    pub async fn validate_job(&mut self, job: &BacktestJob) -> Result<ValidationReport> {
+        let mut problems = job.validate().err().map_or_else(Vec::new, |err| err.problems);
         
-        // Load market data
-        let market_data = self.load_market_data(symbol, job).await?;
+        if !problems.contains(&JobProblem::MissingStrategyHash) {
+            let strategy = self.wasm_runtime.load_strategy(&job.strategy_wasm_hash, &job.strategy_params).await?;
+            if let Err(err) = strategy.get_metadata()?.validate(&self.indicators) {
+                problems.push(JobProblem::Strategy(err));
+            }
+        }
+        let unchecked = if problems.contains(&JobProblem::MissingSnapshot) {
+            Vec::new()
+        } else {
+            vec![UncheckedItem::SnapshotAvailability]
+        };
         
-        // Initialize indicators
-        let mut indicator_values = HashMap::new();
-        for indicator_name in strategy.get_required_indicators() {
-            let values = self.indicators.calculate(
-                &indicator_name,
-                &market_data,
-            )?;
-            indicator_values.insert(indicator_name, values);
+        Ok(ValidationReport {
+            job_id: job.job_id.clone(),
+            problems,
+            unchecked,
+        })
+    }
+    
+    /// Backtest `bars` with a native strategy, without a job, snapshot or WASM module
+    /// 
+    /// `strategy_fn` is called once per bar with the bar index and bar, as in
+    /// [`simulator::ExchangeSimulator::simulate_with_signals`]. The bars are validated by
+    /// [`data::MarketDataBuilder`] and the timeframe comes from their smallest spacing
+    /// (1m for a single bar), fills walk one-second
+    /// sub-bars without slippage, and trades carry [`IN_MEMORY_SYMBOL`] unless a signal
+    /// names a symbol. The run uses its own simulator, seeded and warmed up from the
+    /// engine config, so the engine's state is left untouched.
+    pub async fn run_on_bars<F>(&self, bars: Vec<Bar>, rules: ExchangeRules, strategy_fn: F) -> Result<SymbolResult>
+    where
+        F: FnMut(usize, &Bar) -> Vec<StrategySignal>,
+    {
+        let mut builder = data::MarketDataBuilder::new(IN_MEMORY_SYMBOL);
+        match bars.len() {
+            0 => return Err(error::BacktestError::InsufficientData {
+                symbol: IN_MEMORY_SYMBOL.to_string(),
+                indicator: "bars".to_string(),
+                required: 1,
+                available: 0,
+            }.into()),
+            1 => builder = builder.timeframe("1m"),
+            _ => {}
         }
+        let market_data = builder.bars(bars).rules(rules).build()?;
+        
+        let mut simulator = simulator::ExchangeSimulator::with_capacity(market_data.bars.len())?;
+        simulator.set_random_seed(self.config.random_seed);
+        simulator.set_warmup_bars(self.config.warmup_bars.unwrap_or(0));
+        let intrabar_policy = IntrabarPolicy::OneSecondBars;
+        let slippage_mode = SlippageMode::None;
         
-        // Run simulation
-        let simulation_result = self.simulator.simulate(
-            &market_data,
-            &indicator_values,
-            strategy,
-            &job.intrabar_policy,
-            &job.slippage_mode,
-        ).await?;
+        let simulation_result = simulator.simulate_with_signals(&market_data, strategy_fn, &intrabar_policy, &slippage_mode).await?;
+        simulator.finish(market_data.bars.last(), &market_data.rules, &slippage_mode)?;
         
         Ok(SymbolResult {
-            symbol: symbol.to_string(),
+            symbol: market_data.symbol.clone(),
             trades: simulation_result.trades,
             positions: simulation_result.positions,
             equity_curve: simulation_result.equity_curve,
             drawdown: simulation_result.max_drawdown,
             exposure: simulation_result.exposure,
-            attribution: simulation_result.attribution,
-            trade_table: Some(self.simulator.get_trade_table_result()),
+            net_exposure: simulation_result.net_exposure,
+            attribution: simulator.attribution(),
+            trade_table: Some(simulator.get_trade_table_result()),
+            data_quality: None,
         })
     }
     
+    /// Backtest already loaded data with any [`strategy::Strategy`], WASM or native
+    /// 
+    /// Runs the same pipeline as [`BacktestEngine::execute_job`] for one symbol: data
+    /// preparation, indicators, simulation and the trade table, with the job supplying
+    /// the intrabar policy, slippage, trade table and partial-bar settings. Each call
+    /// starts from a fresh simulator.
+    pub async fn run_strategy(
+        &mut self,
+        market_data: MarketData,
+        job: &BacktestJob,
+        strategy: &mut dyn strategy::Strategy,
+    ) -> Result<SymbolResult> {
+        self.start_job(job)?;
+        self.run_symbol(market_data, job, strategy).await
+    }
+    
+    /// Prepare and backtest one symbol's data on the job's simulator
+    async fn run_symbol(
+        &mut self,
+        market_data: MarketData,
+        job: &BacktestJob,
+        strategy: &mut dyn strategy::Strategy,
+    ) -> Result<SymbolResult> {
+        let mut results = self.run_symbols(vec![market_data], job, &mut [strategy]).await?;
+        results.pop().context("no result for the symbol")
+    }
+    
+    /// Prepare and backtest symbols' data together on the job's simulator
+    /// 
+    /// `strategies` pairs with `markets`, one instance per symbol.
+    async fn run_symbols(
+        &mut self,
+        mut markets: Vec<MarketData>,
+        job: &BacktestJob,
+        strategies: &mut [&mut dyn strategy::Strategy],
+    ) -> Result<Vec<SymbolResult>> {
+        let phase_start = std::time::Instant::now();
+        let data_quality = markets.iter_mut()
+            .map(|market_data| self.prepare_market_data(market_data, job))
+            .collect::<Result<Vec<_>>>()?;
+        self.performance_metrics.phase_timings.data_load_ms += elapsed_ms(phase_start);
+        
+        let mut results = if self.config.verify_determinism {
+            self.backtest_verified(&markets, job, strategies).await?
+        } else {
+            self.backtest_symbols(&markets, job, strategies).await?
+        };
+        for (result, data_quality) in results.iter_mut().zip(data_quality) {
+            result.data_quality = Some(data_quality);
+        }
+        Ok(results)
+    }
+    
+    /// Normalize, validate and gap-fill loaded data, dropping a partial last bar if configured
+    fn prepare_market_data(&self, market_data: &mut MarketData, job: &BacktestJob) -> Result<data::DataQualityReport> {
+        data::normalize_timestamps(market_data)?;
+        data::validate_trade_counts(&market_data.bars)?;
+        
+        let partial_bar = if self.config.exclude_last_partial_bar {
+            let as_of = job.as_of.context("exclude_last_partial_bar needs the job's as_of time")?;
+            data::exclude_partial_last_bar(market_data, as_of)?
+        } else {
+            None
+        };
+        
+        let mut data_quality = data::fill_gaps(market_data, self.config.gap_policy)?;
+        data_quality.partial_bar = partial_bar;
+        Ok(data_quality)
+    }
+    
+    /// Backtest the data twice from the same simulator state and require identical results
+    /// 
+    /// The second run starts from a checkpoint taken before the first and recomputes
+    /// indicators instead of reading them from the in-memory cache.
+    async fn backtest_verified(
+        &mut self,
+        markets: &[MarketData],
+        job: &BacktestJob,
+        strategies: &mut [&mut dyn strategy::Strategy],
+    ) -> Result<Vec<SymbolResult>> {
+        let checkpoint = self.simulator.snapshot();
+        let first = self.backtest_symbols(markets, job, strategies).await?;
+        
+        self.simulator = simulator::ExchangeSimulator::restore(checkpoint);
+        self.indicators.clear_memory_cache();
+        let second = self.backtest_symbols(markets, job, strategies).await?;
+        
+        for (first, second) in first.iter().zip(&second) {
+            self.check_determinism(&job.job_id, first, second)?;
+        }
+        Ok(second)
+    }
+    
+    /// Compare two runs of one symbol, recording a violation if they differ
+    fn check_determinism(&self, job_id: &str, first: &SymbolResult, second: &SymbolResult) -> Result<()> {
+        let first_json = canonical::to_canonical_json(first)?;
+        let second_json = canonical::to_canonical_json(second)?;
+        if first_json == second_json {
+            return Ok(());
+        }
+        
+        if let Some(metrics) = &self.metrics {
+            metrics.record_determinism_violation(job_id);
+        }
+        
+        // Canonical JSON of one part of a symbol's result
+        type Component = fn(&SymbolResult) -> Result<String>;
+        let mut differing = Vec::new();
+        let components: [(&str, Component); 4] = [
+            ("trades", |r| canonical::to_canonical_json(&r.trades)),
+            ("positions", |r| canonical::to_canonical_json(&r.positions)),
+            ("equity_curve", |r| canonical::to_canonical_json(&r.equity_curve)),
+            ("trade_table", |r| canonical::to_canonical_json(&r.trade_table)),
+        ];
+        for (name, component) in components {
+            if component(first)? != component(second)? {
+                differing.push(name);
+            }
+        }
+        
+        Err(error::BacktestError::DeterminismViolation {
+            symbol: first.symbol.clone(),
+            detail: format!(
+                "result hash {:x} vs {:x}, differing: {}",
+                Sha256::digest(first_json.as_bytes()),
+                Sha256::digest(second_json.as_bytes()),
+                if differing.is_empty() { "summary fields".to_string() } else { differing.join(", ") },
+            ),
+        }.into())
+    }
+    
+    /// Run indicators, then simulate `markets` together with their bars in time order
+    /// 
+    /// Each symbol runs its own strategy instance from `strategies`, draws stochastic
+    /// slippage from its own seed and gets a trade table of its own trades; positions and
+    /// portfolio limits are the account's. Each phase's wall-clock time is added to
+    /// `performance_metrics.phase_timings`.
+    async fn backtest_symbols(
+        &mut self,
+        markets: &[MarketData],
+        job: &BacktestJob,
+        strategies: &mut [&mut dyn strategy::Strategy],
+    ) -> Result<Vec<SymbolResult>> {
+        let phase_start = std::time::Instant::now();
+        let mut indicator_streams = Vec::with_capacity(markets.len());
+        let mut indicator_values = Vec::with_capacity(markets.len());
+        let mut warmup_bars = 0;
+        for (market_data, strategy) in markets.iter().zip(strategies.iter_mut()) {
+            strategy.reset()?;
+            
+            // Refuse to run on fewer bars than the longest indicator warmup
+            let required_indicators = strategy.required_indicators();
+            let indicator_params = self.symbol_indicator_params(&**strategy, &required_indicators, &market_data.rules);
+            self.indicators.check_sufficient_data_with_params(market_data, &required_indicators, &indicator_params)?;
+            
+            let indicator_stream = if self.config.streaming_indicators {
+                self.indicators.stream(&required_indicators, &indicator_params)
+            } else {
+                None
+            };
+            
+            // Initialize indicators with the parameters the strategy declared, unless they stream
+            let mut values = HashMap::new();
+            if indicator_stream.is_none() {
+                for indicator_name in &required_indicators {
+                    let series = match indicator_params.get(indicator_name) {
+                        Some(params) => self.indicators.calculate_with_params(indicator_name, market_data, params)?,
+                        None => self.indicators.calculate(indicator_name, market_data)?,
+                    };
+                    values.insert(indicator_name.clone(), series);
+                }
+            }
+            let symbol_warmup = match self.config.warmup_bars {
+                Some(bars) => bars,
+                None => self.indicators.warmup_bars(&required_indicators, &indicator_params)? as u64,
+            };
+            warmup_bars = warmup_bars.max(symbol_warmup);
+            indicator_streams.push(indicator_stream);
+            indicator_values.push(values);
+            self.simulator.seed_positions(job.initial_positions.iter().filter(|p| p.symbol == market_data.symbol));
+        }
+        self.simulator.set_warmup_bars(warmup_bars);
+        self.simulator.set_fill_model(job.fill_model);
+        self.performance_metrics.phase_timings.indicator_ms += elapsed_ms(phase_start);
+        
+        // Run simulation; strategy calls inside it are counted as WASM time
+        let phase_start = std::time::Instant::now();
+        let mut runs: Vec<simulator::SymbolRun> = markets.iter()
+            .zip(strategies.iter_mut())
+            .zip(indicator_streams.iter_mut().zip(&indicator_values))
+            .map(|((market_data, strategy), (stream, values))| simulator::SymbolRun {
+                market_data,
+                indicators: match stream {
+                    Some(stream) => simulator::RunIndicators::Streaming(stream),
+                    None => simulator::RunIndicators::Materialized(values),
+                },
+                strategy: &mut **strategy,
+                seed: trade_table::symbol_seed(self.config.random_seed, &market_data.symbol),
+            })
+            .collect();
+        let simulation_results = self.simulator.simulate_symbols(&mut runs, &job.intrabar_policy, &job.slippage_mode).await?;
+        let wasm_ms = self.simulator.strategy_time().as_secs_f64() * 1000.0;
+        self.performance_metrics.phase_timings.wasm_ms += wasm_ms;
+        self.performance_metrics.phase_timings.simulation_ms += elapsed_ms(phase_start) - wasm_ms;
+        
+        // Build each symbol's trade table; the simulation already closed them out
+        let phase_start = std::time::Instant::now();
+        let results = markets.iter().zip(simulation_results)
+            .map(|(market_data, simulation_result)| SymbolResult {
+                symbol: market_data.symbol.clone(),
+                trades: simulation_result.trades,
+                positions: simulation_result.positions,
+                equity_curve: simulation_result.equity_curve,
+                drawdown: simulation_result.max_drawdown,
+                exposure: simulation_result.exposure,
+                net_exposure: simulation_result.net_exposure,
+                attribution: simulation_result.attribution,
+                trade_table: Some(self.simulator.get_symbol_trade_table_result(&market_data.symbol)),
+                data_quality: None,
+            })
+            .collect();
+        self.performance_metrics.phase_timings.trade_table_ms += elapsed_ms(phase_start);
+        
+        Ok(results)
+    }
+    
     /// Load market data for a symbol
     async fn load_market_data(
         &self,
@@ -216,12 +790,45 @@ impl BacktestEngine {
         Ok(MarketData {
             symbol: symbol.to_string(),
             timeframe: job.timeframe.clone(),
-            bars: Vec::new(),
+            bars: self.preloaded_bars.get(symbol).cloned().unwrap_or_default(),
             trades: Vec::new(),
-            rules: ExchangeRules::default(),
+            rules: self.rules_for(symbol),
+            timestamp_convention: TimestampConvention::OpenTime,
         })
     }
     
+    /// The strategy's indicator parameters, with price-denominated outputs rounded to the
+    /// symbol's price precision when `round_indicator_prices` is set and the strategy
+    /// chose none
+    fn symbol_indicator_params(
+        &self,
+        strategy: &dyn strategy::Strategy,
+        required_indicators: &[String],
+        rules: &ExchangeRules,
+    ) -> HashMap<String, IndicatorParams> {
+        let mut declared = strategy.indicator_params(&self.indicators);
+        required_indicators.iter()
+            .filter_map(|name| {
+                let mut params = declared.remove(name).or_else(|| self.indicators.default_params(name))?;
+                if self.config.round_indicator_prices {
+                    params.price_precision.get_or_insert(rules.precision_price);
+                }
+                Some((name.clone(), params))
+            })
+            .collect()
+    }
+    
+    /// Exchange rules configured for `symbol`, or the defaults with a warning
+    fn rules_for(&self, symbol: &str) -> ExchangeRules {
+        match self.config.symbol_rules.get(symbol) {
+            Some(rules) => rules.clone(),
+            None => {
+                warn!("No exchange rules configured for {}, using defaults", symbol);
+                ExchangeRules::default()
+            }
+        }
+    }
+    
     /// Set CPU affinity for deterministic performance
     fn set_cpu_affinity(cores: &[usize]) -> Result<()> {
         #[cfg(target_os = "linux")]
@@ -230,7 +837,7 @@ impl BacktestEngine {
             let pid = std::process::id();
             for &core in cores {
                 Command::new("taskset")
-                    .args(&["-cp", &core.to_string(), &pid.to_string()])
+                    .args(["-cp", &core.to_string(), &pid.to_string()])
                     .output()?;
             }
         }
@@ -248,6 +855,36 @@ pub struct PerformanceMetrics {
     pub memory_allocated_bytes: u64,
     pub cache_hits: u64,
     pub cache_misses: u64,
+    /// Where `execution_time_ms` went, summed over the job's symbols
+    #[serde(default)]
+    pub phase_timings: PhaseTimings,
+}
+
+/// Wall-clock milliseconds per engine phase
+/// 
+/// Fractional so that phases shorter than a millisecond still show up. `wasm_ms`
+/// covers loading the strategy and every call into it during simulation;
+/// `simulation_ms` excludes those calls.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhaseTimings {
+    pub data_load_ms: f64,
+    pub indicator_ms: f64,
+    pub wasm_ms: f64,
+    pub simulation_ms: f64,
+    /// Closing out positions and building the trade table
+    #[serde(alias = "export_ms")]
+    pub trade_table_ms: f64,
+}
+
+impl PhaseTimings {
+    /// Sum over all phases
+    pub fn total_ms(&self) -> f64 {
+        self.data_load_ms + self.indicator_ms + self.wasm_ms + self.simulation_ms + self.trade_table_ms
+    }
+}
+
+fn elapsed_ms(start: std::time::Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
 }
 
 impl PerformanceMetrics {
@@ -260,6 +897,7 @@ impl PerformanceMetrics {
             memory_allocated_bytes: 0,
             cache_hits: 0,
             cache_misses: 0,
+            phase_timings: PhaseTimings::default(),
         }
     }
 }
@@ -267,13 +905,22 @@ impl PerformanceMetrics {
 /// Run manifest for reproducibility
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RunManifest {
+    /// [`canonical::SCHEMA_VERSION`] of the result hashes; 0 predates versioning
+    #[serde(default)]
+    pub schema_version: u32,
     pub job_id: String,
     pub snapshot_id: String,
     pub engine_version: String,
     pub strategy_hash: String,
+    /// Parameters the strategy was initialized with
+    #[serde(default)]
+    pub strategy_params: serde_json::Value,
     pub intrabar_policy: String,
     pub fee_version: String,
     pub slippage_mode: String,
+    /// Data cutoff the partial last bar was judged against, when one was applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub as_of: Option<u64>,
     pub created_at: u64,
     pub cpu_features: Vec<String>,
     pub fp_flags: String,
@@ -282,13 +929,16 @@ pub struct RunManifest {
 impl RunManifest {
     fn from_job(job: &BacktestJob) -> Self {
         Self {
+            schema_version: canonical::SCHEMA_VERSION,
             job_id: job.job_id.clone(),
             snapshot_id: job.snapshot_id.clone(),
             engine_version: env!("CARGO_PKG_VERSION").to_string(),
             strategy_hash: job.strategy_wasm_hash.clone(),
+            strategy_params: job.strategy_params.clone(),
             intrabar_policy: format!("{:?}", job.intrabar_policy),
             fee_version: job.fee_version.clone(),
             slippage_mode: format!("{:?}", job.slippage_mode),
+            as_of: job.as_of,
             created_at: chrono::Utc::now().timestamp_millis() as u64,
             cpu_features: vec!["simd".to_string()], // Would detect actual features
             fp_flags: "nearest-even".to_string(),
@@ -296,3 +946,717 @@ impl RunManifest {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+
+    pub(crate) fn job() -> BacktestJob {
+        BacktestJob {
+            job_id: "job-1".to_string(),
+            symbols: vec!["BTCUSDT".to_string()],
+            timeframe: "1m".to_string(),
+            start_time: 1609459200000,
+            end_time: 1609545600000,
+            intrabar_policy: IntrabarPolicy::ExactTrades,
+            fee_version: "v1".to_string(),
+            slippage_mode: SlippageMode::None,
+            strategy_wasm_hash: "abc123".to_string(),
+            strategy_params: serde_json::Value::Null,
+            snapshot_id: "snap-1".to_string(),
+            annualization: timeframe::AnnualizationConvention::default(),
+            risk_free_rate: Decimal::ZERO,
+            return_method: ratios::ReturnMethod::default(),
+            as_of: None,
+            initial_positions: Vec::new(),
+            fill_model: FillModel::SameBar,
+            trade_table: trade_table::TradeTableConfig::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_job_reports_every_problem() {
+        let mut engine = BacktestEngine::new(EngineConfig {
+            prealloc_size: 0,
+            ..EngineConfig::default()
+        }).unwrap();
+
+        let report = engine.validate_job(&job()).await.unwrap();
+        assert!(report.is_valid(), "{:?}", report.problems);
+        assert_eq!(report.unchecked, vec![UncheckedItem::SnapshotAvailability]);
+
+        let mut bad = job();
+        bad.end_time = bad.start_time - 1;
+        bad.fee_version = "v9".to_string();
+        let report = engine.validate_job(&bad).await.unwrap();
+
+        assert!(!report.is_valid());
+        assert_eq!(report.problems, vec![
+            JobProblem::InvalidTimeRange { start_time: bad.start_time, end_time: bad.end_time },
+            JobProblem::UnknownFeeVersion("v9".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_job_validate_rejects_each_invalid_field() {
+        assert_eq!(job().validate(), Ok(()));
+
+        type Breakage = fn(&mut BacktestJob);
+        let cases: Vec<(Breakage, JobProblem)> = vec![
+            (|j| j.end_time = j.start_time, JobProblem::InvalidTimeRange { start_time: 1609459200000, end_time: 1609459200000 }),
+            (|j| j.symbols.clear(), JobProblem::NoSymbols),
+            (|j| j.timeframe = "7x".to_string(), JobProblem::InvalidTimeframe("7x".to_string())),
+            (|j| j.strategy_wasm_hash = " ".to_string(), JobProblem::MissingStrategyHash),
+            (|j| j.snapshot_id.clear(), JobProblem::MissingSnapshot),
+            (|j| j.fee_version = "v9".to_string(), JobProblem::UnknownFeeVersion("v9".to_string())),
+            (|j| j.initial_positions.push(initial_long("ETHUSDT", dec!(100.0), dec!(105.0))), JobProblem::InvalidInitialPosition("ETHUSDT".to_string())),
+        ];
+        for (break_field, problem) in &cases {
+            let mut bad = job();
+            break_field(&mut bad);
+            assert_eq!(bad.validate().unwrap_err().problems, vec![problem.clone()]);
+        }
+
+        // Everything wrong at once is reported together, in check order
+        let mut bad = job();
+        for (break_field, _) in &cases {
+            break_field(&mut bad);
+        }
+        let err = bad.validate().unwrap_err();
+        assert_eq!(err.problems, cases.into_iter().map(|(_, problem)| problem).collect::<Vec<_>>());
+        assert!(err.to_string().starts_with("Job 'job-1' is invalid: end_time"));
+    }
+
+    #[tokio::test]
+    async fn test_fills_quantize_to_each_symbols_tick() {
+        let mut symbol_rules = HashMap::new();
+        symbol_rules.insert("BTCUSDT".to_string(), ExchangeRules {
+            tick_size: dec!(0.5),
+            ..ExchangeRules::default()
+        });
+        symbol_rules.insert("ETHUSDT".to_string(), ExchangeRules {
+            tick_size: dec!(0.01),
+            ..ExchangeRules::default()
+        });
+        let engine = BacktestEngine::new(EngineConfig {
+            prealloc_size: 0,
+            symbol_rules,
+            ..EngineConfig::default()
+        }).unwrap();
+
+        let mut job = job();
+        job.symbols = vec!["BTCUSDT".to_string(), "ETHUSDT".to_string()];
+        job.slippage_mode = SlippageMode::TradeSweep;
+
+        let mut fill_prices = HashMap::new();
+        for symbol in &job.symbols {
+            let mut market_data = engine.load_market_data(symbol, &job).await.unwrap();
+            market_data.bars = SyntheticDataGenerator::new(SyntheticDataConfig {
+                symbol: symbol.clone(),
+                bar_count: 50,
+                ..SyntheticDataConfig::default()
+            }, 7).generate_bars();
+
+            let buy_every_bar = |_: usize, _: &Bar| vec![StrategySignal {
+                side: TradeSide::Buy,
+                size: dec!(0.01),
+                entry_price: None,
+                take_profit: None,
+                stop_loss: None,
+                time_to_live: None,
+                rule_tag: None,
+                tags: Vec::new(),
+                symbol: String::new(),
+            }];
+            let mut simulator = simulator::ExchangeSimulator::new().unwrap();
+            let result = simulator.simulate_with_signals(
+                &market_data,
+                buy_every_bar,
+                &job.intrabar_policy,
+                &job.slippage_mode,
+            ).await.unwrap();
+
+            let tick = market_data.rules.tick_size;
+            assert!(result.trades.iter().all(|t| t.price % tick == dec!(0)), "{}", symbol);
+            fill_prices.insert(symbol.clone(), result.trades.iter().map(|t| t.price).collect::<Vec<_>>());
+        }
+
+        // The finer tick keeps prices the coarse tick would have rounded away
+        assert!(fill_prices["ETHUSDT"].iter().any(|p| p % dec!(0.5) != dec!(0)));
+
+        // Unconfigured symbols run on the defaults
+        let rules = engine.rules_for("SOLUSDT");
+        assert_eq!(rules.tick_size, ExchangeRules::default().tick_size);
+    }
+
+    #[tokio::test]
+    async fn test_partial_last_bar_is_not_traded() {
+        let bars = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 30,
+            ..SyntheticDataConfig::default()
+        }, 5).generate_bars();
+        let partial_open = bars.last().unwrap().timestamp;
+        let mut job = job();
+        // Pulled 30s into the last 1m bar
+        job.as_of = Some(partial_open + 30_000);
+
+        let buy_every_bar = |_: usize, _: &Bar| vec![StrategySignal {
+            side: TradeSide::Buy,
+            size: dec!(0.01),
+            entry_price: None,
+            take_profit: None,
+            stop_loss: None,
+            time_to_live: None,
+            rule_tag: None,
+            tags: Vec::new(),
+            symbol: String::new(),
+        }];
+        let mut entry_times = Vec::new();
+        for exclude_last_partial_bar in [true, false] {
+            let engine = BacktestEngine::new(EngineConfig {
+                prealloc_size: 0,
+                exclude_last_partial_bar,
+                ..EngineConfig::default()
+            }).unwrap();
+            let mut market_data = engine.load_market_data("BTCUSDT", &job).await.unwrap();
+            market_data.bars = bars.clone();
+            let report = engine.prepare_market_data(&mut market_data, &job).unwrap();
+            assert_eq!(report.partial_bar.is_some(), exclude_last_partial_bar);
+
+            let mut simulator = simulator::ExchangeSimulator::new().unwrap();
+            let result = simulator.simulate_with_signals(
+                &market_data,
+                buy_every_bar,
+                &job.intrabar_policy,
+                &job.slippage_mode,
+            ).await.unwrap();
+            entry_times.push(result.trades.iter().map(|t| t.timestamp).collect::<Vec<_>>());
+        }
+
+        assert!(!entry_times[0].contains(&partial_open), "{:?}", entry_times[0]);
+        assert!(entry_times[1].contains(&partial_open), "{:?}", entry_times[1]);
+
+        // A bar that has closed by `as_of` is kept
+        let mut closed = data::MarketDataBuilder::new("BTCUSDT").bars(bars).build().unwrap();
+        assert_eq!(data::exclude_partial_last_bar(&mut closed, partial_open + 60_000).unwrap(), None);
+        assert_eq!(closed.bars.len(), 30);
+    }
+
+    #[tokio::test]
+    async fn test_run_on_bars_with_native_strategy() {
+        let bars = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 10,
+            interval_ms: 300_000,
+            ..SyntheticDataConfig::default()
+        }, 9).generate_bars();
+        let engine = BacktestEngine::new(EngineConfig { prealloc_size: 0, ..EngineConfig::default() }).unwrap();
+
+        let result = engine.run_on_bars(bars, ExchangeRules::default(), |_, _| vec![StrategySignal {
+            side: TradeSide::Buy,
+            size: dec!(0.01),
+            entry_price: None,
+            take_profit: None,
+            stop_loss: None,
+            time_to_live: None,
+            rule_tag: None,
+            tags: Vec::new(),
+            symbol: String::new(),
+        }]).await.unwrap();
+
+        assert_eq!(result.symbol, IN_MEMORY_SYMBOL);
+        assert!(!result.trades.is_empty());
+        assert_eq!(result.equity_curve.len(), 10);
+        let trade_table = result.trade_table.unwrap();
+        assert!(!trade_table.trades.is_empty());
+        assert_eq!(trade_table.trades[0].symbol, IN_MEMORY_SYMBOL);
+
+        assert!(engine.run_on_bars(Vec::new(), ExchangeRules::default(), |_, _| Vec::new()).await.is_err());
+    }
+
+    /// Long of 10 opened a minute before the job starts, with no stop or expiry
+    fn initial_long(symbol: &str, entry_price: Decimal, take_profit: Decimal) -> ActivePosition {
+        ActivePosition {
+            symbol: symbol.to_string(),
+            trade_type: TradeType::Long,
+            entry_time: job().start_time - 60_000,
+            entry_price,
+            quantity: dec!(10.0),
+            take_profit: Some(take_profit),
+            stop_loss: None,
+            time_to_live: None,
+            bars_held: 0,
+            entry_fee: dec!(0.5),
+            entry_slippage_usd: dec!(0.0),
+            entry_outside_bar: false,
+            size_usd: entry_price * dec!(10.0),
+            liquidation_price: None,
+            tags: Vec::new(),
+            rule_tag: None,
+        }
+    }
+
+    /// Native strategy that never trades
+    struct Idle;
+
+    impl strategy::Strategy for Idle {
+        fn required_indicators(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        fn on_bar(&mut self, _ctx: &strategy::BarContext) -> Result<Vec<StrategySignal>> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_initial_position_exits_with_pnl_from_seeded_entry() {
+        let mut engine = BacktestEngine::new(EngineConfig { prealloc_size: 0, ..EngineConfig::default() }).unwrap();
+        let mut job = job();
+        job.initial_positions.push(initial_long("BTCUSDT", dec!(100.0), dec!(105.0)));
+        assert_eq!(job.validate(), Ok(()));
+
+        let mut market_data = engine.load_market_data("BTCUSDT", &job).await.unwrap();
+        market_data.bars = [dec!(101.0), dec!(103.0), dec!(106.0), dec!(104.0)].into_iter().enumerate()
+            .map(|(i, close)| Bar {
+                timestamp: job.start_time + i as u64 * 60_000,
+                open: close - dec!(1.0),
+                high: close,
+                low: close - dec!(2.0),
+                close,
+                volume: dec!(1000.0),
+                trade_count: 100,
+            })
+            .collect();
+        let rules = market_data.rules.clone();
+
+        let result = engine.run_strategy(market_data, &job, &mut Idle).await.unwrap();
+
+        // Marked to market from the seeded entry, net of its fee, before the take profit fills
+        assert_eq!(result.equity_curve[0].unrealized_pnl, dec!(10.0) - dec!(0.5));
+
+        let trades = result.trade_table.unwrap().trades;
+        assert_eq!(trades.len(), 1);
+        let trade = &trades[0];
+        assert_eq!(trade.exit_reason, ExitReason::TakeProfit);
+        assert_eq!(trade.entry_price, dec!(100.0));
+        assert_eq!(trade.exit_price, dec!(105.0));
+        assert_eq!(trade.entry_time_utc, "2020-12-31T23:59:00.000Z");
+        let exit_fee = trade_table::TradeTableGenerator::new()
+            .calculate_fee(dec!(10.0), dec!(105.0), Liquidity::Maker, &rules).unwrap();
+        assert_eq!(trade.fees_usd, dec!(0.5) + exit_fee);
+        assert_eq!(trade.pnl_usd, dec!(50.0) - dec!(0.5) - exit_fee);
+    }
+
+    /// Native strategy that buys on the given bar and holds to the end of the data
+    struct BuyAtBar(usize);
+
+    impl strategy::Strategy for BuyAtBar {
+        fn required_indicators(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        fn on_bar(&mut self, ctx: &strategy::BarContext) -> Result<Vec<StrategySignal>> {
+            if ctx.bar_index != self.0 {
+                return Ok(Vec::new());
+            }
+            Ok(vec![StrategySignal {
+                side: TradeSide::Buy,
+                size: dec!(1.0),
+                entry_price: None,
+                take_profit: None,
+                stop_loss: None,
+                time_to_live: None,
+                rule_tag: None,
+                tags: Vec::new(),
+                symbol: String::new(),
+            }])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stochastic_fills_do_not_depend_on_earlier_symbols() {
+        let mut job = job();
+        job.slippage_mode = SlippageMode::Stochastic { mean: dec!(0.001), stddev: dec!(0.0005) };
+        let btc_fills = |symbols: Vec<&'static str>| {
+            let job = job.clone();
+            async move {
+                let mut engine = BacktestEngine::new(EngineConfig { prealloc_size: 0, ..EngineConfig::default() }).unwrap();
+                engine.start_job(&job).unwrap();
+                let mut fills = Vec::new();
+                for symbol in symbols {
+                    let mut market_data = engine.load_market_data(symbol, &job).await.unwrap();
+                    market_data.bars = SyntheticDataGenerator::new(SyntheticDataConfig::default(), 9).generate_bars();
+                    fills = engine.run_symbol(market_data, &job, &mut BuyAtBar(0)).await.unwrap().trades;
+                }
+                fills.iter().map(|t| t.price).collect::<Vec<_>>()
+            }
+        };
+
+        let alone = btc_fills(vec!["BTCUSDT"]).await;
+        assert!(!alone.is_empty());
+        assert_eq!(btc_fills(vec!["ETHUSDT", "BTCUSDT"]).await, alone);
+        assert_ne!(trade_table::symbol_seed(42, "BTCUSDT"), trade_table::symbol_seed(42, "ETHUSDT"));
+    }
+
+    /// Trade tables of a BTCUSDT then ETHUSDT job capped at one open position, with
+    /// each symbol buying on the given bar of five flat bars
+    async fn capped_job_trade_tables(btc_entry: usize, eth_entry: usize) -> Vec<TradeTableResult> {
+        let mut engine = BacktestEngine::new(EngineConfig { prealloc_size: 0, ..EngineConfig::default() }).unwrap();
+        let mut job = job();
+        job.symbols = vec!["BTCUSDT".to_string(), "ETHUSDT".to_string()];
+        job.trade_table.risk_limits.max_open_positions = Some(1);
+
+        engine.start_job(&job).unwrap();
+        let mut markets = Vec::new();
+        for symbol in &job.symbols {
+            let mut market_data = engine.load_market_data(symbol, &job).await.unwrap();
+            market_data.bars = (0..5).map(|i| Bar {
+                timestamp: job.start_time + i * 60_000,
+                open: dec!(100.0),
+                high: dec!(101.0),
+                low: dec!(99.0),
+                close: dec!(100.0),
+                volume: dec!(1000.0),
+                trade_count: 100,
+            }).collect();
+            markets.push(market_data);
+        }
+        let results = engine.run_symbols(markets, &job, &mut [&mut BuyAtBar(btc_entry), &mut BuyAtBar(eth_entry)]).await.unwrap();
+        results.into_iter().map(|result| result.trade_table.unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_job_max_open_positions_counts_earlier_symbols() {
+        let tables = capped_job_trade_tables(0, 0).await;
+
+        // Both entered on the same bar; BTCUSDT is listed first and took the only slot
+        assert_eq!(tables[0].trades.len(), 1);
+        assert_eq!(tables[0].trades[0].symbol, "BTCUSDT");
+        assert!(tables[0].rejected_trades.is_empty());
+        assert!(tables[1].trades.is_empty());
+        assert_eq!(tables[1].rejected_trades.len(), 1);
+        assert_eq!(tables[1].rejected_trades[0].symbol, "ETHUSDT");
+        assert_eq!(tables[1].rejected_trades[0].reason, ReasonCode::MaxOpenPositions);
+    }
+
+    #[tokio::test]
+    async fn test_job_max_open_positions_counts_later_symbols_that_entered_first() {
+        let tables = capped_job_trade_tables(2, 1).await;
+
+        // ETHUSDT is listed second but entered a bar earlier, so BTCUSDT found the slot taken
+        assert!(tables[0].trades.is_empty());
+        assert_eq!(tables[0].rejected_trades.len(), 1);
+        assert_eq!(tables[0].rejected_trades[0].symbol, "BTCUSDT");
+        assert_eq!(tables[0].rejected_trades[0].reason, ReasonCode::MaxOpenPositions);
+        assert_eq!(tables[1].trades.len(), 1);
+        assert_eq!(tables[1].trades[0].symbol, "ETHUSDT");
+        assert_eq!(tables[1].trades[0].exit_reason, ExitReason::EndOfData);
+    }
+
+    /// Native strategy that records the indicator values it is shown
+    #[derive(Default)]
+    struct Recorder {
+        seen: Vec<(u64, HashMap<String, Option<Decimal>>)>,
+    }
+
+    impl strategy::Strategy for Recorder {
+        fn required_indicators(&self) -> Vec<String> {
+            vec!["ema".to_string(), "atr".to_string(), "rsi".to_string()]
+        }
+
+        fn on_bar(&mut self, ctx: &strategy::BarContext) -> Result<Vec<StrategySignal>> {
+            self.seen.push((ctx.bar.timestamp, ctx.indicators.clone()));
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_indicators_round_to_symbol_price_precision() {
+        let mut symbol_rules = HashMap::new();
+        symbol_rules.insert("BTCUSDT".to_string(), ExchangeRules {
+            precision_price: 2,
+            ..ExchangeRules::default()
+        });
+
+        for streaming_indicators in [false, true] {
+            let mut engine = BacktestEngine::new(EngineConfig {
+                prealloc_size: 0,
+                symbol_rules: symbol_rules.clone(),
+                streaming_indicators,
+                round_indicator_prices: true,
+                ..EngineConfig::default()
+            }).unwrap();
+            let job = job();
+            let mut market_data = engine.load_market_data("BTCUSDT", &job).await.unwrap();
+            market_data.bars = SyntheticDataGenerator::new(SyntheticDataConfig { bar_count: 200, ..SyntheticDataConfig::default() }, 5)
+                .generate_bars();
+
+            // Full-precision series to compare against
+            let mut registry = indicators::IndicatorRegistry::new(false).unwrap();
+            let full: HashMap<String, HashMap<u64, Decimal>> = ["ema", "atr", "rsi"].iter()
+                .map(|name| {
+                    let values = registry.calculate(name, &market_data).unwrap();
+                    (name.to_string(), values.into_iter().map(|v| (v.timestamp, v.value)).collect())
+                })
+                .collect();
+
+            let mut recorder = Recorder::default();
+            engine.run_strategy(market_data, &job, &mut recorder).await.unwrap();
+
+            let mut rsi_finer = false;
+            for (timestamp, values) in &recorder.seen {
+                for name in ["ema", "atr"] {
+                    if let Some(value) = values[name] {
+                        assert!(value.scale() <= 2, "{} {} at {}", name, value, timestamp);
+                        assert_eq!(value, full[name][timestamp].round_dp(2), "{} at {}", name, timestamp);
+                    }
+                }
+                // An oscillator is not a price and keeps its precision
+                if let Some(rsi) = values["rsi"] {
+                    assert_eq!(rsi, full["rsi"][timestamp]);
+                    rsi_finer |= rsi.scale() > 2;
+                }
+            }
+            assert!(rsi_finer);
+            assert!(recorder.seen.iter().any(|(_, values)| values["ema"].is_some()));
+        }
+
+        // Without opting in the series reach the strategy at full precision
+        let mut engine = BacktestEngine::new(EngineConfig {
+            prealloc_size: 0,
+            symbol_rules,
+            ..EngineConfig::default()
+        }).unwrap();
+        let job = job();
+        let mut market_data = engine.load_market_data("BTCUSDT", &job).await.unwrap();
+        market_data.bars = SyntheticDataGenerator::new(SyntheticDataConfig { bar_count: 200, ..SyntheticDataConfig::default() }, 5)
+            .generate_bars();
+        let ema: HashMap<u64, Decimal> = indicators::IndicatorRegistry::new(false).unwrap()
+            .calculate("ema", &market_data).unwrap()
+            .into_iter().map(|v| (v.timestamp, v.value)).collect();
+        let mut recorder = Recorder::default();
+        engine.run_strategy(market_data, &job, &mut recorder).await.unwrap();
+        assert!(recorder.seen.iter().any(|(timestamp, values)| values["ema"].is_some_and(|value| value.scale() > 2 && value == ema[timestamp])));
+    }
+
+    #[tokio::test]
+    async fn test_phase_timings_cover_symbol_backtest() {
+        let mut engine = BacktestEngine::new(EngineConfig {
+            prealloc_size: 0,
+            ..EngineConfig::default()
+        }).unwrap();
+        let job = job();
+        let mut strategy = engine.wasm_runtime.load_strategy(&job.strategy_wasm_hash, &job.strategy_params).await.unwrap();
+        let mut market_data = engine.load_market_data("BTCUSDT", &job).await.unwrap();
+        market_data.bars = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 2_000,
+            ..SyntheticDataConfig::default()
+        }, 11).generate_bars();
+
+        engine.backtest_symbols(std::slice::from_ref(&market_data), &job, &mut [&mut strategy]).await.unwrap();
+
+        // Wall-clock figures vary run to run; only check each phase was timed
+        let timings = &engine.performance_metrics.phase_timings;
+        for (phase, ms) in [
+            ("indicator", timings.indicator_ms),
+            ("wasm", timings.wasm_ms),
+            ("simulation", timings.simulation_ms),
+            ("trade_table", timings.trade_table_ms),
+        ] {
+            assert!(ms >= 0.0, "{} {:?}", phase, timings);
+        }
+        assert!(timings.total_ms() > 0.0, "{:?}", timings);
+    }
+
+    #[tokio::test]
+    async fn test_determinism_check_flags_diverging_runs() {
+        let metrics = Arc::new(monitoring::MetricsCollector::new().unwrap());
+        let mut engine = BacktestEngine::new(EngineConfig {
+            prealloc_size: 0,
+            verify_determinism: true,
+            ..EngineConfig::default()
+        }).unwrap().with_metrics(metrics.clone());
+        let job = job();
+        let mut strategy = engine.wasm_runtime.load_strategy(&job.strategy_wasm_hash, &job.strategy_params).await.unwrap();
+        let mut market_data = engine.load_market_data("BTCUSDT", &job).await.unwrap();
+        market_data.bars = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 200,
+            ..SyntheticDataConfig::default()
+        }, 3).generate_bars();
+
+        // The engine itself is deterministic
+        engine.backtest_verified(std::slice::from_ref(&market_data), &job, &mut [&mut strategy]).await.unwrap();
+        assert_eq!(metrics.determinism_violations.get(), 0.0);
+
+        // A signal source with hidden state behaves differently on its second run
+        let mut results = Vec::new();
+        for runs in 1..=2 {
+            let mut simulator = simulator::ExchangeSimulator::new().unwrap();
+            let stateful = |bar_idx: usize, _: &Bar| if bar_idx == 10 * runs {
+                vec![StrategySignal {
+                    side: TradeSide::Buy,
+                    size: dec!(0.01),
+                    entry_price: None,
+                    take_profit: None,
+                    stop_loss: None,
+                    time_to_live: None,
+                    rule_tag: None,
+                    tags: Vec::new(),
+                    symbol: String::new(),
+                }]
+            } else {
+                Vec::new()
+            };
+            let result = simulator.simulate_with_signals(&market_data, stateful, &job.intrabar_policy, &job.slippage_mode).await.unwrap();
+            results.push(SymbolResult {
+                symbol: market_data.symbol.clone(),
+                trades: result.trades,
+                positions: result.positions,
+                equity_curve: result.equity_curve,
+                drawdown: result.max_drawdown,
+                exposure: result.exposure,
+                net_exposure: result.net_exposure,
+                attribution: result.attribution,
+                trade_table: Some(simulator.get_trade_table_result()),
+                data_quality: None,
+            });
+        }
+
+        let err = engine.check_determinism(&job.job_id, &results[0], &results[1]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<error::BacktestError>(),
+            Some(error::BacktestError::DeterminismViolation { symbol, .. }) if symbol == "BTCUSDT"
+        ));
+        assert!(err.to_string().contains("trades"), "{}", err);
+        assert_eq!(metrics.determinism_violations.get(), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_engine_computes_indicators_declared_by_strategy() {
+        let mut engine = BacktestEngine::new(EngineConfig {
+            prealloc_size: 0,
+            ..EngineConfig::default()
+        }).unwrap();
+        let metadata = serde_json::json!({
+            "name": "macd_atr",
+            "required_indicators": ["macd", {"name": "atr", "period": 10}],
+        });
+        let module = wasm::abi::metadata_module(&serde_json::to_vec(&metadata).unwrap());
+        let mut strategy = engine.wasm_runtime.load_strategy_bytes(&module, &serde_json::Value::Null).unwrap();
+        assert_eq!(strategy.get_required_indicators(), vec!["macd".to_string(), "atr".to_string()]);
+
+        let job = job();
+        let mut market_data = engine.load_market_data("BTCUSDT", &job).await.unwrap();
+        market_data.bars = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 200,
+            ..SyntheticDataConfig::default()
+        }, 5).generate_bars();
+        engine.backtest_symbols(std::slice::from_ref(&market_data), &job, &mut [&mut strategy]).await.unwrap();
+        assert_eq!(engine.indicators.cache_stats().computed, 2);
+
+        // Both declared series are cached, the declared ATR period included
+        let macd_params = engine.indicators.default_params("macd").unwrap();
+        let atr_params = IndicatorParams { period: 10, ..engine.indicators.default_params("atr").unwrap() };
+        engine.indicators.calculate_with_params("macd", &market_data, &macd_params).unwrap();
+        engine.indicators.calculate_with_params("atr", &market_data, &atr_params).unwrap();
+        assert_eq!(engine.indicators.cache_stats().memory_hits, 2);
+        assert_eq!(engine.indicators.cache_stats().computed, 2);
+
+        // ema was never computed
+        engine.indicators.calculate("ema", &market_data).unwrap();
+        assert_eq!(engine.indicators.cache_stats().computed, 3);
+    }
+
+    #[tokio::test]
+    async fn test_strategy_params_reach_the_module() {
+        let mut engine = BacktestEngine::new(EngineConfig {
+            prealloc_size: 0,
+            ..EngineConfig::default()
+        }).unwrap();
+        let metadata = serde_json::json!({"name": "echo", "required_indicators": ["ema"]});
+        let module = test_support::echo_strategy_module(&serde_json::to_vec(&metadata).unwrap());
+
+        let mut job = job();
+        let mut market_data = engine.load_market_data("BTCUSDT", &job).await.unwrap();
+        market_data.bars = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 50,
+            ..SyntheticDataConfig::default()
+        }, 9).generate_bars();
+
+        // Same module, two parameter sets
+        let mut fills = Vec::new();
+        for (side, size) in [("Buy", "0.01"), ("Sell", "0.02")] {
+            job.strategy_params = serde_json::json!([{"side": side, "size": size}]);
+            let mut strategy = engine.wasm_runtime.load_strategy_bytes(&module, &job.strategy_params).unwrap();
+            engine.simulator = simulator::ExchangeSimulator::new().unwrap();
+            let mut results = engine.backtest_symbols(std::slice::from_ref(&market_data), &job, &mut [&mut strategy]).await.unwrap();
+            fills.push(results.remove(0).trades);
+        }
+
+        // Every bar after the 20-bar EMA burn-in fills
+        assert_eq!(fills[0].len(), market_data.bars.len() - 20);
+        assert!(fills[0].iter().all(|t| matches!(t.side, TradeSide::Buy) && t.quantity == dec!(0.01)));
+        assert_eq!(fills[1].len(), market_data.bars.len() - 20);
+        assert!(fills[1].iter().all(|t| matches!(t.side, TradeSide::Sell) && t.quantity == dec!(0.02)));
+
+        // Parameters for a module that cannot take them are an error, not silently dropped
+        let plain = wasm::abi::metadata_module(&serde_json::to_vec(&metadata).unwrap());
+        assert!(engine.wasm_runtime.load_strategy_bytes(&plain, &job.strategy_params).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verified_run_resets_wasm_strategy_state() {
+        let metrics = Arc::new(monitoring::MetricsCollector::new().unwrap());
+        let mut engine = BacktestEngine::new(EngineConfig {
+            prealloc_size: 0,
+            verify_determinism: true,
+            warmup_bars: Some(0),
+            ..EngineConfig::default()
+        }).unwrap().with_metrics(metrics.clone());
+        let metadata = serde_json::json!({"name": "first_bar", "required_indicators": ["ema"]});
+        let module = test_support::first_bar_strategy_module(&serde_json::to_vec(&metadata).unwrap());
+
+        let mut job = job();
+        job.strategy_params = serde_json::json!([{"side": "Buy", "size": "0.01"}]);
+        let mut strategy = engine.wasm_runtime.load_strategy_bytes(&module, &job.strategy_params).unwrap();
+        let mut market_data = engine.load_market_data("BTCUSDT", &job).await.unwrap();
+        market_data.bars = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 50,
+            ..SyntheticDataConfig::default()
+        }, 11).generate_bars();
+
+        // Both runs of the verified backtest see the module as loaded, so both fill once
+        let result = engine.run_strategy(market_data.clone(), &job, &mut strategy).await.unwrap();
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(metrics.determinism_violations.get(), 0.0);
+
+        // And so does the next run with the same strategy
+        let result = engine.run_strategy(market_data, &job, &mut strategy).await.unwrap();
+        assert_eq!(result.trades.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_partial_bar_cutoff_is_pinned_once_per_job() {
+        let engine = BacktestEngine::new(EngineConfig {
+            prealloc_size: 0,
+            exclude_last_partial_bar: true,
+            ..EngineConfig::default()
+        }).unwrap();
+        let job = job();
+        let mut market_data = engine.load_market_data("BTCUSDT", &job).await.unwrap();
+        market_data.bars = SyntheticDataGenerator::new(SyntheticDataConfig::default(), 5).generate_bars();
+
+        // Preparing data never reads the clock itself
+        assert!(engine.prepare_market_data(&mut market_data.clone(), &job).is_err());
+
+        let pinned = engine.pin_as_of(job);
+        assert!(pinned.as_of.is_some());
+        assert!(engine.prepare_market_data(&mut market_data, &pinned).is_ok());
+        assert_eq!(RunManifest::from_job(&pinned).as_of, pinned.as_of);
+
+        // An explicit cutoff is kept as given
+        let explicit = BacktestJob { as_of: Some(1609459200000), ..pinned };
+        assert_eq!(engine.pin_as_of(explicit).as_of, Some(1609459200000));
+    }
+}
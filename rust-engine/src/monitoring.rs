@@ -2,12 +2,11 @@
 //! 
 //! Implements Prometheus metrics and OpenTelemetry tracing for the backtesting engine.
 
-use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::Instant;
 use anyhow::Result;
 use prometheus::{
-    Counter, CounterVec, Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec,
+    Counter, Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec,
     Opts, Registry, TextEncoder,
 };
 use tracing::{info, warn, error, instrument};
@@ -175,7 +174,7 @@ impl MetricsCollector {
     }
     
     /// Record backtest start
-    #[instrument]
+    #[instrument(skip(self))]
     pub fn record_backtest_start(&self, job_id: &str) -> BacktestTimer {
         self.active_backtests.inc();
         self.backtest_total.inc();
@@ -183,7 +182,6 @@ impl MetricsCollector {
         BacktestTimer {
             start_time: Instant::now(),
             job_id: job_id.to_string(),
-            metrics: self,
         }
     }
     
@@ -204,13 +202,13 @@ impl MetricsCollector {
     }
     
     /// Record indicator calculation
-    pub fn record_indicator_calculation<F>(
+    pub fn record_indicator_calculation<F, T>(
         &self,
         indicator_name: &str,
         calculation: F,
-    ) -> Result<F::Output>
+    ) -> Result<T>
     where
-        F: FnOnce() -> Result<F::Output>,
+        F: FnOnce() -> Result<T>,
     {
         let start_time = Instant::now();
         let result = calculation()?;
@@ -226,9 +224,9 @@ impl MetricsCollector {
     }
     
     /// Record trade execution
-    pub fn record_trade_execution<F>(&self, execution: F) -> Result<F::Output>
+    pub fn record_trade_execution<F, T>(&self, execution: F) -> Result<T>
     where
-        F: FnOnce() -> Result<F::Output>,
+        F: FnOnce() -> Result<T>,
     {
         let start_time = Instant::now();
         let result = execution()?;
@@ -317,13 +315,12 @@ impl MetricsCollector {
 }
 
 /// Timer for tracking backtest execution
-pub struct BacktestTimer<'a> {
+pub struct BacktestTimer {
     start_time: Instant,
     job_id: String,
-    metrics: &'a MetricsCollector,
 }
 
-impl<'a> Drop for BacktestTimer<'a> {
+impl Drop for BacktestTimer {
     fn drop(&mut self) {
         // Timer is dropped when backtest completes
         // The actual completion is recorded by the caller
@@ -400,6 +397,11 @@ impl PerformanceMonitor {
         Self { metrics }
     }
     
+    /// Collector this monitor reports to
+    pub fn metrics(&self) -> &Arc<MetricsCollector> {
+        &self.metrics
+    }
+    
     /// Monitor a function execution
     pub async fn monitor_execution<F, R>(
         &self,
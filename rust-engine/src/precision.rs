@@ -6,8 +6,14 @@
 use rust_decimal::Decimal;
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::ops::{Add, Sub, Mul, Div};
 use anyhow::Result;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+use crate::error::BacktestError;
 
 /// Precision configuration for financial calculations
 #[derive(Debug, Clone)]
@@ -30,7 +36,7 @@ impl Default for PrecisionConfig {
 }
 
 /// Rounding modes for deterministic calculations
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RoundingMode {
     NearestEven,    // IEEE 754 default
     NearestAway,    // Round half away from zero
@@ -39,6 +45,22 @@ pub enum RoundingMode {
     TowardNegative, // Round toward negative infinity
 }
 
+/// Round `value` to `precision` decimal places in the given direction
+pub fn round_to_precision(value: Decimal, precision: u8, mode: RoundingMode) -> Decimal {
+    let scale = 10_u64.pow(precision as u32);
+    let scaled = value * Decimal::from(scale);
+    
+    let rounded = match mode {
+        RoundingMode::NearestEven => scaled.round(),
+        RoundingMode::NearestAway => scaled.round(),
+        RoundingMode::TowardZero => scaled.trunc(),
+        RoundingMode::TowardPositive => scaled.ceil(),
+        RoundingMode::TowardNegative => scaled.floor(),
+    };
+    
+    rounded / Decimal::from(scale)
+}
+
 /// Precision-aware decimal operations
 pub struct PreciseDecimal {
     value: Decimal,
@@ -49,7 +71,7 @@ pub struct PreciseDecimal {
 impl PreciseDecimal {
     pub fn new(value: Decimal, precision: u8, rounding_mode: RoundingMode) -> Self {
         Self {
-            value: Self::round_to_precision(value, precision, rounding_mode),
+            value: round_to_precision(value, precision, rounding_mode),
             precision,
             rounding_mode,
         }
@@ -69,22 +91,6 @@ impl PreciseDecimal {
         self.precision
     }
     
-    /// Round to specified precision using configured rounding mode
-    fn round_to_precision(value: Decimal, precision: u8, mode: RoundingMode) -> Decimal {
-        let scale = 10_u64.pow(precision as u32);
-        let scaled = value * Decimal::from(scale);
-        
-        let rounded = match mode {
-            RoundingMode::NearestEven => scaled.round(),
-            RoundingMode::NearestAway => scaled.round(),
-            RoundingMode::TowardZero => scaled.trunc(),
-            RoundingMode::TowardPositive => scaled.ceil(),
-            RoundingMode::TowardNegative => scaled.floor(),
-        };
-        
-        rounded / Decimal::from(scale)
-    }
-    
     /// Quantize to tick size
     pub fn quantize_to_tick(&self, tick_size: Decimal) -> Result<Self> {
         let quantized = (self.value / tick_size).round() * tick_size;
@@ -92,14 +98,10 @@ impl PreciseDecimal {
     }
     
     /// Ensure minimum notional value
-    pub fn ensure_min_notional(&self, min_notional: Decimal, quantity: Decimal) -> Result<Self> {
+    pub fn ensure_min_notional(&self, min_notional: Decimal, quantity: Decimal) -> Result<Self, BacktestError> {
         let notional = self.value * quantity;
         if notional < min_notional {
-            return Err(anyhow::anyhow!(
-                "Notional value {} below minimum {}", 
-                notional, 
-                min_notional
-            ));
+            return Err(BacktestError::MinNotional { notional, min_notional });
         }
         Ok(self.clone())
     }
@@ -175,9 +177,9 @@ impl Default for FloatConfig {
 pub fn set_fp_rounding_mode(mode: RoundingMode) -> Result<()> {
     #[cfg(target_arch = "x86_64")]
     {
-        use std::arch::x86_64::*;
-        
-        let control_word = unsafe { _mm_getcsr() };
+        // MXCSR through stmxcsr/ldmxcsr; the `_mm_getcsr` intrinsics are deprecated
+        let mut control_word: u32 = 0;
+        unsafe { std::arch::asm!("stmxcsr [{}]", in(reg) &mut control_word, options(nostack, preserves_flags)) };
         let new_control_word = match mode {
             RoundingMode::NearestEven => control_word & !0x6000, // Clear rounding bits
             RoundingMode::TowardZero => control_word | 0x2000,    // Set to 01
@@ -186,7 +188,7 @@ pub fn set_fp_rounding_mode(mode: RoundingMode) -> Result<()> {
             RoundingMode::NearestAway => control_word & !0x6000,   // Same as nearest even
         };
         
-        unsafe { _mm_setcsr(new_control_word) };
+        unsafe { std::arch::asm!("ldmxcsr [{}]", in(reg) &new_control_word, options(nostack, readonly)) };
     }
     
     #[cfg(not(target_arch = "x86_64"))]
@@ -223,6 +225,41 @@ pub fn validate_fp_determinism() -> Result<()> {
     Ok(())
 }
 
+/// Seeded random source for reproducible simulations
+/// 
+/// Wraps PCG64 so every stochastic component (synthetic data, randomized
+/// execution) draws from the same algorithm and replays exactly for a seed.
+#[derive(Debug, Clone)]
+pub struct DeterministicRng {
+    rng: Pcg64,
+}
+
+impl DeterministicRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Pcg64::seed_from_u64(seed),
+        }
+    }
+    
+    /// Uniform draw in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        self.rng.gen::<f64>()
+    }
+    
+    /// Uniform draw over all of `u32`
+    pub fn next_u32(&mut self) -> u32 {
+        self.rng.gen::<u32>()
+    }
+    
+    /// Standard normal draw (Box-Muller)
+    pub fn next_gaussian(&mut self) -> f64 {
+        // Shift away from zero so ln() stays finite
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
 /// Convert f64 to Decimal with proper precision handling
 pub fn f64_to_decimal(value: f64, precision: u8) -> Result<Decimal> {
     let decimal = Decimal::from_f64(value)
@@ -243,13 +280,160 @@ pub fn decimal_to_f64(value: Decimal) -> Result<f64> {
         .ok_or_else(|| anyhow::anyhow!("Decimal too large for f64: {}", value))
 }
 
+/// Square root in Decimal via Newton's method
+/// 
+/// Seeded from the (correctly rounded, hence deterministic) f64 square root and
+/// refined until the iterate stops changing. Non-positive inputs return zero.
+pub fn decimal_sqrt(value: Decimal) -> Decimal {
+    if value <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    
+    let mut x = value.to_f64()
+        .map(f64::sqrt)
+        .and_then(Decimal::from_f64)
+        .filter(|x| *x > Decimal::ZERO)
+        .unwrap_or(value);
+    
+    for _ in 0..64 {
+        let next = (x + value / x) / dec!(2);
+        if next == x {
+            break;
+        }
+        x = next;
+    }
+    
+    x
+}
+
+/// ln 2 to Decimal's 28 significant digits
+const LN_2: Decimal = dec!(0.6931471805599453094172321215);
+
+/// Natural logarithm in Decimal
+/// 
+/// Scales `value` by powers of two into [0.75, 1.5), then sums the series
+/// `ln m = 2 * atanh((m - 1) / (m + 1))` until its terms vanish, so the result depends
+/// on Decimal arithmetic alone. Non-positive inputs return zero.
+pub fn decimal_ln(value: Decimal) -> Decimal {
+    if value <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    
+    let mut mantissa = value;
+    let mut exponent = 0i64;
+    while mantissa >= dec!(1.5) {
+        mantissa /= dec!(2);
+        exponent += 1;
+    }
+    while mantissa < dec!(0.75) {
+        mantissa *= dec!(2);
+        exponent -= 1;
+    }
+    
+    let z = (mantissa - Decimal::ONE) / (mantissa + Decimal::ONE);
+    let z_squared = z * z;
+    let mut power = z;
+    let mut sum = Decimal::ZERO;
+    for n in (1..200u32).step_by(2) {
+        let term = power / Decimal::from(n);
+        if term.is_zero() {
+            break;
+        }
+        sum += term;
+        power *= z_squared;
+    }
+    
+    dec!(2) * sum + Decimal::from(exponent) * LN_2
+}
+
+/// A percentage with its unit explicit: `Percent::new(dec!(12.5))` is 12.5%
+/// 
+/// Serializes and displays as the bare number of percent (`12.5`), so exports keep a
+/// numeric column. Build it from a fraction with [`Percent::from_ratio`] instead of
+/// multiplying by 100 by hand.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Percent(Decimal);
+
+impl Percent {
+    pub const ZERO: Percent = Percent(Decimal::ZERO);
+    
+    /// Wrap a value already expressed in percent
+    pub fn new(percent: Decimal) -> Self {
+        Self(percent)
+    }
+    
+    /// Convert a fraction (0.125) to a percentage (12.5%)
+    pub fn from_ratio(ratio: Decimal) -> Self {
+        Self(ratio * dec!(100))
+    }
+    
+    /// Number of percent, e.g. 12.5
+    pub fn value(&self) -> Decimal {
+        self.0
+    }
+    
+    /// Fraction, e.g. 0.125
+    pub fn as_ratio(&self) -> Decimal {
+        self.0 / dec!(100)
+    }
+    
+    pub fn to_basis_points(&self) -> BasisPoints {
+        BasisPoints(self.0 * dec!(100))
+    }
+}
+
+impl fmt::Display for Percent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// Basis points (1 bp = 0.01%), for fee and slippage rates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BasisPoints(Decimal);
+
+impl BasisPoints {
+    pub const ZERO: BasisPoints = BasisPoints(Decimal::ZERO);
+    
+    /// Wrap a value already expressed in basis points
+    pub fn new(basis_points: Decimal) -> Self {
+        Self(basis_points)
+    }
+    
+    /// Convert a fraction (0.0001) to basis points (1 bp)
+    pub fn from_ratio(ratio: Decimal) -> Self {
+        Self(ratio * dec!(10000))
+    }
+    
+    /// Number of basis points
+    pub fn value(&self) -> Decimal {
+        self.0
+    }
+    
+    /// Fraction, e.g. 0.0001 for 1 bp
+    pub fn as_ratio(&self) -> Decimal {
+        self.0 / dec!(10000)
+    }
+    
+    pub fn to_percent(&self) -> Percent {
+        Percent(self.0 / dec!(100))
+    }
+}
+
+impl fmt::Display for BasisPoints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     
     #[test]
     fn test_precision_rounding() {
-        let config = PrecisionConfig::default();
         let precise = PreciseDecimal::from_f64(1.23456789, 4, RoundingMode::NearestEven).unwrap();
         assert_eq!(precise.value(), dec!(1.2346));
     }
@@ -271,5 +455,40 @@ mod tests {
         let result = price.ensure_min_notional(min_notional, quantity);
         assert!(result.is_err());
     }
+    
+    #[test]
+    fn test_decimal_sqrt() {
+        assert_eq!(decimal_sqrt(dec!(144)), dec!(12));
+        assert_eq!(decimal_sqrt(dec!(0)), dec!(0));
+        let root = decimal_sqrt(dec!(2));
+        assert!((root * root - dec!(2)).abs() < dec!(0.0000000000000000000001));
+    }
+    
+    #[test]
+    fn test_decimal_ln() {
+        assert_eq!(decimal_ln(dec!(1)), dec!(0));
+        assert_eq!(decimal_ln(dec!(0)), dec!(0));
+        assert_eq!(decimal_ln(dec!(2)), LN_2);
+        let e = dec!(2.7182818284590452353602874714);
+        assert!((decimal_ln(e) - dec!(1)).abs() < dec!(0.0000000000000000000001));
+        assert!((decimal_ln(dec!(0.5)) + LN_2).abs() < dec!(0.0000000000000000000001));
+    }
+    
+    #[test]
+    fn test_percent_and_basis_point_conversions() {
+        let percent = Percent::from_ratio(dec!(0.125));
+        assert_eq!(percent.value(), dec!(12.5));
+        assert_eq!(percent.as_ratio(), dec!(0.125));
+        assert_eq!(percent.to_basis_points(), BasisPoints::new(dec!(1250)));
+        assert_eq!(format!("{:.1}%", percent), "12.5%");
+        
+        let taker = BasisPoints::from_ratio(dec!(0.0001));
+        assert_eq!(taker.value(), dec!(1));
+        assert_eq!(taker.as_ratio(), dec!(0.0001));
+        assert_eq!(taker.to_percent(), Percent::new(dec!(0.01)));
+        
+        // Serialized as the bare number in its own unit
+        assert_eq!(serde_json::to_string(&Percent::new(dec!(12.5))).unwrap(), "\"12.5\"");
+        assert_eq!(serde_json::from_str::<Percent>("\"12.5\"").unwrap(), Percent::new(dec!(12.5)));
+    }
 }
-
@@ -0,0 +1,240 @@
+//! Annualized risk-adjusted return ratios
+//! 
+//! Sharpe, Sortino and Calmar computed from an equity series sampled once per bar.
+//! Every ratio annualizes with the same periods-per-year factor, taken from the bar
+//! timeframe and an [`AnnualizationConvention`], so they stay comparable across
+//! timeframes. Returns are bar-over-bar, simple by default or logarithmic (see
+//! [`ReturnMethod`]), and every ratio uses the same kind. Sharpe and Sortino measure
+//! them in excess of an annual risk-free rate spread evenly over the bars of a year.
+//! The arithmetic is done in `Decimal` so results are reproducible across platforms.
+
+use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::precision::{decimal_ln, decimal_sqrt};
+use crate::timeframe::{AnnualizationConvention, Timeframe};
+
+/// How per-bar returns are derived from consecutive equity values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReturnMethod {
+    /// Simple return `(E_t - E_t-1) / E_t-1`
+    #[default]
+    Arithmetic,
+    /// Log return `ln(E_t / E_t-1)`, additive over time; suits compounding strategies
+    Log,
+}
+
+/// Annualized ratios of one equity series
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct RiskAdjustedRatios {
+    pub sharpe: Decimal,
+    pub sortino: Decimal,
+    pub calmar: Decimal,
+    /// Annualized sample standard deviation of the returns
+    #[serde(default)]
+    pub volatility: Decimal,
+}
+
+/// Ratios of `equity` sampled once per `timeframe` bar
+/// 
+/// `risk_free_rate` is annual (0.04 for 4%); its per-bar share is subtracted from each
+/// return before Sharpe and Sortino. Calmar uses the raw returns.
+/// 
+/// All ratios are zero with fewer than two returns; a ratio whose denominator is zero
+/// (flat returns, no losing bars, no drawdown) is zero as well.
+pub fn risk_adjusted_ratios(
+    equity: &[Decimal],
+    timeframe: &Timeframe,
+    convention: AnnualizationConvention,
+    risk_free_rate: Decimal,
+    method: ReturnMethod,
+) -> RiskAdjustedRatios {
+    let returns = bar_returns(equity, method);
+    if returns.len() < 2 {
+        return RiskAdjustedRatios::default();
+    }
+    let periods_per_year = Decimal::from_f64(timeframe.periods_per_year_with(convention))
+        .unwrap_or(Decimal::ZERO);
+    let excess = excess_returns(&returns, risk_free_rate, periods_per_year);
+    
+    RiskAdjustedRatios {
+        sharpe: sharpe_ratio(&excess, periods_per_year),
+        sortino: sortino_ratio(&excess, periods_per_year),
+        calmar: calmar_ratio(&returns, max_drawdown(equity), periods_per_year),
+        volatility: annualized_volatility(&returns, periods_per_year),
+    }
+}
+
+/// Returns between consecutive equity values
+/// 
+/// Steps from non-positive equity are skipped, and so are log steps to non-positive
+/// equity, whose logarithm is undefined.
+pub fn bar_returns(equity: &[Decimal], method: ReturnMethod) -> Vec<Decimal> {
+    equity.windows(2)
+        .filter(|w| w[0] > Decimal::ZERO)
+        .filter_map(|w| match method {
+            ReturnMethod::Arithmetic => Some((w[1] - w[0]) / w[0]),
+            ReturnMethod::Log => (w[1] > Decimal::ZERO).then(|| decimal_ln(w[1] / w[0])),
+        })
+        .collect()
+}
+
+/// `returns` less the per-bar share of an annual risk-free rate
+pub fn excess_returns(returns: &[Decimal], risk_free_rate: Decimal, periods_per_year: Decimal) -> Vec<Decimal> {
+    if risk_free_rate.is_zero() || periods_per_year.is_zero() {
+        return returns.to_vec();
+    }
+    let per_bar = risk_free_rate / periods_per_year;
+    returns.iter().map(|r| r - per_bar).collect()
+}
+
+/// Mean return over its sample standard deviation, scaled by `sqrt(periods_per_year)`
+pub fn sharpe_ratio(returns: &[Decimal], periods_per_year: Decimal) -> Decimal {
+    let std_dev = sample_std_dev(returns);
+    if std_dev.is_zero() {
+        return Decimal::ZERO;
+    }
+    mean(returns) / std_dev * decimal_sqrt(periods_per_year)
+}
+
+/// Sample standard deviation of the returns, scaled by `sqrt(periods_per_year)`
+pub fn annualized_volatility(returns: &[Decimal], periods_per_year: Decimal) -> Decimal {
+    sample_std_dev(returns) * decimal_sqrt(periods_per_year)
+}
+
+/// Mean return over the downside deviation (root mean square of negative returns),
+/// scaled by `sqrt(periods_per_year)`
+pub fn sortino_ratio(returns: &[Decimal], periods_per_year: Decimal) -> Decimal {
+    if returns.is_empty() {
+        return Decimal::ZERO;
+    }
+    let downside = returns.iter()
+        .filter(|r| r.is_sign_negative())
+        .map(|r| r * r)
+        .sum::<Decimal>()
+        / Decimal::from(returns.len());
+    let downside_dev = decimal_sqrt(downside);
+    if downside_dev.is_zero() {
+        return Decimal::ZERO;
+    }
+    mean(returns) / downside_dev * decimal_sqrt(periods_per_year)
+}
+
+/// Annualized mean return over the maximum drawdown (as a fraction of peak equity)
+pub fn calmar_ratio(returns: &[Decimal], max_drawdown: Decimal, periods_per_year: Decimal) -> Decimal {
+    if returns.is_empty() || max_drawdown.is_zero() {
+        return Decimal::ZERO;
+    }
+    mean(returns) * periods_per_year / max_drawdown
+}
+
+/// Largest peak-to-trough decline of `equity` as a fraction of the peak
+fn max_drawdown(equity: &[Decimal]) -> Decimal {
+    let mut peak = Decimal::ZERO;
+    let mut max_drawdown = Decimal::ZERO;
+    for &value in equity {
+        peak = peak.max(value);
+        if peak > Decimal::ZERO {
+            max_drawdown = max_drawdown.max((peak - value) / peak);
+        }
+    }
+    max_drawdown
+}
+
+fn mean(values: &[Decimal]) -> Decimal {
+    values.iter().sum::<Decimal>() / Decimal::from(values.len())
+}
+
+/// Zero with fewer than two values
+fn sample_std_dev(values: &[Decimal]) -> Decimal {
+    if values.len() < 2 {
+        return Decimal::ZERO;
+    }
+    let mean = mean(values);
+    let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<Decimal>()
+        / Decimal::from(values.len() - 1);
+    decimal_sqrt(variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    /// Daily equity alternating between +2% and -1% days
+    fn daily_equity(days: usize) -> Vec<Decimal> {
+        let mut equity = vec![dec!(10000)];
+        for day in 0..days {
+            let step = if day % 2 == 0 { dec!(1.02) } else { dec!(0.99) };
+            equity.push(equity[day] * step);
+        }
+        equity
+    }
+
+    #[test]
+    fn test_daily_sharpe_scales_with_convention() {
+        let equity = daily_equity(60);
+        let daily: Timeframe = "1d".parse().unwrap();
+        let calendar = risk_adjusted_ratios(&equity, &daily, AnnualizationConvention::Calendar365, Decimal::ZERO, ReturnMethod::default());
+        let trading = risk_adjusted_ratios(&equity, &daily, AnnualizationConvention::Trading252, Decimal::ZERO, ReturnMethod::default());
+
+        assert!(calendar.sharpe > Decimal::ZERO);
+        // Sharpe and Sortino scale with sqrt(periods), Calmar linearly
+        let sqrt_ratio = decimal_sqrt(dec!(365) / dec!(252));
+        assert_eq!((calendar.sharpe / trading.sharpe).round_dp(10), sqrt_ratio.round_dp(10));
+        assert_eq!((calendar.sortino / trading.sortino).round_dp(10), sqrt_ratio.round_dp(10));
+        assert_eq!((calendar.calmar / trading.calmar).round_dp(10), (dec!(365) / dec!(252)).round_dp(10));
+    }
+
+    #[test]
+    fn test_flat_or_short_series_has_zero_ratios() {
+        let minute: Timeframe = "1m".parse().unwrap();
+        let flat = vec![dec!(10000); 10];
+        for method in [ReturnMethod::Arithmetic, ReturnMethod::Log] {
+            let ratios = |equity: &[Decimal]| risk_adjusted_ratios(equity, &minute, AnnualizationConvention::default(), Decimal::ZERO, method);
+            assert_eq!(ratios(&flat), RiskAdjustedRatios::default());
+            assert_eq!(ratios(&flat[..2]), RiskAdjustedRatios::default());
+        }
+    }
+
+    #[test]
+    fn test_risk_free_rate_lowers_sharpe() {
+        let equity = daily_equity(60);
+        let daily: Timeframe = "1d".parse().unwrap();
+        let ratios = |rate: Decimal| risk_adjusted_ratios(&equity, &daily, AnnualizationConvention::Calendar365, rate, ReturnMethod::Arithmetic);
+        
+        let zero = ratios(Decimal::ZERO);
+        let four_percent = ratios(dec!(0.04));
+        assert!(four_percent.sharpe < zero.sharpe);
+        assert!(four_percent.sortino < zero.sortino);
+        assert_eq!(four_percent.calmar, zero.calmar);
+        
+        // Each daily return gives up a 365th of the annual rate
+        let returns = bar_returns(&equity, ReturnMethod::Arithmetic);
+        let shifted = excess_returns(&returns, dec!(0.04), dec!(365));
+        assert_eq!(shifted[0], returns[0] - dec!(0.04) / dec!(365));
+    }
+
+    #[test]
+    fn test_log_returns_on_volatile_curve() {
+        // Doubles then halves: simple returns +100% and -50% average +25% for no gain
+        let equity = vec![dec!(100), dec!(200), dec!(100), dec!(200), dec!(100)];
+        let arithmetic = bar_returns(&equity, ReturnMethod::Arithmetic);
+        assert_eq!(arithmetic, vec![dec!(1), dec!(-0.5), dec!(1), dec!(-0.5)]);
+        assert!(mean(&arithmetic) > Decimal::ZERO);
+        
+        // Log returns cancel exactly, so the mean matches the flat round trip
+        let log = bar_returns(&equity, ReturnMethod::Log);
+        assert_eq!(log[0], -log[1]);
+        assert_eq!(mean(&log), Decimal::ZERO);
+        
+        // Arithmetic is the default; the same curve reads as a winner only under it
+        assert_eq!(ReturnMethod::default(), ReturnMethod::Arithmetic);
+        let daily: Timeframe = "1d".parse().unwrap();
+        let ratios = |method| risk_adjusted_ratios(&equity, &daily, AnnualizationConvention::Calendar365, Decimal::ZERO, method);
+        assert!(ratios(ReturnMethod::Arithmetic).sharpe > Decimal::ZERO);
+        assert_eq!(ratios(ReturnMethod::Log).sharpe, Decimal::ZERO);
+        assert!(ratios(ReturnMethod::Log).volatility < ratios(ReturnMethod::Arithmetic).volatility);
+    }
+}
@@ -0,0 +1,247 @@
+//! Caller-driven backtest sessions
+//! 
+//! [`BacktestSession`] runs the same per-bar pipeline as the engine (fills, trade table,
+//! positions, equity) but lets the caller own the loop, e.g. to interleave historical
+//! bars with a live feed or to drive a strategy that lives outside the engine.
+
+use anyhow::Result;
+
+use crate::simulator::ExchangeSimulator;
+use crate::timeframe::Timeframe;
+use crate::types::*;
+use crate::{IntrabarPolicy, SlippageMode};
+
+/// Everything produced by one [`BacktestSession::on_bar`] call
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    /// Fills executed on this bar
+    pub fills: Vec<ExecutedTrade>,
+    /// Trade table rows for positions closed on this bar
+    pub closed_trades: Vec<TradeRecord>,
+    /// Signals rejected on this bar
+    pub rejected: Vec<RejectedTrade>,
+    /// Position after the bar, once the symbol has traded (flat positions included)
+    pub position: Option<Position>,
+    /// Equity point recorded for the bar
+    pub equity: Option<EquityPoint>,
+}
+
+/// Single-symbol backtest driven one bar at a time
+pub struct BacktestSession {
+    symbol: String,
+    timeframe: Timeframe,
+    rules: ExchangeRules,
+    intrabar_policy: IntrabarPolicy,
+    slippage_mode: SlippageMode,
+    simulator: ExchangeSimulator,
+    last_bar: Option<Bar>,
+}
+
+impl BacktestSession {
+    pub fn new(
+        symbol: &str,
+        timeframe: Timeframe,
+        rules: ExchangeRules,
+        intrabar_policy: IntrabarPolicy,
+        slippage_mode: SlippageMode,
+    ) -> Result<Self> {
+        Ok(Self {
+            symbol: symbol.to_string(),
+            timeframe,
+            rules,
+            intrabar_policy,
+            slippage_mode,
+            simulator: ExchangeSimulator::new()?,
+            last_bar: None,
+        })
+    }
+    
+    /// Process one bar with the signals the caller's strategy produced for it
+    /// 
+    /// Bars must be fed in timestamp order; the results match running the same bars
+    /// and signals through [`ExchangeSimulator::simulate_with_signals`].
+    pub async fn on_bar(&mut self, bar: &Bar, signals: &[StrategySignal]) -> Result<StepResult> {
+        let closed_before = self.simulator.trade_table_generator().trade_records().len();
+        let rejected_before = self.simulator.trade_table_generator().rejected_trades().len();
+        
+        let mut fills = Vec::new();
+        let mut positions = Vec::with_capacity(1);
+        self.simulator.step_bar(
+            &self.symbol,
+            &self.rules,
+            self.timeframe.as_millis(),
+            bar,
+            &[],
+            signals,
+            &self.intrabar_policy,
+            &self.slippage_mode,
+            &mut fills,
+            &mut positions,
+        ).await?;
+        self.last_bar = Some(bar.clone());
+        
+        let generator = self.simulator.trade_table_generator();
+        Ok(StepResult {
+            fills,
+            closed_trades: generator.trade_records()[closed_before..].to_vec(),
+            rejected: generator.rejected_trades()[rejected_before..].to_vec(),
+            position: positions.pop(),
+            equity: self.simulator.last_equity_point().cloned(),
+        })
+    }
+    
+    /// End the session, closing positions still open at the last bar
+    /// 
+    /// Returns the `EndOfData` trade rows, matching [`ExchangeSimulator::finish`] after a
+    /// full run.
+    pub fn finish(&mut self) -> Result<Vec<TradeRecord>> {
+        let closed_before = self.simulator.trade_table_generator().trade_records().len();
+        self.simulator.finish(self.last_bar.as_ref(), &self.rules, &self.slippage_mode)?;
+        Ok(self.simulator.trade_table_generator().trade_records()[closed_before..].to_vec())
+    }
+    
+    /// Trade table for everything processed so far
+    pub fn trade_table(&self) -> TradeTableResult {
+        self.simulator.get_trade_table_result()
+    }
+    
+    /// Underlying simulator, e.g. to checkpoint it with [`ExchangeSimulator::snapshot`]
+    pub fn simulator(&self) -> &ExchangeSimulator {
+        &self.simulator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use serde::Serialize;
+    use crate::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+
+    fn canonical<T: Serialize>(value: &T) -> String {
+        crate::canonical::to_canonical_json(value).unwrap()
+    }
+
+    /// Alternate buys and sells every 30 bars with a 1% bracket
+    fn signals_for(bar: &Bar) -> Vec<StrategySignal> {
+        let index = bar.timestamp / 60_000;
+        if !index.is_multiple_of(30) {
+            return Vec::new();
+        }
+        let side = if index.is_multiple_of(60) { TradeSide::Buy } else { TradeSide::Sell };
+        vec![StrategySignal {
+            side,
+            size: dec!(0.01),
+            entry_price: None,
+            take_profit: Some(bar.close * dec!(1.01)),
+            stop_loss: Some(bar.close * dec!(0.99)),
+            time_to_live: Some(TimeToLive::Duration(1_800_000)),
+            rule_tag: None,
+            tags: Vec::new(),
+            symbol: String::new(),
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_manual_bars_match_simulator_run() {
+        let data = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 600,
+            ..SyntheticDataConfig::default()
+        }, 5).generate();
+
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        let expected = simulator.simulate_with_signals(
+            &data,
+            |_, bar| signals_for(bar),
+            &IntrabarPolicy::ExactTrades,
+            &SlippageMode::TradeSweep,
+        ).await.unwrap();
+        simulator.finish(data.bars.last(), &data.rules, &SlippageMode::TradeSweep).unwrap();
+        let expected_table = simulator.get_trade_table_result();
+
+        let mut session = BacktestSession::new(
+            &data.symbol,
+            data.timeframe.parse().unwrap(),
+            data.rules.clone(),
+            IntrabarPolicy::ExactTrades,
+            SlippageMode::TradeSweep,
+        ).unwrap();
+        let mut fills = Vec::new();
+        let mut closed = Vec::new();
+        let mut equity = Vec::new();
+        for bar in &data.bars {
+            let step = session.on_bar(bar, &signals_for(bar)).await.unwrap();
+            assert_eq!(step.equity.as_ref().map(|e| e.timestamp), Some(bar.timestamp));
+            fills.extend(step.fills);
+            closed.extend(step.closed_trades);
+            equity.extend(step.equity);
+        }
+        closed.extend(session.finish().unwrap());
+
+        assert!(!closed.is_empty());
+        assert_eq!(canonical(&fills), canonical(&expected.trades));
+        assert_eq!(canonical(&equity), canonical(&expected.equity_curve));
+        assert_eq!(canonical(&closed), canonical(&expected_table.trades));
+        assert_eq!(canonical(&session.trade_table()), canonical(&expected_table));
+    }
+
+    #[tokio::test]
+    async fn test_current_state_tracks_open_position_between_bars() {
+        let mut session = BacktestSession::new(
+            "BTCUSDT",
+            "1m".parse().unwrap(),
+            ExchangeRules::default(),
+            IntrabarPolicy::ExactTrades,
+            SlippageMode::None,
+        ).unwrap();
+        let bar = |i: u64, close: Decimal| Bar {
+            timestamp: 1609459200000 + i * 60_000,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: dec!(1000.0),
+            trade_count: 100,
+        };
+        let buy = StrategySignal {
+            side: TradeSide::Buy,
+            size: dec!(1.0),
+            entry_price: None,
+            take_profit: None,
+            stop_loss: None,
+            time_to_live: None,
+            rule_tag: None,
+            tags: Vec::new(),
+            symbol: String::new(),
+        };
+
+        let state = session.simulator().current_state();
+        assert_eq!(state.timestamp, None);
+        assert!(state.positions.is_empty());
+        assert_eq!(state.equity, dec!(10000.0));
+
+        // The trade table opens $1000 at 100, paying a 0.10 fee
+        session.on_bar(&bar(0, dec!(100.0)), &[buy]).await.unwrap();
+        let state = session.simulator().current_state();
+        assert_eq!(state.timestamp, Some(1609459200000));
+        assert_eq!(state.positions["BTCUSDT"].quantity, dec!(10.0));
+        assert_eq!(state.positions["BTCUSDT"].avg_price, dec!(100.0));
+        assert_eq!(state.open_trades["BTCUSDT"].quantity, state.positions["BTCUSDT"].quantity);
+        assert_eq!(state.equity, dec!(9999.90));
+
+        session.on_bar(&bar(1, dec!(110.0)), &[]).await.unwrap();
+        let state = session.simulator().current_state();
+        assert_eq!(state.positions["BTCUSDT"].unrealized_pnl, dec!(100.0));
+        assert_eq!(state.equity, dec!(10099.90));
+        assert_eq!(state.peak_equity, dec!(10099.90));
+        assert_eq!(state.drawdown, dec!(0.0));
+        assert_eq!(state.exposure["BTCUSDT"], dec!(1100.0) / dec!(10099.90));
+
+        session.on_bar(&bar(2, dec!(105.0)), &[]).await.unwrap();
+        let state = session.simulator().current_state();
+        assert_eq!(state.equity, dec!(10049.90));
+        assert_eq!(state.drawdown, dec!(50.0) / dec!(10099.90));
+        assert_eq!(state.max_drawdown, state.drawdown);
+    }
+}
@@ -3,230 +3,1121 @@
 //! Simulates exchange behavior including order matching, fee calculation,
 //! slippage modeling, and position tracking with deterministic precision.
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::time::{Duration, Instant};
 use rust_decimal::Decimal;
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
 use anyhow::Result;
-use tracing::{debug, warn, error};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
 
+use crate::data::group_trades_by_bar;
+use crate::precision::round_to_precision;
+use crate::strategy::{BarContext, SignalTape, Strategy};
+use crate::streaming::IndicatorStream;
 use crate::types::*;
-use crate::trade_table::TradeTableGenerator;
+use crate::timeframe::Timeframe;
+use crate::trade_table::{outside_bar, resolve_overlapping, synthetic_book_rate, FillRangePolicy, PositionMode, SlippageSampler, TradeTableConfig, TradeTableGenerator, TradeTableState};
 
 /// Exchange simulator for backtesting
 pub struct ExchangeSimulator {
+    /// Positions by symbol, or by symbol and leg (`BTCUSDT:Long`) in hedge mode
     positions: HashMap<String, Position>,
     equity_history: Vec<EquityPoint>,
+    initial_equity: Decimal,
     current_equity: Decimal,
     max_drawdown: Decimal,
     peak_equity: Decimal,
     trade_table_generator: TradeTableGenerator,
+    /// Time spent waiting on the strategy during the last `simulate`
+    strategy_time: Duration,
+    /// Bars at the start of each symbol's run during which entries are suppressed
+    warmup_bars: u64,
+    /// Bars stepped so far per symbol
+    bars_seen: HashMap<String, u64>,
+    /// Draws for `SlippageMode::Stochastic` fills
+    slippage_sampler: SlippageSampler,
+    /// Symbol names fills share instead of each holding a copy
+    symbols: HashSet<Symbol>,
+    /// The bar's signals with their symbol filled in; kept between bars to reuse its buffer
+    stamped_signals: Vec<StrategySignal>,
+    /// Whether fills land on the signal's bar or at the next bar's open
+    fill_model: FillModel,
+    /// Signals of the last bar, filled at this bar's open under `FillModel::NextBarOpen`
+    pending_fills: Vec<StrategySignal>,
+    /// Last bar close per symbol, which open trade table positions are marked to
+    marks: HashMap<String, Decimal>,
+    /// Trade table records already realized into `positions`
+    booked_trades: usize,
 }
 
+/// Checkpoint of an [`ExchangeSimulator`] between bars
+/// 
+/// Holds positions, the equity curve so far, peak/drawdown tracking and the trade
+/// table state, which attribution is read from. Random slippage carries only its seed and draw count, so a
+/// restored run continues the same draws. Fills and trade snapshots already returned to the caller are not
+/// part of the checkpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatorState {
+    #[serde(serialize_with = "crate::canonical::sorted_map")]
+    pub positions: HashMap<String, Position>,
+    pub equity_history: Vec<EquityPoint>,
+    pub initial_equity: Decimal,
+    pub current_equity: Decimal,
+    pub max_drawdown: Decimal,
+    pub peak_equity: Decimal,
+    pub trade_table: TradeTableState,
+    #[serde(default)]
+    pub warmup_bars: u64,
+    #[serde(default, serialize_with = "crate::canonical::sorted_map")]
+    pub bars_seen: HashMap<String, u64>,
+    #[serde(default)]
+    pub slippage_sampler: SlippageSampler,
+    #[serde(default)]
+    pub fill_model: FillModel,
+    #[serde(default)]
+    pub pending_fills: Vec<StrategySignal>,
+    #[serde(default, serialize_with = "crate::canonical::sorted_map")]
+    pub marks: HashMap<String, Decimal>,
+    #[serde(default)]
+    pub booked_trades: usize,
+}
+
+/// Read-only view of a simulation between bars, for dashboards and event hooks
+/// 
+/// Unlike [`SimulatorState`] it is not meant to resume from: it carries only what the
+/// run looks like now, not the history and bookkeeping a restore needs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveState {
+    /// Open time of the last bar processed, `None` before the first
+    pub timestamp: Option<u64>,
+    /// Positions not flat, keyed by symbol (by symbol and leg in hedge mode) and marked
+    /// to their last bar's close
+    #[serde(serialize_with = "crate::canonical::sorted_map")]
+    pub positions: HashMap<String, Position>,
+    /// Trade table positions waiting on their exits, keyed by position slot
+    #[serde(serialize_with = "crate::canonical::sorted_map")]
+    pub open_trades: HashMap<String, ActivePosition>,
+    pub equity: Decimal,
+    pub peak_equity: Decimal,
+    /// Current fall from `peak_equity`, as a fraction of it
+    pub drawdown: Decimal,
+    pub max_drawdown: Decimal,
+    /// Notional at the last mark / equity of each open position
+    #[serde(serialize_with = "crate::canonical::sorted_map")]
+    pub exposure: HashMap<String, Decimal>,
+}
+
+/// One symbol of [`ExchangeSimulator::simulate_symbols`]
+pub struct SymbolRun<'a> {
+    pub market_data: &'a MarketData,
+    pub indicators: RunIndicators<'a>,
+    pub strategy: &'a mut dyn Strategy,
+    /// Seed of the symbol's `SlippageMode::Stochastic` draws
+    pub seed: u64,
+}
+
+/// Indicator values a [`SymbolRun`]'s strategy sees at each bar
+pub enum RunIndicators<'a> {
+    /// Series computed up front, looked up by bar timestamp
+    Materialized(&'a HashMap<String, Vec<IndicatorValue>>),
+    /// Advanced alongside the bar loop
+    Streaming(&'a mut IndicatorStream),
+}
+
+/// Progress of one [`SymbolRun`] through its bars
+struct RunCursor {
+    /// Index of the next bar to step
+    next: usize,
+    bar_interval_ms: u64,
+    tick_ranges: Vec<Range<usize>>,
+    /// Materialized indicators' values at the last bar stepped
+    current: HashMap<String, Option<Decimal>>,
+    /// The symbol's own fill and trade table samplers while another symbol is stepped
+    samplers: (SlippageSampler, SlippageSampler),
+    trades: Vec<ExecutedTrade>,
+    positions: Vec<Position>,
+    finished: bool,
+}
+
+impl RunCursor {
+    fn new(run: &SymbolRun<'_>) -> Result<Self> {
+        let market_data = run.market_data;
+        ensure_open_time(market_data)?;
+        let bar_interval_ms = market_data.timeframe.parse::<Timeframe>()?.as_millis();
+        let current = match &run.indicators {
+            RunIndicators::Materialized(values) => values.keys().map(|name| (name.clone(), None)).collect(),
+            RunIndicators::Streaming(_) => HashMap::new(),
+        };
+        Ok(Self {
+            next: 0,
+            bar_interval_ms,
+            tick_ranges: tick_ranges(market_data, bar_interval_ms)?,
+            current,
+            samplers: (SlippageSampler::new(run.seed), SlippageSampler::new(exit_seed(run.seed))),
+            trades: Vec::with_capacity(market_data.bars.len() / BARS_PER_TRADE_ESTIMATE),
+            positions: Vec::with_capacity(market_data.bars.len()),
+            finished: false,
+        })
+    }
+    
+    /// Open time of the run's next bar, `None` once its bars are all stepped
+    fn next_timestamp(&self, run: &SymbolRun<'_>) -> Option<u64> {
+        run.market_data.bars.get(self.next).map(|bar| bar.timestamp)
+    }
+}
+
+/// Seed of the trade table's draws in a run seeded with `seed`
+/// 
+/// A distinct stream, so the trade table does not replay the fills' draws.
+fn exit_seed(seed: u64) -> u64 {
+    seed.rotate_left(32)
+}
+
+/// Shared name of `symbol` from `symbols`; only its first use allocates
+fn intern(symbols: &mut HashSet<Symbol>, symbol: &str) -> Symbol {
+    if let Some(interned) = symbols.get(symbol) {
+        return interned.clone();
+    }
+    let interned = Symbol::from(symbol);
+    symbols.insert(interned.clone());
+    interned
+}
+
+/// Copy `signals` into `stamped`; those without a symbol trade the one being simulated
+fn stamp_symbol(stamped: &mut Vec<StrategySignal>, signals: &[StrategySignal], symbol: &str) {
+    stamped.clear();
+    stamped.extend(signals.iter().cloned().map(|mut signal| {
+        if signal.symbol.is_empty() {
+            signal.symbol = symbol.to_string();
+        }
+        signal
+    }));
+}
+
+/// Fill logic assumes open-time bars; refuse anything not yet normalized
+fn ensure_open_time(market_data: &MarketData) -> Result<()> {
+    if market_data.timestamp_convention != TimestampConvention::OpenTime {
+        return Err(anyhow::anyhow!(
+            "{} bars are stamped with close time; normalize them with data::normalize_timestamps first",
+            market_data.symbol
+        ));
+    }
+    Ok(())
+}
+
+/// Index range into `market_data.trades` of each bar's ticks; empty when no ticks are attached
+fn tick_ranges(market_data: &MarketData, bar_interval_ms: u64) -> Result<Vec<Range<usize>>> {
+    if market_data.trades.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(group_trades_by_bar(&market_data.bars, &market_data.trades, bar_interval_ms)?)
+}
+
+/// Ticks of bar `bar_idx`, per [`tick_ranges`]
+fn ticks_at<'a>(market_data: &'a MarketData, tick_ranges: &[Range<usize>], bar_idx: usize) -> &'a [Trade] {
+    tick_ranges.get(bar_idx).map_or(&[], |range| &market_data.trades[range.clone()])
+}
+
+/// Rough upper bound on bars per closed trade, used to size trade buffers from a bar count
+const BARS_PER_TRADE_ESTIMATE: usize = 100;
+
+/// Sub-step length walked by [`IntrabarPolicy::OneSecondBars`]
+const SUB_STEP_MS: u64 = 1_000;
+
 impl ExchangeSimulator {
     pub fn new() -> Result<Self> {
+        Self::with_capacity(0)
+    }
+    
+    /// Create a simulator with hot vectors preallocated for `prealloc_size` bars
+    /// 
+    /// Avoids repeated regrowth of the equity curve and trade records on long runs;
+    /// vectors still grow if a run exceeds the estimate.
+    pub fn with_capacity(prealloc_size: usize) -> Result<Self> {
+        Self::with_config(TradeTableConfig::default(), prealloc_size)
+    }
+    
+    /// Create a simulator whose trade table runs with `trade_table_config`
+    /// 
+    /// Equity starts at the config's `initial_equity`. Trade records are reserved for
+    /// `prealloc_size` bars as in [`ExchangeSimulator::with_capacity`], unless the config
+    /// asks for more.
+    pub fn with_config(trade_table_config: TradeTableConfig, prealloc_size: usize) -> Result<Self> {
+        let trade_table_config = TradeTableConfig {
+            prealloc_trades: trade_table_config.prealloc_trades.max(prealloc_size / BARS_PER_TRADE_ESTIMATE),
+            ..trade_table_config
+        };
+        let initial_equity = trade_table_config.initial_equity;
+        let fill_model = trade_table_config.fill_model;
+        
         Ok(Self {
             positions: HashMap::new(),
-            equity_history: Vec::new(),
-            current_equity: dec!(10000.0), // Starting equity
+            equity_history: Vec::with_capacity(prealloc_size),
+            initial_equity,
+            current_equity: initial_equity,
             max_drawdown: dec!(0.0),
-            peak_equity: dec!(10000.0),
-            trade_table_generator: TradeTableGenerator::new(),
+            peak_equity: initial_equity,
+            trade_table_generator: TradeTableGenerator::with_config(trade_table_config),
+            strategy_time: Duration::ZERO,
+            warmup_bars: 0,
+            bars_seen: HashMap::new(),
+            slippage_sampler: SlippageSampler::default(),
+            symbols: HashSet::new(),
+            stamped_signals: Vec::new(),
+            fill_model,
+            pending_fills: Vec::new(),
+            marks: HashMap::new(),
+            booked_trades: 0,
         })
     }
     
+    /// Capture the simulator's state after the last processed bar
+    pub fn snapshot(&self) -> SimulatorState {
+        SimulatorState {
+            positions: self.positions.clone(),
+            equity_history: self.equity_history.clone(),
+            initial_equity: self.initial_equity,
+            current_equity: self.current_equity,
+            max_drawdown: self.max_drawdown,
+            peak_equity: self.peak_equity,
+            trade_table: self.trade_table_generator.snapshot(),
+            warmup_bars: self.warmup_bars,
+            bars_seen: self.bars_seen.clone(),
+            slippage_sampler: self.slippage_sampler.clone(),
+            fill_model: self.fill_model,
+            pending_fills: self.pending_fills.clone(),
+            marks: self.marks.clone(),
+            booked_trades: self.booked_trades,
+        }
+    }
+    
+    /// Resume from a [`ExchangeSimulator::snapshot`]
+    /// 
+    /// Feeding the remaining bars to the restored simulator gives the same trade table,
+    /// equity curve and attribution as an uninterrupted run.
+    pub fn restore(state: SimulatorState) -> Self {
+        Self {
+            positions: state.positions,
+            equity_history: state.equity_history,
+            initial_equity: state.initial_equity,
+            current_equity: state.current_equity,
+            max_drawdown: state.max_drawdown,
+            peak_equity: state.peak_equity,
+            trade_table_generator: TradeTableGenerator::restore(state.trade_table),
+            strategy_time: Duration::ZERO,
+            warmup_bars: state.warmup_bars,
+            bars_seen: state.bars_seen,
+            slippage_sampler: state.slippage_sampler,
+            symbols: HashSet::new(),
+            stamped_signals: Vec::new(),
+            fill_model: state.fill_model,
+            pending_fills: state.pending_fills,
+            marks: state.marks,
+            booked_trades: state.booked_trades,
+        }
+    }
+    
+    /// Suppress entries for the first `bars` bars of every symbol
+    /// 
+    /// Applies to every signal source alike: entry signals in the window produce no fills
+    /// and are recorded as [`ReasonCode::Warmup`] rejections, while signals that reduce a
+    /// held position, such as one restored with [`Self::seed_positions`], still
+    /// fill. Bars are counted per symbol over the simulator's life; the engine runs every
+    /// job on a fresh simulator, so each job warms up again.
+    pub fn set_warmup_bars(&mut self, bars: u64) {
+        self.warmup_bars = bars;
+    }
+    
+    /// Fill entry signals on their own bar or at the next bar's open
+    /// 
+    /// Under [`FillModel::NextBarOpen`] a bar's signals fill at the following bar's open
+    /// with [`ReasonCode::NextBarOpen`], and the trade table opens their positions there;
+    /// signals on a symbol's last bar never fill. Exits are unaffected.
+    pub fn set_fill_model(&mut self, fill_model: FillModel) {
+        self.fill_model = fill_model;
+        self.trade_table_generator.set_fill_model(fill_model);
+    }
+    
+    /// Start the run already holding `positions`
+    /// 
+    /// Each goes to the trade table, which exits it on its own TP/SL/TTL, and takes its
+    /// slot in the position book at its entry price, so the equity curve marks it to
+    /// market from the first bar.
+    pub fn seed_positions<'a>(&mut self, positions: impl IntoIterator<Item = &'a ActivePosition>) {
+        for position in positions {
+            self.trade_table_generator.seed_position(position.clone());
+            self.sync_positions(Some(&position.symbol), position.entry_time);
+        }
+    }
+    
+    /// Seed `SlippageMode::Stochastic` draws for fills and trade table exits alike
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.slippage_sampler = SlippageSampler::new(seed);
+        self.trade_table_generator.set_random_seed(exit_seed(seed));
+    }
+    
     /// Simulate trading with given market data and strategy signals
     pub async fn simulate(
         &mut self,
         market_data: &MarketData,
         indicator_values: &HashMap<String, Vec<IndicatorValue>>,
-        strategy: &crate::wasm::Strategy,
+        strategy: &mut dyn Strategy,
         intrabar_policy: &IntrabarPolicy,
         slippage_mode: &SlippageMode,
     ) -> Result<SimulationResult> {
         debug!("Starting simulation for symbol: {}", market_data.symbol);
+        ensure_open_time(market_data)?;
         
-        let mut trades = Vec::new();
-        let mut positions = Vec::new();
+        // At most one position snapshot per bar
+        let mut trades = Vec::with_capacity(market_data.bars.len() / BARS_PER_TRADE_ESTIMATE);
+        let mut positions = Vec::with_capacity(market_data.bars.len());
+        self.equity_history.reserve(market_data.bars.len());
+        let bar_interval_ms = market_data.timeframe.parse::<Timeframe>()?.as_millis();
+        let tick_ranges = tick_ranges(market_data, bar_interval_ms)?;
+        self.strategy_time = Duration::ZERO;
+        let mut current: HashMap<String, Option<Decimal>> = indicator_values.keys()
+            .map(|name| (name.clone(), None))
+            .collect();
         
         // Process each bar
         for (bar_idx, bar) in market_data.bars.iter().enumerate() {
+            // Each indicator's value at this bar, updated in place
+            for (name, values) in indicator_values {
+                if let Some(slot) = current.get_mut(name) {
+                    *slot = values.binary_search_by_key(&bar.timestamp, |v| v.timestamp)
+                        .ok()
+                        .map(|i| values[i].value);
+                }
+            }
+            
             // Get strategy signals for this bar
-            let signals = self.get_strategy_signals(
-                strategy,
+            let strategy_start = Instant::now();
+            let signals = strategy.on_bar(&BarContext {
+                symbol: &market_data.symbol,
+                bar_index: bar_idx,
                 bar,
-                indicator_values,
-                bar_idx,
-            ).await?;
+                indicators: &current,
+                position: self.strategy_position(&market_data.symbol),
+            })?;
+            self.strategy_time += strategy_start.elapsed();
             
-            // Process intrabar simulation
-            let bar_trades = self.simulate_intrabar(
-                bar,
-                &signals,
-                intrabar_policy,
-                slippage_mode,
-                &market_data.rules,
-            ).await?;
+            let ticks = ticks_at(market_data, &tick_ranges, bar_idx);
+            self.step_bar(&market_data.symbol, &market_data.rules, bar_interval_ms, bar, ticks, &signals, intrabar_policy, slippage_mode, &mut trades, &mut positions).await?;
+        }
+        
+        Ok(self.build_result(trades, positions))
+    }
+    
+    /// Simulate with indicators advanced alongside the bar loop
+    /// 
+    /// Produces the same result as [`ExchangeSimulator::simulate`] over the series the
+    /// stream's indicators would materialize, but only their current values are held.
+    pub async fn simulate_streaming(
+        &mut self,
+        market_data: &MarketData,
+        indicators: &mut IndicatorStream,
+        strategy: &mut dyn Strategy,
+        intrabar_policy: &IntrabarPolicy,
+        slippage_mode: &SlippageMode,
+    ) -> Result<SimulationResult> {
+        debug!("Starting streaming simulation for symbol: {}", market_data.symbol);
+        let symbol = market_data.symbol.as_str();
+        self.stream_bars(market_data, indicators, intrabar_policy, slippage_mode, |bar_index, bar, indicators, position| {
+            strategy.on_bar(&BarContext { symbol, bar_index, bar, indicators, position })
+        }).await
+    }
+    
+    /// [`ExchangeSimulator::simulate_with_signals`] with each bar's current indicator values
+    /// 
+    /// `signal_source` is called once per bar, after `indicators` has advanced to it.
+    pub async fn simulate_with_indicator_stream<F>(
+        &mut self,
+        market_data: &MarketData,
+        indicators: &mut IndicatorStream,
+        mut signal_source: F,
+        intrabar_policy: &IntrabarPolicy,
+        slippage_mode: &SlippageMode,
+    ) -> Result<SimulationResult>
+    where
+        F: FnMut(usize, &Bar, &HashMap<String, Option<Decimal>>) -> Vec<StrategySignal>,
+    {
+        debug!("Starting in-process streaming simulation for symbol: {}", market_data.symbol);
+        self.stream_bars(market_data, indicators, intrabar_policy, slippage_mode, |bar_idx, bar, values, _| {
+            Ok(signal_source(bar_idx, bar, values))
+        }).await
+    }
+    
+    /// Simulate several symbols on one account, stepping their bars in time order
+    /// 
+    /// Bars are merged by open time, ties taken in `runs` order, so the trade table sees
+    /// every symbol's positions as they stand at each bar: a portfolio cap such as
+    /// `max_open_positions` counts whatever is open then, in whichever order the symbols
+    /// are listed. Each symbol draws stochastic slippage from its own seed, so its fills
+    /// do not depend on the others', and is closed out as by [`Self::finish`] once every
+    /// bar at its last open time has been stepped. Results come back in `runs` order,
+    /// each with the symbol's fills and position snapshots and the account's equity
+    /// curve at the symbol's bars.
+    pub async fn simulate_symbols(
+        &mut self,
+        runs: &mut [SymbolRun<'_>],
+        intrabar_policy: &IntrabarPolicy,
+        slippage_mode: &SlippageMode,
+    ) -> Result<Vec<SimulationResult>> {
+        let mut cursors = runs.iter().map(RunCursor::new).collect::<Result<Vec<_>>>()?;
+        let history_start = self.equity_history.len();
+        self.equity_history.reserve(runs.iter().map(|run| run.market_data.bars.len()).max().unwrap_or(0));
+        self.strategy_time = Duration::ZERO;
+        
+        // The earliest next bar of any symbol; `min_by_key` keeps the first of a tie
+        let next_run = |runs: &[SymbolRun<'_>], cursors: &[RunCursor]| {
+            runs.iter().zip(cursors).enumerate()
+                .filter_map(|(i, (run, cursor))| Some((i, cursor.next_timestamp(run)?)))
+                .min_by_key(|&(_, timestamp)| timestamp)
+        };
+        while let Some((i, timestamp)) = next_run(runs, &cursors) {
+            let run = &mut runs[i];
+            let cursor = &mut cursors[i];
+            let market_data = run.market_data;
+            let symbol = market_data.symbol.as_str();
+            let bar_idx = cursor.next;
+            let bar = &market_data.bars[bar_idx];
             
-            trades.extend(bar_trades);
+            let indicators = match &mut run.indicators {
+                RunIndicators::Materialized(values) => {
+                    for (name, values) in values.iter() {
+                        if let Some(slot) = cursor.current.get_mut(name) {
+                            *slot = values.binary_search_by_key(&bar.timestamp, |v| v.timestamp)
+                                .ok()
+                                .map(|i| values[i].value);
+                        }
+                    }
+                    &cursor.current
+                }
+                RunIndicators::Streaming(stream) => {
+                    stream.update(bar);
+                    stream.current()
+                }
+            };
             
-            // Process bar with trade table generator
-            self.trade_table_generator.process_bar(
+            let strategy_start = Instant::now();
+            let signals = run.strategy.on_bar(&BarContext {
+                symbol,
+                bar_index: bar_idx,
                 bar,
-                &signals,
-                intrabar_policy,
-                slippage_mode,
-                &market_data.rules,
-            )?;
+                indicators,
+                position: self.strategy_position(symbol),
+            })?;
+            self.strategy_time += strategy_start.elapsed();
             
-            // Update positions and equity
-            self.update_positions(&market_data.symbol, bar.timestamp)?;
-            self.update_equity(bar.timestamp);
+            let ticks = ticks_at(market_data, &cursor.tick_ranges, bar_idx);
+            self.swap_samplers(&mut cursor.samplers);
+            let stepped = self.step_bar(symbol, &market_data.rules, cursor.bar_interval_ms, bar, ticks, &signals, intrabar_policy, slippage_mode, &mut cursor.trades, &mut cursor.positions).await;
+            self.swap_samplers(&mut cursor.samplers);
+            stepped?;
+            cursor.next += 1;
             
-            // Record position snapshot
-            if let Some(position) = self.positions.get(&market_data.symbol) {
-                positions.push(position.clone());
+            // Close out symbols whose data ended here once no bar at this time remains
+            if next_run(runs, &cursors).is_some_and(|(_, next)| next == timestamp) {
+                continue;
+            }
+            for (run, cursor) in runs.iter().zip(cursors.iter_mut()) {
+                let Some(last_bar) = run.market_data.bars.last() else {
+                    continue;
+                };
+                if cursor.finished || cursor.next_timestamp(run).is_some() {
+                    continue;
+                }
+                self.swap_samplers(&mut cursor.samplers);
+                let finished = self.finish_symbol(&run.market_data.symbol, last_bar, &run.market_data.rules, slippage_mode);
+                self.swap_samplers(&mut cursor.samplers);
+                finished?;
+                cursor.finished = true;
             }
         }
         
-        Ok(SimulationResult {
+        let history = &self.equity_history[history_start..];
+        Ok(runs.iter().zip(cursors)
+            .map(|(run, cursor)| {
+                let symbol = run.market_data.symbol.as_str();
+                SimulationResult {
+                    trades: cursor.trades,
+                    positions: cursor.positions,
+                    equity_curve: run.market_data.bars.iter()
+                        .filter_map(|bar| history.binary_search_by_key(&bar.timestamp, |point| point.timestamp).ok())
+                        .map(|i| history[i].clone())
+                        .collect(),
+                    max_drawdown: self.max_drawdown,
+                    exposure: self.calculate_exposure(),
+                    net_exposure: self.calculate_net_exposure(),
+                    attribution: self.symbol_attribution(symbol),
+                }
+            })
+            .collect())
+    }
+    
+    /// Bar loop shared by the streaming entry points
+    async fn stream_bars<F>(
+        &mut self,
+        market_data: &MarketData,
+        indicators: &mut IndicatorStream,
+        intrabar_policy: &IntrabarPolicy,
+        slippage_mode: &SlippageMode,
+        mut signal_source: F,
+    ) -> Result<SimulationResult>
+    where
+        F: FnMut(usize, &Bar, &HashMap<String, Option<Decimal>>, Option<&Position>) -> Result<Vec<StrategySignal>>,
+    {
+        ensure_open_time(market_data)?;
+        
+        let mut trades = Vec::with_capacity(market_data.bars.len() / BARS_PER_TRADE_ESTIMATE);
+        let mut positions = Vec::with_capacity(market_data.bars.len());
+        self.equity_history.reserve(market_data.bars.len());
+        let bar_interval_ms = market_data.timeframe.parse::<Timeframe>()?.as_millis();
+        let tick_ranges = tick_ranges(market_data, bar_interval_ms)?;
+        self.strategy_time = Duration::ZERO;
+        
+        for (bar_idx, bar) in market_data.bars.iter().enumerate() {
+            indicators.update(bar);
+            
+            let strategy_start = Instant::now();
+            let signals = signal_source(bar_idx, bar, indicators.current(), self.strategy_position(&market_data.symbol))?;
+            self.strategy_time += strategy_start.elapsed();
+            
+            let ticks = ticks_at(market_data, &tick_ranges, bar_idx);
+            self.step_bar(&market_data.symbol, &market_data.rules, bar_interval_ms, bar, ticks, &signals, intrabar_policy, slippage_mode, &mut trades, &mut positions).await?;
+        }
+        
+        Ok(self.build_result(trades, positions))
+    }
+    
+    /// Time the last [`ExchangeSimulator::simulate`] spent in strategy calls
+    pub fn strategy_time(&self) -> Duration {
+        self.strategy_time
+    }
+    
+    /// Simulate with signals supplied in-process instead of by a WASM strategy
+    /// 
+    /// `signal_source` is called once per bar with the bar index and bar. Used by the
+    /// determinism tests and anywhere a strategy runs natively.
+    pub async fn simulate_with_signals<F>(
+        &mut self,
+        market_data: &MarketData,
+        mut signal_source: F,
+        intrabar_policy: &IntrabarPolicy,
+        slippage_mode: &SlippageMode,
+    ) -> Result<SimulationResult>
+    where
+        F: FnMut(usize, &Bar) -> Vec<StrategySignal>,
+    {
+        debug!("Starting in-process simulation for symbol: {}", market_data.symbol);
+        ensure_open_time(market_data)?;
+        
+        let mut trades = Vec::with_capacity(market_data.bars.len() / BARS_PER_TRADE_ESTIMATE);
+        let mut positions = Vec::with_capacity(market_data.bars.len());
+        self.equity_history.reserve(market_data.bars.len());
+        let bar_interval_ms = market_data.timeframe.parse::<Timeframe>()?.as_millis();
+        let tick_ranges = tick_ranges(market_data, bar_interval_ms)?;
+        
+        for (bar_idx, bar) in market_data.bars.iter().enumerate() {
+            let signals = signal_source(bar_idx, bar);
+            let ticks = ticks_at(market_data, &tick_ranges, bar_idx);
+            self.step_bar(&market_data.symbol, &market_data.rules, bar_interval_ms, bar, ticks, &signals, intrabar_policy, slippage_mode, &mut trades, &mut positions).await?;
+        }
+        
+        Ok(self.build_result(trades, positions))
+    }
+    
+    /// Replay a recorded [`SignalTape`] instead of running a strategy
+    /// 
+    /// The same tape, data and settings always give the same fills, so execution can be
+    /// checked without any signal generation in the loop.
+    pub async fn replay(
+        &mut self,
+        market_data: &MarketData,
+        tape: &SignalTape,
+        intrabar_policy: &IntrabarPolicy,
+        slippage_mode: &SlippageMode,
+    ) -> Result<SimulationResult> {
+        self.simulate_with_signals(market_data, |bar_idx, _| tape.signals_at(bar_idx).to_vec(), intrabar_policy, slippage_mode).await
+    }
+    
+    /// Run one bar of the simulation: fills, trade table, positions and equity
+    /// 
+    /// Fills are appended to `trades` and the symbol's position to `positions`. A bar
+    /// without signals allocates nothing beyond that: buffers are reused across bars and
+    /// fills share the interned symbol name. `ticks` are the bar's trade ticks, used by
+    /// `IntrabarPolicy::ExactTrades` and empty when none are attached.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn step_bar(
+        &mut self,
+        symbol: &str,
+        rules: &ExchangeRules,
+        bar_interval_ms: u64,
+        bar: &Bar,
+        ticks: &[Trade],
+        signals: &[StrategySignal],
+        intrabar_policy: &IntrabarPolicy,
+        slippage_mode: &SlippageMode,
+        trades: &mut Vec<ExecutedTrade>,
+        positions: &mut Vec<Position>,
+    ) -> Result<()> {
+        let mut stamped = std::mem::take(&mut self.stamped_signals);
+        stamp_symbol(&mut stamped, signals, symbol);
+        
+        // Burn-in: early indicator values are too immature to trade on
+        let in_warmup = self.count_bar(symbol) < self.warmup_bars;
+        if in_warmup && !stamped.is_empty() {
+            let entries: Vec<StrategySignal> = stamped.iter()
+                .filter(|signal| !self.reduces_position(signal))
+                .cloned()
+                .collect();
+            stamped.retain(|signal| self.reduces_position(signal));
+            self.trade_table_generator.reject_warmup_signals(bar, &entries);
+        }
+        let signals = stamped.as_slice();
+        
+        // Process intrabar simulation, or fill the last bar's signals at this bar's open
+        match self.fill_model {
+            FillModel::SameBar => {
+                let resolved = self.resolve_overlapping(signals);
+                let fill_signals = self.spot_constrained(symbol, &resolved, rules);
+                self.simulate_intrabar(
+                    bar,
+                    bar_interval_ms,
+                    ticks,
+                    fill_signals.as_deref().unwrap_or(&resolved),
+                    intrabar_policy,
+                    slippage_mode,
+                    rules,
+                    trades,
+                ).await?;
+            }
+            FillModel::NextBarOpen => {
+                let mut pending = std::mem::take(&mut self.pending_fills);
+                if pending.iter().any(|signal| signal.symbol != symbol) {
+                    // Other symbols' signals wait for their own next bar
+                    let (due, waiting) = pending.into_iter().partition(|signal| signal.symbol == symbol);
+                    pending = due;
+                    self.pending_fills = waiting;
+                }
+                let fill_signals = self.spot_constrained(symbol, &pending, rules);
+                self.simulate_next_bar_open(bar, fill_signals.as_deref().unwrap_or(&pending), slippage_mode, rules, trades)?;
+                pending.clear();
+                pending.extend_from_slice(&self.resolve_overlapping(signals));
+                if self.pending_fills.is_empty() {
+                    self.pending_fills = pending;
+                } else {
+                    self.pending_fills.append(&mut pending);
+                }
+            }
+        }
+        
+        // Process bar with trade table generator
+        self.trade_table_generator.set_bar_interval_ms(bar_interval_ms);
+        self.trade_table_generator.process_bar(
+            symbol,
+            bar,
+            if in_warmup { &[] } else { signals },
+            intrabar_policy,
+            slippage_mode,
+            rules,
+        )?;
+        
+        // Update positions and equity
+        self.marks.insert(symbol.to_string(), bar.close);
+        self.sync_positions(Some(symbol), bar.timestamp);
+        self.update_equity(bar.timestamp);
+        
+        // Record position snapshot
+        positions.extend(self.symbol_positions(symbol).cloned());
+        
+        self.stamped_signals = stamped;
+        Ok(())
+    }
+    
+    /// Count a bar of `symbol`'s run, returning how many came before it
+    fn count_bar(&mut self, symbol: &str) -> u64 {
+        match self.bars_seen.get_mut(symbol) {
+            Some(seen) => {
+                *seen += 1;
+                *seen - 1
+            }
+            None => {
+                self.bars_seen.insert(symbol.to_string(), 1);
+                0
+            }
+        }
+    }
+    
+    /// Sides whose fills book to separate positions: both in hedge mode, else one
+    fn book_sides(&self) -> &'static [TradeSide] {
+        match self.trade_table_generator.position_mode() {
+            PositionMode::OneWay => &[TradeSide::Buy],
+            PositionMode::Hedge => &[TradeSide::Buy, TradeSide::Sell],
+        }
+    }
+    
+    /// Book key of the position a fill of `side` on `symbol` goes to
+    /// 
+    /// The symbol itself, or in hedge mode its long or short leg, named as the trade
+    /// table names its position slots.
+    fn position_key<'a>(&self, symbol: &'a str, side: &TradeSide) -> Cow<'a, str> {
+        match self.trade_table_generator.position_mode() {
+            PositionMode::OneWay => Cow::Borrowed(symbol),
+            PositionMode::Hedge => Cow::Owned(match side {
+                TradeSide::Buy => format!("{}:{:?}", symbol, TradeType::Long),
+                TradeSide::Sell => format!("{}:{:?}", symbol, TradeType::Short),
+            }),
+        }
+    }
+    
+    /// `symbol`'s positions in the book; in hedge mode the long leg comes first
+    fn symbol_positions<'a>(&'a self, symbol: &'a str) -> impl Iterator<Item = &'a Position> + 'a {
+        self.book_sides().iter()
+            .filter_map(move |side| self.positions.get(self.position_key(symbol, side).as_ref()))
+    }
+    
+    /// Whether `signal` trades against its symbol's position rather than opening one
+    /// 
+    /// Hedge-mode signals always open or add to a leg of their own side.
+    fn reduces_position(&self, signal: &StrategySignal) -> bool {
+        if matches!(self.trade_table_generator.position_mode(), PositionMode::Hedge) {
+            return false;
+        }
+        self.positions.get(signal.symbol.as_str()).is_some_and(|position| match signal.side {
+            TradeSide::Buy => position.quantity < Decimal::ZERO,
+            TradeSide::Sell => position.quantity > Decimal::ZERO,
+        })
+    }
+    
+    /// Position strategies see for `symbol`: its only one, or its long leg in hedge mode
+    fn strategy_position(&self, symbol: &str) -> Option<&Position> {
+        self.positions.get(self.position_key(symbol, &TradeSide::Buy).as_ref())
+    }
+    
+    /// Shared name of `symbol`; only its first use allocates
+    fn intern(&mut self, symbol: &str) -> Symbol {
+        intern(&mut self.symbols, symbol)
+    }
+    
+    /// Signals as the simulator fills them, with several for one symbol and side resolved
+    /// by the trade table's `OverlappingEntryPolicy`, which records the rejections
+    fn resolve_overlapping<'a>(&self, signals: &'a [StrategySignal]) -> Cow<'a, [StrategySignal]> {
+        let overlapping = signals.iter().enumerate().any(|(i, signal)| {
+            signals[..i].iter().any(|earlier| {
+                earlier.symbol == signal.symbol
+                    && matches!(earlier.side, TradeSide::Buy) == matches!(signal.side, TradeSide::Buy)
+            })
+        });
+        if !overlapping {
+            return Cow::Borrowed(signals);
+        }
+        let (resolved, _) = resolve_overlapping(signals, self.trade_table_generator.overlapping_entries());
+        Cow::Owned(resolved.into_iter().map(|(signal, _)| signal).collect())
+    }
+    
+    /// Signals as they can execute on a symbol that cannot be shorted
+    /// 
+    /// `None` when shorts are allowed and the signals execute as given. Otherwise sells
+    /// are capped at the inventory held and dropped once it runs out; the trade table
+    /// records the refused short entries.
+    fn spot_constrained(&self, symbol: &str, signals: &[StrategySignal], rules: &ExchangeRules) -> Option<Vec<StrategySignal>> {
+        if rules.allow_shorts || !signals.iter().any(|s| matches!(s.side, TradeSide::Sell)) {
+            return None;
+        }
+        
+        // A hedge-mode sell opens a short leg rather than selling the long one
+        let mut inventory = match self.trade_table_generator.position_mode() {
+            PositionMode::OneWay => self.positions.get(symbol)
+                .map_or(Decimal::ZERO, |position| position.quantity.max(Decimal::ZERO)),
+            PositionMode::Hedge => Decimal::ZERO,
+        };
+        let mut constrained = Vec::with_capacity(signals.len());
+        for signal in signals {
+            match signal.side {
+                TradeSide::Buy => constrained.push(signal.clone()),
+                TradeSide::Sell if inventory > Decimal::ZERO => {
+                    let size = signal.size.min(inventory);
+                    inventory -= size;
+                    constrained.push(StrategySignal { size, ..signal.clone() });
+                }
+                TradeSide::Sell => debug!("Dropping sell on {} with no inventory: shorts not allowed", symbol),
+            }
+        }
+        Some(constrained)
+    }
+    
+    /// Close trade table positions left open when the data runs out
+    /// 
+    /// Call once after the final bar. Checkpointed runs call it only after the last
+    /// chunk, so positions carry across the checkpoint instead of being closed early.
+    /// Signals of the last bar still waiting to fill under `FillModel::NextBarOpen` are dropped.
+    pub fn finish(&mut self, last_bar: Option<&Bar>, rules: &ExchangeRules, slippage_mode: &SlippageMode) -> Result<()> {
+        self.pending_fills.clear();
+        if let Some(last_bar) = last_bar {
+            self.trade_table_generator.finalize(last_bar, slippage_mode, rules)?;
+            self.sync_positions(None, last_bar.timestamp);
+        }
+        Ok(())
+    }
+    
+    /// [`Self::finish`] for `symbol` alone, once its data has run out at `last_bar`
+    fn finish_symbol(&mut self, symbol: &str, last_bar: &Bar, rules: &ExchangeRules, slippage_mode: &SlippageMode) -> Result<()> {
+        self.pending_fills.retain(|signal| signal.symbol != symbol);
+        self.trade_table_generator.finalize_symbol(symbol, last_bar, slippage_mode, rules)?;
+        self.sync_positions(Some(symbol), last_bar.timestamp);
+        Ok(())
+    }
+    
+    /// Exchange the fill and trade table samplers with `samplers`, a symbol's own pair
+    fn swap_samplers(&mut self, samplers: &mut (SlippageSampler, SlippageSampler)) {
+        std::mem::swap(&mut self.slippage_sampler, &mut samplers.0);
+        self.trade_table_generator.swap_slippage_sampler(&mut samplers.1);
+    }
+    
+    fn build_result(&self, trades: Vec<ExecutedTrade>, positions: Vec<Position>) -> SimulationResult {
+        SimulationResult {
             trades,
             positions,
             equity_curve: self.equity_history.clone(),
             max_drawdown: self.max_drawdown,
             exposure: self.calculate_exposure(),
-            attribution: self.calculate_attribution(),
-        })
-    }
-    
-    /// Get strategy signals for a given bar
-    async fn get_strategy_signals(
-        &self,
-        strategy: &crate::wasm::Strategy,
-        bar: &Bar,
-        indicator_values: &HashMap<String, Vec<IndicatorValue>>,
-        bar_idx: usize,
-    ) -> Result<Vec<StrategySignal>> {
-        // This would call the WASM strategy with current market state
-        // For now, return empty signals
-        Ok(Vec::new())
+            net_exposure: self.calculate_net_exposure(),
+            attribution: self.attribution(),
+        }
     }
     
-    /// Simulate intrabar trading
+    /// Simulate intrabar trading, appending fills to `trades`
+    #[allow(clippy::too_many_arguments)]
     async fn simulate_intrabar(
         &mut self,
         bar: &Bar,
+        bar_interval_ms: u64,
+        ticks: &[Trade],
         signals: &[StrategySignal],
         intrabar_policy: &IntrabarPolicy,
         slippage_mode: &SlippageMode,
         rules: &ExchangeRules,
-    ) -> Result<Vec<ExecutedTrade>> {
-        let mut trades = Vec::new();
+        trades: &mut Vec<ExecutedTrade>,
+    ) -> Result<()> {
+        if signals.is_empty() {
+            return Ok(());
+        }
         
         match intrabar_policy {
             IntrabarPolicy::ExactTrades => {
-                // Use exact trade paths - would require trade data
-                trades.extend(self.simulate_exact_trades(bar, signals, slippage_mode, rules).await?);
+                self.simulate_exact_trades(bar, ticks, signals, slippage_mode, rules, trades).await
             },
             IntrabarPolicy::OneSecondBars => {
                 // Use 1s bars with fixed path order
-                trades.extend(self.simulate_one_second_bars(bar, signals, slippage_mode, rules).await?);
+                self.simulate_one_second_bars(bar, bar_interval_ms, signals, slippage_mode, rules, trades).await
             },
             IntrabarPolicy::LinearInterpolation => {
                 // Linear interpolation between OHLC
-                trades.extend(self.simulate_linear_interpolation(bar, signals, slippage_mode, rules).await?);
+                self.simulate_linear_interpolation(bar, signals, slippage_mode, rules, trades).await
             },
         }
+    }
+    
+    /// Fill the previous bar's signals at `bar.open`, appending to `trades`
+    fn simulate_next_bar_open(
+        &mut self,
+        bar: &Bar,
+        signals: &[StrategySignal],
+        slippage_mode: &SlippageMode,
+        rules: &ExchangeRules,
+        trades: &mut Vec<ExecutedTrade>,
+    ) -> Result<()> {
+        for signal in signals {
+            let executed_price = self.calculate_execution_price(
+                bar.open,
+                bar,
+                &signal.side,
+                slippage_mode,
+                rules,
+            )?;
+            
+            let fee = self.calculate_fee(signal.size, executed_price, Liquidity::of_entry(signal), rules)?;
+            let slippage = (executed_price - bar.open).abs();
+            
+            trades.push(ExecutedTrade {
+                timestamp: bar.timestamp,
+                symbol: self.intern(&signal.symbol),
+                side: signal.side.clone(),
+                quantity: signal.size,
+                price: executed_price,
+                fee,
+                slippage,
+                reason_code: ReasonCode::NextBarOpen,
+                rule_tag: signal.rule_tag.clone(),
+                fill_outside_bar: outside_bar(executed_price, bar),
+            });
+        }
         
-        Ok(trades)
+        Ok(())
     }
     
     /// Simulate exact trade execution
+    /// 
+    /// With ticks attached, each signal fills at the first of the bar's ticks it can trade
+    /// against: the first tick for market orders, the first at or through `entry_price`
+    /// for limits. Fills come out in tick order, which is microsecond order, ties kept in
+    /// signal order. Bars without ticks fill at the close.
     async fn simulate_exact_trades(
         &mut self,
         bar: &Bar,
+        ticks: &[Trade],
         signals: &[StrategySignal],
         slippage_mode: &SlippageMode,
         rules: &ExchangeRules,
-    ) -> Result<Vec<ExecutedTrade>> {
-        let mut trades = Vec::new();
+        trades: &mut Vec<ExecutedTrade>,
+    ) -> Result<()> {
+        if !ticks.is_empty() {
+            return self.simulate_ticks(bar, ticks, signals, slippage_mode, rules, trades);
+        }
         
-        // This would use actual trade data for precise execution
-        // For now, simulate at bar close with slippage
         for signal in signals {
             let executed_price = self.calculate_execution_price(
                 bar.close,
+                bar,
                 &signal.side,
                 slippage_mode,
                 rules,
             )?;
             
-            let fee = self.calculate_fee(signal.size, executed_price, rules)?;
+            let fee = self.calculate_fee(signal.size, executed_price, Liquidity::of_entry(signal), rules)?;
             let slippage = (executed_price - bar.close).abs();
             
             trades.push(ExecutedTrade {
                 timestamp: bar.timestamp,
-                symbol: "BTCUSDT".to_string(), // Would come from context
+                symbol: self.intern(&signal.symbol),
                 side: signal.side.clone(),
                 quantity: signal.size,
                 price: executed_price,
                 fee,
                 slippage,
-                reason_code: "strategy_signal".to_string(),
+                reason_code: ReasonCode::BarClose,
+                rule_tag: signal.rule_tag.clone(),
+                fill_outside_bar: outside_bar(executed_price, bar),
             });
         }
         
-        Ok(trades)
+        Ok(())
     }
     
-    /// Simulate using 1-second bars
-    async fn simulate_one_second_bars(
+    /// Fill `signals` against the bar's `ticks`, see [`ExchangeSimulator::simulate_exact_trades`]
+    fn simulate_ticks(
         &mut self,
         bar: &Bar,
+        ticks: &[Trade],
         signals: &[StrategySignal],
         slippage_mode: &SlippageMode,
         rules: &ExchangeRules,
-    ) -> Result<Vec<ExecutedTrade>> {
-        let mut trades = Vec::new();
-        
-        // Simulate 60 1-second executions within the minute bar
-        let bar_duration_ms = 60000; // 1 minute
-        let second_duration_ms = 1000;
-        
-        for (second, signal) in signals.iter().enumerate() {
-            if second >= 60 {
-                break; // Limit to 60 seconds
+        trades: &mut Vec<ExecutedTrade>,
+    ) -> Result<()> {
+        let mut scheduled: Vec<(usize, &StrategySignal)> = Vec::with_capacity(signals.len());
+        for signal in signals {
+            let reachable = ticks.iter().position(|tick| match (signal.entry_price, &signal.side) {
+                (None, _) => true,
+                (Some(limit), TradeSide::Buy) => tick.price <= limit,
+                (Some(limit), TradeSide::Sell) => tick.price >= limit,
+            });
+            match reachable {
+                Some(index) => scheduled.push((index, signal)),
+                None => debug!("Entry price {:?} not traded within bar at {}", signal.entry_price, bar.timestamp),
             }
+        }
+        // Stable sort keeps signal order within a tick
+        scheduled.sort_by_key(|(index, _)| *index);
+        
+        for (index, signal) in scheduled {
+            let tick = &ticks[index];
+            let executed_price = self.calculate_execution_price(
+                tick.price,
+                bar,
+                &signal.side,
+                slippage_mode,
+                rules,
+            )?;
             
-            let timestamp = bar.timestamp + (second * second_duration_ms) as u64;
+            let fee = self.calculate_fee(signal.size, executed_price, Liquidity::of_entry(signal), rules)?;
+            let slippage = (executed_price - tick.price).abs();
             
-            // Interpolate price within the bar
-            let progress = second as f64 / 60.0;
-            let interpolated_price = bar.open + (bar.close - bar.open) * Decimal::from_f64(progress).unwrap_or(dec!(0.0));
+            trades.push(ExecutedTrade {
+                timestamp: tick.timestamp.as_millis(),
+                symbol: self.intern(&signal.symbol),
+                side: signal.side.clone(),
+                quantity: signal.size,
+                price: executed_price,
+                fee,
+                slippage,
+                reason_code: ReasonCode::Tick,
+                rule_tag: signal.rule_tag.clone(),
+                fill_outside_bar: outside_bar(executed_price, bar),
+            });
+        }
+        
+        Ok(())
+    }
+    
+    /// Simulate using 1-second bars
+    /// 
+    /// Walks the bar's one-second path (see [`one_second_path`]) and executes each signal
+    /// on the sub-step it applies to (see [`signal_sub_step`]). Fills come out in sub-step
+    /// order, ties kept in signal order.
+    async fn simulate_one_second_bars(
+        &mut self,
+        bar: &Bar,
+        bar_interval_ms: u64,
+        signals: &[StrategySignal],
+        slippage_mode: &SlippageMode,
+        rules: &ExchangeRules,
+        trades: &mut Vec<ExecutedTrade>,
+    ) -> Result<()> {
+        let path = one_second_path(bar, bar_interval_ms);
+        let mut scheduled: Vec<(usize, &StrategySignal)> = Vec::with_capacity(signals.len());
+        for signal in signals {
+            match signal_sub_step(&path, signal) {
+                Some(step) => scheduled.push((step, signal)),
+                None => debug!("Entry price {:?} not reached within bar at {}", signal.entry_price, bar.timestamp),
+            }
+        }
+        // Stable sort keeps signal order within a sub-step
+        scheduled.sort_by_key(|(step, _)| *step);
+        
+        for (step, signal) in scheduled {
+            let (timestamp, path_price) = path[step];
             
             let executed_price = self.calculate_execution_price(
-                interpolated_price,
+                path_price,
+                bar,
                 &signal.side,
                 slippage_mode,
                 rules,
             )?;
             
-            let fee = self.calculate_fee(signal.size, executed_price, rules)?;
-            let slippage = (executed_price - interpolated_price).abs();
+            let fee = self.calculate_fee(signal.size, executed_price, Liquidity::of_entry(signal), rules)?;
+            let slippage = (executed_price - path_price).abs();
             
             trades.push(ExecutedTrade {
                 timestamp,
-                symbol: "BTCUSDT".to_string(),
+                symbol: self.intern(&signal.symbol),
                 side: signal.side.clone(),
                 quantity: signal.size,
                 price: executed_price,
                 fee,
                 slippage,
-                reason_code: "one_second_bar".to_string(),
+                reason_code: ReasonCode::OneSecondBar,
+                rule_tag: signal.rule_tag.clone(),
+                fill_outside_bar: outside_bar(executed_price, bar),
             });
         }
         
-        Ok(trades)
+        Ok(())
     }
     
     /// Simulate using linear interpolation
@@ -236,9 +1127,8 @@ impl ExchangeSimulator {
         signals: &[StrategySignal],
         slippage_mode: &SlippageMode,
         rules: &ExchangeRules,
-    ) -> Result<Vec<ExecutedTrade>> {
-        let mut trades = Vec::new();
-        
+        trades: &mut Vec<ExecutedTrade>,
+    ) -> Result<()> {
         // Simple linear interpolation from open to close
         for (i, signal) in signals.iter().enumerate() {
             let progress = if signals.len() > 1 {
@@ -251,33 +1141,40 @@ impl ExchangeSimulator {
             
             let executed_price = self.calculate_execution_price(
                 interpolated_price,
+                bar,
                 &signal.side,
                 slippage_mode,
                 rules,
             )?;
             
-            let fee = self.calculate_fee(signal.size, executed_price, rules)?;
+            let fee = self.calculate_fee(signal.size, executed_price, Liquidity::of_entry(signal), rules)?;
             let slippage = (executed_price - interpolated_price).abs();
             
             trades.push(ExecutedTrade {
                 timestamp: bar.timestamp,
-                symbol: "BTCUSDT".to_string(),
+                symbol: self.intern(&signal.symbol),
                 side: signal.side.clone(),
                 quantity: signal.size,
                 price: executed_price,
                 fee,
                 slippage,
-                reason_code: "linear_interpolation".to_string(),
+                reason_code: ReasonCode::LinearInterpolation,
+                rule_tag: signal.rule_tag.clone(),
+                fill_outside_bar: outside_bar(executed_price, bar),
             });
         }
         
-        Ok(trades)
+        Ok(())
     }
     
     /// Calculate execution price with slippage
+    /// 
+    /// Under [`FillRangePolicy::Clamp`] the fill is kept within `bar`'s low and high, as
+    /// the trade table keeps its own fills, at the nearest tick inside them.
     fn calculate_execution_price(
-        &self,
+        &mut self,
         base_price: Decimal,
+        bar: &Bar,
         side: &TradeSide,
         slippage_mode: &SlippageMode,
         rules: &ExchangeRules,
@@ -290,10 +1187,11 @@ impl ExchangeSimulator {
                 base_price * slippage_rate
             },
             SlippageMode::SyntheticBook => {
-                // Simulate synthetic order book slippage (0.05% - 0.5%)
-                let slippage_rate = dec!(0.0005); // 0.05%
-                base_price * slippage_rate
+                // Synthetic order book slippage, thinner on bars with fewer trades
+                base_price * synthetic_book_rate(bar.trade_count)
             },
+            SlippageMode::FixedTicks(ticks) => rules.tick_size * Decimal::from(*ticks),
+            SlippageMode::Stochastic { mean, stddev } => base_price * self.slippage_sampler.next_rate(*mean, *stddev),
         };
         
         let execution_price = match side {
@@ -304,7 +1202,14 @@ impl ExchangeSimulator {
         // Quantize to tick size
         let quantized_price = self.quantize_price(execution_price, rules)?;
         
-        Ok(quantized_price)
+        if self.trade_table_generator.fill_range_policy() == FillRangePolicy::Flag {
+            return Ok(quantized_price);
+        }
+        
+        // The bar's extremes brought onto the tick grid, so a clamped fill stays on it
+        let low = (bar.low / rules.tick_size).ceil() * rules.tick_size;
+        let high = (bar.high / rules.tick_size).floor() * rules.tick_size;
+        Ok(if low <= high { quantized_price.clamp(low, high) } else { quantized_price })
     }
     
     /// Calculate trading fees
@@ -312,11 +1217,10 @@ impl ExchangeSimulator {
         &self,
         quantity: Decimal,
         price: Decimal,
+        liquidity: Liquidity,
         rules: &ExchangeRules,
     ) -> Result<Decimal> {
-        let notional = quantity * price;
-        let fee_rate = rules.taker_fee; // Assume taker for simplicity
-        let fee = notional * fee_rate;
+        let fee = rules.fee(quantity, price, liquidity);
         
         // Quantize fee to precision
         let quantized_fee = self.quantize_fee(fee, rules)?;
@@ -330,40 +1234,79 @@ impl ExchangeSimulator {
         Ok(quantized)
     }
     
-    /// Quantize quantity to lot size
-    fn quantize_quantity(&self, quantity: Decimal, rules: &ExchangeRules) -> Result<Decimal> {
-        let quantized = (quantity / rules.lot_size).round() * rules.lot_size;
-        Ok(quantized)
-    }
-    
     /// Quantize fee to precision
     fn quantize_fee(&self, fee: Decimal, rules: &ExchangeRules) -> Result<Decimal> {
-        let precision = Decimal::from(10_u64.pow(rules.precision_price as u32));
-        let quantized = (fee * precision).round() / precision;
-        Ok(quantized)
+        Ok(round_to_precision(fee, rules.precision_price, rules.fee_rounding))
     }
     
-    /// Update positions after trade execution
-    fn update_positions(&mut self, symbol: &str, timestamp: u64) -> Result<()> {
-        // This would update positions based on executed trades
-        // For now, maintain current position
-        if let Some(position) = self.positions.get_mut(symbol) {
+    /// Bring the position book in line with the trade table
+    /// 
+    /// Trades it closed since the last sync realize their PnL (net of fees) into their
+    /// slot and flatten it; its open positions hold their slot at their entry price,
+    /// marked to their symbol's last close. Slots of `symbol` are stamped `timestamp`.
+    /// Fills never net into the book themselves, so positions, exposure and
+    /// [`Self::current_state`] agree with the trade table's open trades.
+    fn sync_positions(&mut self, symbol: Option<&str>, timestamp: u64) {
+        let Self { positions, trade_table_generator, symbols, marks, booked_trades, .. } = self;
+        
+        for trade in &trade_table_generator.trade_records()[*booked_trades..] {
+            let key = trade_table_generator.position_key(&trade.symbol, &trade.trade_type);
+            let position = positions.entry(key).or_insert_with(|| Position {
+                timestamp,
+                symbol: intern(symbols, &trade.symbol),
+                quantity: dec!(0.0),
+                avg_price: dec!(0.0),
+                unrealized_pnl: dec!(0.0),
+                realized_pnl: dec!(0.0),
+            });
             position.timestamp = timestamp;
-            // Update unrealized PnL based on current market price
+            position.quantity = dec!(0.0);
+            position.avg_price = dec!(0.0);
+            position.unrealized_pnl = dec!(0.0);
+            position.realized_pnl += trade.pnl_usd;
         }
+        *booked_trades = trade_table_generator.trade_records().len();
         
-        Ok(())
+        for (key, active) in trade_table_generator.active_positions() {
+            let quantity = match active.trade_type {
+                TradeType::Long => active.quantity,
+                TradeType::Short => -active.quantity,
+            };
+            let mark = marks.get(&active.symbol).copied().unwrap_or(active.entry_price);
+            // Looked up by name first, so only a slot's first position allocates its key
+            if !positions.contains_key(key) {
+                positions.insert(key.clone(), Position {
+                    timestamp: active.entry_time,
+                    symbol: intern(symbols, &active.symbol),
+                    quantity: dec!(0.0),
+                    avg_price: dec!(0.0),
+                    unrealized_pnl: dec!(0.0),
+                    realized_pnl: dec!(0.0),
+                });
+            }
+            let Some(position) = positions.get_mut(key) else {
+                continue;
+            };
+            if symbol == Some(active.symbol.as_str()) {
+                position.timestamp = timestamp;
+            }
+            position.quantity = quantity;
+            position.avg_price = active.entry_price;
+            position.unrealized_pnl = (mark - active.entry_price) * quantity;
+        }
     }
     
     /// Update equity curve
+    /// 
+    /// Equity is the trade table's: starting cash plus its closed trades' PnL, with its
+    /// open positions marked to their symbol's last close (their entry price before it
+    /// has one).
     fn update_equity(&mut self, timestamp: u64) {
-        // Calculate total equity from all positions
-        let mut total_equity = self.current_equity;
-        
-        for position in self.positions.values() {
-            total_equity += position.realized_pnl;
-            total_equity += position.unrealized_pnl;
-        }
+        let realized_equity = self.initial_equity + self.trade_table_generator.realized_pnl();
+        let unrealized_pnl: Decimal = self.trade_table_generator.active_positions().values()
+            .map(|position| position.unrealized_pnl(self.marks.get(&position.symbol).copied().unwrap_or(position.entry_price)))
+            .sum();
+        let total_equity = realized_equity + unrealized_pnl;
         
         // Update peak equity and drawdown
         if total_equity > self.peak_equity {
@@ -375,33 +1318,93 @@ impl ExchangeSimulator {
             self.max_drawdown = current_drawdown;
         }
         
-        // Record equity point
-        self.equity_history.push(EquityPoint {
+        // Exposure is measured against the equity we just computed
+        self.current_equity = total_equity;
+        
+        // Record equity point, one per timestamp: symbols stepped together update it in turn
+        let point = EquityPoint {
             timestamp,
             equity: total_equity,
+            realized_equity,
+            unrealized_pnl,
             drawdown: current_drawdown,
             exposure: self.calculate_exposure(),
-        });
-        
-        self.current_equity = total_equity;
+            net_exposure: self.calculate_net_exposure(),
+        };
+        match self.equity_history.last_mut() {
+            Some(last) if last.timestamp == timestamp => *last = point,
+            _ => self.equity_history.push(point),
+        }
     }
     
-    /// Calculate current exposure
+    /// Calculate gross exposure as a fraction of current equity
+    /// 
+    /// Longs and shorts both add to gross notional, so 1.5 means the book is
+    /// 150% leveraged regardless of direction. Notional is taken at the last mark.
     fn calculate_exposure(&self) -> Decimal {
-        self.positions.values()
-            .map(|p| p.quantity.abs() * p.avg_price)
-            .sum()
+        let gross_notional: Decimal = self.positions.values()
+            .map(|p| Self::marked_notional(p).abs())
+            .sum();
+        
+        Self::exposure_ratio(gross_notional, self.current_equity)
     }
     
-    /// Calculate attribution by rule/signal
-    fn calculate_attribution(&self) -> HashMap<String, Decimal> {
-        let mut attribution = HashMap::new();
+    /// Calculate net exposure (long notional minus short notional) as a fraction of current equity
+    fn calculate_net_exposure(&self) -> Decimal {
+        let net_notional: Decimal = self.positions.values()
+            .map(Self::marked_notional)
+            .sum();
         
-        // This would calculate attribution based on strategy rules
-        attribution.insert("momentum".to_string(), dec!(0.0));
-        attribution.insert("mean_reversion".to_string(), dec!(0.0));
-        attribution.insert("breakout".to_string(), dec!(0.0));
+        Self::exposure_ratio(net_notional, self.current_equity)
+    }
+    
+    /// Signed notional of a position at its last mark (quantity × bar close)
+    /// 
+    /// `sync_positions` sets the unrealized PnL to `(close - avg_price) × quantity`,
+    /// so adding it to the cost basis gives the marked value without storing the close.
+    fn marked_notional(position: &Position) -> Decimal {
+        position.quantity * position.avg_price + position.unrealized_pnl
+    }
+    
+    /// Express a notional amount relative to equity (cash plus marked positions)
+    fn exposure_ratio(notional: Decimal, equity: Decimal) -> Decimal {
+        // A wiped-out account has no meaningful leverage figure
+        if equity <= dec!(0.0) {
+            return dec!(0.0);
+        }
         
+        notional / equity
+    }
+    
+    /// Realized PnL by rule/signal
+    /// 
+    /// Each entry sums the PnL (net of fees) of the trade table's closed trades opened
+    /// by signals with that rule tag, however they exited: take profit, stop, timeout,
+    /// liquidation, flatten or end of data. Trades opened untagged are grouped under
+    /// "untagged". Call after [`Self::finish`] to include end-of-data closes.
+    pub fn attribution(&self) -> HashMap<String, Decimal> {
+        self.attribution_of(None)
+    }
+    
+    /// [`Self::attribution`] of `symbol`'s trades alone
+    pub fn symbol_attribution(&self, symbol: &str) -> HashMap<String, Decimal> {
+        self.attribution_of(Some(symbol))
+    }
+    
+    /// Attribution of the trades of `symbol`, or of every symbol
+    fn attribution_of(&self, symbol: Option<&str>) -> HashMap<String, Decimal> {
+        let mut attribution: HashMap<String, Decimal> = HashMap::new();
+        let trades = self.trade_table_generator.trade_records().iter()
+            .filter(|trade| symbol.is_none_or(|symbol| trade.symbol == symbol));
+        for trade in trades {
+            let tag = trade.rule_tag.as_deref().unwrap_or("untagged");
+            match attribution.get_mut(tag) {
+                Some(total) => *total += trade.pnl_usd,
+                None => {
+                    attribution.insert(tag.to_string(), trade.pnl_usd);
+                }
+            }
+        }
         attribution
     }
     
@@ -409,5 +1412,896 @@ impl ExchangeSimulator {
     pub fn get_trade_table_result(&self) -> TradeTableResult {
         self.trade_table_generator.generate_result()
     }
+    
+    /// Trade table result of `symbol`'s trades and rejections
+    pub fn get_symbol_trade_table_result(&self, symbol: &str) -> TradeTableResult {
+        self.trade_table_generator.generate_symbol_result(symbol)
+    }
+    
+    /// Open positions, equity, drawdown and exposure as of the last processed bar
+    pub fn current_state(&self) -> LiveState {
+        let positions: HashMap<String, Position> = self.positions.iter()
+            .filter(|(_, position)| !position.quantity.is_zero())
+            .map(|(symbol, position)| (symbol.clone(), position.clone()))
+            .collect();
+        let exposure = positions.iter()
+            .map(|(symbol, position)| {
+                let notional = Self::marked_notional(position).abs();
+                (symbol.clone(), Self::exposure_ratio(notional, self.current_equity))
+            })
+            .collect();
+        let drawdown = if self.peak_equity > dec!(0.0) {
+            (self.peak_equity - self.current_equity) / self.peak_equity
+        } else {
+            dec!(0.0)
+        };
+        
+        LiveState {
+            timestamp: self.equity_history.last().map(|point| point.timestamp),
+            positions,
+            open_trades: self.trade_table_generator.active_positions().clone(),
+            equity: self.current_equity,
+            peak_equity: self.peak_equity,
+            drawdown,
+            max_drawdown: self.max_drawdown,
+            exposure,
+        }
+    }
+    
+    pub(crate) fn trade_table_generator(&self) -> &TradeTableGenerator {
+        &self.trade_table_generator
+    }
+    
+    pub(crate) fn last_equity_point(&self) -> Option<&EquityPoint> {
+        self.equity_history.last()
+    }
 }
 
+/// One-second price path through a bar, interpolated from open to close
+/// 
+/// A bar spanning `bar_interval_ms` has one step per second (60 for 1m, 300 for 5m),
+/// each stamped with the start of its second. Bars shorter than a second get one step.
+pub(crate) fn one_second_path(bar: &Bar, bar_interval_ms: u64) -> Vec<(u64, Decimal)> {
+    let steps = (bar_interval_ms / SUB_STEP_MS).max(1);
+    (0..steps)
+        .map(|step| {
+            let price = bar.open + (bar.close - bar.open) * Decimal::from(step) / Decimal::from(steps);
+            (bar.timestamp + step * SUB_STEP_MS, price)
+        })
+        .collect()
+}
+
+/// Sub-step a signal executes on
+/// 
+/// Market signals (no entry price) execute on the first step. Signals with an entry
+/// price execute on the first step whose path price reaches it (at or below for buys,
+/// at or above for sells), or not at all if the path never gets there.
+/// The trade table applies the same rule, so an unreached limit neither fills nor opens
+/// a position.
+pub(crate) fn signal_sub_step(path: &[(u64, Decimal)], signal: &StrategySignal) -> Option<usize> {
+    match signal.entry_price {
+        None => Some(0),
+        Some(limit) => path.iter().position(|&(_, price)| match signal.side {
+            TradeSide::Buy => price <= limit,
+            TradeSide::Sell => price >= limit,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trade_table::OverlappingEntryPolicy;
+
+    fn position(symbol: &str, quantity: Decimal, avg_price: Decimal) -> Position {
+        Position {
+            timestamp: 1609459200000,
+            symbol: symbol.into(),
+            quantity,
+            avg_price,
+            unrealized_pnl: dec!(0.0),
+            realized_pnl: dec!(0.0),
+        }
+    }
+
+    #[test]
+    fn test_gross_and_net_exposure_with_long_and_short() {
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        simulator.positions.insert("BTCUSDT".to_string(), position("BTCUSDT", dec!(0.2), dec!(50000.0)));
+        simulator.positions.insert("ETHUSDT".to_string(), position("ETHUSDT", dec!(-2.0), dec!(2500.0)));
+        
+        // $10,000 long + $5,000 short against $10,000 equity
+        assert_eq!(simulator.calculate_exposure(), dec!(1.5));
+        assert_eq!(simulator.calculate_net_exposure(), dec!(0.5));
+    }
+
+    #[tokio::test]
+    async fn test_attribution_by_rule_tag() {
+        let bar = |minute: u64, high, close| Bar {
+            timestamp: 1609459200000 + minute * 60_000,
+            open: dec!(100.0),
+            high,
+            low: dec!(100.0),
+            close,
+            volume: dec!(1.0),
+            trade_count: 1,
+        };
+        let data = crate::data::MarketDataBuilder::new("BTCUSDT")
+            .bars(vec![
+                bar(0, dec!(100.0), dec!(100.0)),
+                bar(1, dec!(111.0), dec!(100.0)),
+                bar(2, dec!(100.0), dec!(100.0)),
+                bar(3, dec!(102.0), dec!(102.0)),
+                bar(4, dec!(102.0), dec!(102.0)),
+            ])
+            .build()
+            .unwrap();
+        let tagged = |side, take_profit, stop_loss, rule_tag: Option<&str>| StrategySignal {
+            take_profit,
+            stop_loss,
+            rule_tag: rule_tag.map(str::to_string),
+            ..signal(side, None)
+        };
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        
+        // No signal ever closes a trade: a take profit, a stop and the end of data do
+        simulator.simulate_with_signals(
+            &data,
+            |bar_idx, _| match bar_idx {
+                0 => vec![tagged(TradeSide::Buy, Some(dec!(110.0)), None, Some("breakout"))],
+                2 => vec![tagged(TradeSide::Sell, None, Some(dec!(101.0)), Some("mean_reversion"))],
+                4 => vec![tagged(TradeSide::Buy, None, None, None)],
+                _ => Vec::new(),
+            },
+            &IntrabarPolicy::ExactTrades,
+            &SlippageMode::None,
+        ).await.unwrap();
+        simulator.finish(data.bars.last(), &data.rules, &SlippageMode::None).unwrap();
+        
+        let table = simulator.get_trade_table_result();
+        let exits: Vec<ExitReason> = table.trades.iter().map(|t| t.exit_reason.clone()).collect();
+        assert_eq!(exits, vec![ExitReason::TakeProfit, ExitReason::StopLoss, ExitReason::EndOfData]);
+        let attribution = simulator.attribution();
+        assert_eq!(attribution.len(), 3);
+        assert_eq!(attribution["breakout"], table.trades[0].pnl_usd);
+        assert!(attribution["breakout"] > dec!(0.0));
+        assert_eq!(attribution["mean_reversion"], table.trades[1].pnl_usd);
+        assert!(attribution["mean_reversion"] < dec!(0.0));
+        assert_eq!(attribution["untagged"], table.trades[2].pnl_usd);
+    }
+
+    #[test]
+    fn test_preallocated_equity_history_does_not_regrow() {
+        let mut simulator = ExchangeSimulator::with_capacity(10_000).unwrap();
+        let initial_capacity = simulator.equity_history.capacity();
+        assert!(initial_capacity >= 10_000);
+        
+        for i in 0..10_000u64 {
+            simulator.update_equity(1609459200000 + i * 60_000);
+        }
+        
+        assert_eq!(simulator.equity_history.len(), 10_000);
+        assert_eq!(simulator.equity_history.capacity(), initial_capacity);
+    }
+
+    #[test]
+    fn test_open_winner_is_unrealized_until_closed() {
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        simulator.seed_positions(&[open_long("BTCUSDT", dec!(1.0), dec!(100.0))]);
+        
+        for (i, close) in [dec!(101.0), dec!(104.0), dec!(110.0)].into_iter().enumerate() {
+            simulator.marks.insert("BTCUSDT".to_string(), close);
+            simulator.update_equity(1609459200000 + i as u64 * 60_000);
+        }
+        
+        let unrealized: Vec<Decimal> = simulator.equity_history.iter().map(|p| p.unrealized_pnl).collect();
+        assert_eq!(unrealized, vec![dec!(1.0), dec!(4.0), dec!(10.0)]);
+        for point in &simulator.equity_history {
+            assert_eq!(point.realized_equity, simulator.initial_equity);
+            assert_eq!(point.equity, point.realized_equity + point.unrealized_pnl);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_take_profit_exit_moves_pnl_from_unrealized_to_realized() {
+        let bar = |minute: u64, high, close| Bar {
+            timestamp: 1609459200000 + minute * 60_000,
+            open: dec!(100.0),
+            high,
+            low: dec!(99.0),
+            close,
+            volume: dec!(1.0),
+            trade_count: 1,
+        };
+        let data = crate::data::MarketDataBuilder::new("BTCUSDT")
+            .bars(vec![
+                bar(0, dec!(100.0), dec!(100.0)),
+                bar(1, dec!(103.0), dec!(102.0)),
+                bar(2, dec!(106.0), dec!(101.0)),
+            ])
+            .build()
+            .unwrap();
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        
+        // No signal ever sells: only the trade table's take profit closes the long
+        let result = simulator.simulate_with_signals(
+            &data,
+            |bar_idx, _| match bar_idx {
+                0 => vec![StrategySignal { take_profit: Some(dec!(105.0)), ..signal(TradeSide::Buy, None) }],
+                _ => Vec::new(),
+            },
+            &IntrabarPolicy::ExactTrades,
+            &SlippageMode::None,
+        ).await.unwrap();
+        
+        let table = simulator.get_trade_table_result();
+        assert_eq!(table.trades.len(), 1);
+        assert_eq!(table.trades[0].exit_reason, ExitReason::TakeProfit);
+        let open = &result.equity_curve[1];
+        assert!(open.unrealized_pnl > dec!(0.0));
+        assert_eq!(open.realized_equity, simulator.initial_equity);
+        let closed = &result.equity_curve[2];
+        assert_eq!(closed.unrealized_pnl, dec!(0.0));
+        assert_eq!(closed.realized_equity, simulator.initial_equity + table.trades[0].pnl_usd);
+        
+        // The position book closes with the trade table, not with a fill
+        assert!(result.positions[1].quantity > dec!(0.0));
+        let flat = result.positions.last().unwrap();
+        assert_eq!(flat.quantity, dec!(0.0));
+        assert_eq!(flat.realized_pnl, table.trades[0].pnl_usd);
+        let state = simulator.current_state();
+        assert!(state.positions.is_empty());
+        assert!(state.open_trades.is_empty());
+        assert!(state.exposure.is_empty());
+    }
+
+    #[test]
+    fn test_hedge_mode_keeps_long_and_short_legs() {
+        let mut simulator = ExchangeSimulator::with_config(TradeTableConfig {
+            position_mode: PositionMode::Hedge,
+            ..TradeTableConfig::default()
+        }, 0).unwrap();
+        let short = ActivePosition { trade_type: TradeType::Short, ..open_long("BTCUSDT", dec!(1.0), dec!(100.0)) };
+        simulator.seed_positions(&[open_long("BTCUSDT", dec!(1.0), dec!(100.0)), short]);
+        
+        let bar = Bar {
+            timestamp: 1609459260000,
+            open: dec!(110.0),
+            high: dec!(110.0),
+            low: dec!(110.0),
+            close: dec!(110.0),
+            volume: dec!(1.0),
+            trade_count: 1,
+        };
+        simulator.marks.insert("BTCUSDT".to_string(), bar.close);
+        simulator.sync_positions(Some("BTCUSDT"), bar.timestamp);
+        simulator.update_equity(bar.timestamp);
+        
+        // The short leg sits beside the long one instead of netting against it
+        let legs: Vec<&Position> = simulator.symbol_positions("BTCUSDT").collect();
+        assert_eq!(legs.len(), 2);
+        assert_eq!((legs[0].quantity, legs[0].unrealized_pnl), (dec!(1.0), dec!(10.0)));
+        assert_eq!((legs[1].quantity, legs[1].unrealized_pnl), (dec!(-1.0), dec!(-10.0)));
+        assert!(legs.iter().all(|leg| leg.realized_pnl.is_zero()));
+        assert_eq!(simulator.current_state().positions.len(), 2);
+        
+        // Both legs count towards gross exposure at the 110 mark and cancel in net
+        assert_eq!(simulator.current_equity, simulator.initial_equity);
+        assert_eq!(simulator.calculate_exposure(), dec!(220.0) / simulator.current_equity);
+        assert_eq!(simulator.calculate_net_exposure(), dec!(0.0));
+    }
+
+    /// Trade table long with no brackets or fees
+    fn open_long(symbol: &str, quantity: Decimal, entry_price: Decimal) -> ActivePosition {
+        ActivePosition {
+            symbol: symbol.to_string(),
+            trade_type: TradeType::Long,
+            entry_time: 1609459200000,
+            entry_price,
+            quantity,
+            take_profit: None,
+            stop_loss: None,
+            time_to_live: None,
+            bars_held: 0,
+            entry_fee: dec!(0.0),
+            entry_slippage_usd: dec!(0.0),
+            entry_outside_bar: false,
+            size_usd: entry_price * quantity,
+            liquidation_price: None,
+            tags: Vec::new(),
+            rule_tag: None,
+        }
+    }
+
+    fn signal(side: TradeSide, entry_price: Option<Decimal>) -> StrategySignal {
+        StrategySignal {
+            side,
+            size: dec!(1.0),
+            entry_price,
+            take_profit: None,
+            stop_loss: None,
+            time_to_live: None,
+            rule_tag: None,
+            tags: Vec::new(),
+            symbol: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_one_second_bars_follow_bar_timeframe() {
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        let five_minutes = "5m".parse::<Timeframe>().unwrap().as_millis();
+        let bar = Bar {
+            timestamp: 1609459200000,
+            open: dec!(100.0),
+            high: dec!(100.0),
+            low: dec!(70.0),
+            close: dec!(70.0),
+            volume: dec!(1.0),
+            trade_count: 1,
+        };
+        
+        let path = one_second_path(&bar, five_minutes);
+        assert_eq!(path.len(), 300);
+        assert_eq!(path[0], (bar.timestamp, dec!(100.0)));
+        assert_eq!(path[299].0, bar.timestamp + 299_000);
+        
+        // The limit is listed first but only trades once the path falls to 85, halfway through
+        let signals = [
+            signal(TradeSide::Buy, Some(dec!(85.0))),
+            signal(TradeSide::Sell, None),
+            signal(TradeSide::Sell, Some(dec!(101.0))),
+        ];
+        let mut trades = Vec::new();
+        simulator.simulate_one_second_bars(
+            &bar,
+            five_minutes,
+            &signals,
+            &SlippageMode::None,
+            &ExchangeRules::default(),
+            &mut trades,
+        ).await.unwrap();
+        
+        assert_eq!(trades.len(), 2);
+        assert!(matches!(trades[0].side, TradeSide::Sell));
+        assert_eq!((trades[0].timestamp, trades[0].price), (bar.timestamp, dec!(100.0)));
+        assert!(matches!(trades[1].side, TradeSide::Buy));
+        assert_eq!((trades[1].timestamp, trades[1].price), (bar.timestamp + 150_000, dec!(85.0)));
+    }
+
+    #[tokio::test]
+    async fn test_unreached_limit_entries_neither_fill_nor_open_positions() {
+        let data = crate::data::MarketDataBuilder::new("BTCUSDT")
+            .timeframe("1m")
+            .bars(vec![Bar {
+                timestamp: 1609459200000,
+                open: dec!(100.0),
+                high: dec!(110.0),
+                low: dec!(100.0),
+                close: dec!(110.0),
+                volume: dec!(1.0),
+                trade_count: 1,
+            }])
+            .build()
+            .unwrap();
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        
+        // The path rises from 100, so a buy limit at 95 is never reached but a sell at 105 is
+        let result = simulator.simulate_with_signals(
+            &data,
+            |_, _| vec![signal(TradeSide::Buy, Some(dec!(95.0))), signal(TradeSide::Sell, Some(dec!(105.0)))],
+            &IntrabarPolicy::OneSecondBars,
+            &SlippageMode::None,
+        ).await.unwrap();
+        
+        assert_eq!(result.trades.len(), 1);
+        assert!(matches!(result.trades[0].side, TradeSide::Sell));
+        
+        simulator.finish(data.bars.last(), &data.rules, &SlippageMode::None).unwrap();
+        let table = simulator.get_trade_table_result();
+        assert_eq!(table.trades.len(), 1);
+        assert!(matches!(table.trades[0].trade_type, TradeType::Short));
+    }
+
+    #[tokio::test]
+    async fn test_limit_entries_pay_maker_fees_under_every_policy() {
+        let data = crate::data::MarketDataBuilder::new("BTCUSDT")
+            .timeframe("1m")
+            .bars(vec![Bar {
+                timestamp: 1609459200000,
+                open: dec!(100.0),
+                high: dec!(100.0),
+                low: dec!(90.0),
+                close: dec!(90.0),
+                volume: dec!(1.0),
+                trade_count: 1,
+            }])
+            .rules(ExchangeRules { maker_fee: dec!(0.0), taker_fee: dec!(0.001), ..ExchangeRules::default() })
+            .build()
+            .unwrap();
+        
+        for policy in [IntrabarPolicy::ExactTrades, IntrabarPolicy::OneSecondBars, IntrabarPolicy::LinearInterpolation] {
+            let mut simulator = ExchangeSimulator::new().unwrap();
+            let result = simulator.simulate_with_signals(
+                &data,
+                |_, _| vec![signal(TradeSide::Buy, Some(dec!(95.0)))],
+                &policy,
+                &SlippageMode::None,
+            ).await.unwrap();
+            
+            assert_eq!(result.trades.len(), 1, "{:?}", policy);
+            assert_eq!(result.trades[0].fee, dec!(0.0), "{:?}", policy);
+            let position = &simulator.trade_table_generator().active_positions()["BTCUSDT"];
+            assert_eq!(position.entry_fee, dec!(0.0), "{:?}", policy);
+        }
+    }
+    
+    #[test]
+    fn test_exposure_with_zero_equity() {
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        simulator.positions.insert("BTCUSDT".to_string(), position("BTCUSDT", dec!(0.2), dec!(50000.0)));
+        simulator.current_equity = dec!(0.0);
+        
+        assert_eq!(simulator.calculate_exposure(), dec!(0.0));
+        assert_eq!(simulator.calculate_net_exposure(), dec!(0.0));
+    }
+
+    fn canonical<T: Serialize>(value: &T) -> String {
+        crate::canonical::to_canonical_json(value).unwrap()
+    }
+
+    /// Buy every 25th bar and sell every 40th (by timestamp), with TP/SL for the trade table
+    fn checkpoint_signals(bar: &Bar) -> Vec<StrategySignal> {
+        let index = bar.timestamp / 60_000;
+        let side = if index.is_multiple_of(25) {
+            TradeSide::Buy
+        } else if index.is_multiple_of(40) {
+            TradeSide::Sell
+        } else {
+            return Vec::new();
+        };
+        vec![StrategySignal {
+            side,
+            size: dec!(0.01),
+            entry_price: None,
+            take_profit: Some(bar.close * dec!(1.01)),
+            stop_loss: Some(bar.close * dec!(0.99)),
+            time_to_live: Some(TimeToLive::Duration(3_600_000)),
+            rule_tag: Some("checkpoint".to_string()),
+            tags: Vec::new(),
+            symbol: String::new(),
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_checkpoint_matches_continuous_run() {
+        use crate::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+
+        let data = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 1000,
+            ..SyntheticDataConfig::default()
+        }, 11).generate();
+        let policy = IntrabarPolicy::ExactTrades;
+        let slippage = SlippageMode::TradeSweep;
+
+        let mut continuous = ExchangeSimulator::new().unwrap();
+        let full = continuous.simulate_with_signals(&data, |_, bar| checkpoint_signals(bar), &policy, &slippage)
+            .await.unwrap();
+
+        // First half, checkpoint through JSON as a worker would, then resume
+        let (head, tail) = data.bars.split_at(data.bars.len() / 2);
+        let first_half = MarketData { bars: head.to_vec(), ..data.clone() };
+        let second_half = MarketData { bars: tail.to_vec(), ..data.clone() };
+
+        let mut first = ExchangeSimulator::new().unwrap();
+        let part_one = first.simulate_with_signals(&first_half, |_, bar| checkpoint_signals(bar), &policy, &slippage)
+            .await.unwrap();
+        let checkpoint = serde_json::to_string(&first.snapshot()).unwrap();
+        drop(first);
+
+        let mut resumed = ExchangeSimulator::restore(serde_json::from_str(&checkpoint).unwrap());
+        let part_two = resumed.simulate_with_signals(&second_half, |_, bar| checkpoint_signals(bar), &policy, &slippage)
+            .await.unwrap();
+
+        let mut trades = part_one.trades.clone();
+        trades.extend(part_two.trades.clone());
+        assert_eq!(canonical(&trades), canonical(&full.trades));
+        assert_eq!(canonical(&part_two.equity_curve), canonical(&full.equity_curve));
+        assert_eq!(part_two.max_drawdown, full.max_drawdown);
+        assert_eq!(canonical(&part_two.attribution), canonical(&full.attribution));
+
+        let resumed_table = resumed.get_trade_table_result();
+        let full_table = continuous.get_trade_table_result();
+        assert!(!full_table.trades.is_empty());
+        assert_eq!(canonical(&resumed_table), canonical(&full_table));
+    }
+    
+    #[tokio::test]
+    async fn test_no_entries_during_warmup() {
+        use crate::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+
+        let data = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 20,
+            ..SyntheticDataConfig::default()
+        }, 3).generate();
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        simulator.set_warmup_bars(5);
+        
+        // A signal on every bar, including the burn-in
+        let result = simulator.simulate_with_signals(
+            &data,
+            |_, _| vec![signal(TradeSide::Buy, None)],
+            &IntrabarPolicy::ExactTrades,
+            &SlippageMode::None,
+        ).await.unwrap();
+        
+        assert_eq!(result.trades.first().map(|t| t.timestamp), Some(data.bars[5].timestamp));
+        let table = simulator.get_trade_table_result();
+        let warmup: Vec<u64> = table.rejected_trades.iter()
+            .filter(|r| r.reason == ReasonCode::Warmup)
+            .map(|r| r.timestamp)
+            .collect();
+        assert_eq!(warmup, data.bars[..5].iter().map(|b| b.timestamp).collect::<Vec<_>>());
+    }
+    
+    #[tokio::test]
+    async fn test_warmup_lets_signals_close_a_seeded_position() {
+        use crate::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+
+        let data = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 10,
+            ..SyntheticDataConfig::default()
+        }, 3).generate();
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        simulator.set_warmup_bars(5);
+        simulator.seed_positions(&[ActivePosition {
+            entry_time: data.bars[0].timestamp,
+            ..open_long(&data.symbol, dec!(1.0), data.bars[0].open)
+        }]);
+        
+        // Bar 1 sells the seeded long; bar 2's buy would open a fresh one
+        let result = simulator.simulate_with_signals(
+            &data,
+            |bar_idx, _| match bar_idx {
+                1 => vec![signal(TradeSide::Sell, None)],
+                2 => vec![signal(TradeSide::Buy, None)],
+                _ => Vec::new(),
+            },
+            &IntrabarPolicy::ExactTrades,
+            &SlippageMode::None,
+        ).await.unwrap();
+        
+        assert_eq!(result.trades.len(), 1);
+        assert!(matches!(result.trades[0].side, TradeSide::Sell));
+        assert_eq!(result.trades[0].timestamp, data.bars[1].timestamp);
+        let rejected = &simulator.get_trade_table_result().rejected_trades;
+        assert_eq!(rejected.len(), 1);
+        assert_eq!((rejected[0].reason, rejected[0].timestamp), (ReasonCode::Warmup, data.bars[2].timestamp));
+    }
+    
+    #[tokio::test]
+    async fn test_slipped_fills_follow_the_fill_range_policy() {
+        // Closes on its high, so any adverse slippage on a buy leaves the bar's range
+        let data = crate::data::MarketDataBuilder::new("BTCUSDT")
+            .bars(vec![Bar {
+                timestamp: 1609459200000,
+                open: dec!(100.0),
+                high: dec!(101.0),
+                low: dec!(99.0),
+                close: dec!(101.0),
+                volume: dec!(1.0),
+                trade_count: 1,
+            }])
+            .timeframe("1m")
+            .build()
+            .unwrap();
+        let fills = |fill_range_policy: FillRangePolicy| {
+            let data = data.clone();
+            async move {
+                let config = TradeTableConfig { fill_range_policy, ..TradeTableConfig::default() };
+                let mut simulator = ExchangeSimulator::with_config(config, 0).unwrap();
+                simulator.simulate_with_signals(
+                    &data,
+                    |_, _| vec![signal(TradeSide::Buy, None)],
+                    &IntrabarPolicy::ExactTrades,
+                    &SlippageMode::FixedTicks(3),
+                ).await.unwrap().trades
+            }
+        };
+        
+        let clamped = fills(FillRangePolicy::Clamp).await;
+        assert_eq!(clamped[0].price, dec!(101.0));
+        assert!(!clamped[0].fill_outside_bar);
+        
+        let flagged = fills(FillRangePolicy::Flag).await;
+        assert!(flagged[0].price > dec!(101.0));
+        assert!(flagged[0].fill_outside_bar);
+    }
+    
+    #[tokio::test]
+    async fn test_overlapping_entries_fill_by_the_trade_tables_policy() {
+        let data = crate::data::MarketDataBuilder::new("BTCUSDT")
+            .bars(vec![Bar {
+                timestamp: 1609459200000,
+                open: dec!(100.0),
+                high: dec!(101.0),
+                low: dec!(99.0),
+                close: dec!(100.0),
+                volume: dec!(1.0),
+                trade_count: 1,
+            }])
+            .timeframe("1m")
+            .build()
+            .unwrap();
+        let fills = |overlapping_entries: OverlappingEntryPolicy| {
+            let data = data.clone();
+            async move {
+                let config = TradeTableConfig { overlapping_entries, ..TradeTableConfig::default() };
+                let mut simulator = ExchangeSimulator::with_config(config, 0).unwrap();
+                simulator.simulate_with_signals(
+                    &data,
+                    |_, _| vec![signal(TradeSide::Buy, None), signal(TradeSide::Buy, None)],
+                    &IntrabarPolicy::ExactTrades,
+                    &SlippageMode::None,
+                ).await.unwrap().trades
+            }
+        };
+        
+        let first = fills(OverlappingEntryPolicy::FirstWins).await;
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].quantity, dec!(1.0));
+        
+        let aggregate = fills(OverlappingEntryPolicy::Aggregate).await;
+        assert_eq!(aggregate.len(), 1);
+        assert_eq!(aggregate[0].quantity, dec!(2.0));
+        
+        assert!(fills(OverlappingEntryPolicy::Reject).await.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_next_bar_open_fills_at_the_following_open() {
+        let bar = |minute: u64, open, high, low, close| Bar {
+            timestamp: 1609459200000 + minute * 60_000,
+            open,
+            high,
+            low,
+            close,
+            volume: dec!(1.0),
+            trade_count: 1,
+        };
+        let data = crate::data::MarketDataBuilder::new("BTCUSDT")
+            .bars(vec![
+                bar(0, dec!(100.0), dec!(102.0), dec!(99.0), dec!(101.0)),
+                bar(1, dec!(103.0), dec!(106.0), dec!(102.0), dec!(104.0)),
+                bar(2, dec!(104.0), dec!(105.0), dec!(103.0), dec!(104.0)),
+            ])
+            .build()
+            .unwrap();
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        simulator.set_fill_model(FillModel::NextBarOpen);
+        
+        // Signalled on bar 0's close of 101, with a take profit bar 1 trades through
+        let result = simulator.simulate_with_signals(
+            &data,
+            |bar_idx, _| match bar_idx {
+                0 => vec![StrategySignal { take_profit: Some(dec!(105.0)), ..signal(TradeSide::Buy, None) }],
+                _ => Vec::new(),
+            },
+            &IntrabarPolicy::ExactTrades,
+            &SlippageMode::None,
+        ).await.unwrap();
+        
+        assert_eq!(result.trades.len(), 1);
+        let fill = &result.trades[0];
+        assert_eq!((fill.timestamp, fill.price), (data.bars[1].timestamp, data.bars[1].open));
+        assert_ne!(fill.price, data.bars[0].close);
+        assert_eq!(fill.reason_code, ReasonCode::NextBarOpen);
+        
+        // The position opens at the same open and its take profit still hits within bar 1
+        let table = simulator.get_trade_table_result();
+        assert_eq!(table.trades.len(), 1);
+        assert_eq!(table.trades[0].entry_price, data.bars[1].open);
+        assert_eq!(table.trades[0].exit_reason, ExitReason::TakeProfit);
+        assert_eq!(table.trades[0].exit_price, dec!(105.0));
+    }
+    
+    #[tokio::test]
+    async fn test_exact_trades_fill_sub_millisecond_ticks_in_order() {
+        let tick = |micros: u64, price, trade_id: &str| Trade {
+            timestamp: Timestamp::from_micros(micros),
+            price,
+            quantity: dec!(1.0),
+            side: TradeSide::Buy,
+            trade_id: trade_id.to_string(),
+        };
+        // Two ticks inside the same millisecond, 250us apart
+        let data = crate::data::MarketDataBuilder::new("BTCUSDT")
+            .bars(vec![Bar {
+                timestamp: 1609459200000,
+                open: dec!(100.6),
+                high: dec!(100.6),
+                low: dec!(100.5),
+                close: dec!(100.5),
+                volume: dec!(2.0),
+                trade_count: 2,
+            }])
+            .timeframe("1m")
+            .trades(vec![
+                tick(1609459200100250, dec!(100.6), "t1"),
+                tick(1609459200100500, dec!(100.5), "t2"),
+            ])
+            .build()
+            .unwrap();
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        
+        // The buy only trades on the later tick, so the sell signalled after it fills first
+        let result = simulator.simulate_with_signals(
+            &data,
+            |_, _| vec![signal(TradeSide::Buy, Some(dec!(100.55))), signal(TradeSide::Sell, Some(dec!(100.6)))],
+            &IntrabarPolicy::ExactTrades,
+            &SlippageMode::None,
+        ).await.unwrap();
+        
+        let fills: Vec<(bool, Decimal, u64)> = result.trades.iter()
+            .map(|t| (matches!(t.side, TradeSide::Buy), t.price, t.timestamp))
+            .collect();
+        assert_eq!(fills, vec![(false, dec!(100.6), 1609459200100), (true, dec!(100.5), 1609459200100)]);
+        assert!(result.trades.iter().all(|t| t.reason_code == ReasonCode::Tick));
+    }
+    
+    #[tokio::test]
+    async fn test_next_bar_open_drops_signals_on_the_last_bar() {
+        use crate::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+
+        let data = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 5,
+            ..SyntheticDataConfig::default()
+        }, 3).generate();
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        simulator.set_fill_model(FillModel::NextBarOpen);
+        
+        let result = simulator.simulate_with_signals(
+            &data,
+            |bar_idx, _| if bar_idx == 4 { vec![signal(TradeSide::Buy, None)] } else { Vec::new() },
+            &IntrabarPolicy::OneSecondBars,
+            &SlippageMode::None,
+        ).await.unwrap();
+        simulator.finish(data.bars.last(), &data.rules, &SlippageMode::None).unwrap();
+        
+        assert!(result.trades.is_empty());
+        assert!(simulator.get_trade_table_result().trades.is_empty());
+        assert!(simulator.snapshot().pending_fills.is_empty());
+    }
+    
+    #[test]
+    fn test_spot_sells_are_capped_at_inventory() {
+        let mut simulator = ExchangeSimulator::new().unwrap();
+        let spot = ExchangeRules {
+            allow_shorts: false,
+            ..ExchangeRules::default()
+        };
+        let sell = StrategySignal { size: dec!(3.0), ..signal(TradeSide::Sell, None) };
+        
+        // Nothing held: the sell would open a short, so it is dropped
+        let constrained = simulator.spot_constrained("BTCUSDT", std::slice::from_ref(&sell), &spot).unwrap();
+        assert!(constrained.is_empty());
+        
+        // With one unit held, the sell closes exactly that unit
+        simulator.positions.insert("BTCUSDT".to_string(), position("BTCUSDT", dec!(1.0), dec!(100.0)));
+        let constrained = simulator.spot_constrained("BTCUSDT", std::slice::from_ref(&sell), &spot).unwrap();
+        assert_eq!(constrained.len(), 1);
+        assert_eq!(constrained[0].size, dec!(1.0));
+        
+        assert!(simulator.spot_constrained("BTCUSDT", &[sell], &ExchangeRules::default()).is_none());
+    }
+
+    /// Buy above the EMA while RSI is below 70, sell below it
+    fn indicator_signals(bar: &Bar, ema: Option<Decimal>, rsi: Option<Decimal>) -> Vec<StrategySignal> {
+        let (Some(ema), Some(rsi)) = (ema, rsi) else {
+            return Vec::new();
+        };
+        let side = if bar.close > ema && rsi < dec!(70) {
+            TradeSide::Buy
+        } else if bar.close < ema {
+            TradeSide::Sell
+        } else {
+            return Vec::new();
+        };
+        vec![StrategySignal {
+            side,
+            size: dec!(0.01),
+            entry_price: None,
+            take_profit: Some(bar.close * dec!(1.01)),
+            stop_loss: Some(bar.close * dec!(0.99)),
+            time_to_live: Some(TimeToLive::Duration(3_600_000)),
+            rule_tag: None,
+            tags: Vec::new(),
+            symbol: String::new(),
+        }]
+    }
+
+    #[tokio::test]
+    async fn test_streaming_pipeline_matches_materialized() {
+        use crate::indicators::IndicatorRegistry;
+        use crate::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+
+        let data = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 1000,
+            ..SyntheticDataConfig::default()
+        }, 5).generate();
+        let policy = IntrabarPolicy::OneSecondBars;
+        let slippage = SlippageMode::TradeSweep;
+        let names = vec!["ema".to_string(), "rsi".to_string()];
+        let mut registry = IndicatorRegistry::new(true).unwrap();
+
+        let ema = registry.calculate("ema", &data).unwrap();
+        let rsi = registry.calculate("rsi", &data).unwrap();
+        let at = |series: &[IndicatorValue], bar: &Bar| series.binary_search_by_key(&bar.timestamp, |v| v.timestamp)
+            .ok()
+            .map(|i| series[i].value);
+        let mut materialized_signals = Vec::new();
+        let mut materialized = ExchangeSimulator::new().unwrap();
+        let expected = materialized.simulate_with_signals(&data, |_, bar| {
+            let signals = indicator_signals(bar, at(&ema, bar), at(&rsi, bar));
+            materialized_signals.push(signals.clone());
+            signals
+        }, &policy, &slippage).await.unwrap();
+
+        let mut stream = registry.stream(&names, &HashMap::new()).unwrap();
+        let mut streamed_signals = Vec::new();
+        let mut fused = ExchangeSimulator::new().unwrap();
+        let result = fused.simulate_with_indicator_stream(&data, &mut stream, |_, bar, values| {
+            let signals = indicator_signals(bar, values["ema"], values["rsi"]);
+            streamed_signals.push(signals.clone());
+            signals
+        }, &policy, &slippage).await.unwrap();
+
+        assert_eq!(canonical(&streamed_signals), canonical(&materialized_signals));
+        assert!(!result.trades.is_empty());
+        assert_eq!(canonical(&result.trades), canonical(&expected.trades));
+        assert_eq!(canonical(&result.equity_curve), canonical(&expected.equity_curve));
+        assert_eq!(canonical(&fused.get_trade_table_result()), canonical(&materialized.get_trade_table_result()));
+    }
+
+    #[tokio::test]
+    async fn test_signal_tape_replays_identical_fills() {
+        use crate::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+
+        let data = SyntheticDataGenerator::new(SyntheticDataConfig {
+            bar_count: 50,
+            ..SyntheticDataConfig::default()
+        }, 3).generate();
+        let half = |side: TradeSide| StrategySignal { size: dec!(0.5), ..signal(side, None) };
+        let tape = SignalTape::new(vec![
+            (5, vec![signal(TradeSide::Buy, None)]),
+            (12, vec![signal(TradeSide::Sell, None)]),
+            (20, vec![half(TradeSide::Buy)]),
+            (20, vec![half(TradeSide::Buy)]),
+            (49, vec![signal(TradeSide::Sell, None)]),
+        ]);
+
+        let replay = || async {
+            let mut simulator = ExchangeSimulator::new().unwrap();
+            simulator.replay(&data, &tape, &IntrabarPolicy::ExactTrades, &SlippageMode::None).await.unwrap()
+        };
+        let first = replay().await;
+
+        // Each recorded signal fills on its bar, at the close, and nothing else trades; the
+        // two entries on bar 20 overlap and the default policy keeps the first
+        let fills: Vec<(u64, bool, Decimal, Decimal)> = first.trades.iter()
+            .map(|trade| (trade.timestamp, matches!(trade.side, TradeSide::Buy), trade.quantity, trade.price))
+            .collect();
+        let at = |i: usize, is_buy: bool, quantity: Decimal| (data.bars[i].timestamp, is_buy, quantity, data.bars[i].close);
+        assert_eq!(fills, vec![
+            at(5, true, dec!(1.0)),
+            at(12, false, dec!(1.0)),
+            at(20, true, dec!(0.5)),
+            at(49, false, dec!(1.0)),
+        ]);
+        assert!(first.trades.iter().all(|trade| trade.symbol == data.symbol));
+
+        let second = replay().await;
+        assert_eq!(canonical(&second.trades), canonical(&first.trades));
+        assert_eq!(canonical(&second.equity_curve), canonical(&first.equity_curve));
+    }
+}
@@ -0,0 +1,114 @@
+//! Strategy interface
+//! 
+//! [`Strategy`] is what the engine runs once per bar: the WASM runtime's
+//! [`wasm::Strategy`](crate::wasm::Strategy) is one implementation, and Rust strategies
+//! can implement it directly to run in-process, which is faster and debuggable with
+//! ordinary tools. The example WASM strategy in `wasm-strategies/rust` implements a copy
+//! of this trait behind its WASM exports.
+
+use std::collections::{BTreeMap, HashMap};
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::indicators::IndicatorRegistry;
+use crate::types::*;
+
+/// Everything a strategy sees for one bar
+#[derive(Debug, Clone, Copy)]
+pub struct BarContext<'a> {
+    pub symbol: &'a str,
+    pub bar_index: usize,
+    pub bar: &'a Bar,
+    /// Each required indicator's value at the bar, `None` during its warmup
+    pub indicators: &'a HashMap<String, Option<Decimal>>,
+    /// The simulator's position in the symbol, if it has traded (its long leg in hedge mode)
+    pub position: Option<&'a Position>,
+}
+
+impl BarContext<'_> {
+    /// Value of `indicator_name` at the bar, `None` during warmup or if not required
+    pub fn indicator(&self, indicator_name: &str) -> Option<Decimal> {
+        self.indicators.get(indicator_name).copied().flatten()
+    }
+}
+
+/// Signal logic the engine runs bar by bar
+pub trait Strategy {
+    /// Indicators the engine computes for [`Strategy::on_bar`]
+    fn required_indicators(&self) -> Vec<String>;
+
+    /// Parameters to compute each required indicator with
+    /// 
+    /// Defaults to the registry's parameters; indicators it does not know are left out.
+    fn indicator_params(&self, registry: &IndicatorRegistry) -> HashMap<String, IndicatorParams> {
+        self.required_indicators().into_iter()
+            .filter_map(|name| Some((name.clone(), registry.default_params(&name)?)))
+            .collect()
+    }
+
+    /// Signals for the bar in `ctx`
+    fn on_bar(&mut self, ctx: &BarContext) -> Result<Vec<StrategySignal>>;
+
+    /// Forget per-symbol state; called before each symbol's run, and before each run of a
+    /// symbol that is backtested twice to verify determinism
+    fn reset(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl Strategy for crate::wasm::Strategy {
+    fn required_indicators(&self) -> Vec<String> {
+        self.get_required_indicators()
+    }
+
+    fn indicator_params(&self, registry: &IndicatorRegistry) -> HashMap<String, IndicatorParams> {
+        self.get_metadata()
+            .map(|metadata| metadata.resolved_indicator_params(registry))
+            .unwrap_or_default()
+    }
+
+    fn on_bar(&mut self, ctx: &BarContext) -> Result<Vec<StrategySignal>> {
+        self.execute_current(ctx.bar, ctx.bar_index, ctx.indicators, ctx.position)
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        crate::wasm::Strategy::reset(self)
+    }
+}
+
+/// Precomputed signals replayed by bar index
+/// 
+/// Stands in for a strategy so execution and fill logic can be tested apart from signal
+/// generation: bar `i` gets exactly the signals recorded for index `i`, and no others.
+/// Indices count the bars the simulator is handed, after any data preparation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignalTape {
+    signals: BTreeMap<usize, Vec<StrategySignal>>,
+}
+
+impl SignalTape {
+    /// Tape of `(bar_index, signals)` entries; signals for a repeated index are kept in order
+    pub fn new(entries: Vec<(usize, Vec<StrategySignal>)>) -> Self {
+        let mut signals: BTreeMap<usize, Vec<StrategySignal>> = BTreeMap::new();
+        for (bar_index, bar_signals) in entries {
+            signals.entry(bar_index).or_default().extend(bar_signals);
+        }
+        Self { signals }
+    }
+
+    /// Signals recorded for `bar_index`
+    pub fn signals_at(&self, bar_index: usize) -> &[StrategySignal] {
+        self.signals.get(&bar_index).map_or(&[], Vec::as_slice)
+    }
+}
+
+impl Strategy for SignalTape {
+    fn required_indicators(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn on_bar(&mut self, ctx: &BarContext) -> Result<Vec<StrategySignal>> {
+        Ok(self.signals_at(ctx.bar_index).to_vec())
+    }
+}
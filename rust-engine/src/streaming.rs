@@ -0,0 +1,247 @@
+//! Streaming indicators
+//! 
+//! Incremental counterparts of the registry's Decimal indicators. Each holds only the
+//! state needed for its next value (a window or a running average), so the engine can
+//! advance indicators alongside the bar loop instead of materializing whole series
+//! first. Values match [`IndicatorRegistry::calculate_with_params`] bar for bar: `None`
+//! wherever the materialized series has no value at the bar's timestamp.
+//! 
+//! [`IndicatorRegistry::calculate_with_params`]: crate::indicators::IndicatorRegistry::calculate_with_params
+
+use std::collections::{HashMap, VecDeque};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::indicators::{is_price_denominated, round_price};
+use crate::types::{Bar, EmaSeed, IndicatorParams, Precision};
+
+/// State of one indicator advanced a bar at a time
+#[derive(Debug, Clone)]
+pub enum StreamingIndicator {
+    /// EMA seeded with the mean of its first `warmup` closes
+    Ema {
+        alpha: Decimal,
+        warmup: usize,
+        seen: usize,
+        seed_sum: Decimal,
+        ema: Decimal,
+    },
+    Sma {
+        period: usize,
+        window: VecDeque<Decimal>,
+    },
+    /// Wilder RSI; like the materialized series, the first value follows the first smoothing step
+    Rsi {
+        period: usize,
+        prev_close: Option<Decimal>,
+        changes: usize,
+        avg_gain: Decimal,
+        avg_loss: Decimal,
+    },
+    /// Wilder ATR
+    Atr {
+        period: usize,
+        prev_close: Option<Decimal>,
+        ranges: usize,
+        atr: Decimal,
+    },
+    HighestHigh {
+        period: usize,
+        window: VecDeque<Decimal>,
+    },
+    LowestLow {
+        period: usize,
+        window: VecDeque<Decimal>,
+    },
+    /// A price-denominated indicator whose values round to `precision` decimals
+    Rounded {
+        precision: u8,
+        inner: Box<StreamingIndicator>,
+    },
+}
+
+impl StreamingIndicator {
+    /// Streaming state for `indicator_name`, or `None` when it has no streaming form
+    /// 
+    /// Only the Decimal paths stream; `Precision::Float64` parameters return `None`.
+    pub fn new(indicator_name: &str, params: &IndicatorParams) -> Option<Self> {
+        let period = params.period;
+        if period == 0 || params.compute_precision != Precision::Decimal {
+            return None;
+        }
+
+        let indicator = match indicator_name {
+            "ema" => Self::Ema {
+                alpha: params.alpha.unwrap_or(dec!(2.0) / Decimal::from(period + 1)),
+                warmup: match params.ema_seed {
+                    EmaSeed::SmaSeed => period,
+                    EmaSeed::FirstValueSeed => 1,
+                },
+                seen: 0,
+                seed_sum: Decimal::ZERO,
+                ema: Decimal::ZERO,
+            },
+            "sma" => Self::Sma { period, window: VecDeque::with_capacity(period) },
+            "rsi" => Self::Rsi {
+                period,
+                prev_close: None,
+                changes: 0,
+                avg_gain: Decimal::ZERO,
+                avg_loss: Decimal::ZERO,
+            },
+            "atr" => Self::Atr { period, prev_close: None, ranges: 0, atr: Decimal::ZERO },
+            "hh" => Self::HighestHigh { period, window: VecDeque::with_capacity(period) },
+            "ll" => Self::LowestLow { period, window: VecDeque::with_capacity(period) },
+            _ => return None,
+        };
+        
+        Some(match params.price_precision.filter(|_| is_price_denominated(indicator_name)) {
+            Some(precision) => Self::Rounded { precision, inner: Box::new(indicator) },
+            None => indicator,
+        })
+    }
+
+    /// Advance by one bar and return the indicator's value at it
+    pub fn update(&mut self, bar: &Bar) -> Option<Decimal> {
+        match self {
+            Self::Ema { alpha, warmup, seen, seed_sum, ema } => {
+                *seen += 1;
+                if *seen < *warmup {
+                    *seed_sum += bar.close;
+                    None
+                } else if *seen == *warmup {
+                    *ema = (*seed_sum + bar.close) / Decimal::from(*warmup);
+                    Some(*ema)
+                } else {
+                    *ema = *alpha * bar.close + (dec!(1.0) - *alpha) * *ema;
+                    Some(*ema)
+                }
+            }
+            Self::Sma { period, window } => {
+                push_window(window, *period, bar.close);
+                (window.len() == *period).then(|| window.iter().sum::<Decimal>() / Decimal::from(*period))
+            }
+            Self::Rsi { period, prev_close, changes, avg_gain, avg_loss } => {
+                let previous = prev_close.replace(bar.close)?;
+                let change = bar.close - previous;
+                let (gain, loss) = if change > dec!(0.0) { (change, dec!(0.0)) } else { (dec!(0.0), -change) };
+                let period_dec = Decimal::from(*period);
+
+                *changes += 1;
+                if *changes <= *period {
+                    // Seed window: sum now, average once it is full
+                    *avg_gain += gain;
+                    *avg_loss += loss;
+                    if *changes == *period {
+                        *avg_gain /= period_dec;
+                        *avg_loss /= period_dec;
+                    }
+                    return None;
+                }
+
+                *avg_gain = (*avg_gain * Decimal::from(*period - 1) + gain) / period_dec;
+                *avg_loss = (*avg_loss * Decimal::from(*period - 1) + loss) / period_dec;
+                let rs = if *avg_loss == dec!(0.0) { dec!(100.0) } else { *avg_gain / *avg_loss };
+                Some(dec!(100.0) - (dec!(100.0) / (dec!(1.0) + rs)))
+            }
+            Self::Atr { period, prev_close, ranges, atr } => {
+                let previous = prev_close.replace(bar.close)?;
+                let hl = bar.high - bar.low;
+                let hc = (bar.high - previous).abs();
+                let lc = (bar.low - previous).abs();
+                let true_range = hl.max(hc).max(lc);
+
+                *ranges += 1;
+                if *ranges < *period {
+                    *atr += true_range;
+                    None
+                } else if *ranges == *period {
+                    *atr = (*atr + true_range) / Decimal::from(*period);
+                    Some(*atr)
+                } else {
+                    *atr = (*atr * Decimal::from(*period - 1) + true_range) / Decimal::from(*period);
+                    Some(*atr)
+                }
+            }
+            Self::HighestHigh { period, window } => {
+                push_window(window, *period, bar.high);
+                (window.len() == *period).then(|| window.iter().copied().max().unwrap_or(dec!(0.0)))
+            }
+            Self::LowestLow { period, window } => {
+                push_window(window, *period, bar.low);
+                (window.len() == *period).then(|| window.iter().copied().min().unwrap_or(dec!(0.0)))
+            }
+            Self::Rounded { precision, inner } => inner.update(bar).map(|value| round_price(value, *precision)),
+        }
+    }
+}
+
+fn push_window(window: &mut VecDeque<Decimal>, period: usize, value: Decimal) {
+    if window.len() == period {
+        window.pop_front();
+    }
+    window.push_back(value);
+}
+
+/// A strategy's indicators advanced together, holding only their current values
+#[derive(Debug, Clone)]
+pub struct IndicatorStream {
+    indicators: Vec<(String, StreamingIndicator)>,
+    current: HashMap<String, Option<Decimal>>,
+}
+
+impl IndicatorStream {
+    /// Advance `indicators` together, reporting each under its name
+    /// 
+    /// [`IndicatorRegistry::stream`](crate::indicators::IndicatorRegistry::stream) builds
+    /// one for a strategy's required indicators.
+    pub fn new(indicators: Vec<(String, StreamingIndicator)>) -> Self {
+        let current = indicators.iter().map(|(name, _)| (name.clone(), None)).collect();
+        Self { indicators, current }
+    }
+
+    /// Advance every indicator by `bar`
+    pub fn update(&mut self, bar: &Bar) {
+        for (name, indicator) in &mut self.indicators {
+            let value = indicator.update(bar);
+            if let Some(slot) = self.current.get_mut(name) {
+                *slot = value;
+            }
+        }
+    }
+
+    /// Each indicator's value at the last bar, `None` during its warmup
+    pub fn current(&self) -> &HashMap<String, Option<Decimal>> {
+        &self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::IndicatorRegistry;
+    use crate::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+
+    #[test]
+    fn test_streaming_matches_materialized_series() {
+        let data = SyntheticDataGenerator::new(SyntheticDataConfig { bar_count: 300, ..SyntheticDataConfig::default() }, 11).generate();
+        let mut registry = IndicatorRegistry::new(false).unwrap();
+
+        for name in ["ema", "sma", "rsi", "atr", "hh", "ll"] {
+            let defaults = registry.default_params(name).unwrap();
+            for params in [
+                defaults.clone(),
+                IndicatorParams { price_precision: Some(2), ..defaults.clone() },
+                IndicatorParams { period: 3, ema_seed: EmaSeed::FirstValueSeed, ..defaults },
+            ] {
+                let series = registry.calculate_with_params(name, &data, &params).unwrap();
+                let mut streaming = StreamingIndicator::new(name, &params).unwrap();
+                let streamed: Vec<(u64, Decimal)> = data.bars.iter()
+                    .filter_map(|bar| streaming.update(bar).map(|value| (bar.timestamp, value)))
+                    .collect();
+                let expected: Vec<(u64, Decimal)> = series.iter().map(|v| (v.timestamp, v.value)).collect();
+                assert_eq!(streamed, expected, "{} {:?}", name, params);
+            }
+        }
+    }
+}
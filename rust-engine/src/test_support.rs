@@ -0,0 +1,228 @@
+//! Test support utilities
+//! 
+//! Deterministic synthetic market data for benchmarks, golden datasets, and tests.
+//! Series are fully determined by their configuration and seed. Also hand-assembled
+//! strategy modules for exercising the WASM ABI without a strategy toolchain.
+
+use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+
+use crate::data::MarketDataBuilder;
+use crate::precision::DeterministicRng;
+use crate::types::*;
+use crate::wasm::abi::{assemble_module, FunctionDef, METADATA_OFFSET};
+use crate::wasm::{ALLOC_EXPORT, INIT_EXPORT, ON_BAR_EXPORT};
+
+/// Milliseconds in a 365-day year, used to scale annual drift/volatility
+const MS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0 * 1000.0;
+
+/// Synthetic OHLCV series configuration
+#[derive(Debug, Clone)]
+pub struct SyntheticDataConfig {
+    pub symbol: String,
+    pub timeframe: String,
+    /// First bar timestamp (Unix milliseconds)
+    pub start_time: u64,
+    /// Spacing between bars in milliseconds
+    pub interval_ms: u64,
+    pub bar_count: usize,
+    pub initial_price: Decimal,
+    /// Annualized drift of the GBM (trend), e.g. 0.5 = +50%/year
+    pub annual_drift: f64,
+    /// Annualized volatility of the GBM, e.g. 0.8 = 80%/year
+    pub annual_volatility: f64,
+    /// Average volume per bar
+    pub base_volume: Decimal,
+}
+
+impl Default for SyntheticDataConfig {
+    fn default() -> Self {
+        Self {
+            symbol: "BTCUSDT".to_string(),
+            timeframe: "1m".to_string(),
+            start_time: 1609459200000, // 2021-01-01
+            interval_ms: 60_000,
+            bar_count: 1000,
+            initial_price: dec!(100.0),
+            annual_drift: 0.0,
+            annual_volatility: 0.8,
+            base_volume: dec!(1000.0),
+        }
+    }
+}
+
+/// Deterministic OHLCV generator driven by geometric Brownian motion
+pub struct SyntheticDataGenerator {
+    config: SyntheticDataConfig,
+    rng: DeterministicRng,
+}
+
+impl SyntheticDataGenerator {
+    /// Create a generator; pass `EngineConfig::random_seed` so series reproduce per run
+    pub fn new(config: SyntheticDataConfig, random_seed: u64) -> Self {
+        Self {
+            config,
+            rng: DeterministicRng::new(random_seed),
+        }
+    }
+    
+    /// Generate the configured series as `MarketData`
+    pub fn generate(&mut self) -> MarketData {
+        let bars = self.generate_bars();
+        
+        MarketDataBuilder::new(self.config.symbol.clone())
+            .timeframe(self.config.timeframe.clone())
+            .bars(bars)
+            .build()
+            .expect("synthetic bars are ordered and consistent")
+    }
+    
+    /// Generate bars only
+    pub fn generate_bars(&mut self) -> Vec<Bar> {
+        let dt = self.config.interval_ms as f64 / MS_PER_YEAR;
+        let sigma = self.config.annual_volatility;
+        let drift_step = (self.config.annual_drift - 0.5 * sigma * sigma) * dt;
+        let vol_step = sigma * dt.sqrt();
+        
+        let mut bars = Vec::with_capacity(self.config.bar_count);
+        let mut close = self.config.initial_price.to_f64().unwrap_or(100.0);
+        
+        for i in 0..self.config.bar_count {
+            let open = close;
+            close = open * (drift_step + vol_step * self.rng.next_gaussian()).exp();
+            
+            // Wicks extend beyond the body by a fraction of one step's volatility
+            let upper_wick = self.rng.next_gaussian().abs() * vol_step * 0.5;
+            let lower_wick = self.rng.next_gaussian().abs() * vol_step * 0.5;
+            let high = open.max(close) * (1.0 + upper_wick);
+            let low = open.min(close) * (1.0 - lower_wick);
+            
+            let volume_factor = 1.0 + self.rng.next_gaussian().abs();
+            let volume = self.config.base_volume * Self::to_decimal(volume_factor, 4);
+            
+            bars.push(Bar {
+                timestamp: self.config.start_time + i as u64 * self.config.interval_ms,
+                open: Self::to_decimal(open, 2),
+                high: Self::to_decimal(high, 2),
+                low: Self::to_decimal(low, 2),
+                close: Self::to_decimal(close, 2),
+                volume: volume.round_dp(4),
+                trade_count: (volume_factor * 100.0) as u32,
+            });
+        }
+        
+        bars
+    }
+    
+    fn to_decimal(value: f64, dp: u32) -> Decimal {
+        Decimal::from_f64(value).unwrap_or(dec!(0.0)).round_dp(dp)
+    }
+}
+
+/// Strategy module that returns its parameters as its signals on every bar
+/// 
+/// `strategy_params` must therefore be a JSON array of [`StrategySignal`]s. The module
+/// keeps the config where the engine wrote it and hands that location back from
+/// `strategy_on_bar`, so everything it emits comes from the job, not from code.
+pub fn echo_strategy_module(metadata_json: &[u8]) -> Vec<u8> {
+    // Globals: bump allocator top (past the metadata), config ptr, config len
+    let heap_start = (METADATA_OFFSET as usize + metadata_json.len()).next_multiple_of(8) as i32;
+    let alloc = vec![
+        0x23, 0x00, // global.get top, the result
+        0x23, 0x00, 0x20, 0x00, 0x6a, 0x24, 0x00, // top += len
+    ];
+    let init = vec![
+        0x20, 0x00, 0x24, 0x01, // config ptr
+        0x20, 0x01, 0x24, 0x02, // config len
+        0x41, 0x01, // handle 1
+    ];
+    let on_bar = vec![
+        0x23, 0x01, 0xad, 0x42, 0x20, 0x86, // config ptr << 32
+        0x23, 0x02, 0xad, 0x84, // | config len
+    ];
+    
+    assemble_module(metadata_json, &[heap_start, 0, 0], &[
+        FunctionDef { export: ALLOC_EXPORT, params: 1, result: 0x7f, body: alloc },
+        FunctionDef { export: INIT_EXPORT, params: 2, result: 0x7f, body: init },
+        FunctionDef { export: ON_BAR_EXPORT, params: 3, result: 0x7e, body: on_bar },
+    ])
+}
+
+/// [`echo_strategy_module`] that only echoes on the first bar it sees after init
+/// 
+/// Afterwards it points its output at an empty array it wrote during init, so a run that
+/// reuses the instance without resetting it never signals.
+pub fn first_bar_strategy_module(metadata_json: &[u8]) -> Vec<u8> {
+    // Globals: bump allocator top, output ptr, output len, empty array ptr
+    let heap_start = (METADATA_OFFSET as usize + metadata_json.len()).next_multiple_of(8) as i32;
+    let alloc = vec![
+        0x23, 0x00, // global.get top, the result
+        0x23, 0x00, 0x20, 0x00, 0x6a, 0x24, 0x00, // top += len
+    ];
+    let init = vec![
+        0x20, 0x00, 0x24, 0x01, // output ptr = config ptr
+        0x20, 0x01, 0x24, 0x02, // output len = config len
+        0x23, 0x00, 0x24, 0x03, // empty array at top
+        0x23, 0x03, 0x41, 0xdb, 0xba, 0x01, 0x3b, 0x00, 0x00, // i32.store16 "[]"
+        0x23, 0x00, 0x41, 0x02, 0x6a, 0x24, 0x00, // top += 2
+        0x41, 0x01, // handle 1
+    ];
+    let on_bar = vec![
+        0x23, 0x01, 0xad, 0x42, 0x20, 0x86, // output ptr << 32
+        0x23, 0x02, 0xad, 0x84, // | output len
+        0x23, 0x03, 0x24, 0x01, // then the empty array
+        0x41, 0x02, 0x24, 0x02,
+    ];
+    
+    assemble_module(metadata_json, &[heap_start, 0, 0, 0], &[
+        FunctionDef { export: ALLOC_EXPORT, params: 1, result: 0x7f, body: alloc },
+        FunctionDef { export: INIT_EXPORT, params: 2, result: 0x7f, body: init },
+        FunctionDef { export: ON_BAR_EXPORT, params: 3, result: 0x7e, body: on_bar },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(seed: u64) -> MarketData {
+        SyntheticDataGenerator::new(SyntheticDataConfig::default(), seed).generate()
+    }
+
+    #[test]
+    fn test_same_seed_reproduces_bars() {
+        let a = generate(42);
+        let b = generate(42);
+        
+        assert_eq!(a.bars.len(), 1000);
+        assert_eq!(
+            serde_json::to_string(&a.bars).unwrap(),
+            serde_json::to_string(&b.bars).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_different_seeds_diverge() {
+        let a = generate(42);
+        let b = generate(43);
+        
+        let differing = a.bars.iter().zip(b.bars.iter())
+            .filter(|(x, y)| x.close != y.close)
+            .count();
+        assert!(differing > 900);
+    }
+
+    #[test]
+    fn test_bars_are_well_formed() {
+        let data = generate(7);
+        
+        for (i, bar) in data.bars.iter().enumerate() {
+            assert!(bar.high >= bar.open.max(bar.close));
+            assert!(bar.low <= bar.open.min(bar.close));
+            assert!(bar.low > dec!(0.0));
+            assert_eq!(bar.timestamp, 1609459200000 + i as u64 * 60_000);
+        }
+    }
+}
+
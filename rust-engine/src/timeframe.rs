@@ -0,0 +1,201 @@
+//! Timeframe parsing and interval arithmetic
+//! 
+//! Shared parser for timeframe strings such as `"1s"`, `"15m"`, `"4h"`, `"1d"` used by
+//! resampling, synthetic data, and return annualization.
+
+use std::fmt;
+use std::str::FromStr;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Milliseconds in a 365-day year; crypto markets trade around the clock
+const MS_PER_YEAR: u64 = 365 * 86_400_000;
+
+/// Milliseconds in a day
+const MS_PER_DAY: u64 = 86_400_000;
+
+/// Trading days in a year under [`AnnualizationConvention::Trading252`]
+const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+/// Trading days in a week under [`AnnualizationConvention::Trading252`]
+const TRADING_DAYS_PER_WEEK: f64 = 5.0;
+
+/// How many days make up a year when annualizing per-bar statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AnnualizationConvention {
+    /// Every calendar day trades (24/7 markets): 365 days, 525 600 one-minute bars
+    #[default]
+    Calendar365,
+    /// 252 trading days of five-day weeks; intraday bars are counted over a full day
+    Trading252,
+}
+
+/// Timeframe unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeframeUnit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+}
+
+impl TimeframeUnit {
+    fn as_millis(self) -> u64 {
+        match self {
+            TimeframeUnit::Second => 1_000,
+            TimeframeUnit::Minute => 60_000,
+            TimeframeUnit::Hour => 3_600_000,
+            TimeframeUnit::Day => 86_400_000,
+            TimeframeUnit::Week => 7 * 86_400_000,
+        }
+    }
+    
+    fn suffix(self) -> &'static str {
+        match self {
+            TimeframeUnit::Second => "s",
+            TimeframeUnit::Minute => "m",
+            TimeframeUnit::Hour => "h",
+            TimeframeUnit::Day => "d",
+            TimeframeUnit::Week => "w",
+        }
+    }
+}
+
+/// Bar timeframe, e.g. 15 minutes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timeframe {
+    pub count: u32,
+    pub unit: TimeframeUnit,
+}
+
+impl Timeframe {
+    /// Bar interval in milliseconds
+    pub fn as_millis(&self) -> u64 {
+        self.count as u64 * self.unit.as_millis()
+    }
+    
+    /// Number of bars in a 365-day year, for annualizing per-bar statistics
+    pub fn periods_per_year(&self) -> f64 {
+        periods_per_year_for_interval(self.as_millis())
+    }
+    
+    /// Number of bars per year under `convention`
+    /// 
+    /// The single source of truth for annualization: `Calendar365` matches
+    /// [`Timeframe::periods_per_year`], `Trading252` counts a 1d bar as one trading day
+    /// and a 1w bar as five.
+    pub fn periods_per_year_with(&self, convention: AnnualizationConvention) -> f64 {
+        match convention {
+            AnnualizationConvention::Calendar365 => self.periods_per_year(),
+            AnnualizationConvention::Trading252 => {
+                let trading_days_per_bar = match self.unit {
+                    TimeframeUnit::Week => self.count as f64 * TRADING_DAYS_PER_WEEK,
+                    _ => self.as_millis() as f64 / MS_PER_DAY as f64,
+                };
+                TRADING_DAYS_PER_YEAR / trading_days_per_bar
+            }
+        }
+    }
+    
+    /// Timeframe of bars spaced `interval_ms` apart, in the largest unit that divides it
+    /// 
+    /// `None` for intervals that are not a whole number of seconds.
+    pub fn from_millis(interval_ms: u64) -> Option<Self> {
+        [TimeframeUnit::Week, TimeframeUnit::Day, TimeframeUnit::Hour, TimeframeUnit::Minute, TimeframeUnit::Second]
+            .into_iter()
+            .find(|unit| interval_ms > 0 && interval_ms.is_multiple_of(unit.as_millis()))
+            .and_then(|unit| Some(Self { count: u32::try_from(interval_ms / unit.as_millis()).ok()?, unit }))
+    }
+    
+    /// Whether `self` divides evenly into `coarser` (e.g. 1m into 5m)
+    pub fn divides(&self, coarser: &Timeframe) -> bool {
+        coarser.as_millis() >= self.as_millis() && coarser.as_millis().is_multiple_of(self.as_millis())
+    }
+}
+
+/// Number of `interval_ms` bars in a 365-day year, when only the bar spacing is known
+pub fn periods_per_year_for_interval(interval_ms: u64) -> f64 {
+    MS_PER_YEAR as f64 / interval_ms as f64
+}
+
+impl FromStr for Timeframe {
+    type Err = anyhow::Error;
+    
+    fn from_str(s: &str) -> Result<Self> {
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (count, unit) = s.split_at(split);
+        
+        let count: u32 = count.parse()
+            .map_err(|_| anyhow::anyhow!("Invalid timeframe '{}': expected a count such as 15m", s))?;
+        if count == 0 {
+            return Err(anyhow::anyhow!("Invalid timeframe '{}': count must be positive", s));
+        }
+        
+        let unit = match unit {
+            "s" => TimeframeUnit::Second,
+            "m" => TimeframeUnit::Minute,
+            "h" => TimeframeUnit::Hour,
+            "d" => TimeframeUnit::Day,
+            "w" => TimeframeUnit::Week,
+            other => return Err(anyhow::anyhow!(
+                "Invalid timeframe '{}': unknown unit '{}' (expected s, m, h, d or w)", s, other
+            )),
+        };
+        
+        Ok(Self { count, unit })
+    }
+}
+
+impl fmt::Display for Timeframe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.count, self.unit.suffix())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_common_timeframes() {
+        let cases = [
+            ("1m", 60_000, 525_600.0),
+            ("15m", 900_000, 35_040.0),
+            ("4h", 14_400_000, 2_190.0),
+            ("1d", 86_400_000, 365.0),
+        ];
+
+        for (input, millis, periods) in cases {
+            let timeframe: Timeframe = input.parse().unwrap();
+            assert_eq!(timeframe.as_millis(), millis, "{}", input);
+            assert_eq!(timeframe.periods_per_year(), periods, "{}", input);
+            assert_eq!(timeframe.to_string(), input);
+            assert_eq!(Timeframe::from_millis(millis), Some(timeframe));
+        }
+    }
+
+    #[test]
+    fn test_periods_per_year_by_convention() {
+        let cases = [
+            ("1m", 525_600.0, 362_880.0),
+            ("1h", 8_760.0, 6_048.0),
+            ("1d", 365.0, 252.0),
+            ("1w", 365.0 / 7.0, 50.4),
+        ];
+
+        for (input, calendar, trading) in cases {
+            let timeframe: Timeframe = input.parse().unwrap();
+            assert_eq!(timeframe.periods_per_year_with(AnnualizationConvention::Calendar365), calendar, "{}", input);
+            assert_eq!(timeframe.periods_per_year_with(AnnualizationConvention::Trading252), trading, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_rejects_unknown_unit() {
+        let err = "3x".parse::<Timeframe>().unwrap_err();
+        assert!(err.to_string().contains("unknown unit 'x'"));
+        assert!("m".parse::<Timeframe>().is_err());
+        assert!("0m".parse::<Timeframe>().is_err());
+    }
+}
@@ -3,17 +3,407 @@
 //! Implements the complete trade table generation system as specified in the plan.
 //! Produces one row per closed trade with comprehensive PnL, fee, and exit reason tracking.
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use chrono::Weekday;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
 use anyhow::Result;
-use tracing::{debug, warn, error};
-
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::indicators::rolling_std;
+use crate::precision::{decimal_sqrt, round_to_precision, DeterministicRng};
+use crate::simulator::{one_second_path, signal_sub_step};
+use crate::timeframe::{periods_per_year_for_interval, AnnualizationConvention, Timeframe};
 use crate::types::*;
 
+/// Milliseconds in a UTC day, for daily loss bucketing
+const MS_PER_DAY: u64 = 86_400_000;
+
+/// Bar length assumed for one-second paths until [`TradeTableGenerator::set_bar_interval_ms`] is called
+const DEFAULT_BAR_INTERVAL_MS: u64 = 60_000;
+
+/// Trades per bar at which `SlippageMode::SyntheticBook` charges its base rate
+pub const SYNTHETIC_BOOK_REFERENCE_TRADES: u32 = 100;
+
+/// `SlippageMode::SyntheticBook` slippage rate for a bar with `trade_count` trades
+/// 
+/// The base 0.05% is scaled by `sqrt(SYNTHETIC_BOOK_REFERENCE_TRADES / trade_count)`,
+/// clamped to 0.5x-4x, so fewer trades model a thinner book. A count of zero means
+/// the data does not report trades and keeps the base rate.
+pub fn synthetic_book_rate(trade_count: u32) -> Decimal {
+    let base_rate = dec!(0.0005); // 0.05%
+    if trade_count == 0 {
+        return base_rate;
+    }
+    
+    let depth = Decimal::from(SYNTHETIC_BOOK_REFERENCE_TRADES) / Decimal::from(trade_count);
+    base_rate * decimal_sqrt(depth).clamp(dec!(0.5), dec!(4.0))
+}
+
+/// Seeded source of `SlippageMode::Stochastic` draws
+/// 
+/// The n-th draw comes from a [`DeterministicRng`] seeded from `seed` and `n`, so only
+/// the draw count has to be checkpointed for a restored run to continue the sequence.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SlippageSampler {
+    pub seed: u64,
+    /// Draws taken so far
+    pub draws: u64,
+}
+
+impl SlippageSampler {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, draws: 0 }
+    }
+
+    /// Next slippage rate, `mean + stddev * z` floored at zero so it is always adverse
+    /// 
+    /// `z` is the Irwin-Hall approximation of a standard normal, twelve uniform integer
+    /// draws summed less six, worked in `Decimal` so no float `ln`/`cos` (whose last bits
+    /// vary by platform) reaches a fill price. Its tails stop at six deviations.
+    pub fn next_rate(&mut self, mean: Decimal, stddev: Decimal) -> Decimal {
+        let mut rng = DeterministicRng::new(self.seed ^ self.draws.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        self.draws += 1;
+        let sum: u64 = (0..12).map(|_| u64::from(rng.next_u32())).sum();
+        let z = Decimal::from(sum) / Decimal::from(1u64 << 32) - dec!(6);
+        (mean + stddev * z).max(Decimal::ZERO)
+    }
+}
+
+/// Seed of `symbol`'s stochastic draws in a run seeded with `seed`
+/// 
+/// Hashed from both, so a symbol draws the same sequence whichever symbols ran before it.
+pub fn symbol_seed(seed: u64, symbol: &str) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(seed.to_le_bytes());
+    hasher.update(symbol.as_bytes());
+    let digest = hasher.finalize();
+    u64::from_le_bytes(digest[..8].try_into().expect("SHA-256 digest is 32 bytes"))
+}
+
+/// Trade table generator configuration
+/// 
+/// Fields left out when deserializing take their [`Default`] values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TradeTableConfig {
+    /// Account equity before any trade; equity is this plus realized PnL
+    pub initial_equity: Decimal,
+    /// Position leverage; above 1x positions carry a liquidation price
+    pub leverage: Decimal,
+    /// What to do with orders larger than the symbol's `max_qty`
+    pub max_qty_policy: MaxQtyPolicy,
+    /// How order quantities are snapped to the lot size (prices always use nearest tick)
+    pub qty_rounding: QtyRoundingMode,
+    /// Trade records reserved up front to avoid regrowth on long runs
+    pub prealloc_trades: usize,
+    /// How the notional of each new entry is chosen
+    pub sizing_mode: SizingMode,
+    /// Price path assumed inside each bar under `IntrabarPolicy::LinearInterpolation`
+    pub intrabar_path: IntrabarPath,
+    /// Whether a symbol holds one position or separate long and short legs
+    pub position_mode: PositionMode,
+    /// PnL window around zero that counts a trade as a scratch rather than a win or loss
+    pub breakeven_band: BreakevenBand,
+    /// Circuit breakers that stop new entries
+    pub risk_limits: RiskLimits,
+    /// Close positions still open when the data ends, marked to the last close
+    /// 
+    /// When false they stay out of the trade table and remain in `active_positions`.
+    pub close_at_end_of_data: bool,
+    /// Minimum spacing between entries on the same symbol
+    pub entry_cooldown: Option<EntryCooldown>,
+    /// Capital shared by all symbols, in USD of open notional at entry prices
+    /// 
+    /// Entries that would take the open notional above it are rejected with
+    /// [`ReasonCode::InsufficientCapital`]. Which of several same-bar entries gets the
+    /// capital follows the order described on [`TradeTableGenerator::process_bar`].
+    pub capital_limit: Option<Decimal>,
+    /// Intraday window outside which entries are rejected; `None` trades around the clock
+    pub session: Option<TradingSession>,
+    /// What to do with slipped fills that land outside their bar's low/high
+    pub fill_range_policy: FillRangePolicy,
+    /// Which of several same-bar entry signals for one symbol and side is taken
+    pub overlapping_entries: OverlappingEntryPolicy,
+    /// Whether entries open on their signal's bar or at the next bar's open
+    pub fill_model: FillModel,
+    /// Days per year used to annualize realized volatility for
+    /// [`SizingMode::VolatilityTarget`]
+    pub annualization: AnnualizationConvention,
+}
+
+impl Default for TradeTableConfig {
+    fn default() -> Self {
+        Self {
+            initial_equity: dec!(10000.0),
+            leverage: dec!(1.0),
+            max_qty_policy: MaxQtyPolicy::Cap,
+            qty_rounding: QtyRoundingMode::Floor,
+            prealloc_trades: 0,
+            sizing_mode: SizingMode::FixedNotional,
+            intrabar_path: IntrabarPath::Range,
+            position_mode: PositionMode::OneWay,
+            breakeven_band: BreakevenBand::Usd(dec!(0.0)),
+            risk_limits: RiskLimits::default(),
+            close_at_end_of_data: true,
+            entry_cooldown: None,
+            capital_limit: None,
+            session: None,
+            fill_range_policy: FillRangePolicy::Clamp,
+            overlapping_entries: OverlappingEntryPolicy::FirstWins,
+            fill_model: FillModel::SameBar,
+            annualization: AnnualizationConvention::Calendar365,
+        }
+    }
+}
+
+/// Intraday window in which new entries are allowed
+/// 
+/// Times are milliseconds since UTC midnight, `start_ms_of_day` inclusive and
+/// `end_ms_of_day` exclusive; a start after the end wraps past midnight (e.g.
+/// 22:00-04:00). Bars are placed in the window by their open time and UTC weekday.
+/// Entry signals outside it are rejected with [`ReasonCode::OutsideSession`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradingSession {
+    pub start_ms_of_day: u64,
+    pub end_ms_of_day: u64,
+    /// Weekdays that trade; empty means every day
+    pub days_of_week: Vec<Weekday>,
+    /// Close open positions at the last bar inside each session, as [`ExitReason::Flatten`]
+    /// 
+    /// The close is detected per symbol: its first bar outside the session flattens its
+    /// open positions at the close of its previous bar, its last session bar. Positions still open when the data ends inside a session are left to
+    /// [`TradeTableGenerator::finalize`] and close as [`ExitReason::EndOfData`].
+    pub flatten_at_close: bool,
+}
+
+impl TradingSession {
+    /// Whether a bar opening at `timestamp` falls inside the session
+    pub fn contains(&self, timestamp: u64) -> bool {
+        let day = timestamp / MS_PER_DAY;
+        // 1970-01-01 was a Thursday
+        let weekday_index = (day + 3) % 7;
+        if !self.days_of_week.is_empty()
+            && !self.days_of_week.iter().any(|d| u64::from(d.num_days_from_monday()) == weekday_index)
+        {
+            return false;
+        }
+
+        let ms_of_day = timestamp % MS_PER_DAY;
+        if self.start_ms_of_day <= self.end_ms_of_day {
+            ms_of_day >= self.start_ms_of_day && ms_of_day < self.end_ms_of_day
+        } else {
+            ms_of_day >= self.start_ms_of_day || ms_of_day < self.end_ms_of_day
+        }
+    }
+}
+
+/// Minimum spacing between consecutive entries on a symbol
+/// 
+/// Measured from the previous accepted entry (both legs count in hedge mode); signals
+/// inside the window are rejected with [`ReasonCode::Cooldown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntryCooldown {
+    /// Milliseconds of bar time
+    Duration(u64),
+    /// Bars processed, so gaps in the data do not shorten the wait
+    Bars(u32),
+}
+
+/// Capital-preservation limits; every limit is off by default
+/// 
+/// Once a loss limit trips, later entry signals are rejected with [`ReasonCode::RiskLimit`].
+/// Positions already open keep running to their own exits.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RiskLimits {
+    /// Drawdown from peak equity that halts new entries for the rest of the run
+    pub max_drawdown_pct: Option<Percent>,
+    /// Realized loss in USD (positive) that halts new entries until the next UTC midnight
+    pub daily_loss_limit: Option<Decimal>,
+    /// Open positions allowed at once across all symbols (each hedge leg counts)
+    /// 
+    /// Entries beyond it are rejected with [`ReasonCode::MaxOpenPositions`] until a
+    /// position closes; same-bar entries claim slots in the order described on
+    /// [`TradeTableGenerator::process_bar`]. A job steps its symbols' bars in time order,
+    /// so a slot is held by whichever symbol entered first; symbols entering on the
+    /// same bar claim slots in the order the job lists them.
+    pub max_open_positions: Option<usize>,
+}
+
+/// Width of the breakeven band used to classify trade outcomes
+/// 
+/// A trade whose net PnL lies within `±band` (inclusive) is a scratch; above it is a
+/// win and below it a loss. A zero band still scratches trades that net exactly zero.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BreakevenBand {
+    /// Fixed band in USD
+    Usd(Decimal),
+    /// Each trade's own round-trip fees
+    Fees,
+}
+
+impl BreakevenBand {
+    /// Classify a closed trade against the band
+    pub fn classify(&self, trade: &TradeRecord) -> TradeOutcome {
+        let band = match self {
+            BreakevenBand::Usd(band) => *band,
+            BreakevenBand::Fees => trade.fees_usd,
+        };
+        if trade.pnl_usd > band {
+            TradeOutcome::Win
+        } else if trade.pnl_usd < -band {
+            TradeOutcome::Loss
+        } else {
+            TradeOutcome::Scratch
+        }
+    }
+}
+
+/// How positions on the same symbol combine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PositionMode {
+    /// One position per symbol; new signals are ignored while it is open
+    OneWay,
+    /// Independent long and short legs per symbol
+    /// 
+    /// An opposite signal opens the other leg instead of netting against the open one;
+    /// each leg closes only on its own TP/SL/timeout/liquidation and books its own PnL.
+    Hedge,
+}
+
+/// Intrabar price path for exit checks under `IntrabarPolicy::LinearInterpolation`
+/// 
+/// Each variant fixes how a bar is traversed, so exits are reproducible:
+/// - `Range`: every price between low and high is reachable but the order is unknown;
+///   simultaneous exits fall back to the conservative priority of [`resolve_exit`].
+/// - `OpenClose`: a straight line from open to close; wicks beyond it never trade.
+/// - `NearestExtremeFirst`: open -> high -> low -> close when the high is nearer the
+///   open, otherwise open -> low -> high -> close (ties visit the low first); exits
+///   fire in the order the path touches them.
+/// 
+/// `ExactTrades` and `OneSecondBars` always walk the `NearestExtremeFirst` path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntrabarPath {
+    Range,
+    OpenClose,
+    NearestExtremeFirst,
+}
+/// Position sizing for new entries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SizingMode {
+    /// Every entry uses the default notional ($1000), so profits never compound
+    FixedNotional,
+    /// Each entry commits `fraction` of current equity (e.g. 0.1 for 10%)
+    /// 
+    /// Equity is `initial_equity` plus realized PnL as of the previous bar, so
+    /// winning trades grow later positions and losing trades shrink them.
+    PercentEquity {
+        fraction: Decimal,
+    },
+    /// Size inversely to recent realized volatility so each position targets
+    /// `annual_vol_target` (e.g. 0.15 for 15%) of equity
+    /// 
+    /// Realized volatility is the rolling standard deviation of the entry symbol's last
+    /// `lookback` close-to-close returns, annualized from the bar spacing under the config's
+    /// `annualization` convention. Notional is
+    /// `equity * annual_vol_target / realized_vol`, capped at `equity * leverage`.
+    /// Until `lookback` returns are available entries fall back to the default notional.
+    VolatilityTarget {
+        annual_vol_target: Decimal,
+        lookback: usize,
+    },
+}
+
+/// Lot-size rounding for order quantities
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum QtyRoundingMode {
+    /// Round to the nearest lot; may round up past the intended notional
+    Nearest,
+    /// Round down to a whole lot, as exchanges do; never over-allocates capital
+    Floor,
+}
+
+/// Handling of orders above the exchange's maximum order size
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MaxQtyPolicy {
+    /// Reduce the order to the largest allowed quantity
+    Cap,
+    /// Reject the order outright
+    Reject,
+}
+
+/// Handling of slippage-adjusted fills beyond the range their bar traded in
+/// 
+/// Slippage is added to the fill's base price without regard to the bar, so a market
+/// exit at a close that is also the high, for example, can fill above anything that
+/// traded.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FillRangePolicy {
+    /// Move the fill to the bar's high or low, the worst price that actually traded
+    Clamp,
+    /// Keep the slipped price and mark the trade with `fill_outside_bar`
+    Flag,
+}
+
+/// Handling of several entry signals for the same symbol and side on one bar
+/// 
+/// "First" and "last" follow the order the strategy emitted the signals in. Dropped
+/// signals are recorded as [`ReasonCode::OverlappingEntry`] rejections.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OverlappingEntryPolicy {
+    /// Take the first signal and drop the rest
+    FirstWins,
+    /// Take the last signal and drop the rest
+    LastWins,
+    /// Open one position committing the notional of every signal, with the first
+    /// signal's take profit, stop loss, time to live and tags
+    Aggregate,
+    /// Drop them all
+    Reject,
+}
+
+/// Checkpoint of everything a [`TradeTableGenerator`] carries between bars
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeTableState {
+    pub config: TradeTableConfig,
+    pub default_size_usd: Decimal,
+    #[serde(serialize_with = "crate::canonical::sorted_map")]
+    pub active_positions: HashMap<String, ActivePosition>,
+    pub trade_records: Vec<TradeRecord>,
+    pub rejected_trades: Vec<RejectedTrade>,
+    pub realized_pnl: Decimal,
+    #[serde(serialize_with = "crate::canonical::sorted_map")]
+    pub recent_closes: HashMap<String, VecDeque<(u64, Decimal)>>,
+    pub current_equity: Decimal,
+    pub peak_equity: Decimal,
+    pub max_drawdown: Decimal,
+    #[serde(default)]
+    pub risk_halted: bool,
+    #[serde(default)]
+    pub trading_day: u64,
+    #[serde(default)]
+    pub day_start_pnl: Decimal,
+    #[serde(default)]
+    pub daily_halted: bool,
+    #[serde(default, serialize_with = "crate::canonical::sorted_map")]
+    pub bars_seen: HashMap<String, u64>,
+    #[serde(default, serialize_with = "crate::canonical::sorted_map")]
+    pub last_entries: HashMap<String, (u64, u64)>,
+    #[serde(default, serialize_with = "crate::canonical::sorted_map")]
+    pub last_bars: HashMap<String, Bar>,
+    #[serde(default)]
+    pub slippage_sampler: SlippageSampler,
+    #[serde(default)]
+    pub pending_entries: Vec<StrategySignal>,
+}
+
 /// Trade table generator
 pub struct TradeTableGenerator {
+    config: TradeTableConfig,
     /// Default size in USD
     default_size_usd: Decimal,
     /// Active positions being tracked
@@ -22,29 +412,153 @@ pub struct TradeTableGenerator {
     trade_records: Vec<TradeRecord>,
     /// Rejected trades
     rejected_trades: Vec<RejectedTrade>,
+    /// Running sum of closed-trade PnL
+    realized_pnl: Decimal,
+    /// Recent (timestamp, close) pairs per symbol for volatility-targeted sizing
+    recent_closes: HashMap<String, VecDeque<(u64, Decimal)>>,
     /// Current equity for drawdown calculation
     current_equity: Decimal,
     peak_equity: Decimal,
     max_drawdown: Decimal,
+    /// Set once `max_drawdown_pct` trips; blocks entries for the rest of the run
+    risk_halted: bool,
+    /// UTC day (days since epoch) of the last processed bar
+    trading_day: u64,
+    /// Realized PnL at the start of `trading_day`
+    day_start_pnl: Decimal,
+    /// Set once `daily_loss_limit` trips; cleared at the next UTC midnight
+    daily_halted: bool,
+    /// Bars seen so far per symbol, for bar-count cooldowns
+    bars_seen: HashMap<String, u64>,
+    /// (timestamp, bar index) of the last accepted entry per symbol
+    last_entries: HashMap<String, (u64, u64)>,
+    /// Previous bar processed per symbol, where a session flatten closes its positions
+    last_bars: HashMap<String, Bar>,
+    /// Draws for `SlippageMode::Stochastic`
+    slippage_sampler: SlippageSampler,
+    /// Positions exiting on the bar being processed; kept between bars to reuse its buffer
+    exits_due: Vec<(String, ExitInfo)>,
+    /// Entry signals of the last bar, opened at this bar's open under `FillModel::NextBarOpen`
+    pending_entries: Vec<StrategySignal>,
+    /// Length of the bars being processed, for the one-second path limit entries must reach
+    bar_interval_ms: u64,
+}
+
+impl Default for TradeTableGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TradeTableGenerator {
     /// Create a new trade table generator
     pub fn new() -> Self {
+        Self::with_config(TradeTableConfig::default())
+    }
+
+    /// Create a trade table generator with explicit configuration
+    pub fn with_config(config: TradeTableConfig) -> Self {
+        let trade_records = Vec::with_capacity(config.prealloc_trades);
+
         Self {
             default_size_usd: dec!(1000.0),
             active_positions: HashMap::new(),
-            trade_records: Vec::new(),
+            trade_records,
             rejected_trades: Vec::new(),
-            current_equity: dec!(10000.0), // Starting equity
-            peak_equity: dec!(10000.0),
+            realized_pnl: dec!(0.0),
+            recent_closes: HashMap::new(),
+            current_equity: config.initial_equity,
+            peak_equity: config.initial_equity,
             max_drawdown: dec!(0.0),
+            risk_halted: false,
+            trading_day: 0,
+            day_start_pnl: dec!(0.0),
+            daily_halted: false,
+            bars_seen: HashMap::new(),
+            last_entries: HashMap::new(),
+            last_bars: HashMap::new(),
+            slippage_sampler: SlippageSampler::default(),
+            exits_due: Vec::new(),
+            pending_entries: Vec::new(),
+            bar_interval_ms: DEFAULT_BAR_INTERVAL_MS,
+            config,
         }
     }
 
-    /// Process a bar and generate trade records
+    /// Capture the generator's state so a run can resume from this bar
+    pub fn snapshot(&self) -> TradeTableState {
+        TradeTableState {
+            config: self.config.clone(),
+            default_size_usd: self.default_size_usd,
+            active_positions: self.active_positions.clone(),
+            trade_records: self.trade_records.clone(),
+            rejected_trades: self.rejected_trades.clone(),
+            realized_pnl: self.realized_pnl,
+            recent_closes: self.recent_closes.clone(),
+            current_equity: self.current_equity,
+            peak_equity: self.peak_equity,
+            max_drawdown: self.max_drawdown,
+            risk_halted: self.risk_halted,
+            trading_day: self.trading_day,
+            day_start_pnl: self.day_start_pnl,
+            daily_halted: self.daily_halted,
+            bars_seen: self.bars_seen.clone(),
+            last_entries: self.last_entries.clone(),
+            last_bars: self.last_bars.clone(),
+            slippage_sampler: self.slippage_sampler.clone(),
+            pending_entries: self.pending_entries.clone(),
+        }
+    }
+
+    /// Rebuild a generator from a [`TradeTableGenerator::snapshot`]
+    pub fn restore(state: TradeTableState) -> Self {
+        Self {
+            config: state.config,
+            default_size_usd: state.default_size_usd,
+            active_positions: state.active_positions,
+            trade_records: state.trade_records,
+            rejected_trades: state.rejected_trades,
+            realized_pnl: state.realized_pnl,
+            recent_closes: state.recent_closes,
+            current_equity: state.current_equity,
+            peak_equity: state.peak_equity,
+            max_drawdown: state.max_drawdown,
+            risk_halted: state.risk_halted,
+            trading_day: state.trading_day,
+            day_start_pnl: state.day_start_pnl,
+            daily_halted: state.daily_halted,
+            bars_seen: state.bars_seen,
+            last_entries: state.last_entries,
+            last_bars: state.last_bars,
+            slippage_sampler: state.slippage_sampler,
+            exits_due: Vec::new(),
+            pending_entries: state.pending_entries,
+            bar_interval_ms: DEFAULT_BAR_INTERVAL_MS,
+        }
+    }
+
+    /// Process one of `symbol`'s bars and generate trade records
+    /// 
+    /// Only `symbol`'s open positions are checked for exits against `bar` and count it
+    /// toward their `bars_held`, so several symbols can share a generator, fed their bars
+    /// in time order. Entry signals may name other symbols and enter at `bar`'s prices.
+    /// 
+    /// Entry signals are taken in a fixed order rather than as given: by symbol name,
+    /// then by `size` (largest first), with exact ties kept in emission order. When
+    /// capital or risk limits leave room for only some of them, the outcome is therefore
+    /// the same on every run and platform, whatever order the signals were collected in.
+    /// 
+    /// A position can close on the bar it opens on, but only on the part of the intrabar
+    /// path after its entry: from the open under `OneSecondBars` and
+    /// `LinearInterpolation`, where the path decides whether TP or SL is touched first,
+    /// and not at all under `ExactTrades`, which enters at the close.
+    /// 
+    /// Under [`FillModel::NextBarOpen`] the bar's signals are held back and enter at the
+    /// open of their symbol's next bar processed, whatever the intrabar policy, so that
+    /// bar's whole path can hit their exits.
     pub fn process_bar(
         &mut self,
+        symbol: &str,
         bar: &Bar,
         signals: &[StrategySignal],
         intrabar_policy: &IntrabarPolicy,
@@ -53,15 +567,55 @@ impl TradeTableGenerator {
     ) -> Result<()> {
         debug!("Processing bar at timestamp: {}", bar.timestamp);
 
-        // 1. Process entry signals
-        self.process_entry_signals(bar, signals, intrabar_policy, slippage_mode, rules)?;
+        // Start a fresh daily loss budget at each UTC midnight
+        let day = bar.timestamp / MS_PER_DAY;
+        if day != self.trading_day {
+            self.trading_day = day;
+            self.day_start_pnl = self.realized_pnl;
+            self.daily_halted = false;
+        }
+
+        if let SizingMode::VolatilityTarget { lookback, .. } = self.config.sizing_mode {
+            let closes = self.recent_closes.entry(symbol.to_string()).or_default();
+            closes.push_back((bar.timestamp, bar.close));
+            while closes.len() > lookback + 1 {
+                closes.pop_front();
+            }
+        }
+
+        // Session ended since the symbol's last bar: close out at that bar, the session's last
+        let flatten_sessions = self.config.session.as_ref().is_some_and(|session| session.flatten_at_close);
+        if flatten_sessions {
+            let session_over = self.config.session.as_ref().is_some_and(|session| !session.contains(bar.timestamp));
+            if session_over {
+                if let Some(last_bar) = self.last_bars.remove(symbol) {
+                    self.close_positions(Some(symbol), &last_bar, ExitReason::Flatten, slippage_mode, rules)?;
+                }
+            }
+            self.last_bars.insert(symbol.to_string(), bar.clone());
+        }
 
-        // 2. Check for exits on existing positions
-        self.process_exits(bar, intrabar_policy, slippage_mode, rules)?;
+        // 1. Process entry signals: this bar's, or those left pending on the symbol's last bar under NextBarOpen
+        match self.config.fill_model {
+            FillModel::SameBar => self.process_entry_signals(bar, signals, intrabar_policy, slippage_mode, rules)?,
+            FillModel::NextBarOpen => {
+                let (due, mut pending): (Vec<StrategySignal>, Vec<StrategySignal>) = std::mem::take(&mut self.pending_entries)
+                    .into_iter()
+                    .partition(|signal| signal.symbol == symbol);
+                self.process_entry_signals(bar, &due, intrabar_policy, slippage_mode, rules)?;
+                pending.extend_from_slice(signals);
+                self.pending_entries = pending;
+            }
+        }
+
+        // 2. Check for exits on the symbol's existing positions
+        self.process_exits(symbol, bar, intrabar_policy, slippage_mode, rules)?;
 
         // 3. Update equity and drawdown
         self.update_equity_and_drawdown();
 
+        *self.bars_seen.entry(symbol.to_string()).or_insert(0) += 1;
+
         Ok(())
     }
 
@@ -74,32 +628,78 @@ impl TradeTableGenerator {
         slippage_mode: &SlippageMode,
         rules: &ExchangeRules,
     ) -> Result<()> {
-        for signal in signals {
-            // Skip if we already have a position for this symbol
-            if self.active_positions.contains_key(&signal.symbol) {
+        let signals = self.reachable_entries(bar, signals, intrabar_policy);
+        let mut ordered = self.resolve_overlapping_entries(bar, &signals);
+        ordered.sort_by(|(a, _), (b, _)| a.symbol.cmp(&b.symbol).then_with(|| b.size.cmp(&a.size)));
+        
+        for (signal, merged) in &ordered {
+            let trade_type = match signal.side {
+                TradeSide::Buy => TradeType::Long,
+                TradeSide::Sell => TradeType::Short,
+            };
+
+            // Aggregated signals commit one entry's notional each
+            let notional = self.entry_notional(&signal.symbol) * Decimal::from(*merged);
+
+            // One position per symbol (or per leg, in hedge mode); an opposite signal
+            // does not close or flip it here
+            let key = self.position_key(&signal.symbol, &trade_type);
+            if self.active_positions.contains_key(&key) {
+                self.reject_signal(bar, signal, ReasonCode::PositionOpen, notional);
+                continue;
+            }
+
+            if self.config.session.as_ref().is_some_and(|session| !session.contains(bar.timestamp)) {
+                self.reject_signal(bar, signal, ReasonCode::OutsideSession, notional);
+                continue;
+            }
+
+            if matches!(trade_type, TradeType::Short) && !rules.allow_shorts {
+                self.reject_signal(bar, signal, ReasonCode::ShortsNotAllowed, notional);
+                continue;
+            }
+
+            if self.risk_halted || self.daily_halted {
+                self.reject_signal(bar, signal, ReasonCode::RiskLimit, notional);
+                continue;
+            }
+
+            if self.config.risk_limits.max_open_positions.is_some_and(|max| self.active_positions.len() >= max) {
+                self.reject_signal(bar, signal, ReasonCode::MaxOpenPositions, notional);
                 continue;
             }
 
-            // 1. Apply capital rule ($1000 default)
-            let notional = self.default_size_usd;
+            if self.in_cooldown(&signal.symbol, bar.timestamp) {
+                self.reject_signal(bar, signal, ReasonCode::Cooldown, notional);
+                continue;
+            }
+
+            // 1. Apply capital rule ($1000 default, or volatility-targeted)
             let raw_quantity = notional / bar.close;
             
             // 2. Apply symbol filters
             let quantity = self.apply_symbol_filters(raw_quantity, rules)?;
             let final_notional = quantity * bar.close;
 
+            if let Some(reason) = self.check_quantity_limits(quantity, rules) {
+                self.reject_signal(bar, signal, reason, final_notional);
+                continue;
+            }
+
             // 3. Check minimum notional requirement
             if final_notional < rules.min_notional {
-                self.rejected_trades.push(RejectedTrade {
-                    timestamp: bar.timestamp,
-                    symbol: signal.symbol.clone(),
-                    side: signal.side.clone(),
-                    reason: "Rejected – NotionalMin".to_string(),
-                    notional: final_notional,
-                });
+                self.reject_signal(bar, signal, ReasonCode::NotionalMin, final_notional);
                 continue;
             }
 
+            if let Some(limit) = self.config.capital_limit {
+                let (open_notional, _) = self.open_notional();
+                if open_notional + final_notional > limit {
+                    self.reject_signal(bar, signal, ReasonCode::InsufficientCapital, final_notional);
+                    continue;
+                }
+            }
+
             // 4. Calculate entry execution price
             let entry_price = self.calculate_entry_price(
                 bar,
@@ -109,14 +709,16 @@ impl TradeTableGenerator {
                 rules,
             )?;
 
-            // 5. Calculate entry fee
-            let entry_fee = self.calculate_fee(quantity, entry_price, rules)?;
+            // 5. Calculate entry fee and the slippage paid against the unslipped price
+            let entry_fee = self.calculate_fee(quantity, entry_price, Liquidity::of_entry(signal), rules)?;
+            let base_price = entry_base_price(bar, intrabar_policy, self.config.fill_model);
+            let entry_slippage_usd = match trade_type {
+                TradeType::Long => entry_price - base_price,
+                TradeType::Short => base_price - entry_price,
+            } * quantity;
 
             // 6. Create active position
-            let trade_type = match signal.side {
-                TradeSide::Buy => TradeType::Long,
-                TradeSide::Sell => TradeType::Short,
-            };
+            let liquidation_price = self.liquidation_price(&trade_type, entry_price, rules);
 
             let position = ActivePosition {
                 symbol: signal.symbol.clone(),
@@ -127,41 +729,244 @@ impl TradeTableGenerator {
                 take_profit: signal.take_profit,
                 stop_loss: signal.stop_loss,
                 time_to_live: signal.time_to_live,
+                bars_held: 0,
                 entry_fee,
-                size_usd: self.default_size_usd,
+                entry_slippage_usd,
+                entry_outside_bar: outside_bar(entry_price, bar),
+                size_usd: notional,
+                liquidation_price,
+                tags: signal.tags.clone(),
+                rule_tag: signal.rule_tag.clone(),
             };
 
-            self.active_positions.insert(signal.symbol.clone(), position);
+            self.active_positions.insert(key, position);
+            self.last_entries.insert(signal.symbol.clone(), (bar.timestamp, self.symbol_bars(&signal.symbol)));
             debug!("Created position for symbol: {}", signal.symbol);
         }
 
         Ok(())
     }
 
-    /// Process exits for existing positions
+    /// Signals that can enter on `bar`
+    /// 
+    /// Under `OneSecondBars` a limit entry (one with an `entry_price`) fills only once the
+    /// bar's one-second path reaches it, so those the path never reaches are dropped here
+    /// as the simulator drops them. Other policies, and entries deferred to the next
+    /// bar's open, take every signal.
+    fn reachable_entries<'a>(
+        &self,
+        bar: &Bar,
+        signals: &'a [StrategySignal],
+        intrabar_policy: &IntrabarPolicy,
+    ) -> Cow<'a, [StrategySignal]> {
+        let limits_apply = matches!(intrabar_policy, IntrabarPolicy::OneSecondBars)
+            && self.config.fill_model == FillModel::SameBar;
+        if !limits_apply || signals.iter().all(|signal| signal.entry_price.is_none()) {
+            return Cow::Borrowed(signals);
+        }
+
+        let path = one_second_path(bar, self.bar_interval_ms);
+        Cow::Owned(signals.iter()
+            .filter(|signal| {
+                let reached = signal_sub_step(&path, signal).is_some();
+                if !reached {
+                    debug!("Entry price {:?} not reached within bar at {}", signal.entry_price, bar.timestamp);
+                }
+                reached
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Apply `overlapping_entries` to a bar's signals
+    /// 
+    /// Returns the signals left to enter, each with the number of signals it stands for,
+    /// in emission order; dropped signals are recorded as rejections.
+    fn resolve_overlapping_entries(&mut self, bar: &Bar, signals: &[StrategySignal]) -> Vec<(StrategySignal, u32)> {
+        let (resolved, dropped) = resolve_overlapping(signals, self.config.overlapping_entries);
+        for signal in dropped {
+            let notional = self.entry_notional(&signal.symbol);
+            self.reject_signal(bar, signal, ReasonCode::OverlappingEntry, notional);
+        }
+        resolved
+    }
+
+    /// Whether an entry on `symbol` at `timestamp` falls inside the entry cooldown
+    fn in_cooldown(&self, symbol: &str, timestamp: u64) -> bool {
+        let (Some(cooldown), Some(&(last_time, last_bar))) = (self.config.entry_cooldown, self.last_entries.get(symbol)) else {
+            return false;
+        };
+        match cooldown {
+            EntryCooldown::Duration(ms) => timestamp < last_time + ms,
+            EntryCooldown::Bars(n) => self.symbol_bars(symbol) - last_bar < u64::from(n),
+        }
+    }
+
+    /// Bars of `symbol` processed so far
+    fn symbol_bars(&self, symbol: &str) -> u64 {
+        self.bars_seen.get(symbol).copied().unwrap_or(0)
+    }
+
+    /// Take over a position opened before the run
+    /// 
+    /// It exits like any entry would, and its trade record measures PnL from the
+    /// position's own `entry_price` and `entry_fee`. Replaces any position already in
+    /// its slot.
+    pub fn seed_position(&mut self, position: ActivePosition) {
+        let key = self.position_key(&position.symbol, &position.trade_type);
+        self.active_positions.insert(key, position);
+    }
+
+    /// Notional of one entry under `SizingMode::FixedNotional`
+    pub fn default_size_usd(&self) -> Decimal {
+        self.default_size_usd
+    }
+
+    /// Whether each symbol holds one position or a long and a short leg
+    pub fn position_mode(&self) -> PositionMode {
+        self.config.position_mode
+    }
+
+    /// What happens to slipped fills beyond their bar's low/high
+    pub fn fill_range_policy(&self) -> FillRangePolicy {
+        self.config.fill_range_policy
+    }
+
+    /// Which of several same-bar entries for one symbol and side is taken
+    pub fn overlapping_entries(&self) -> OverlappingEntryPolicy {
+        self.config.overlapping_entries
+    }
+
+    /// Key of the position slot a new entry would occupy
+    pub(crate) fn position_key(&self, symbol: &str, trade_type: &TradeType) -> String {
+        match self.config.position_mode {
+            PositionMode::OneWay => symbol.to_string(),
+            PositionMode::Hedge => format!("{}:{:?}", symbol, trade_type),
+        }
+    }
+
+    /// Gross and net notional of open positions at their entry prices
+    /// 
+    /// Gross adds every leg; net counts longs positive and shorts negative, so a fully
+    /// hedged symbol contributes its two legs to gross and nothing to net.
+    pub fn open_notional(&self) -> (Decimal, Decimal) {
+        self.active_positions.values().fold((dec!(0.0), dec!(0.0)), |(gross, net), position| {
+            let notional = position.entry_price * position.quantity;
+            match position.trade_type {
+                TradeType::Long => (gross + notional, net + notional),
+                TradeType::Short => (gross + notional, net - notional),
+            }
+        })
+    }
+
+    /// Notional for a new entry on `symbol` under the configured sizing mode
+    fn entry_notional(&self, symbol: &str) -> Decimal {
+        let annual_vol_target = match self.config.sizing_mode {
+            SizingMode::FixedNotional => return self.default_size_usd,
+            SizingMode::PercentEquity { fraction } => return self.current_equity * fraction,
+            SizingMode::VolatilityTarget { annual_vol_target, .. } => annual_vol_target,
+        };
+        
+        match self.realized_annual_vol(symbol) {
+            Some(vol) => {
+                let cap = self.current_equity * self.config.leverage;
+                if vol > Decimal::ZERO {
+                    (self.current_equity * annual_vol_target / vol).min(cap)
+                } else {
+                    cap
+                }
+            }
+            None => self.default_size_usd,
+        }
+    }
+
+    /// Annualized standard deviation of `symbol`'s recent close-to-close returns
+    /// 
+    /// The rolling standard deviation indicator's calculation, run over returns rather
+    /// than closes.
+    fn realized_annual_vol(&self, symbol: &str) -> Option<Decimal> {
+        let lookback = match self.config.sizing_mode {
+            SizingMode::VolatilityTarget { lookback, .. } => lookback,
+            SizingMode::FixedNotional | SizingMode::PercentEquity { .. } => return None,
+        };
+        let closes = self.recent_closes.get(symbol)?;
+        if lookback < 2 || closes.len() < lookback + 1 {
+            return None;
+        }
+        
+        let returns: Vec<Decimal> = closes.iter()
+            .zip(closes.iter().skip(1))
+            .filter(|((_, prev), _)| *prev > Decimal::ZERO)
+            .map(|((_, prev), (_, close))| *close / *prev - dec!(1.0))
+            .collect();
+        let std = *rolling_std(&returns, lookback).last()?;
+        
+        let (first, _) = closes.front()?;
+        let (last, _) = closes.back()?;
+        let interval_ms = last.saturating_sub(*first) / lookback as u64;
+        if interval_ms == 0 {
+            return None;
+        }
+        // Spacing that is no whole timeframe can only be annualized over calendar time
+        let periods_per_year = Timeframe::from_millis(interval_ms)
+            .map(|timeframe| timeframe.periods_per_year_with(self.config.annualization))
+            .unwrap_or_else(|| periods_per_year_for_interval(interval_ms));
+        let periods_per_year = Decimal::from_f64(periods_per_year)?;
+        
+        Some(std * decimal_sqrt(periods_per_year))
+    }
+
+    /// Reject every entry signal of a bar that falls in the warmup period
+    pub(crate) fn reject_warmup_signals(&mut self, bar: &Bar, signals: &[StrategySignal]) {
+        for signal in signals {
+            let notional = self.entry_notional(&signal.symbol);
+            self.reject_signal(bar, signal, ReasonCode::Warmup, notional);
+        }
+    }
+
+    /// Record a signal that could not be turned into a position
+    fn reject_signal(&mut self, bar: &Bar, signal: &StrategySignal, reason: ReasonCode, notional: Decimal) {
+        self.rejected_trades.push(RejectedTrade {
+            timestamp: bar.timestamp,
+            symbol: signal.symbol.clone(),
+            side: signal.side.clone(),
+            reason,
+            notional,
+        });
+    }
+
+    /// Process exits for `symbol`'s existing positions against its `bar`
     fn process_exits(
         &mut self,
+        symbol: &str,
         bar: &Bar,
         intrabar_policy: &IntrabarPolicy,
         slippage_mode: &SlippageMode,
         rules: &ExchangeRules,
     ) -> Result<()> {
-        let mut positions_to_close = Vec::new();
+        let mut exits_due = std::mem::take(&mut self.exits_due);
 
-        for (symbol, position) in &self.active_positions {
+        for position in self.active_positions.values_mut().filter(|position| position.symbol == symbol) {
+            if bar.timestamp > position.entry_time {
+                position.bars_held += 1;
+            }
+        }
+
+        for (key, position) in self.active_positions.iter().filter(|(_, position)| position.symbol == symbol) {
             // Check for TP/SL hits using first-touch logic
-            if let Some(exit_info) = self.check_exit_conditions(bar, position)? {
-                positions_to_close.push((symbol.clone(), exit_info));
+            if let Some(exit_info) = self.check_exit_conditions(bar, position, intrabar_policy)? {
+                exits_due.push((key.clone(), exit_info));
             }
         }
 
         // Close positions and create trade records
-        for (symbol, exit_info) in positions_to_close {
-            if let Some(position) = self.active_positions.remove(&symbol) {
+        for (key, exit_info) in exits_due.drain(..) {
+            if let Some(position) = self.active_positions.remove(&key) {
                 self.create_trade_record(position, exit_info, bar, slippage_mode, rules)?;
             }
         }
 
+        self.exits_due = exits_due;
         Ok(())
     }
 
@@ -170,14 +975,28 @@ impl TradeTableGenerator {
         &self,
         bar: &Bar,
         position: &ActivePosition,
+        intrabar_policy: &IntrabarPolicy,
     ) -> Result<Option<ExitInfo>> {
         let mut exit_candidates = Vec::new();
 
+        // Lowest and highest prices the assumed intrabar path trades through
+        let mut path = bar_path(bar, intrabar_policy, self.config.intrabar_path);
+        if position.entry_time == bar.timestamp {
+            // Opened on this bar: only the path from the entry on can hit its exits
+            if let Some(path) = &mut path {
+                path.skip(entry_waypoint(intrabar_policy, self.config.fill_model, path.len()));
+            }
+        }
+        let (low, high) = match &path {
+            Some(path) => path.iter().fold((bar.close, bar.close), |(low, high), &price| (low.min(price), high.max(price))),
+            None => (bar.low, bar.high),
+        };
+
         // Check Take Profit
         if let Some(tp) = position.take_profit {
             let hit_tp = match position.trade_type {
-                TradeType::Long => bar.high >= tp,
-                TradeType::Short => bar.low <= tp,
+                TradeType::Long => high >= tp,
+                TradeType::Short => low <= tp,
             };
             if hit_tp {
                 exit_candidates.push(ExitInfo {
@@ -192,8 +1011,8 @@ impl TradeTableGenerator {
         // Check Stop Loss
         if let Some(sl) = position.stop_loss {
             let hit_sl = match position.trade_type {
-                TradeType::Long => bar.low <= sl,
-                TradeType::Short => bar.high >= sl,
+                TradeType::Long => low <= sl,
+                TradeType::Short => high >= sl,
             };
             if hit_sl {
                 exit_candidates.push(ExitInfo {
@@ -205,9 +1024,29 @@ impl TradeTableGenerator {
             }
         }
 
+        // Check liquidation
+        if let Some(liquidation) = position.liquidation_price {
+            let hit_liquidation = match position.trade_type {
+                TradeType::Long => low <= liquidation,
+                TradeType::Short => high >= liquidation,
+            };
+            if hit_liquidation {
+                exit_candidates.push(ExitInfo {
+                    exit_price: liquidation,
+                    exit_time: bar.timestamp,
+                    exit_reason: ExitReason::Liquidation,
+                    hit_tp_sl: HitTpSl::None,
+                });
+            }
+        }
+
         // Check timeout
         if let Some(ttl) = position.time_to_live {
-            if bar.timestamp >= position.entry_time + ttl {
+            let expired = match ttl {
+                TimeToLive::Duration(ms) => bar.timestamp >= position.entry_time + ms,
+                TimeToLive::Bars(n) => position.bars_held >= n,
+            };
+            if expired {
                 exit_candidates.push(ExitInfo {
                     exit_price: bar.close, // Use close price for timeout
                     exit_time: bar.timestamp,
@@ -217,10 +1056,8 @@ impl TradeTableGenerator {
             }
         }
 
-        // Return the first exit condition that was hit
-        // In a real implementation, you'd need to determine which was hit first
-        // based on the intrabar policy and actual price movement
-        Ok(exit_candidates.first().cloned())
+        // Several conditions can trigger on the same bar; pick one deterministically
+        Ok(resolve_exit(&exit_candidates, path.as_deref(), position))
     }
 
     /// Create a trade record from a closed position
@@ -232,82 +1069,191 @@ impl TradeTableGenerator {
         slippage_mode: &SlippageMode,
         rules: &ExchangeRules,
     ) -> Result<()> {
-        // Calculate exit fee
-        let exit_fee = self.calculate_fee(position.quantity, exit_info.exit_price, rules)?;
+        // TP/SL rest at their trigger price and end-of-data is a mark, not a fill;
+        // market-style closes pay slippage like entries
+        let exit_price = match exit_info.exit_reason {
+            ExitReason::TakeProfit | ExitReason::StopLoss | ExitReason::EndOfData => exit_info.exit_price,
+            ExitReason::Timeout | ExitReason::StrategyExit | ExitReason::Liquidation | ExitReason::Flatten => {
+                let closing_side = match position.trade_type {
+                    TradeType::Long => TradeSide::Sell,
+                    TradeType::Short => TradeSide::Buy,
+                };
+                self.apply_bar_slippage(exit_info.exit_price, &closing_side, slippage_mode, bar, rules)?
+            }
+        };
+
+        // Calculate exit fee; take profits rest on the book and earn the maker rate
+        let liquidity = match exit_info.exit_reason {
+            ExitReason::TakeProfit => Liquidity::Maker,
+            _ => Liquidity::Taker,
+        };
+        let exit_fee = self.calculate_fee(position.quantity, exit_price, liquidity, rules)?;
         let total_fees = position.entry_fee + exit_fee;
 
         // Calculate PnL
         let pnl_usd = match position.trade_type {
             TradeType::Long => {
-                (exit_info.exit_price - position.entry_price) * position.quantity - total_fees
+                (exit_price - position.entry_price) * position.quantity - total_fees
             }
             TradeType::Short => {
-                (position.entry_price - exit_info.exit_price) * position.quantity - total_fees
+                (position.entry_price - exit_price) * position.quantity - total_fees
             }
         };
 
-        let pnl_pct = pnl_usd / position.size_usd;
+        let pnl_pct = Percent::from_ratio(pnl_usd / position.size_usd);
+
+        // Slippage the exit paid against the trade, per unit
+        let exit_slippage = match position.trade_type {
+            TradeType::Long => exit_info.exit_price - exit_price,
+            TradeType::Short => exit_price - exit_info.exit_price,
+        };
+        let breakeven_price = breakeven_price(&position.trade_type, position.entry_price, position.quantity, total_fees, exit_slippage);
 
         // Convert timestamps to ISO UTC strings
         let entry_time_utc = self.timestamp_to_iso_utc(position.entry_time);
         let exit_time_utc = self.timestamp_to_iso_utc(exit_info.exit_time);
         let date = exit_time_utc.split('T').next().unwrap_or(&exit_time_utc).to_string();
+        let fill_outside_bar = position.entry_outside_bar || outside_bar(exit_price, bar);
 
         let trade_record = TradeRecord {
             date,
             trade_type: position.trade_type,
             entry_price: position.entry_price,
             entry_time_utc,
-            exit_price: exit_info.exit_price,
+            exit_price,
             exit_time_utc,
             exit_reason: exit_info.exit_reason,
             hit_tp_sl: exit_info.hit_tp_sl,
             size_usd: position.size_usd,
             qty: position.quantity,
             fees_usd: total_fees,
+            slippage_usd: position.entry_slippage_usd + exit_slippage * position.quantity,
             pnl_usd,
             pnl_pct,
+            breakeven_price,
+            fill_outside_bar,
             symbol: position.symbol,
+            tags: position.tags,
+            rule_tag: position.rule_tag,
         };
 
+        debug!("Created trade record for symbol: {}", trade_record.symbol);
+        self.realized_pnl += trade_record.pnl_usd;
         self.trade_records.push(trade_record);
-        debug!("Created trade record for symbol: {}", position.symbol);
 
         Ok(())
     }
 
+    /// Calculate the isolated-margin liquidation price for a new position
+    /// 
+    /// Margin is `1 / leverage` of notional; the position is liquidated once the
+    /// adverse move leaves only the maintenance margin. Unleveraged positions
+    /// cannot be liquidated.
+    fn liquidation_price(&self, trade_type: &TradeType, entry_price: Decimal, rules: &ExchangeRules) -> Option<Decimal> {
+        if self.config.leverage <= dec!(1.0) {
+            return None;
+        }
+
+        let margin_buffer = dec!(1.0) / self.config.leverage - rules.maintenance_margin_rate;
+        let liquidation = match trade_type {
+            TradeType::Long => entry_price * (dec!(1.0) - margin_buffer),
+            TradeType::Short => entry_price * (dec!(1.0) + margin_buffer),
+        };
+
+        Some((liquidation / rules.tick_size).round() * rules.tick_size)
+    }
+
     /// Apply symbol filters (tick size, quantity step, etc.)
-    fn apply_symbol_filters(&self, quantity: Decimal, rules: &ExchangeRules) -> Result<Decimal> {
+    pub fn apply_symbol_filters(&self, quantity: Decimal, rules: &ExchangeRules) -> Result<Decimal> {
         // Quantize quantity to lot size
-        let quantized = (quantity / rules.lot_size).round() * rules.lot_size;
+        let lots = quantity / rules.lot_size;
+        let quantized = match self.config.qty_rounding {
+            QtyRoundingMode::Nearest => lots.round(),
+            QtyRoundingMode::Floor => lots.floor(),
+        } * rules.lot_size;
+
+        // Oversized orders are capped to the largest whole number of lots allowed
+        if self.config.max_qty_policy == MaxQtyPolicy::Cap && quantized > rules.max_qty {
+            return Ok((rules.max_qty / rules.lot_size).floor() * rules.lot_size);
+        }
+
         Ok(quantized)
     }
 
+    /// Check a filtered quantity against the exchange's order size limits
+    /// 
+    /// Returns the rejection reason, if any. Lot-size quantization can round a
+    /// small order down to nothing, which is reported separately from `min_qty`.
+    fn check_quantity_limits(&self, quantity: Decimal, rules: &ExchangeRules) -> Option<ReasonCode> {
+        if quantity <= dec!(0.0) {
+            return Some(ReasonCode::ZeroQtyAfterRounding);
+        }
+
+        if quantity < rules.min_qty {
+            return Some(ReasonCode::MinQty);
+        }
+
+        if quantity > rules.max_qty {
+            return Some(ReasonCode::MaxQty);
+        }
+
+        None
+    }
+
     /// Calculate entry execution price based on intrabar policy
     fn calculate_entry_price(
-        &self,
+        &mut self,
         bar: &Bar,
         side: &TradeSide,
         intrabar_policy: &IntrabarPolicy,
         slippage_mode: &SlippageMode,
         rules: &ExchangeRules,
     ) -> Result<Decimal> {
-        let base_price = match intrabar_policy {
-            IntrabarPolicy::ExactTrades => bar.close, // Use strategy's chosen price
-            IntrabarPolicy::OneSecondBars => bar.open, // Use bar open
-            IntrabarPolicy::LinearInterpolation => bar.open, // Start with open
-        };
-
-        self.apply_slippage(base_price, side, slippage_mode, rules)
+        let base_price = entry_base_price(bar, intrabar_policy, self.config.fill_model);
+        self.apply_bar_slippage(base_price, side, slippage_mode, bar, rules)
     }
 
     /// Apply slippage to execution price
-    fn apply_slippage(
-        &self,
+    /// 
+    /// Buys fill above and sells below `base_price`, then the price is rounded to the
+    /// nearest tick. `SlippageMode::SyntheticBook` assumes a bar of
+    /// [`SYNTHETIC_BOOK_REFERENCE_TRADES`] trades; see [`Self::apply_bar_slippage`].
+    pub fn apply_slippage(
+        &mut self,
         base_price: Decimal,
         side: &TradeSide,
         slippage_mode: &SlippageMode,
         rules: &ExchangeRules,
+    ) -> Result<Decimal> {
+        self.slipped_price(base_price, side, slippage_mode, SYNTHETIC_BOOK_REFERENCE_TRADES, rules)
+    }
+
+    /// [`Self::apply_slippage`] for a fill inside `bar`, scaling synthetic book
+    /// slippage by the bar's trade count
+    /// 
+    /// Under [`FillRangePolicy::Clamp`] the fill is kept within the bar's low and high.
+    pub fn apply_bar_slippage(
+        &mut self,
+        base_price: Decimal,
+        side: &TradeSide,
+        slippage_mode: &SlippageMode,
+        bar: &Bar,
+        rules: &ExchangeRules,
+    ) -> Result<Decimal> {
+        let price = self.slipped_price(base_price, side, slippage_mode, bar.trade_count, rules)?;
+        Ok(match self.config.fill_range_policy {
+            FillRangePolicy::Clamp => price.clamp(bar.low, bar.high),
+            FillRangePolicy::Flag => price,
+        })
+    }
+
+    fn slipped_price(
+        &mut self,
+        base_price: Decimal,
+        side: &TradeSide,
+        slippage_mode: &SlippageMode,
+        trade_count: u32,
+        rules: &ExchangeRules,
     ) -> Result<Decimal> {
         let slippage = match slippage_mode {
             SlippageMode::None => dec!(0.0),
@@ -315,10 +1261,9 @@ impl TradeTableGenerator {
                 let slippage_rate = dec!(0.0001); // 0.01%
                 base_price * slippage_rate
             }
-            SlippageMode::SyntheticBook => {
-                let slippage_rate = dec!(0.0005); // 0.05%
-                base_price * slippage_rate
-            }
+            SlippageMode::SyntheticBook => base_price * synthetic_book_rate(trade_count),
+            SlippageMode::FixedTicks(ticks) => rules.tick_size * Decimal::from(*ticks),
+            SlippageMode::Stochastic { mean, stddev } => base_price * self.slippage_sampler.next_rate(*mean, *stddev),
         };
 
         let execution_price = match side {
@@ -332,21 +1277,19 @@ impl TradeTableGenerator {
     }
 
     /// Calculate trading fees
-    fn calculate_fee(&self, quantity: Decimal, price: Decimal, rules: &ExchangeRules) -> Result<Decimal> {
-        let notional = quantity * price;
-        let fee_rate = rules.taker_fee; // Assume taker for simplicity
-        let fee = notional * fee_rate;
-        
-        // Quantize fee to precision
-        let precision = Decimal::from(10_u64.pow(rules.precision_price as u32));
-        let quantized = (fee * precision).round() / precision;
+    /// 
+    /// The fee `rules.fee_basis` calls for, rounded to `rules.precision_price` decimals.
+    /// On a notional basis a negative maker rate yields a negative fee (a rebate).
+    pub fn calculate_fee(&self, quantity: Decimal, price: Decimal, liquidity: Liquidity, rules: &ExchangeRules) -> Result<Decimal> {
+        let fee = rules.fee(quantity, price, liquidity);
         
-        Ok(quantized)
+        // Quantize fee to precision in the exchange's rounding direction
+        Ok(round_to_precision(fee, rules.precision_price, rules.fee_rounding))
     }
 
     /// Convert Unix timestamp to ISO UTC string
     fn timestamp_to_iso_utc(&self, timestamp: u64) -> String {
-        use std::time::{SystemTime, UNIX_EPOCH};
+        use std::time::UNIX_EPOCH;
         
         let duration = std::time::Duration::from_millis(timestamp);
         let datetime = UNIX_EPOCH + duration;
@@ -355,14 +1298,123 @@ impl TradeTableGenerator {
         format!("{}", chrono::DateTime::<chrono::Utc>::from(datetime).format("%Y-%m-%dT%H:%M:%S%.3fZ"))
     }
 
+    /// Close whatever is still open after the final bar
+    /// 
+    /// Each remaining position becomes an `EndOfData` trade at `last_bar.close`, in
+    /// position-key order. Does nothing when `close_at_end_of_data` is off. Entries still
+    /// waiting on a next bar under `FillModel::NextBarOpen` are dropped.
+    pub fn finalize(&mut self, last_bar: &Bar, slippage_mode: &SlippageMode, rules: &ExchangeRules) -> Result<()> {
+        self.finalize_positions(None, last_bar, slippage_mode, rules)
+    }
+
+    /// [`Self::finalize`] for `symbol` alone, whose data ran out at `last_bar`
+    /// 
+    /// For runs that step several symbols in time order: the others keep their
+    /// positions and pending entries.
+    pub fn finalize_symbol(&mut self, symbol: &str, last_bar: &Bar, slippage_mode: &SlippageMode, rules: &ExchangeRules) -> Result<()> {
+        self.finalize_positions(Some(symbol), last_bar, slippage_mode, rules)
+    }
+
+    /// [`Self::finalize`] limited to `symbol`'s positions and entries when one is given
+    fn finalize_positions(
+        &mut self,
+        symbol: Option<&str>,
+        last_bar: &Bar,
+        slippage_mode: &SlippageMode,
+        rules: &ExchangeRules,
+    ) -> Result<()> {
+        let pending = self.pending_entries.len();
+        self.pending_entries.retain(|signal| symbol.is_some_and(|symbol| signal.symbol != symbol));
+        if self.pending_entries.len() < pending {
+            debug!("Dropping {} entries signalled on the last bar", pending - self.pending_entries.len());
+        }
+        if !self.config.close_at_end_of_data {
+            return Ok(());
+        }
+        self.close_positions(symbol, last_bar, ExitReason::EndOfData, slippage_mode, rules)
+    }
+
+    /// Close every open position at `bar.close`, regardless of TP/SL or signals
+    /// 
+    /// For event-driven exits such as a session-end flatten or a halt. Each position
+    /// becomes a trade with `reason`, in position-key order; market-style reasons pay
+    /// slippage on the close as usual.
+    pub fn flatten_all(
+        &mut self,
+        bar: &Bar,
+        reason: ExitReason,
+        slippage_mode: &SlippageMode,
+        rules: &ExchangeRules,
+    ) -> Result<()> {
+        self.close_positions(None, bar, reason, slippage_mode, rules)
+    }
+
+    /// [`Self::flatten_all`] limited to `symbol`'s positions when one is given
+    fn close_positions(
+        &mut self,
+        symbol: Option<&str>,
+        bar: &Bar,
+        reason: ExitReason,
+        slippage_mode: &SlippageMode,
+        rules: &ExchangeRules,
+    ) -> Result<()> {
+        let mut keys: Vec<String> = self.active_positions.iter()
+            .filter(|(_, position)| symbol.is_none_or(|symbol| position.symbol == symbol))
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort();
+        for key in keys {
+            if let Some(position) = self.active_positions.remove(&key) {
+                let exit_info = ExitInfo {
+                    exit_price: bar.close,
+                    exit_time: bar.timestamp,
+                    exit_reason: reason.clone(),
+                    hit_tp_sl: HitTpSl::None,
+                };
+                self.create_trade_record(position, exit_info, bar, slippage_mode, rules)?;
+            }
+        }
+        self.update_equity_and_drawdown();
+
+        Ok(())
+    }
+
+    /// Open entries on their signal's bar or at the next bar's open
+    pub fn set_fill_model(&mut self, fill_model: FillModel) {
+        self.config.fill_model = fill_model;
+    }
+
+    /// Length of the bars being processed, which sets the one-second path limit entries
+    /// must reach under `OneSecondBars`; the simulator sets it every bar (default 1m)
+    pub fn set_bar_interval_ms(&mut self, bar_interval_ms: u64) {
+        self.bar_interval_ms = bar_interval_ms;
+    }
+
+    /// Seed the `SlippageMode::Stochastic` draws, restarting their sequence
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.slippage_sampler = SlippageSampler::new(seed);
+    }
+
+    /// Exchange the sampler exits draw from with `sampler`, for symbols drawing their own
+    pub(crate) fn swap_slippage_sampler(&mut self, sampler: &mut SlippageSampler) {
+        std::mem::swap(&mut self.slippage_sampler, sampler);
+    }
+
+    /// Positions still open, keyed by symbol (and leg in hedge mode)
+    pub fn active_positions(&self) -> &HashMap<String, ActivePosition> {
+        &self.active_positions
+    }
+
+    /// PnL of the trades closed so far, net of their fees
+    pub fn realized_pnl(&self) -> Decimal {
+        self.realized_pnl
+    }
+
     /// Update equity and drawdown tracking
     fn update_equity_and_drawdown(&mut self) {
         // Calculate current equity based on open positions and realized PnL
-        let realized_pnl: Decimal = self.trade_records.iter()
-            .map(|trade| trade.pnl_usd)
-            .sum();
-        
-        self.current_equity = dec!(10000.0) + realized_pnl; // Starting equity + realized PnL
+        // (kept as a running total so each bar stays O(1) in the number of closed trades)
+        self.current_equity = self.config.initial_equity + self.realized_pnl;
         
         if self.current_equity > self.peak_equity {
             self.peak_equity = self.current_equity;
@@ -372,6 +1424,31 @@ impl TradeTableGenerator {
         if current_drawdown > self.max_drawdown {
             self.max_drawdown = current_drawdown;
         }
+
+        if let Some(limit) = self.config.risk_limits.max_drawdown_pct {
+            if !self.risk_halted && Percent::from_ratio(current_drawdown) >= limit {
+                warn!("Drawdown {}% reached the {}% limit; halting new entries", Percent::from_ratio(current_drawdown), limit);
+                self.risk_halted = true;
+            }
+        }
+
+        if let Some(limit) = self.config.risk_limits.daily_loss_limit {
+            let day_loss = self.day_start_pnl - self.realized_pnl;
+            if !self.daily_halted && day_loss >= limit {
+                warn!("Day {} lost ${} against a ${} limit; halting entries until midnight UTC", self.trading_day, day_loss, limit);
+                self.daily_halted = true;
+            }
+        }
+    }
+
+    /// Trade records closed so far, in close order
+    pub fn trade_records(&self) -> &[TradeRecord] {
+        &self.trade_records
+    }
+
+    /// Signals rejected so far, in bar order
+    pub fn rejected_trades(&self) -> &[RejectedTrade] {
+        &self.rejected_trades
     }
 
     /// Generate final trade table result
@@ -382,88 +1459,137 @@ impl TradeTableGenerator {
             trades: self.trade_records.clone(),
             summary,
             rejected_trades: self.rejected_trades.clone(),
+            summary_by_tag: self.summary_by_tag(),
         }
     }
 
+    /// [`Self::generate_result`] for `symbol`'s trades and rejections alone
+    /// 
+    /// Summaries cover only its trades, but `max_drawdown` stays the whole run's, as
+    /// every symbol trades one account.
+    pub fn generate_symbol_result(&self, symbol: &str) -> TradeTableResult {
+        let trades: Vec<&TradeRecord> = self.trade_records.iter()
+            .filter(|trade| trade.symbol == symbol)
+            .collect();
+        
+        TradeTableResult {
+            trades: trades.iter().map(|trade| (*trade).clone()).collect(),
+            summary: self.summarize(&trades),
+            rejected_trades: self.rejected_trades.iter()
+                .filter(|rejection| rejection.symbol == symbol)
+                .cloned()
+                .collect(),
+            summary_by_tag: self.tag_summaries(trades.iter().copied()),
+        }
+    }
+
+    /// Summary of the trades carrying each tag, keyed `key=value`
+    /// 
+    /// A trade with several tags counts toward each of them. `max_drawdown` is the
+    /// whole run's, since tagged trades share one equity curve.
+    pub fn summary_by_tag(&self) -> BTreeMap<String, TradeSummary> {
+        self.tag_summaries(&self.trade_records)
+    }
+
+    /// [`Self::summary_by_tag`] over `trades`
+    fn tag_summaries<'a>(&self, trades: impl IntoIterator<Item = &'a TradeRecord>) -> BTreeMap<String, TradeSummary> {
+        let mut groups: BTreeMap<String, Vec<&TradeRecord>> = BTreeMap::new();
+        for trade in trades {
+            for (key, value) in &trade.tags {
+                groups.entry(format!("{}={}", key, value)).or_default().push(trade);
+            }
+        }
+        
+        groups.into_iter()
+            .map(|(tag, trades)| (tag, self.summarize(&trades)))
+            .collect()
+    }
+
     /// Calculate trade summary statistics
     fn calculate_summary(&self) -> TradeSummary {
-        let total_trades = self.trade_records.len() as u32;
+        let trades: Vec<&TradeRecord> = self.trade_records.iter().collect();
+        self.summarize(&trades)
+    }
+
+    fn summarize(&self, trades: &[&TradeRecord]) -> TradeSummary {
+        let total_trades = trades.len() as u32;
         
         if total_trades == 0 {
             return TradeSummary {
                 total_trades: 0,
                 wins: 0,
                 losses: 0,
-                win_rate: dec!(0.0),
+                scratches: 0,
+                win_rate: Percent::ZERO,
                 net_pnl_usd: dec!(0.0),
                 avg_win_usd: dec!(0.0),
                 avg_loss_usd: dec!(0.0),
                 expectancy: dec!(0.0),
-                max_drawdown: self.max_drawdown,
+                max_drawdown: Percent::from_ratio(self.max_drawdown),
                 profit_factor: dec!(0.0),
                 avg_holding_time_hours: dec!(0.0),
+                total_fees_usd: dec!(0.0),
+                total_slippage_usd: dec!(0.0),
             };
         }
 
-        let wins = self.trade_records.iter()
-            .filter(|trade| trade.pnl_usd > dec!(0.0))
-            .count() as u32;
-        
-        let losses = total_trades - wins;
+        let outcome_pnl = |outcome: TradeOutcome| -> Vec<Decimal> {
+            trades.iter()
+                .filter(|trade| self.config.breakeven_band.classify(trade) == outcome)
+                .map(|trade| trade.pnl_usd)
+                .collect()
+        };
+        let winning_pnl = outcome_pnl(TradeOutcome::Win);
+        let losing_pnl = outcome_pnl(TradeOutcome::Loss);
+
+        let wins = winning_pnl.len() as u32;
+        let losses = losing_pnl.len() as u32;
+        let scratches = total_trades - wins - losses;
         
-        let win_rate = if total_trades > 0 {
-            Decimal::from(wins) / Decimal::from(total_trades) * dec!(100.0)
+        let win_rate = if wins + losses > 0 {
+            Percent::from_ratio(Decimal::from(wins) / Decimal::from(wins + losses))
         } else {
-            dec!(0.0)
+            Percent::ZERO
         };
 
-        let net_pnl_usd: Decimal = self.trade_records.iter()
+        let net_pnl_usd: Decimal = trades.iter()
             .map(|trade| trade.pnl_usd)
             .sum();
 
-        let winning_trades: Vec<&TradeRecord> = self.trade_records.iter()
-            .filter(|trade| trade.pnl_usd > dec!(0.0))
-            .collect();
-        
-        let losing_trades: Vec<&TradeRecord> = self.trade_records.iter()
-            .filter(|trade| trade.pnl_usd <= dec!(0.0))
-            .collect();
-
         let avg_win_usd = if wins > 0 {
-            winning_trades.iter()
-                .map(|trade| trade.pnl_usd)
-                .sum::<Decimal>() / Decimal::from(wins)
+            winning_pnl.iter().sum::<Decimal>() / Decimal::from(wins)
         } else {
             dec!(0.0)
         };
 
         let avg_loss_usd = if losses > 0 {
-            losing_trades.iter()
-                .map(|trade| trade.pnl_usd)
-                .sum::<Decimal>() / Decimal::from(losses)
+            losing_pnl.iter().sum::<Decimal>() / Decimal::from(losses)
         } else {
             dec!(0.0)
         };
 
-        let expectancy = (win_rate / dec!(100.0)) * avg_win_usd + 
-                        (dec!(1.0) - win_rate / dec!(100.0)) * avg_loss_usd;
+        // Average over every trade, so scratches dilute it as they dilute real returns
+        let expectancy = net_pnl_usd / Decimal::from(total_trades);
 
-        let gross_profit: Decimal = winning_trades.iter()
-            .map(|trade| trade.pnl_usd)
+        // Gross figures use every trade's sign; scratch PnL is small but real money
+        let gross_profit: Decimal = trades.iter()
+            .map(|trade| trade.pnl_usd.max(dec!(0.0)))
             .sum();
         
-        let gross_loss: Decimal = losing_trades.iter()
-            .map(|trade| trade.pnl_usd.abs())
+        let gross_loss: Decimal = trades.iter()
+            .map(|trade| (-trade.pnl_usd).max(dec!(0.0)))
             .sum();
 
         let profit_factor = if gross_loss > dec!(0.0) {
             gross_profit / gross_loss
+        } else if gross_profit > dec!(0.0) {
+            PROFIT_FACTOR_NO_LOSSES
         } else {
             dec!(0.0)
         };
 
         // Calculate average holding time
-        let total_holding_time_ms: u64 = self.trade_records.iter()
+        let total_holding_time_ms: u64 = trades.iter()
             .map(|trade| {
                 let entry_time = self.iso_utc_to_timestamp(&trade.entry_time_utc);
                 let exit_time = self.iso_utc_to_timestamp(&trade.exit_time_utc);
@@ -481,14 +1607,17 @@ impl TradeTableGenerator {
             total_trades,
             wins,
             losses,
+            scratches,
             win_rate,
             net_pnl_usd,
             avg_win_usd,
             avg_loss_usd,
             expectancy,
-            max_drawdown: self.max_drawdown,
+            max_drawdown: Percent::from_ratio(self.max_drawdown),
             profit_factor,
             avg_holding_time_hours,
+            total_fees_usd: trades.iter().map(|trade| trade.fees_usd).sum(),
+            total_slippage_usd: trades.iter().map(|trade| trade.slippage_usd).sum(),
         }
     }
 
@@ -500,6 +1629,198 @@ impl TradeTableGenerator {
     }
 }
 
+/// Apply `policy` to signals sharing a symbol and side
+/// 
+/// Returns the signals kept, each with the number of signals it stands for, in emission
+/// order, and the signals dropped.
+pub(crate) fn resolve_overlapping(
+    signals: &[StrategySignal],
+    policy: OverlappingEntryPolicy,
+) -> (Vec<(StrategySignal, u32)>, Vec<&StrategySignal>) {
+    let mut groups: Vec<Vec<&StrategySignal>> = Vec::new();
+    for signal in signals {
+        let is_buy = matches!(signal.side, TradeSide::Buy);
+        match groups.iter_mut().find(|group| group[0].symbol == signal.symbol && matches!(group[0].side, TradeSide::Buy) == is_buy) {
+            Some(group) => group.push(signal),
+            None => groups.push(vec![signal]),
+        }
+    }
+
+    let mut resolved = Vec::with_capacity(groups.len());
+    let mut dropped = Vec::new();
+    for group in groups {
+        if group.len() == 1 {
+            resolved.push((group[0].clone(), 1));
+            continue;
+        }
+
+        let kept = match policy {
+            OverlappingEntryPolicy::FirstWins => Some(0),
+            OverlappingEntryPolicy::LastWins => Some(group.len() - 1),
+            OverlappingEntryPolicy::Aggregate => {
+                let size = group.iter().map(|signal| signal.size).sum();
+                resolved.push((StrategySignal { size, ..group[0].clone() }, group.len() as u32));
+                continue;
+            }
+            OverlappingEntryPolicy::Reject => None,
+        };
+        for (i, signal) in group.into_iter().enumerate() {
+            if Some(i) == kept {
+                resolved.push((signal.clone(), 1));
+            } else {
+                dropped.push(signal);
+            }
+        }
+    }
+    (resolved, dropped)
+}
+
+/// Whether `price` lies outside the range `bar` traded in
+pub(crate) fn outside_bar(price: Decimal, bar: &Bar) -> bool {
+    price < bar.low || price > bar.high
+}
+
+/// Market price an entry fills at before slippage
+fn entry_base_price(bar: &Bar, intrabar_policy: &IntrabarPolicy, fill_model: FillModel) -> Decimal {
+    if fill_model == FillModel::NextBarOpen {
+        return bar.open;
+    }
+    match intrabar_policy {
+        IntrabarPolicy::ExactTrades => bar.close, // Use strategy's chosen price
+        IntrabarPolicy::OneSecondBars => bar.open, // Use bar open
+        IntrabarPolicy::LinearInterpolation => bar.open, // Start with open
+    }
+}
+
+/// Index of the waypoint of a `path_len` bar path that entries fill at
+/// 
+/// Matches [`entry_base_price`]: the close under `ExactTrades` on the signal's bar,
+/// otherwise the open.
+fn entry_waypoint(intrabar_policy: &IntrabarPolicy, fill_model: FillModel, path_len: usize) -> usize {
+    if fill_model == FillModel::NextBarOpen {
+        return 0;
+    }
+    match intrabar_policy {
+        IntrabarPolicy::ExactTrades => path_len - 1,
+        IntrabarPolicy::OneSecondBars | IntrabarPolicy::LinearInterpolation => 0,
+    }
+}
+
+/// Pick the exit that fires when several conditions trigger on the same bar
+/// 
+/// When the intrabar path is known (see [`bar_path`]) the level touched earliest on
+/// that path wins. Timeout exits at the close, the end of the path, so any TP/SL
+/// touched within the bar precedes it.
+/// 
+/// Without a path (`LinearInterpolation` over [`IntrabarPath::Range`]) the touch order
+/// is unknown and a conservative fixed priority applies instead: adverse exits
+/// (StopLoss or Liquidation) > Timeout > TakeProfit. The same priority breaks exact
+/// ties on a known path.
+/// 
+/// Stop and liquidation sit on the same side of the entry, so whichever is closer
+/// to the entry price is reached first; on equal distance liquidation wins.
+fn resolve_exit(
+    candidates: &[ExitInfo],
+    path: Option<&[Decimal]>,
+    position: &ActivePosition,
+) -> Option<ExitInfo> {
+    candidates.iter()
+        .min_by_key(|exit| {
+            let touch = path.map_or(Decimal::ZERO, |path| first_touch(path, exit));
+            let adverse_distance = match exit.exit_reason {
+                ExitReason::StopLoss | ExitReason::Liquidation => (exit.exit_price - position.entry_price).abs(),
+                _ => Decimal::ZERO,
+            };
+            (
+                touch,
+                exit_priority(&exit.exit_reason),
+                adverse_distance,
+                exit.exit_reason != ExitReason::Liquidation,
+            )
+        })
+        .cloned()
+}
+
+/// Conservative rank of an exit reason (lower fires first)
+fn exit_priority(reason: &ExitReason) -> u8 {
+    match reason {
+        ExitReason::StopLoss | ExitReason::Liquidation => 0,
+        ExitReason::Timeout => 1,
+        ExitReason::StrategyExit | ExitReason::Flatten => 2,
+        ExitReason::TakeProfit => 3,
+        ExitReason::EndOfData => 4,
+    }
+}
+
+/// Waypoints a bar is assumed to trade through, or `None` when only its range is known
+fn bar_path(bar: &Bar, intrabar_policy: &IntrabarPolicy, intrabar_path: IntrabarPath) -> Option<BarPath> {
+    let nearest_extreme_first = || if bar.high - bar.open < bar.open - bar.low {
+        BarPath::new([bar.open, bar.high, bar.low, bar.close], 4)
+    } else {
+        BarPath::new([bar.open, bar.low, bar.high, bar.close], 4)
+    };
+
+    match (intrabar_policy, intrabar_path) {
+        (IntrabarPolicy::ExactTrades | IntrabarPolicy::OneSecondBars, _) => Some(nearest_extreme_first()),
+        (IntrabarPolicy::LinearInterpolation, IntrabarPath::Range) => None,
+        (IntrabarPolicy::LinearInterpolation, IntrabarPath::OpenClose) => Some(BarPath::new([bar.open, bar.close, bar.close, bar.close], 2)),
+        (IntrabarPolicy::LinearInterpolation, IntrabarPath::NearestExtremeFirst) => Some(nearest_extreme_first()),
+    }
+}
+
+/// Waypoints of a [`bar_path`], held inline since exits are checked every bar
+#[derive(Debug, Clone, Copy)]
+struct BarPath {
+    waypoints: [Decimal; 4],
+    start: usize,
+    end: usize,
+}
+
+impl BarPath {
+    /// Path through the first `len` of `waypoints`
+    fn new(waypoints: [Decimal; 4], len: usize) -> Self {
+        Self { waypoints, start: 0, end: len }
+    }
+
+    /// Drop the first `waypoints` waypoints
+    fn skip(&mut self, waypoints: usize) {
+        self.start = (self.start + waypoints).min(self.end);
+    }
+}
+
+impl std::ops::Deref for BarPath {
+    type Target = [Decimal];
+
+    fn deref(&self) -> &[Decimal] {
+        &self.waypoints[self.start..self.end]
+    }
+}
+
+/// Position along `path` where an exit fires
+/// 
+/// Returned as `segment index + fraction of segment`, so 0 is the open and the last
+/// waypoint index the close.
+fn first_touch(path: &[Decimal], exit: &ExitInfo) -> Decimal {
+    let path_end = Decimal::from(path.len() - 1);
+    if exit.exit_reason == ExitReason::Timeout {
+        return path_end;
+    }
+
+    for (segment, leg) in path.windows(2).enumerate() {
+        let (from, to) = (leg[0], leg[1]);
+        if exit.exit_price >= from.min(to) && exit.exit_price <= from.max(to) {
+            let fraction = if to == from {
+                Decimal::ZERO
+            } else {
+                (exit.exit_price - from).abs() / (to - from).abs()
+            };
+            return Decimal::from(segment) + fraction;
+        }
+    }
+
+    path_end
+}
+
 /// Exit information for position closure
 #[derive(Debug, Clone)]
 struct ExitInfo {
@@ -521,9 +1842,150 @@ mod tests {
         assert!(generator.trade_records.is_empty());
     }
 
+    fn exit(reason: ExitReason, price: Decimal) -> ExitInfo {
+        let hit_tp_sl = match reason {
+            ExitReason::TakeProfit => HitTpSl::TakeProfit,
+            ExitReason::StopLoss => HitTpSl::StopLoss,
+            _ => HitTpSl::None,
+        };
+        ExitInfo {
+            exit_price: price,
+            exit_time: 1609459260000,
+            exit_reason: reason,
+            hit_tp_sl,
+        }
+    }
+
+    fn bar(open: Decimal, high: Decimal, low: Decimal, close: Decimal) -> Bar {
+        Bar {
+            timestamp: 1609459260000,
+            open,
+            high,
+            low,
+            close,
+            volume: dec!(1000.0),
+            trade_count: 100,
+        }
+    }
+
+    /// $1000 market buy on BTCUSDT with the given brackets
+    fn signal(take_profit: Option<Decimal>, stop_loss: Option<Decimal>) -> StrategySignal {
+        StrategySignal {
+            side: TradeSide::Buy,
+            size: dec!(1000.0),
+            entry_price: None,
+            take_profit,
+            stop_loss,
+            time_to_live: None,
+            rule_tag: None,
+            tags: Vec::new(),
+            symbol: "BTCUSDT".to_string(),
+        }
+    }
+
+    fn long_position() -> ActivePosition {
+        ActivePosition {
+            symbol: "BTCUSDT".to_string(),
+            trade_type: TradeType::Long,
+            entry_time: 1609459200000,
+            entry_price: dec!(100.0),
+            quantity: dec!(10.0),
+            take_profit: Some(dec!(105.0)),
+            stop_loss: Some(dec!(95.0)),
+            time_to_live: Some(TimeToLive::Duration(60000)),
+            bars_held: 0,
+            entry_fee: dec!(0.1),
+            entry_slippage_usd: dec!(0.0),
+            entry_outside_bar: false,
+            size_usd: dec!(1000.0),
+            liquidation_price: None,
+            tags: Vec::new(),
+            rule_tag: None,
+        }
+    }
+
+    /// Resolve exits for `long_position()` with the default intrabar path
+    fn resolve(candidates: &[ExitInfo], bar: &Bar, intrabar_policy: &IntrabarPolicy) -> ExitInfo {
+        let path = bar_path(bar, intrabar_policy, TradeTableConfig::default().intrabar_path);
+        resolve_exit(candidates, path.as_deref(), &long_position()).unwrap()
+    }
+
+    /// Long entered at 100 with TP 105, SL 95 and an expired TTL
+    fn all_three_exits(close: Decimal) -> Vec<ExitInfo> {
+        vec![
+            exit(ExitReason::TakeProfit, dec!(105.0)),
+            exit(ExitReason::StopLoss, dec!(95.0)),
+            exit(ExitReason::Timeout, close),
+        ]
+    }
+
+    #[test]
+    fn test_coarse_policy_prefers_stop_over_timeout_over_target() {
+        let bar = bar(dec!(100.0), dec!(106.0), dec!(90.0), dec!(101.0));
+        let candidates = all_three_exits(bar.close);
+
+        let chosen = resolve(&candidates, &bar, &IntrabarPolicy::LinearInterpolation);
+        assert_eq!(chosen.exit_reason, ExitReason::StopLoss);
+
+        // Candidate order must not matter
+        let reversed: Vec<ExitInfo> = candidates.iter().rev().cloned().collect();
+        let chosen = resolve(&reversed, &bar, &IntrabarPolicy::LinearInterpolation);
+        assert_eq!(chosen.exit_reason, ExitReason::StopLoss);
+
+        // Without a stop, timeout still outranks the target
+        let chosen = resolve(&[candidates[0].clone(), candidates[2].clone()], &bar, &IntrabarPolicy::LinearInterpolation);
+        assert_eq!(chosen.exit_reason, ExitReason::Timeout);
+    }
+
+    #[test]
+    fn test_fine_policy_uses_touch_order() {
+        // High is nearer the open, so the path reaches the TP before the SL
+        let up_first = bar(dec!(100.0), dec!(106.0), dec!(90.0), dec!(101.0));
+        let chosen = resolve(&all_three_exits(up_first.close), &up_first, &IntrabarPolicy::OneSecondBars);
+        assert_eq!(chosen.exit_reason, ExitReason::TakeProfit);
+
+        // Low is nearer the open, so the SL is touched first
+        let down_first = bar(dec!(100.0), dec!(110.0), dec!(94.0), dec!(101.0));
+        let chosen = resolve(&all_three_exits(down_first.close), &down_first, &IntrabarPolicy::OneSecondBars);
+        assert_eq!(chosen.exit_reason, ExitReason::StopLoss);
+
+        // Equidistant extremes visit the low first
+        let symmetric = bar(dec!(100.0), dec!(106.0), dec!(94.0), dec!(101.0));
+        let chosen = resolve(&all_three_exits(symmetric.close), &symmetric, &IntrabarPolicy::ExactTrades);
+        assert_eq!(chosen.exit_reason, ExitReason::StopLoss);
+    }
+
+    #[test]
+    fn test_preallocated_trade_records_do_not_regrow() {
+        let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+            prealloc_trades: 500,
+            ..TradeTableConfig::default()
+        });
+        let initial_capacity = generator.trade_records.capacity();
+        assert!(initial_capacity >= 500);
+
+        // Each pair of bars opens a long and takes profit on the next bar
+        for i in 0..500u64 {
+            let mut entry_bar = bar(dec!(100.0), dec!(100.5), dec!(99.5), dec!(100.0));
+            entry_bar.timestamp = 1609459200000 + i * 120_000;
+            let mut exit_bar = bar(dec!(100.0), dec!(102.0), dec!(99.8), dec!(101.5));
+            exit_bar.timestamp = entry_bar.timestamp + 60_000;
+
+            let signals = vec![signal(Some(dec!(101.0)), Some(dec!(99.0)))];
+            generator.process_bar("BTCUSDT", &entry_bar, &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &ExchangeRules::default()).unwrap();
+            generator.process_bar("BTCUSDT", &exit_bar, &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &ExchangeRules::default()).unwrap();
+        }
+
+        assert_eq!(generator.trade_records.len(), 500);
+        assert_eq!(generator.trade_records.capacity(), initial_capacity);
+    }
+
     #[test]
     fn test_symbol_filters() {
-        let generator = TradeTableGenerator::new();
+        let generator = TradeTableGenerator::with_config(TradeTableConfig {
+            qty_rounding: QtyRoundingMode::Nearest,
+            ..TradeTableConfig::default()
+        });
         let rules = ExchangeRules::default();
         
         let quantity = dec!(0.123456789);
@@ -532,7 +1994,242 @@ mod tests {
         // Should be quantized to lot size
         assert_eq!(filtered, dec!(0.12345679));
     }
-}
 
+    #[test]
+    fn test_floor_rounding_never_exceeds_target_notional() {
+        let floor = TradeTableGenerator::new();
+        let nearest = TradeTableGenerator::with_config(TradeTableConfig {
+            qty_rounding: QtyRoundingMode::Nearest,
+            ..TradeTableConfig::default()
+        });
+        let rules = ExchangeRules { lot_size: dec!(0.001), ..ExchangeRules::default() };
+
+        let target = dec!(1000.0);
+        let mut nearest_overshoots = false;
+        for price in [dec!(30001.0), dec!(43210.5), dec!(50500.0), dec!(61999.99), dec!(2999.0)] {
+            let quantity = floor.apply_symbol_filters(target / price, &rules).unwrap();
+            assert!(quantity * price <= target, "floor overshot at {}", price);
+
+            let rounded = nearest.apply_symbol_filters(target / price, &rules).unwrap();
+            nearest_overshoots |= rounded * price > target;
+        }
 
+        // Nearest rounding does over-allocate for at least one of these prices
+        assert!(nearest_overshoots);
+    }
 
+    fn sized_rules() -> ExchangeRules {
+        ExchangeRules {
+            lot_size: dec!(0.001),
+            min_qty: dec!(0.01),
+            max_qty: dec!(5.0),
+            ..ExchangeRules::default()
+        }
+    }
+
+    #[test]
+    fn test_min_qty_boundary() {
+        let generator = TradeTableGenerator::new();
+        let rules = sized_rules();
+
+        assert_eq!(generator.check_quantity_limits(dec!(0.01), &rules), None);
+        assert_eq!(generator.check_quantity_limits(dec!(0.009), &rules), Some(ReasonCode::MinQty));
+    }
+
+    #[test]
+    fn test_zero_quantity_rejected() {
+        let generator = TradeTableGenerator::new();
+        let mut rules = sized_rules();
+        rules.min_qty = dec!(0.0);
+
+        let quantity = generator.apply_symbol_filters(dec!(0.0004), &rules).unwrap();
+        assert_eq!(quantity, dec!(0.0));
+        assert_eq!(generator.check_quantity_limits(quantity, &rules), Some(ReasonCode::ZeroQtyAfterRounding));
+    }
+
+    #[test]
+    fn test_zero_quantity_after_rounding_rejection_recorded() {
+        let mut generator = TradeTableGenerator::new();
+        let rules = ExchangeRules {
+            lot_size: dec!(1.0), // Whole units only
+            min_qty: dec!(0.0),
+            ..ExchangeRules::default()
+        };
+
+        // $1000 at $2,500,000 is 0.0004 units, which rounds to zero lots
+        let entry_bar = bar(dec!(2500000.0), dec!(2500100.0), dec!(2499900.0), dec!(2500000.0));
+        let signals = vec![signal(None, None)];
+
+        generator.process_bar("BTCUSDT", &entry_bar, &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules).unwrap();
+
+        let result = generator.generate_result();
+        assert!(result.trades.is_empty());
+        assert_eq!(result.rejected_trades.len(), 1);
+        assert_eq!(result.rejected_trades[0].reason, ReasonCode::ZeroQtyAfterRounding);
+        assert_eq!(result.rejected_trades[0].notional, dec!(0.0));
+    }
+
+    #[test]
+    fn test_max_qty_capped() {
+        let generator = TradeTableGenerator::new();
+        let rules = sized_rules();
+
+        assert_eq!(generator.apply_symbol_filters(dec!(5.0), &rules).unwrap(), dec!(5.0));
+        let capped = generator.apply_symbol_filters(dec!(7.25), &rules).unwrap();
+        assert_eq!(capped, dec!(5.0));
+        assert_eq!(generator.check_quantity_limits(capped, &rules), None);
+    }
+
+    #[test]
+    fn test_max_qty_rejected() {
+        let generator = TradeTableGenerator::with_config(TradeTableConfig {
+            max_qty_policy: MaxQtyPolicy::Reject,
+            ..TradeTableConfig::default()
+        });
+        let rules = sized_rules();
+
+        assert_eq!(generator.check_quantity_limits(dec!(5.0), &rules), None);
+        let quantity = generator.apply_symbol_filters(dec!(5.001), &rules).unwrap();
+        assert_eq!(generator.check_quantity_limits(quantity, &rules), Some(ReasonCode::MaxQty));
+    }
+
+    #[test]
+    fn test_min_qty_rejection_recorded() {
+        let mut generator = TradeTableGenerator::new();
+        let rules = ExchangeRules {
+            min_qty: dec!(1.0), // $1000 at $50,000 buys only 0.02
+            min_notional: dec!(0.0),
+            ..ExchangeRules::default()
+        };
+
+        let entry_bar = bar(dec!(50000.0), dec!(50100.0), dec!(49900.0), dec!(50000.0));
+        let signals = vec![signal(None, None)];
+
+        generator.process_bar("BTCUSDT", &entry_bar, &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules).unwrap();
+
+        let result = generator.generate_result();
+        assert!(result.trades.is_empty());
+        assert_eq!(result.rejected_trades.len(), 1);
+        assert_eq!(result.rejected_trades[0].reason, ReasonCode::MinQty);
+    }
+
+    /// Feed `lookback + 1` bars alternating by `swing` around 100, then open a long
+    fn vol_targeted_notional(swing: Decimal, annualization: AnnualizationConvention) -> Decimal {
+        let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+            sizing_mode: SizingMode::VolatilityTarget {
+                annual_vol_target: dec!(0.15),
+                lookback: 20,
+            },
+            annualization,
+            ..TradeTableConfig::default()
+        });
+        let rules = ExchangeRules::default();
+
+        for i in 0..21u64 {
+            let close = if i % 2 == 0 { dec!(100.0) } else { dec!(100.0) + swing };
+            let mut b = bar(close, close, close, close);
+            b.timestamp = 1609459200000 + i * 3_600_000;
+
+            let signals = if i == 20 {
+                vec![signal(None, None)]
+            } else {
+                Vec::new()
+            };
+            generator.process_bar("BTCUSDT", &b, &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules).unwrap();
+        }
+
+        let position = generator.active_positions.values().next().unwrap();
+        position.size_usd
+    }
+
+    #[test]
+    fn test_volatility_target_sizes_down_in_volatile_windows() {
+        let calm = vol_targeted_notional(dec!(0.5), AnnualizationConvention::Calendar365);
+        let volatile = vol_targeted_notional(dec!(5.0), AnnualizationConvention::Calendar365);
+
+        assert!(volatile < calm, "volatile {} vs calm {}", volatile, calm);
+        // Neither hits the 1x leverage cap, so size scales inversely with volatility
+        assert!(calm < dec!(10000.0));
+        assert!(volatile > dec!(0.0));
+        let ratio = calm / volatile;
+        assert!(ratio > dec!(9) && ratio < dec!(11), "ratio {}", ratio);
+    }
+
+    #[test]
+    fn test_volatility_target_annualizes_by_convention() {
+        let calendar = vol_targeted_notional(dec!(0.5), AnnualizationConvention::Calendar365);
+        let trading = vol_targeted_notional(dec!(0.5), AnnualizationConvention::Trading252);
+
+        // Fewer periods per year means lower annualized volatility and a larger position
+        let ratio = trading / calendar;
+        let expected = decimal_sqrt(dec!(365) / dec!(252));
+        assert!((ratio - expected).abs() < dec!(0.001), "ratio {} vs {}", ratio, expected);
+    }
+
+    #[test]
+    fn test_volatility_target_sizes_each_symbol_from_its_own_closes() {
+        let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+            sizing_mode: SizingMode::VolatilityTarget {
+                annual_vol_target: dec!(0.15),
+                lookback: 20,
+            },
+            annualization: AnnualizationConvention::Calendar365,
+            ..TradeTableConfig::default()
+        });
+        let rules = ExchangeRules::default();
+
+        // A volatile ETH series interleaved with BTC's, half a bar later
+        for i in 0..21u64 {
+            let timestamp = 1609459200000 + i * 3_600_000;
+            let close = if i % 2 == 0 { dec!(100.0) } else { dec!(105.0) };
+            let mut eth = bar(close, close, close, close);
+            eth.timestamp = timestamp + 1_800_000;
+            let close = if i % 2 == 0 { dec!(100.0) } else { dec!(100.5) };
+            let mut btc = bar(close, close, close, close);
+            btc.timestamp = timestamp;
+
+            let signals = if i == 20 { vec![signal(None, None)] } else { Vec::new() };
+            generator.process_bar("BTCUSDT", &btc, &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules).unwrap();
+            generator.process_bar("ETHUSDT", &eth, &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules).unwrap();
+        }
+
+        let alone = vol_targeted_notional(dec!(0.5), AnnualizationConvention::Calendar365);
+        assert_eq!(generator.active_positions()["BTCUSDT"].size_usd, alone);
+    }
+
+    /// Entry notionals of three sequential long trades that each take profit
+    fn sequential_winner_sizes(sizing_mode: SizingMode) -> Vec<Decimal> {
+        let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+            sizing_mode,
+            ..TradeTableConfig::default()
+        });
+
+        for i in 0..3u64 {
+            let mut entry_bar = bar(dec!(100.0), dec!(100.5), dec!(99.5), dec!(100.0));
+            entry_bar.timestamp = 1609459200000 + i * 120_000;
+            let mut exit_bar = bar(dec!(100.0), dec!(111.0), dec!(99.8), dec!(110.0));
+            exit_bar.timestamp = entry_bar.timestamp + 60_000;
+
+            let signals = vec![signal(Some(dec!(110.0)), Some(dec!(90.0)))];
+            generator.process_bar("BTCUSDT", &entry_bar, &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &ExchangeRules::default()).unwrap();
+            generator.process_bar("BTCUSDT", &exit_bar, &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &ExchangeRules::default()).unwrap();
+        }
+
+        assert_eq!(generator.current_equity, generator.config.initial_equity + generator.realized_pnl);
+        generator.trade_records.iter().map(|t| t.size_usd).collect()
+    }
+
+    #[test]
+    fn test_percent_equity_compounds_while_fixed_notional_does_not() {
+        let fixed = sequential_winner_sizes(SizingMode::FixedNotional);
+        assert_eq!(fixed.len(), 3);
+        assert!(fixed.iter().all(|size| *size == fixed[0]), "fixed sizes {:?}", fixed);
+
+        let compounding = sequential_winner_sizes(SizingMode::PercentEquity { fraction: dec!(0.5) });
+        assert_eq!(compounding.len(), 3);
+        // The first entry sizes off the initial $10,000
+        assert!(compounding[0] <= dec!(5000.0) && compounding[0] > dec!(4999.0), "first size {}", compounding[0]);
+        assert!(compounding[1] > compounding[0], "sizes {:?}", compounding);
+        assert!(compounding[2] > compounding[1], "sizes {:?}", compounding);
+    }
+}
@@ -1,8 +1,9 @@
 //! Type definitions for the backtesting engine
 
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
 /// Market data structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +13,23 @@ pub struct MarketData {
     pub bars: Vec<Bar>,
     pub trades: Vec<Trade>,
     pub rules: ExchangeRules,
+    /// Which end of its interval each bar's timestamp marks
+    #[serde(default)]
+    pub timestamp_convention: TimestampConvention,
+}
+
+/// Which end of the bar interval `Bar::timestamp` marks
+/// 
+/// The engine works in open time throughout (a bar covers `[timestamp, timestamp +
+/// interval)`); close-time data is shifted by [`crate::data::normalize_timestamps`]
+/// before it reaches indicators or the simulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TimestampConvention {
+    /// Stamped with the start of the interval
+    #[default]
+    OpenTime,
+    /// Stamped with the end of the interval (`open + interval`)
+    CloseTime,
 }
 
 /// OHLCV bar data
@@ -26,16 +44,183 @@ pub struct Bar {
     pub trade_count: u32,
 }
 
+/// Column-oriented (struct-of-arrays) view of OHLCV bars
+/// 
+/// Indicators that only read one field (e.g. close) scan a single contiguous array
+/// instead of striding over whole `Bar` structs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BarColumns {
+    pub timestamp: Vec<u64>,
+    pub open: Vec<Decimal>,
+    pub high: Vec<Decimal>,
+    pub low: Vec<Decimal>,
+    pub close: Vec<Decimal>,
+    pub volume: Vec<Decimal>,
+    pub trade_count: Vec<u32>,
+}
+
+impl BarColumns {
+    /// Split bars into columns
+    pub fn from_bars(bars: &[Bar]) -> Self {
+        let mut columns = Self {
+            timestamp: Vec::with_capacity(bars.len()),
+            open: Vec::with_capacity(bars.len()),
+            high: Vec::with_capacity(bars.len()),
+            low: Vec::with_capacity(bars.len()),
+            close: Vec::with_capacity(bars.len()),
+            volume: Vec::with_capacity(bars.len()),
+            trade_count: Vec::with_capacity(bars.len()),
+        };
+        
+        for bar in bars {
+            columns.timestamp.push(bar.timestamp);
+            columns.open.push(bar.open);
+            columns.high.push(bar.high);
+            columns.low.push(bar.low);
+            columns.close.push(bar.close);
+            columns.volume.push(bar.volume);
+            columns.trade_count.push(bar.trade_count);
+        }
+        
+        columns
+    }
+    
+    /// Reassemble the columns into bars
+    pub fn to_bars(&self) -> Vec<Bar> {
+        (0..self.len())
+            .map(|i| Bar {
+                timestamp: self.timestamp[i],
+                open: self.open[i],
+                high: self.high[i],
+                low: self.low[i],
+                close: self.close[i],
+                volume: self.volume[i],
+                trade_count: self.trade_count[i],
+            })
+            .collect()
+    }
+    
+    pub fn len(&self) -> usize {
+        self.timestamp.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.timestamp.is_empty()
+    }
+}
+
+impl From<&[Bar]> for BarColumns {
+    fn from(bars: &[Bar]) -> Self {
+        Self::from_bars(bars)
+    }
+}
+
+/// Unit of a raw integer timestamp
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimestampUnit {
+    Millis,
+    Micros,
+    /// Truncated to microseconds
+    Nanos,
+}
+
+/// Unix timestamp with microsecond resolution
+/// 
+/// Tick feeds can put several trades inside one millisecond; keeping microseconds
+/// preserves their order where millisecond truncation would tie them. Serializes as
+/// the integer number of microseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    pub fn from_millis(millis: u64) -> Self {
+        Self(millis * 1_000)
+    }
+    
+    pub fn from_micros(micros: u64) -> Self {
+        Self(micros)
+    }
+    
+    /// Interpret `value` in `unit`
+    pub fn from_unit(value: u64, unit: TimestampUnit) -> Self {
+        match unit {
+            TimestampUnit::Millis => Self::from_millis(value),
+            TimestampUnit::Micros => Self::from_micros(value),
+            TimestampUnit::Nanos => Self::from_micros(value / 1_000),
+        }
+    }
+    
+    /// Whole milliseconds, rounding down
+    pub fn as_millis(&self) -> u64 {
+        self.0 / 1_000
+    }
+    
+    pub fn as_micros(&self) -> u64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:03}ms", self.as_millis(), self.0 % 1_000)
+    }
+}
+
 /// Trade data
+/// 
+/// Serialized with `timestamp` in Unix milliseconds, as before microsecond support;
+/// ticks that fall between milliseconds also carry the full `timestamp_us`, which wins
+/// when present.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "TradeWire", into = "TradeWire")]
 pub struct Trade {
-    pub timestamp: u64,          // Unix milliseconds
+    pub timestamp: Timestamp,
     pub price: Decimal,
     pub quantity: Decimal,
     pub side: TradeSide,
     pub trade_id: String,
 }
 
+/// Serialized form of [`Trade`]
+#[derive(Serialize, Deserialize)]
+struct TradeWire {
+    /// Unix milliseconds
+    timestamp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp_us: Option<u64>,
+    price: Decimal,
+    quantity: Decimal,
+    side: TradeSide,
+    trade_id: String,
+}
+
+impl From<Trade> for TradeWire {
+    fn from(trade: Trade) -> Self {
+        let millis = trade.timestamp.as_millis();
+        Self {
+            timestamp: millis,
+            timestamp_us: (Timestamp::from_millis(millis) != trade.timestamp).then_some(trade.timestamp.as_micros()),
+            price: trade.price,
+            quantity: trade.quantity,
+            side: trade.side,
+            trade_id: trade.trade_id,
+        }
+    }
+}
+
+impl From<TradeWire> for Trade {
+    fn from(wire: TradeWire) -> Self {
+        Self {
+            timestamp: wire.timestamp_us.map_or(Timestamp::from_millis(wire.timestamp), Timestamp::from_micros),
+            price: wire.price,
+            quantity: wire.quantity,
+            side: wire.side,
+            trade_id: wire.trade_id,
+        }
+    }
+}
+
 /// Trade side enumeration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TradeSide {
@@ -43,16 +228,74 @@ pub enum TradeSide {
     Sell,
 }
 
+/// Which side of the book a fill took, for fee purposes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Liquidity {
+    /// Resting order that added liquidity (limit entries, take profits)
+    Maker,
+    /// Order that crossed the spread (market entries, stops, timeouts)
+    Taker,
+}
+
+impl Liquidity {
+    /// Liquidity an entry fills with: maker for a limit (one with an `entry_price`),
+    /// which rests until touched, taker for a market entry
+    pub fn of_entry(signal: &StrategySignal) -> Self {
+        if signal.entry_price.is_some() {
+            Liquidity::Maker
+        } else {
+            Liquidity::Taker
+        }
+    }
+}
+
+/// What an exchange charges its fees on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum FeeBasis {
+    /// The maker or taker rate times the fill notional
+    #[default]
+    Notional,
+    /// A fixed amount per unit (contract) filled, whatever the price or liquidity
+    PerUnit(Decimal),
+    /// A fixed amount per fill, whatever its size or liquidity; weighs most on small orders
+    PerOrder(Decimal),
+}
+
 /// Exchange trading rules
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExchangeRules {
     pub tick_size: Decimal,
     pub lot_size: Decimal,
+    pub min_qty: Decimal,
+    pub max_qty: Decimal,
     pub min_notional: Decimal,
+    /// Maker fee rate; negative for venues that pay a rebate
     pub maker_fee: Decimal,
     pub taker_fee: Decimal,
     pub precision_price: u8,
     pub precision_quantity: u8,
+    /// Maintenance margin as a fraction of notional (used for liquidation prices)
+    pub maintenance_margin_rate: Decimal,
+    /// Direction fees are rounded to `precision_price`; exchanges round up in their favor
+    #[serde(default = "default_fee_rounding")]
+    pub fee_rounding: RoundingMode,
+    /// Whether the symbol can be sold short; false for spot markets
+    /// 
+    /// When false, sells only close inventory already held and short entries are
+    /// rejected with [`ReasonCode::ShortsNotAllowed`].
+    #[serde(default = "default_allow_shorts")]
+    pub allow_shorts: bool,
+    /// What fees are charged on; the maker and taker rates apply only to `FeeBasis::Notional`
+    #[serde(default)]
+    pub fee_basis: FeeBasis,
+}
+
+fn default_fee_rounding() -> RoundingMode {
+    RoundingMode::TowardPositive
+}
+
+fn default_allow_shorts() -> bool {
+    true
 }
 
 impl Default for ExchangeRules {
@@ -60,11 +303,36 @@ impl Default for ExchangeRules {
         Self {
             tick_size: Decimal::new(1, 8),      // 0.00000001
             lot_size: Decimal::new(1, 8),       // 0.00000001
+            min_qty: Decimal::new(1, 8),        // 0.00000001 (one lot)
+            max_qty: Decimal::MAX,              // No limit
             min_notional: Decimal::new(10, 0),   // 10.0
             maker_fee: Decimal::new(1, 4),       // 0.0001 (0.01%)
             taker_fee: Decimal::new(1, 4),       // 0.0001 (0.01%)
             precision_price: 8,
             precision_quantity: 8,
+            maintenance_margin_rate: Decimal::new(5, 3), // 0.005 (0.5%)
+            fee_rounding: default_fee_rounding(),
+            allow_shorts: default_allow_shorts(),
+            fee_basis: FeeBasis::Notional,
+        }
+    }
+}
+
+impl ExchangeRules {
+    /// Fee rate charged on a fill with the given liquidity
+    pub fn fee_rate(&self, liquidity: Liquidity) -> Decimal {
+        match liquidity {
+            Liquidity::Maker => self.maker_fee,
+            Liquidity::Taker => self.taker_fee,
+        }
+    }
+
+    /// Unrounded fee for filling `quantity` at `price`, per `fee_basis`
+    pub fn fee(&self, quantity: Decimal, price: Decimal, liquidity: Liquidity) -> Decimal {
+        match self.fee_basis {
+            FeeBasis::Notional => quantity * price * self.fee_rate(liquidity),
+            FeeBasis::PerUnit(per_unit) => quantity * per_unit,
+            FeeBasis::PerOrder(per_order) => per_order,
         }
     }
 }
@@ -75,11 +343,20 @@ pub struct SymbolResult {
     pub symbol: String,
     pub trades: Vec<ExecutedTrade>,
     pub positions: Vec<Position>,
+    /// The account's equity at this symbol's bars; a job's symbols share one account,
+    /// so their curves agree where their bars do and are never summed
     pub equity_curve: Vec<EquityPoint>,
     pub drawdown: Decimal,
+    /// Gross notional / equity
     pub exposure: Decimal,
+    /// (Long - short) notional / equity
+    pub net_exposure: Decimal,
+    #[serde(serialize_with = "crate::canonical::sorted_map")]
     pub attribution: HashMap<String, Decimal>,
     pub trade_table: Option<TradeTableResult>,
+    /// Gaps found in the symbol's bars and the bars synthesized for them
+    #[serde(default)]
+    pub data_quality: Option<crate::data::DataQualityReport>,
 }
 
 /// Complete backtest result
@@ -92,24 +369,123 @@ pub struct BacktestResult {
     pub manifest: RunManifest,
 }
 
+/// Shared symbol name
+/// 
+/// Cloning shares one allocation, so fills and per-bar position snapshots do not copy
+/// the name. Serializes as a plain string, so exported and hashed output is unchanged.
+/// 
+/// `ExecutedTrade::symbol` and `Position::symbol` held a `String` before 0.2.0, a
+/// breaking change for Rust callers: build one with `.into()`, compare it with `&str` or
+/// `String` directly, and call `.to_string()` (or `String::from`) where a `String` is
+/// needed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::ops::Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::borrow::Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(symbol: &str) -> Self {
+        Self(Arc::from(symbol))
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(symbol: String) -> Self {
+        Self(Arc::from(symbol))
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.0.to_string()
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialEq<Symbol> for String {
+    fn eq(&self, other: &Symbol) -> bool {
+        **self == *other.0
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
+
 /// Executed trade with fees and slippage
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutedTrade {
     pub timestamp: u64,
-    pub symbol: String,
+    pub symbol: Symbol,
     pub side: TradeSide,
     pub quantity: Decimal,
     pub price: Decimal,
     pub fee: Decimal,
     pub slippage: Decimal,
-    pub reason_code: String,
+    /// How the fill was executed; which strategy rule asked for it is `rule_tag`
+    pub reason_code: ReasonCode,
+    /// Strategy rule that produced the fill
+    pub rule_tag: Option<String>,
+    /// Filled outside its bar's low/high, only possible under `FillRangePolicy::Flag`;
+    /// omitted from JSON when false
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fill_outside_bar: bool,
 }
 
 /// Position at a point in time
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
     pub timestamp: u64,
-    pub symbol: String,
+    pub symbol: Symbol,
     pub quantity: Decimal,
     pub avg_price: Decimal,
     pub unrealized_pnl: Decimal,
@@ -121,8 +497,24 @@ pub struct Position {
 pub struct EquityPoint {
     pub timestamp: u64,
     pub equity: Decimal,
+    /// Starting cash plus PnL locked in by closed trades (net of fees)
+    pub realized_equity: Decimal,
+    /// Mark-to-market PnL of positions still open, net of their entry fees
+    pub unrealized_pnl: Decimal,
     pub drawdown: Decimal,
+    /// Gross notional at the bar close / equity (1.5 = 150% leveraged)
     pub exposure: Decimal,
+    /// (Long - short) notional at the bar close / equity
+    pub net_exposure: Decimal,
+}
+
+/// How long a position may stay open before it is closed at the bar close
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeToLive {
+    /// Wall-clock lifetime in milliseconds since entry
+    Duration(u64),
+    /// Number of bars processed after the entry bar, regardless of gaps between them
+    Bars(u32),
 }
 
 /// Strategy signal
@@ -133,7 +525,15 @@ pub struct StrategySignal {
     pub entry_price: Option<Decimal>,
     pub take_profit: Option<Decimal>,
     pub stop_loss: Option<Decimal>,
-    pub time_to_live: Option<u64>, // milliseconds
+    pub time_to_live: Option<TimeToLive>,
+    /// Strategy rule that emitted the signal (e.g., "breakout")
+    pub rule_tag: Option<String>,
+    /// Free-form key/value labels (e.g. `("regime", "trend")`) carried onto the trade record
+    #[serde(default)]
+    pub tags: Vec<(String, String)>,
+    /// Symbol the signal trades; left empty, the simulator fills in the symbol it is running
+    #[serde(default)]
+    pub symbol: String,
 }
 
 /// Trade type enumeration
@@ -151,6 +551,10 @@ pub enum ExitReason {
     StrategyExit,
     Liquidation,
     Timeout,
+    /// Still open on the last bar; marked to that bar's close
+    EndOfData,
+    /// Force-closed at market mid-run (session end, halt, kill switch)
+    Flatten,
 }
 
 /// Hit TP/SL status
@@ -184,14 +588,70 @@ pub struct TradeRecord {
     pub qty: Decimal,
     /// Total fees (entry + exit)
     pub fees_usd: Decimal,
+    /// Slippage paid on entry + exit in USD, already reflected in the fill prices
+    #[serde(default)]
+    pub slippage_usd: Decimal,
     /// Net PnL in USD
     pub pnl_usd: Decimal,
-    /// PnL as percentage
-    pub pnl_pct: Decimal,
+    /// PnL as a percentage of `size_usd`
+    pub pnl_pct: Percent,
+    /// Market price at which the trade nets zero after its fees and exit slippage
+    #[serde(default)]
+    pub breakeven_price: Decimal,
+    /// Entry or exit filled outside its bar's low/high, only possible under
+    /// `FillRangePolicy::Flag`; omitted from JSON when false
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fill_outside_bar: bool,
     /// Symbol (hidden column for per-symbol breakdowns)
     pub symbol: String,
+    /// Labels of the signal that opened the trade; omitted from JSON when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<(String, String)>,
+    /// Strategy rule of the signal that opened the trade, which its PnL is attributed
+    /// to; omitted from JSON when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_tag: Option<String>,
 }
 
+/// Market exit price at which a trade nets zero PnL
+/// 
+/// Moves the entry fill by the round-trip fees per unit plus the per-unit slippage the
+/// exit pays (`exit_slippage`, positive when it works against the trade): up for longs,
+/// down for shorts. The exit fee is taken as given, so a different exit price shifts
+/// the result only by the fee on the price difference. A zero quantity returns the
+/// entry price.
+pub fn breakeven_price(
+    trade_type: &TradeType,
+    entry_price: Decimal,
+    qty: Decimal,
+    fees_usd: Decimal,
+    exit_slippage: Decimal,
+) -> Decimal {
+    if qty.is_zero() {
+        return entry_price;
+    }
+    let cost_per_unit = fees_usd / qty + exit_slippage;
+    match trade_type {
+        TradeType::Long => entry_price + cost_per_unit,
+        TradeType::Short => entry_price - cost_per_unit,
+    }
+}
+
+/// Three-way classification of a closed trade's PnL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TradeOutcome {
+    Win,
+    Loss,
+    /// PnL inside the breakeven band, counted as neither a win nor a loss
+    Scratch,
+}
+
+/// Profit factor reported when gross loss is zero but gross profit is not
+/// 
+/// Decimal has no infinity; `Decimal::MAX` keeps "no losing trades" sortable above every
+/// real profit factor instead of reading as 0.
+pub const PROFIT_FACTOR_NO_LOSSES: Decimal = Decimal::MAX;
+
 /// Trade summary totals
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeSummary {
@@ -201,22 +661,31 @@ pub struct TradeSummary {
     pub wins: u32,
     /// Number of losing trades
     pub losses: u32,
-    /// Win rate as percentage
-    pub win_rate: Decimal,
+    /// Number of breakeven trades (PnL inside the breakeven band)
+    pub scratches: u32,
+    /// Win rate over decided trades (wins + losses; scratches excluded)
+    pub win_rate: Percent,
     /// Net PnL in USD
     pub net_pnl_usd: Decimal,
     /// Average win in USD
     pub avg_win_usd: Decimal,
     /// Average loss in USD
     pub avg_loss_usd: Decimal,
-    /// Expectancy (win_rate * avg_win + (1 - win_rate) * avg_loss)
+    /// Expectancy per trade (net PnL / total trades, scratches included); 0 with no trades
     pub expectancy: Decimal,
-    /// Maximum drawdown
-    pub max_drawdown: Decimal,
-    /// Profit factor (gross profit / gross loss)
+    /// Maximum drawdown from peak equity
+    pub max_drawdown: Percent,
+    /// Profit factor (gross profit / gross loss); [`PROFIT_FACTOR_NO_LOSSES`] when there
+    /// are winners but no losers, 0 with no winners or no trades
     pub profit_factor: Decimal,
     /// Average holding time in hours
     pub avg_holding_time_hours: Decimal,
+    /// Fees paid across all trades in USD
+    #[serde(default)]
+    pub total_fees_usd: Decimal,
+    /// Modeled slippage paid across all trades in USD
+    #[serde(default)]
+    pub total_slippage_usd: Decimal,
 }
 
 /// Active position with TP/SL tracking
@@ -229,9 +698,37 @@ pub struct ActivePosition {
     pub quantity: Decimal,
     pub take_profit: Option<Decimal>,
     pub stop_loss: Option<Decimal>,
-    pub time_to_live: Option<u64>,
+    pub time_to_live: Option<TimeToLive>,
+    /// Bars processed since the entry bar
+    #[serde(default)]
+    pub bars_held: u32,
     pub entry_fee: Decimal,
+    /// Slippage the entry fill paid in USD
+    #[serde(default)]
+    pub entry_slippage_usd: Decimal,
+    /// Entry filled outside its bar's low/high
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub entry_outside_bar: bool,
     pub size_usd: Decimal,
+    /// Price at which the position's margin is exhausted (None when unleveraged)
+    pub liquidation_price: Option<Decimal>,
+    /// Labels of the opening signal
+    #[serde(default)]
+    pub tags: Vec<(String, String)>,
+    /// Strategy rule of the opening signal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_tag: Option<String>,
+}
+
+impl ActivePosition {
+    /// PnL of closing at `mark` without an exit fee, net of the entry fee already paid
+    pub fn unrealized_pnl(&self, mark: Decimal) -> Decimal {
+        let gross = match self.trade_type {
+            TradeType::Long => (mark - self.entry_price) * self.quantity,
+            TradeType::Short => (self.entry_price - mark) * self.quantity,
+        };
+        gross - self.entry_fee
+    }
 }
 
 /// Trade table generation result
@@ -240,6 +737,9 @@ pub struct TradeTableResult {
     pub trades: Vec<TradeRecord>,
     pub summary: TradeSummary,
     pub rejected_trades: Vec<RejectedTrade>,
+    /// Summary of the trades carrying each tag, keyed `key=value`; omitted when untagged
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub summary_by_tag: BTreeMap<String, TradeSummary>,
 }
 
 /// Rejected trade with reason
@@ -248,15 +748,183 @@ pub struct RejectedTrade {
     pub timestamp: u64,
     pub symbol: String,
     pub side: TradeSide,
-    pub reason: String,
+    pub reason: ReasonCode,
     pub notional: Decimal,
 }
 
+/// Machine-readable code attached to fills and rejections
+/// 
+/// Serialized as a stable snake_case string (e.g. `"one_second_bar"`), which is also
+/// its `Display` form, so exports and downstream filters can match on it. Variants
+/// fall into the groups reported by [`ReasonCode::category`]; the signal that led to
+/// a fill is identified separately by its rule tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasonCode {
+    /// Filled at the bar close (`IntrabarPolicy::ExactTrades` on a bar without ticks)
+    BarClose,
+    /// Filled at a trade tick (`IntrabarPolicy::ExactTrades`)
+    Tick,
+    /// Filled on a one-second sub-step (`IntrabarPolicy::OneSecondBars`)
+    OneSecondBar,
+    /// Filled along the open-to-close line (`IntrabarPolicy::LinearInterpolation`)
+    LinearInterpolation,
+    /// Filled at the open of the bar after the signal's (`FillModel::NextBarOpen`)
+    NextBarOpen,
+    /// Notional below the symbol's minimum
+    NotionalMin,
+    /// Quantity below the symbol's minimum
+    MinQty,
+    /// Quantity above the symbol's maximum under `MaxQtyPolicy::Reject`
+    MaxQty,
+    /// Quantity rounded down to zero lots
+    ZeroQtyAfterRounding,
+    /// Entries halted by a tripped risk limit
+    RiskLimit,
+    /// Entry too soon after the previous entry on the symbol
+    Cooldown,
+    /// Entry would take open notional above the shared capital limit
+    InsufficientCapital,
+    /// Entry during the burn-in bars at the start of a symbol's run
+    Warmup,
+    /// Short entry on a symbol that cannot be shorted (spot)
+    ShortsNotAllowed,
+    /// Entry outside the configured trading session
+    OutsideSession,
+    /// Entry while the portfolio already holds its maximum number of open positions
+    MaxOpenPositions,
+    /// Entry on the same bar, symbol and side as another, dropped by `OverlappingEntryPolicy`
+    OverlappingEntry,
+    /// Entry on a symbol (or hedge-mode leg) that already holds a position
+    PositionOpen,
+}
+
+/// Group a [`ReasonCode`] belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasonCategory {
+    /// How an order was executed
+    Execution,
+    /// Why an order was refused
+    Rejection,
+}
+
+impl ReasonCode {
+    pub fn category(&self) -> ReasonCategory {
+        match self {
+            ReasonCode::BarClose | ReasonCode::Tick | ReasonCode::OneSecondBar
+            | ReasonCode::LinearInterpolation | ReasonCode::NextBarOpen => ReasonCategory::Execution,
+            ReasonCode::NotionalMin | ReasonCode::MinQty | ReasonCode::MaxQty | ReasonCode::ZeroQtyAfterRounding
+            | ReasonCode::RiskLimit | ReasonCode::Cooldown | ReasonCode::InsufficientCapital
+            | ReasonCode::Warmup | ReasonCode::ShortsNotAllowed | ReasonCode::OutsideSession
+            | ReasonCode::MaxOpenPositions | ReasonCode::OverlappingEntry
+            | ReasonCode::PositionOpen => ReasonCategory::Rejection,
+        }
+    }
+    
+    /// Stable snake_case name, identical to the serialized form
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReasonCode::BarClose => "bar_close",
+            ReasonCode::Tick => "tick",
+            ReasonCode::OneSecondBar => "one_second_bar",
+            ReasonCode::LinearInterpolation => "linear_interpolation",
+            ReasonCode::NextBarOpen => "next_bar_open",
+            ReasonCode::NotionalMin => "notional_min",
+            ReasonCode::MinQty => "min_qty",
+            ReasonCode::MaxQty => "max_qty",
+            ReasonCode::ZeroQtyAfterRounding => "zero_qty_after_rounding",
+            ReasonCode::RiskLimit => "risk_limit",
+            ReasonCode::Cooldown => "cooldown",
+            ReasonCode::InsufficientCapital => "insufficient_capital",
+            ReasonCode::Warmup => "warmup",
+            ReasonCode::ShortsNotAllowed => "shorts_not_allowed",
+            ReasonCode::OutsideSession => "outside_session",
+            ReasonCode::MaxOpenPositions => "max_open_positions",
+            ReasonCode::OverlappingEntry => "overlapping_entry",
+            ReasonCode::PositionOpen => "position_open",
+        }
+    }
+}
+
+impl std::fmt::Display for ReasonCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Indicator value
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndicatorValue {
     pub timestamp: u64,
     pub value: Decimal,
+    /// False for values computed from a partial window, such as the session the data
+    /// starts inside under `SessionWarmup::Partial`
+    #[serde(default = "default_warm")]
+    pub warm: bool,
+}
+
+fn default_warm() -> bool {
+    true
+}
+
+/// Upper/middle/lower price channel for one bar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelValue {
+    pub timestamp: u64,
+    pub upper: Decimal,
+    pub middle: Decimal,
+    pub lower: Decimal,
+}
+
+/// SuperTrend line and trend direction for one bar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuperTrendValue {
+    pub timestamp: u64,
+    /// Final lower band in an uptrend, final upper band in a downtrend
+    pub value: Decimal,
+    /// +1 for an uptrend, -1 for a downtrend
+    pub direction: i8,
+}
+
+/// Swing high or low pivot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwingPoint {
+    /// Timestamp of the pivot bar
+    pub timestamp: u64,
+    /// Pivot high (or low) price
+    pub price: Decimal,
+    /// Timestamp of the bar that confirms the pivot (`right` bars later)
+    pub confirmed_at: u64,
+}
+
+/// Divergence between price swings and an oscillator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DivergenceKind {
+    /// Price lower low, oscillator higher low
+    RegularBullish,
+    /// Price higher high, oscillator lower high
+    RegularBearish,
+    /// Price higher low, oscillator lower low
+    HiddenBullish,
+    /// Price lower high, oscillator higher high
+    HiddenBearish,
+}
+
+/// Divergence detected between two consecutive price swings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DivergenceEvent {
+    pub kind: DivergenceKind,
+    /// Timestamp of the earlier swing
+    pub prev_timestamp: u64,
+    /// Timestamp of the later swing
+    pub timestamp: u64,
+    /// Timestamp at which the later swing is confirmed (fractal window bars after it)
+    pub confirmed_at: u64,
+    pub prev_price: Decimal,
+    pub price: Decimal,
+    pub prev_osc: Decimal,
+    pub osc: Decimal,
 }
 
 /// Indicator calculation parameters
@@ -265,6 +933,94 @@ pub struct IndicatorParams {
     pub period: usize,
     pub alpha: Option<Decimal>,
     pub threshold: Option<Decimal>,
+    /// Arithmetic used for the calculation (Decimal unless explicitly opted out)
+    #[serde(default)]
+    pub compute_precision: Precision,
+    /// How EMA is seeded before the recursion starts
+    #[serde(default)]
+    pub ema_seed: EmaSeed,
+    /// Where VWAP accumulation restarts
+    #[serde(default)]
+    pub vwap_anchor: VwapAnchor,
+    /// Whether session-anchored indicators emit values for a session the data starts inside
+    #[serde(default)]
+    pub session_warmup: SessionWarmup,
+    /// What volume-weighted indicators emit while their window has no volume
+    #[serde(default)]
+    pub zero_volume: ZeroVolumePolicy,
+    /// Decimals price-denominated outputs are rounded to (nearest-even); `None` keeps
+    /// full precision. With `EngineConfig::round_indicator_prices` on, the engine fills an
+    /// unset value from the symbol's `ExchangeRules::precision_price`.
+    #[serde(default)]
+    pub price_precision: Option<u8>,
+}
+
+/// Accumulation window of VWAP
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum VwapAnchor {
+    /// One running VWAP over the whole series
+    #[default]
+    Cumulative,
+    /// Reset at every UTC midnight
+    Daily,
+}
+
+/// Output of a volume-weighted indicator (VWAP, MFI) over a window with zero volume
+/// 
+/// Illiquid stretches and halts leave nothing to weight by. Carrying the last value
+/// forward reads as "no new information"; emitting 0 would look like a price collapse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ZeroVolumePolicy {
+    /// Repeat the last value computed from traded volume; nothing before the first one
+    #[default]
+    CarryForward,
+    /// Emit no value for the bar
+    Skip,
+}
+
+/// Handling of the partial session at the start of the data
+/// 
+/// Data that begins mid-session gives an anchored VWAP that only covers part of the
+/// session, which looks like a valid value but is not the one the strategy would see live.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SessionWarmup {
+    /// Emit values from the first bar, those from the partial session marked not `warm`
+    /// (see also `indicators::session_warmup_end`)
+    #[default]
+    Partial,
+    /// Emit nothing until the first complete session starts
+    Strict,
+}
+
+/// EMA warmup/seeding mode
+/// 
+/// Platforms disagree on the first EMA value, and the difference decays only slowly:
+/// - `SmaSeed`: TA-Lib (default compatibility) and most textbook definitions.
+/// - `FirstValueSeed`: TradingView's `ta.ema` and pandas `ewm(adjust=False)`.
+/// 
+/// Pine's reference `pine_ema` script seeds with `ta.sma` instead, so match the mode to
+/// whichever script produced the reference data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EmaSeed {
+    /// Seed with the SMA of the first `period` closes; first value at bar `period - 1`
+    #[default]
+    SmaSeed,
+    /// Seed with the first close; values are emitted from the first bar
+    FirstValueSeed,
+}
+
+/// Arithmetic used for indicator calculations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Precision {
+    /// Exact Decimal arithmetic; bit-reproducible everywhere
+    #[default]
+    Decimal,
+    /// f64 fast path for research sweeps
+    /// 
+    /// Determinism waiver: results are NOT guaranteed bit-identical across
+    /// architectures, compilers or SIMD settings, and must not be used for runs whose
+    /// hashes are compared. Values agree with `Decimal` to roughly 1e-9 relative.
+    Float64,
 }
 
 /// Simulation result
@@ -275,6 +1031,8 @@ pub struct SimulationResult {
     pub equity_curve: Vec<EquityPoint>,
     pub max_drawdown: Decimal,
     pub exposure: Decimal,
+    pub net_exposure: Decimal,
+    #[serde(serialize_with = "crate::canonical::sorted_map")]
     pub attribution: HashMap<String, Decimal>,
 }
 
@@ -282,5 +1040,73 @@ pub struct SimulationResult {
 pub use crate::PerformanceMetrics;
 pub use crate::RunManifest;
 pub use crate::IntrabarPolicy;
+pub use crate::FillModel;
 pub use crate::SlippageMode;
+pub use crate::precision::{BasisPoints, Percent, RoundingMode};
+
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reason_code_serializes_as_stable_snake_case() {
+        let codes = [
+            (ReasonCode::BarClose, "bar_close"),
+            (ReasonCode::Tick, "tick"),
+            (ReasonCode::OneSecondBar, "one_second_bar"),
+            (ReasonCode::LinearInterpolation, "linear_interpolation"),
+            (ReasonCode::NextBarOpen, "next_bar_open"),
+            (ReasonCode::NotionalMin, "notional_min"),
+            (ReasonCode::MinQty, "min_qty"),
+            (ReasonCode::MaxQty, "max_qty"),
+            (ReasonCode::ZeroQtyAfterRounding, "zero_qty_after_rounding"),
+            (ReasonCode::RiskLimit, "risk_limit"),
+            (ReasonCode::Cooldown, "cooldown"),
+            (ReasonCode::InsufficientCapital, "insufficient_capital"),
+            (ReasonCode::Warmup, "warmup"),
+            (ReasonCode::ShortsNotAllowed, "shorts_not_allowed"),
+            (ReasonCode::OutsideSession, "outside_session"),
+            (ReasonCode::MaxOpenPositions, "max_open_positions"),
+            (ReasonCode::OverlappingEntry, "overlapping_entry"),
+            (ReasonCode::PositionOpen, "position_open"),
+        ];
+
+        for (code, name) in codes {
+            let json = serde_json::to_string(&code).unwrap();
+            assert_eq!(json, format!("\"{}\"", name));
+            assert_eq!(code.to_string(), name);
+            assert_eq!(serde_json::from_str::<ReasonCode>(&json).unwrap(), code);
+        }
+
+        assert_eq!(ReasonCode::OneSecondBar.category(), ReasonCategory::Execution);
+        assert_eq!(ReasonCode::MaxQty.category(), ReasonCategory::Rejection);
+    }
+
+    #[test]
+    fn test_trade_timestamp_stays_in_millis_on_the_wire() {
+        let tick = |timestamp| Trade {
+            timestamp,
+            price: Decimal::ONE,
+            quantity: Decimal::ONE,
+            side: TradeSide::Buy,
+            trade_id: "t1".to_string(),
+        };
+
+        // Whole milliseconds serialize exactly as before
+        let json = serde_json::to_value(tick(Timestamp::from_millis(1609459200100))).unwrap();
+        assert_eq!(json["timestamp"], 1609459200100u64);
+        assert!(json.get("timestamp_us").is_none());
+        let old: Trade = serde_json::from_str(
+            r#"{"timestamp":1609459200100,"price":"1","quantity":"1","side":"Buy","trade_id":"t1"}"#
+        ).unwrap();
+        assert_eq!(old.timestamp, Timestamp::from_millis(1609459200100));
+
+        // Sub-millisecond ticks keep their microseconds alongside
+        let json = serde_json::to_value(tick(Timestamp::from_micros(1609459200100250))).unwrap();
+        assert_eq!(json["timestamp"], 1609459200100u64);
+        assert_eq!(json["timestamp_us"], 1609459200100250u64);
+        let back: Trade = serde_json::from_value(json).unwrap();
+        assert_eq!(back.timestamp, Timestamp::from_micros(1609459200100250));
+    }
+}
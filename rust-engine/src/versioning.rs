@@ -2,7 +2,7 @@
 //! 
 //! Implements run manifests, audit chains, and versioning for complete reproducibility.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
 use anyhow::Result;
@@ -10,13 +10,20 @@ use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
-use tracing::{info, warn, error};
+use tracing::{info, warn};
 
+use crate::canonical::{to_canonical_json, SCHEMA_VERSION};
+use crate::ratios::{risk_adjusted_ratios, ReturnMethod};
+use crate::timeframe::{AnnualizationConvention, Timeframe};
 use crate::types::*;
+use crate::{BacktestJob, EngineConfig};
 
 /// Run manifest for complete reproducibility
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RunManifest {
+    /// [`crate::canonical::SCHEMA_VERSION`] the hashes were computed under; 0 predates versioning
+    #[serde(default)]
+    pub schema_version: u32,
     /// Unique run identifier
     pub run_id: String,
     /// Job identifier
@@ -39,6 +46,12 @@ pub struct RunManifest {
     pub result_summary: ResultSummary,
     /// Audit chain
     pub audit_chain: AuditChain,
+    /// Job as submitted, so the run can be re-executed; absent from older manifests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job: Option<BacktestJob>,
+    /// Engine settings the job ran under (warmup, seed, streaming indicators, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine_config: Option<EngineConfig>,
 }
 
 /// Engine version information
@@ -61,6 +74,7 @@ pub struct StrategyInfo {
     pub wasm_hash: String,
     pub source_hash: String,
     pub language: String,
+    #[serde(serialize_with = "crate::canonical::sorted_map")]
     pub parameters: HashMap<String, String>,
     pub required_indicators: Vec<String>,
 }
@@ -75,6 +89,15 @@ pub struct RunConfiguration {
     pub intrabar_policy: String,
     pub slippage_mode: String,
     pub fee_version: String,
+    /// Days per year used for the annualized ratios in the result summary
+    #[serde(default)]
+    pub annualization: AnnualizationConvention,
+    /// Annual risk-free rate subtracted from returns for Sharpe and Sortino
+    #[serde(default)]
+    pub risk_free_rate: Decimal,
+    /// Simple or log per-bar returns behind the annualized ratios
+    #[serde(default)]
+    pub return_method: ReturnMethod,
     pub precision_config: PrecisionConfig,
     pub performance_budget: PerformanceBudget,
 }
@@ -91,6 +114,9 @@ pub struct DataInfo {
     pub gap_count: u32,
     pub total_bars: u64,
     pub total_trades: u64,
+    /// Per-symbol gap handling of the run, including every synthesized bar
+    #[serde(default)]
+    pub quality_reports: Vec<crate::data::DataQualityReport>,
 }
 
 /// Environment information
@@ -121,9 +147,13 @@ pub struct ResultSummary {
     pub total_trades: u32,
     pub total_symbols: u32,
     pub final_equity: Decimal,
-    pub max_drawdown: Decimal,
+    pub max_drawdown: Percent,
     pub sharpe_ratio: Decimal,
-    pub win_rate: Decimal,
+    #[serde(default)]
+    pub sortino_ratio: Decimal,
+    #[serde(default)]
+    pub calmar_ratio: Decimal,
+    pub win_rate: Percent,
     pub profit_factor: Decimal,
     pub execution_time_ms: u64,
     pub bars_processed: u64,
@@ -131,7 +161,7 @@ pub struct ResultSummary {
 }
 
 /// Audit chain for verification
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AuditChain {
     pub manifest_hash: String,
     pub data_hash: String,
@@ -143,6 +173,9 @@ pub struct AuditChain {
 }
 
 /// Precision configuration
+/// 
+/// The top-level precisions are the defaults of symbols without their own
+/// `ExchangeRules`; `symbols` records what each symbol of the run actually used.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PrecisionConfig {
     pub rounding_mode: String,
@@ -150,6 +183,38 @@ pub struct PrecisionConfig {
     pub quantity_precision: u8,
     pub fee_precision: u8,
     pub fp_deterministic: bool,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub symbols: BTreeMap<String, SymbolPrecision>,
+}
+
+impl PrecisionConfig {
+    /// Precisions of `symbol`, falling back to the defaults
+    pub fn for_symbol(&self, symbol: &str) -> SymbolPrecision {
+        self.symbols.get(symbol).cloned().unwrap_or(SymbolPrecision {
+            price_precision: self.price_precision,
+            quantity_precision: self.quantity_precision,
+            fee_precision: self.fee_precision,
+        })
+    }
+}
+
+/// Decimal places one symbol's prices, quantities and fees are rounded to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolPrecision {
+    pub price_precision: u8,
+    pub quantity_precision: u8,
+    pub fee_precision: u8,
+}
+
+impl From<&ExchangeRules> for SymbolPrecision {
+    fn from(rules: &ExchangeRules) -> Self {
+        Self {
+            price_precision: rules.precision_price,
+            quantity_precision: rules.precision_quantity,
+            // Fees are rounded to the price precision
+            fee_precision: rules.precision_price,
+        }
+    }
 }
 
 /// Performance budget
@@ -164,11 +229,27 @@ pub struct PerformanceBudget {
 /// Manifest manager
 pub struct ManifestManager {
     storage_path: String,
+    symbol_rules: HashMap<String, ExchangeRules>,
+    engine_config: Option<EngineConfig>,
 }
 
 impl ManifestManager {
     pub fn new(storage_path: String) -> Self {
-        Self { storage_path }
+        Self { storage_path, symbol_rules: HashMap::new(), engine_config: None }
+    }
+    
+    /// Record `engine_config` in new manifests, along with its symbols' precisions
+    pub fn with_engine_config(mut self, engine_config: EngineConfig) -> Self {
+        self.symbol_rules = engine_config.symbol_rules.clone();
+        self.engine_config = Some(engine_config);
+        self
+    }
+    
+    /// Record precisions from `symbol_rules`, as in `EngineConfig::symbol_rules`;
+    /// symbols it does not list use `ExchangeRules::default()`
+    pub fn with_symbol_rules(mut self, symbol_rules: HashMap<String, ExchangeRules>) -> Self {
+        self.symbol_rules = symbol_rules;
+        self
     }
     
     /// Create a new run manifest
@@ -183,6 +264,7 @@ impl ManifestManager {
         let now = Utc::now();
         
         let manifest = RunManifest {
+            schema_version: SCHEMA_VERSION,
             run_id: run_id.clone(),
             job_id: job.job_id.clone(),
             snapshot_id: job.snapshot_id.clone(),
@@ -199,6 +281,8 @@ impl ManifestManager {
             },
             result_summary: ResultSummary::default(),
             audit_chain: AuditChain::default(),
+            job: Some(job.clone()),
+            engine_config: self.engine_config.clone(),
         };
         
         // Calculate initial hashes
@@ -224,8 +308,17 @@ impl ManifestManager {
         manifest.timestamps.completed_at = Utc::now();
         manifest.timestamps.execution_duration_ms = execution_time_ms;
         
+        // Record the gaps the run actually saw and how they were handled
+        let quality_reports: Vec<_> = result.symbol_results.iter()
+            .filter_map(|r| r.data_quality.clone())
+            .collect();
+        if !quality_reports.is_empty() {
+            manifest.data_info.gap_count = quality_reports.iter().map(|r| r.gaps.len() as u32).sum();
+            manifest.data_info.quality_reports = quality_reports;
+        }
+        
         // Update result summary
-        manifest.result_summary = self.build_result_summary(result, execution_time_ms)?;
+        manifest.result_summary = self.build_result_summary(result, &manifest.configuration, execution_time_ms)?;
         
         // Recalculate audit hashes
         let updated_manifest = self.calculate_audit_hashes(manifest)?;
@@ -249,6 +342,13 @@ impl ManifestManager {
     pub fn verify_manifest(&self, manifest: &RunManifest) -> Result<VerificationResult> {
         let mut issues = Vec::new();
         
+        if manifest.schema_version != SCHEMA_VERSION {
+            issues.push(format!(
+                "Schema version {} differs from the engine's {}; hashes are not comparable",
+                manifest.schema_version, SCHEMA_VERSION
+            ));
+        }
+        
         // Verify audit chain
         let calculated_hashes = self.calculate_audit_hashes(manifest.clone())?;
         
@@ -295,21 +395,34 @@ impl ManifestManager {
     }
     
     /// Reproduce a run from manifest
+    /// 
+    /// Checks that this machine matches the recorded OS, architecture and CPU features,
+    /// that the data snapshot is in `{storage}/snapshots/` and that the strategy module
+    /// is in `{storage}/strategies/`. Every failed check adds a reason to `issues`.
     pub fn reproduce_run(&self, run_id: &str) -> Result<ReproductionResult> {
         let manifest = self.load_manifest(run_id)?;
         
         info!("Reproducing run: {}", run_id);
         
         // Verify we can reproduce the exact environment
-        let environment_match = self.check_environment_compatibility(&manifest.environment)?;
+        let mut issues = self.check_environment_compatibility(&manifest.environment, &manifest.engine_version)?;
+        let environment_match = issues.is_empty();
         
         // Verify data availability
-        let data_available = self.check_data_availability(&manifest.data_info)?;
+        let data_issues = self.check_data_availability(&manifest.snapshot_id, &manifest.data_info)?;
+        let data_available = data_issues.is_empty();
+        issues.extend(data_issues);
         
         // Verify strategy availability
-        let strategy_available = self.check_strategy_availability(&manifest.strategy)?;
+        let strategy_issues = self.check_strategy_availability(&manifest.strategy)?;
+        let strategy_available = strategy_issues.is_empty();
+        issues.extend(strategy_issues);
         
         let can_reproduce = environment_match && data_available && strategy_available;
+        if !can_reproduce {
+            warn!("Run {} cannot be reproduced: {}", run_id, issues.join("; "));
+        }
+        let reproduction_instructions = self.generate_reproduction_instructions(&manifest)?;
         
         Ok(ReproductionResult {
             can_reproduce,
@@ -317,13 +430,70 @@ impl ManifestManager {
             environment_match,
             data_available,
             strategy_available,
-            reproduction_instructions: self.generate_reproduction_instructions(&manifest)?,
+            issues,
+            reproduction_instructions,
+        })
+    }
+    
+    /// Re-execute a stored run on `engine` and compare its result hash to the manifest's
+    /// 
+    /// The job is rebuilt from the manifest, so this needs a manifest that recorded one.
+    /// When the manifest also recorded its engine settings, `engine` must run with the
+    /// same ones.
+    pub async fn execute_reproduction(&self, run_id: &str, engine: &mut crate::BacktestEngine) -> Result<ReproductionCheck> {
+        let manifest = self.load_manifest(run_id)?;
+        let job = manifest.job.clone().ok_or_else(|| {
+            anyhow::anyhow!("Manifest {} does not record its job and cannot be re-executed", run_id)
+        })?;
+        if let Some(recorded) = &manifest.engine_config {
+            // Compared as JSON, where map order does not matter
+            if serde_json::to_value(recorded)? != serde_json::to_value(engine.config())? {
+                anyhow::bail!("Run {} was executed with a different engine config: {:?}", run_id, recorded);
+            }
+        }
+        
+        info!("Re-executing run: {}", run_id);
+        let result = engine.execute_job(job).await?;
+        self.compare_reproduction(&manifest, &result)
+    }
+    
+    /// Compare a re-executed result against the `result_hash` stored in `manifest`
+    /// 
+    /// Execution time and throughput are measurements rather than results, so the
+    /// stored values are reused and only the outcome of the run is compared.
+    pub fn compare_reproduction(&self, manifest: &RunManifest, result: &BacktestResult) -> Result<ReproductionCheck> {
+        let mut summary = self.build_result_summary(result, &manifest.configuration, manifest.result_summary.execution_time_ms)?;
+        summary.throughput_bars_per_sec = manifest.result_summary.throughput_bars_per_sec;
+        let reproduced_result_hash = self.calculate_hash(&to_canonical_json(&summary)?);
+        
+        let passed = reproduced_result_hash == manifest.audit_chain.result_hash;
+        if !passed {
+            warn!(
+                "Run {} did not reproduce: result hash {} vs stored {}",
+                manifest.run_id, reproduced_result_hash, manifest.audit_chain.result_hash
+            );
+        }
+        
+        Ok(ReproductionCheck {
+            run_id: manifest.run_id.clone(),
+            passed,
+            expected_result_hash: manifest.audit_chain.result_hash.clone(),
+            reproduced_result_hash,
         })
     }
     
-    /// Helper methods
+    // Helper methods
     
     fn build_run_configuration(&self, job: &BacktestJob) -> Result<RunConfiguration> {
+        let defaults = SymbolPrecision::from(&ExchangeRules::default());
+        let symbols = job.symbols.iter()
+            .map(|symbol| {
+                let precision = self.symbol_rules.get(symbol)
+                    .map_or_else(|| defaults.clone(), SymbolPrecision::from);
+                (symbol.clone(), precision)
+            })
+            .collect();
+        
         Ok(RunConfiguration {
             symbols: job.symbols.clone(),
             timeframe: job.timeframe.clone(),
@@ -332,12 +502,16 @@ impl ManifestManager {
             intrabar_policy: format!("{:?}", job.intrabar_policy),
             slippage_mode: format!("{:?}", job.slippage_mode),
             fee_version: job.fee_version.clone(),
+            annualization: job.annualization,
+            risk_free_rate: job.risk_free_rate,
+            return_method: job.return_method,
             precision_config: PrecisionConfig {
                 rounding_mode: "nearest-even".to_string(),
-                price_precision: 8,
-                quantity_precision: 8,
-                fee_precision: 8,
+                price_precision: defaults.price_precision,
+                quantity_precision: defaults.quantity_precision,
+                fee_precision: defaults.fee_precision,
                 fp_deterministic: true,
+                symbols,
             },
             performance_budget: PerformanceBudget {
                 max_execution_time_ms: 300_000, // 5 minutes
@@ -360,38 +534,41 @@ impl ManifestManager {
             gap_count: 5,
             total_bars: 1_000_000,
             total_trades: 5_000_000,
+            quality_reports: Vec::new(),
         })
     }
     
     fn calculate_audit_hashes(&self, mut manifest: RunManifest) -> Result<RunManifest> {
+        // All inputs use the canonical serialization so hashes are stable across runs
+        
         // Calculate manifest hash (excluding audit chain)
         let mut temp_manifest = manifest.clone();
         temp_manifest.audit_chain = AuditChain::default();
-        let manifest_json = serde_json::to_string(&temp_manifest)?;
+        let manifest_json = to_canonical_json(&temp_manifest)?;
         manifest.audit_chain.manifest_hash = self.calculate_hash(&manifest_json);
         
         // Calculate data hash
-        let data_json = serde_json::to_string(&manifest.data_info)?;
+        let data_json = to_canonical_json(&manifest.data_info)?;
         manifest.audit_chain.data_hash = self.calculate_hash(&data_json);
         
         // Calculate engine hash
-        let engine_json = serde_json::to_string(&manifest.engine_version)?;
+        let engine_json = to_canonical_json(&manifest.engine_version)?;
         manifest.audit_chain.engine_hash = self.calculate_hash(&engine_json);
         
         // Calculate strategy hash
-        let strategy_json = serde_json::to_string(&manifest.strategy)?;
+        let strategy_json = to_canonical_json(&manifest.strategy)?;
         manifest.audit_chain.strategy_hash = self.calculate_hash(&strategy_json);
         
         // Calculate config hash
-        let config_json = serde_json::to_string(&manifest.configuration)?;
+        let config_json = to_canonical_json(&manifest.configuration)?;
         manifest.audit_chain.config_hash = self.calculate_hash(&config_json);
         
         // Calculate result hash
-        let result_json = serde_json::to_string(&manifest.result_summary)?;
+        let result_json = to_canonical_json(&manifest.result_summary)?;
         manifest.audit_chain.result_hash = self.calculate_hash(&result_json);
         
         // Calculate verification hash
-        let verification_json = serde_json::to_string(&manifest.audit_chain)?;
+        let verification_json = to_canonical_json(&manifest.audit_chain)?;
         manifest.audit_chain.verification_hash = self.calculate_hash(&verification_json);
         
         Ok(manifest)
@@ -403,21 +580,34 @@ impl ManifestManager {
         format!("{:x}", hasher.finalize())
     }
     
-    fn build_result_summary(&self, result: &BacktestResult, execution_time_ms: u64) -> Result<ResultSummary> {
+    fn build_result_summary(
+        &self,
+        result: &BacktestResult,
+        configuration: &RunConfiguration,
+        execution_time_ms: u64,
+    ) -> Result<ResultSummary> {
         let total_trades = result.symbol_results.iter().map(|r| r.trades.len() as u32).sum();
         let total_symbols = result.symbol_results.len() as u32;
         
-        // Calculate final equity
-        let final_equity = result.symbol_results.iter()
-            .filter_map(|r| r.equity_curve.last())
-            .map(|p| p.equity)
-            .sum();
-        
         // Calculate max drawdown
         let max_drawdown = result.symbol_results.iter()
             .map(|r| r.drawdown)
             .max()
-            .unwrap_or(Decimal::ZERO);
+            .map(Percent::from_ratio)
+            .unwrap_or(Percent::ZERO);
+        
+        // Symbols trade one account, each result holding its equity at the symbol's bars;
+        // merged by timestamp they give the account's curve, which is never summed
+        let mut account_equity = BTreeMap::new();
+        for point in result.symbol_results.iter().flat_map(|r| &r.equity_curve) {
+            account_equity.insert(point.timestamp, point.equity);
+        }
+        let equity: Vec<Decimal> = account_equity.into_values().collect();
+        let final_equity = equity.last().copied().unwrap_or(Decimal::ZERO);
+        
+        // Annualized ratios of the account's equity
+        let timeframe: Timeframe = configuration.timeframe.parse()?;
+        let ratios = risk_adjusted_ratios(&equity, &timeframe, configuration.annualization, configuration.risk_free_rate, configuration.return_method);
         
         // Calculate performance metrics
         let bars_processed = result.performance_metrics.bars_processed;
@@ -432,8 +622,10 @@ impl ManifestManager {
             total_symbols,
             final_equity,
             max_drawdown,
-            sharpe_ratio: Decimal::ZERO, // Would calculate actual Sharpe ratio
-            win_rate: Decimal::ZERO,    // Would calculate actual win rate
+            sharpe_ratio: ratios.sharpe,
+            sortino_ratio: ratios.sortino,
+            calmar_ratio: ratios.calmar,
+            win_rate: Percent::ZERO,    // Would calculate actual win rate
             profit_factor: Decimal::ZERO, // Would calculate actual profit factor
             execution_time_ms,
             bars_processed,
@@ -464,20 +656,54 @@ impl ManifestManager {
         }
     }
     
-    fn check_environment_compatibility(&self, environment: &EnvironmentInfo) -> Result<bool> {
-        // Check if current environment matches the manifest environment
-        // This would check OS, architecture, CPU features, etc.
-        Ok(true) // Simplified for now
+    fn check_environment_compatibility(&self, environment: &EnvironmentInfo, engine_version: &EngineVersion) -> Result<Vec<String>> {
+        let mut issues = Vec::new();
+        
+        if environment.os != std::env::consts::OS {
+            issues.push(format!("OS mismatch: run used {}, this machine is {}", environment.os, std::env::consts::OS));
+        }
+        if environment.architecture != std::env::consts::ARCH {
+            issues.push(format!(
+                "Architecture mismatch: run used {}, this machine is {}",
+                environment.architecture, std::env::consts::ARCH
+            ));
+        }
+        
+        let available = current_cpu_features();
+        let missing: Vec<&str> = engine_version.cpu_features.iter()
+            .map(String::as_str)
+            .filter(|feature| !available.iter().any(|a| a == feature))
+            .collect();
+        if !missing.is_empty() {
+            issues.push(format!("CPU features not available on this machine: {}", missing.join(", ")));
+        }
+        
+        Ok(issues)
     }
     
-    fn check_data_availability(&self, data_info: &DataInfo) -> Result<bool> {
-        // Check if the required data snapshot is available
-        Ok(true) // Simplified for now
+    fn check_data_availability(&self, snapshot_id: &str, data_info: &DataInfo) -> Result<Vec<String>> {
+        let path = self.get_snapshot_path(snapshot_id);
+        if Path::new(&path).is_dir() {
+            Ok(Vec::new())
+        } else {
+            Ok(vec![format!("Data snapshot {} ({}) not found at {}", snapshot_id, data_info.snapshot_name, path)])
+        }
     }
     
-    fn check_strategy_availability(&self, strategy: &StrategyInfo) -> Result<bool> {
-        // Check if the strategy WASM is available
-        Ok(true) // Simplified for now
+    fn check_strategy_availability(&self, strategy: &StrategyInfo) -> Result<Vec<String>> {
+        if strategy.wasm_hash.trim().is_empty() {
+            return Ok(vec![format!("Strategy {} has no WASM hash", strategy.name)]);
+        }
+        
+        let path = self.get_strategy_path(&strategy.wasm_hash);
+        if Path::new(&path).is_file() {
+            Ok(Vec::new())
+        } else {
+            Ok(vec![format!(
+                "Strategy {} (hash: {}) not found in strategy store at {}",
+                strategy.name, strategy.wasm_hash, path
+            )])
+        }
     }
     
     fn generate_reproduction_instructions(&self, manifest: &RunManifest) -> Result<String> {
@@ -515,6 +741,14 @@ impl ManifestManager {
     fn get_manifest_path(&self, run_id: &str) -> String {
         format!("{}/manifests/{}.json", self.storage_path, run_id)
     }
+    
+    fn get_snapshot_path(&self, snapshot_id: &str) -> String {
+        format!("{}/snapshots/{}", self.storage_path, snapshot_id)
+    }
+    
+    fn get_strategy_path(&self, wasm_hash: &str) -> String {
+        format!("{}/strategies/{}.wasm", self.storage_path, wasm_hash)
+    }
 }
 
 /// Verification result
@@ -533,9 +767,54 @@ pub struct ReproductionResult {
     pub environment_match: bool,
     pub data_available: bool,
     pub strategy_available: bool,
+    /// Why the run cannot be reproduced; empty when `can_reproduce`
+    pub issues: Vec<String>,
     pub reproduction_instructions: String,
 }
 
+/// CPU features of this machine that a manifest may require, by their `target_feature` names
+pub fn current_cpu_features() -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut features: Vec<&str> = Vec::new();
+    
+    #[cfg(target_arch = "x86_64")]
+    {
+        for (name, detected) in [
+            ("sse2", is_x86_feature_detected!("sse2")),
+            ("sse4.1", is_x86_feature_detected!("sse4.1")),
+            ("sse4.2", is_x86_feature_detected!("sse4.2")),
+            ("avx", is_x86_feature_detected!("avx")),
+            ("avx2", is_x86_feature_detected!("avx2")),
+            ("fma", is_x86_feature_detected!("fma")),
+            ("bmi2", is_x86_feature_detected!("bmi2")),
+            ("avx512f", is_x86_feature_detected!("avx512f")),
+        ] {
+            if detected {
+                features.push(name);
+            }
+        }
+    }
+    
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            features.push("neon");
+        }
+    }
+    
+    features.into_iter().map(str::to_string).collect()
+}
+
+/// Outcome of re-executing a stored run
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReproductionCheck {
+    pub run_id: String,
+    /// Whether the re-executed result hash matches the stored one
+    pub passed: bool,
+    pub expected_result_hash: String,
+    pub reproduced_result_hash: String,
+}
+
 /// Budget check result
 #[derive(Debug, Clone)]
 pub struct BudgetCheckResult {
@@ -549,9 +828,11 @@ impl Default for ResultSummary {
             total_trades: 0,
             total_symbols: 0,
             final_equity: Decimal::ZERO,
-            max_drawdown: Decimal::ZERO,
+            max_drawdown: Percent::ZERO,
             sharpe_ratio: Decimal::ZERO,
-            win_rate: Decimal::ZERO,
+            sortino_ratio: Decimal::ZERO,
+            calmar_ratio: Decimal::ZERO,
+            win_rate: Percent::ZERO,
             profit_factor: Decimal::ZERO,
             execution_time_ms: 0,
             bars_processed: 0,
@@ -560,17 +841,337 @@ impl Default for ResultSummary {
     }
 }
 
-impl Default for AuditChain {
-    fn default() -> Self {
-        Self {
-            manifest_hash: String::new(),
-            data_hash: String::new(),
-            engine_hash: String::new(),
-            strategy_hash: String::new(),
-            config_hash: String::new(),
-            result_hash: String::new(),
-            verification_hash: String::new(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn manifest(parameters: HashMap<String, String>) -> RunManifest {
+        let timestamp = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap().with_timezone(&Utc);
+
+        RunManifest {
+            schema_version: SCHEMA_VERSION,
+            run_id: "run-1".to_string(),
+            job_id: "job-1".to_string(),
+            snapshot_id: "snap-1".to_string(),
+            engine_version: EngineVersion {
+                version: "0.1.0".to_string(),
+                git_commit: "abc123".to_string(),
+                build_timestamp: timestamp,
+                rust_version: "1.75.0".to_string(),
+                cpu_features: vec!["avx2".to_string()],
+                fp_flags: "strict".to_string(),
+                simd_enabled: true,
+            },
+            strategy: StrategyInfo {
+                name: "ema_atr".to_string(),
+                version: "1.0.0".to_string(),
+                wasm_hash: "wasm".to_string(),
+                source_hash: "source".to_string(),
+                language: "rust".to_string(),
+                parameters,
+                required_indicators: vec!["ema".to_string(), "atr".to_string()],
+            },
+            configuration: RunConfiguration {
+                symbols: vec!["BTCUSDT".to_string()],
+                timeframe: "1m".to_string(),
+                start_time: 1609459200000,
+                end_time: 1609545600000,
+                intrabar_policy: "ExactTrades".to_string(),
+                slippage_mode: "None".to_string(),
+                fee_version: "v1".to_string(),
+                annualization: AnnualizationConvention::Calendar365,
+                risk_free_rate: Decimal::ZERO,
+                return_method: ReturnMethod::Arithmetic,
+                precision_config: PrecisionConfig {
+                    rounding_mode: "nearest-even".to_string(),
+                    price_precision: 8,
+                    quantity_precision: 8,
+                    fee_precision: 8,
+                    fp_deterministic: true,
+                    symbols: BTreeMap::new(),
+                },
+                performance_budget: PerformanceBudget {
+                    max_execution_time_ms: 300_000,
+                    min_throughput_bars_per_sec: 1000.0,
+                    max_memory_gb: 8.0,
+                    max_cpu_percent: 80.0,
+                },
+            },
+            data_info: DataInfo {
+                snapshot_name: "snapshot_snap-1".to_string(),
+                data_start: 1609459200000,
+                data_end: 1609545600000,
+                symbols: vec!["BTCUSDT".to_string()],
+                exchanges: vec!["binance".to_string()],
+                data_quality_score: 0.99,
+                gap_count: 0,
+                total_bars: 1440,
+                total_trades: 0,
+                quality_reports: Vec::new(),
+            },
+            environment: EnvironmentInfo {
+                os: "linux".to_string(),
+                architecture: "x86_64".to_string(),
+                cpu_model: "test".to_string(),
+                cpu_cores: 8,
+                memory_gb: 16,
+                rust_toolchain: "stable".to_string(),
+                go_version: "1.21".to_string(),
+                clickhouse_version: "23.8".to_string(),
+            },
+            timestamps: TimestampInfo {
+                created_at: timestamp,
+                started_at: timestamp,
+                completed_at: timestamp,
+                execution_duration_ms: 0,
+            },
+            result_summary: ResultSummary::default(),
+            audit_chain: AuditChain::default(),
+            job: None,
+            engine_config: None,
         }
     }
-}
 
+    fn backtest_result(final_equity: Decimal) -> BacktestResult {
+        let equity_curve = [dec!(10000), dec!(10050), final_equity].iter().enumerate()
+            .map(|(i, equity)| EquityPoint {
+                timestamp: 1609459200000 + i as u64 * 60_000,
+                equity: *equity,
+                realized_equity: *equity,
+                unrealized_pnl: Decimal::ZERO,
+                drawdown: Decimal::ZERO,
+                exposure: Decimal::ZERO,
+                net_exposure: Decimal::ZERO,
+            })
+            .collect();
+
+        BacktestResult {
+            job_id: "job-1".to_string(),
+            execution_time_ms: 0,
+            symbol_results: vec![SymbolResult {
+                symbol: "BTCUSDT".to_string(),
+                trades: Vec::new(),
+                positions: Vec::new(),
+                equity_curve,
+                drawdown: Decimal::ZERO,
+                exposure: Decimal::ZERO,
+                net_exposure: Decimal::ZERO,
+                attribution: HashMap::new(),
+                trade_table: None,
+                data_quality: None,
+            }],
+            performance_metrics: crate::PerformanceMetrics::new(),
+            manifest: crate::RunManifest::from_job(&crate::tests::job()),
+        }
+    }
+
+    #[test]
+    fn test_audit_hashes_independent_of_map_order() {
+        let manager = ManifestManager::new("/tmp".to_string());
+        let entries: Vec<(String, String)> = (0..32)
+            .map(|i| (format!("param_{}", i), i.to_string()))
+            .collect();
+
+        let first = manager.calculate_audit_hashes(manifest(entries.iter().cloned().collect())).unwrap();
+        let second = manager.calculate_audit_hashes(manifest(entries.iter().rev().cloned().collect())).unwrap();
+
+        assert_eq!(first.audit_chain.manifest_hash, second.audit_chain.manifest_hash);
+        assert_eq!(first.audit_chain.strategy_hash, second.audit_chain.strategy_hash);
+        assert_eq!(first.audit_chain.config_hash, second.audit_chain.config_hash);
+        assert_eq!(first.audit_chain.result_hash, second.audit_chain.result_hash);
+        assert_eq!(first.audit_chain.verification_hash, second.audit_chain.verification_hash);
+
+        // A save/load round trip rebuilds the HashMap; verification must still pass
+        let reloaded: RunManifest = serde_json::from_str(&serde_json::to_string(&first).unwrap()).unwrap();
+        let verification = manager.verify_manifest(&reloaded).unwrap();
+        assert!(verification.issues.iter().all(|i| !i.contains("hash")), "{:?}", verification.issues);
+    }
+
+    #[test]
+    fn test_precision_config_records_each_symbols_rules() {
+        let mut symbol_rules = HashMap::new();
+        symbol_rules.insert("BTCUSDT".to_string(), ExchangeRules {
+            precision_price: 2,
+            precision_quantity: 5,
+            ..ExchangeRules::default()
+        });
+        let manager = ManifestManager::new("/tmp".to_string()).with_symbol_rules(symbol_rules);
+        let mut job = crate::tests::job();
+        job.symbols = vec!["BTCUSDT".to_string(), "ETHUSDT".to_string()];
+
+        let precision = manager.build_run_configuration(&job).unwrap().precision_config;
+        assert_eq!(precision.for_symbol("BTCUSDT"), SymbolPrecision { price_precision: 2, quantity_precision: 5, fee_precision: 2 });
+        // Symbols without rules record the defaults they ran on
+        assert_eq!(precision.symbols["ETHUSDT"], SymbolPrecision { price_precision: 8, quantity_precision: 8, fee_precision: 8 });
+        assert_eq!(precision.for_symbol("SOLUSDT"), precision.symbols["ETHUSDT"]);
+    }
+
+    #[test]
+    fn test_reproduction_compares_result_hashes() {
+        let storage = std::env::temp_dir().join(format!("backtest_manifests_{}", std::process::id()));
+        fs::create_dir_all(storage.join("manifests")).unwrap();
+        let manager = ManifestManager::new(storage.to_string_lossy().into_owned());
+
+        let mut stored = manifest(HashMap::new());
+        stored.job = Some(crate::tests::job());
+        manager.save_manifest(&stored).unwrap();
+        let stored = manager.update_manifest_with_results("run-1", &backtest_result(dec!(10100)), 1234).unwrap();
+        assert_eq!(manager.load_manifest("run-1").unwrap().job.unwrap().job_id, "job-1");
+
+        // The same outcome reproduces even though it ran at a different speed
+        let check = manager.compare_reproduction(&stored, &backtest_result(dec!(10100))).unwrap();
+        assert!(check.passed, "{:?}", check);
+        assert_eq!(check.reproduced_result_hash, stored.audit_chain.result_hash);
+
+        let check = manager.compare_reproduction(&stored, &backtest_result(dec!(10101))).unwrap();
+        assert!(!check.passed);
+        assert_eq!(check.expected_result_hash, stored.audit_chain.result_hash);
+        assert_ne!(check.reproduced_result_hash, check.expected_result_hash);
+
+        fs::remove_dir_all(&storage).unwrap();
+    }
+
+    #[test]
+    fn test_result_summary_reads_one_account_equity_curve() {
+        let manager = ManifestManager::new("/tmp".to_string());
+        let configuration = manifest(HashMap::new()).configuration;
+        let single = backtest_result(dec!(10100));
+        let mut shared = backtest_result(dec!(10100));
+        let eth = SymbolResult { symbol: "ETHUSDT".to_string(), ..shared.symbol_results[0].clone() };
+        shared.symbol_results.push(eth);
+
+        // Two symbols on one account report its curve, not twice its equity
+        let single = manager.build_result_summary(&single, &configuration, 0).unwrap();
+        let shared = manager.build_result_summary(&shared, &configuration, 0).unwrap();
+        assert_eq!(shared.final_equity, dec!(10100));
+        assert_eq!(shared.sharpe_ratio, single.sharpe_ratio);
+        assert_eq!(shared.calmar_ratio, single.calmar_ratio);
+    }
+
+    /// Storage holding `stored`, its snapshot and its strategy module, with the
+    /// environment rewritten to match this machine
+    fn reproducible_storage(name: &str, stored: &mut RunManifest) -> (std::path::PathBuf, ManifestManager) {
+        let storage = std::env::temp_dir().join(format!("backtest_reproduce_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&storage);
+        fs::create_dir_all(storage.join("manifests")).unwrap();
+        fs::create_dir_all(storage.join("snapshots").join(&stored.snapshot_id)).unwrap();
+        fs::create_dir_all(storage.join("strategies")).unwrap();
+        fs::write(storage.join("strategies").join(format!("{}.wasm", stored.strategy.wasm_hash)), b"\0asm").unwrap();
+
+        stored.environment.os = std::env::consts::OS.to_string();
+        stored.environment.architecture = std::env::consts::ARCH.to_string();
+        stored.engine_version.cpu_features = current_cpu_features();
+        (storage.clone(), ManifestManager::new(storage.to_string_lossy().into_owned()))
+    }
+
+    #[tokio::test]
+    async fn test_executed_run_reproduces_from_its_manifest() {
+        use crate::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+
+        let bars = SyntheticDataGenerator::new(SyntheticDataConfig::default(), 4).generate_bars();
+        let config = EngineConfig {
+            prealloc_size: 0,
+            random_seed: 7,
+            warmup_bars: Some(3),
+            streaming_indicators: true,
+            ..EngineConfig::default()
+        };
+        let engine = |config: EngineConfig| crate::BacktestEngine::new(config).unwrap().with_bars("BTCUSDT", bars.clone());
+        let job = crate::tests::job();
+        let result = engine(config.clone()).execute_job(job.clone()).await.unwrap();
+
+        let storage = std::env::temp_dir().join(format!("backtest_execute_reproduction_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&storage);
+        fs::create_dir_all(storage.join("manifests")).unwrap();
+        let manager = ManifestManager::new(storage.to_string_lossy().into_owned()).with_engine_config(config.clone());
+        let mut stored = manifest(HashMap::new());
+        stored.job = Some(job);
+        stored.engine_config = manager.engine_config.clone();
+        manager.save_manifest(&stored).unwrap();
+        manager.update_manifest_with_results("run-1", &result, result.execution_time_ms).unwrap();
+
+        let recorded = manager.load_manifest("run-1").unwrap().engine_config.unwrap();
+        assert_eq!((recorded.random_seed, recorded.warmup_bars, recorded.streaming_indicators), (7, Some(3), true));
+
+        let check = manager.execute_reproduction("run-1", &mut engine(config.clone())).await.unwrap();
+        assert!(check.passed, "{:?}", check);
+
+        // An engine configured differently is refused rather than compared
+        let reseeded = EngineConfig { random_seed: 8, ..config };
+        assert!(manager.execute_reproduction("run-1", &mut engine(reseeded)).await.is_err());
+
+        fs::remove_dir_all(&storage).unwrap();
+    }
+
+    #[test]
+    fn test_missing_strategy_prevents_reproduction() {
+        let mut stored = manifest(HashMap::new());
+        let (storage, manager) = reproducible_storage("strategy", &mut stored);
+        manager.save_manifest(&stored).unwrap();
+        let reproduction = manager.reproduce_run("run-1").unwrap();
+        assert!(reproduction.can_reproduce, "{:?}", reproduction.issues);
+        assert!(reproduction.issues.is_empty());
+
+        stored.strategy.wasm_hash = "missing".to_string();
+        manager.save_manifest(&stored).unwrap();
+        let reproduction = manager.reproduce_run("run-1").unwrap();
+        assert!(!reproduction.can_reproduce);
+        assert!(reproduction.environment_match && reproduction.data_available);
+        assert!(!reproduction.strategy_available);
+        assert_eq!(reproduction.issues.len(), 1);
+        assert!(reproduction.issues[0].contains("missing"), "{:?}", reproduction.issues);
+
+        fs::remove_dir_all(&storage).unwrap();
+    }
+
+    #[test]
+    fn test_incompatible_cpu_feature_prevents_reproduction() {
+        let mut stored = manifest(HashMap::new());
+        let (storage, manager) = reproducible_storage("cpu", &mut stored);
+        stored.engine_version.cpu_features.push("quantum-fpu".to_string());
+        manager.save_manifest(&stored).unwrap();
+
+        let reproduction = manager.reproduce_run("run-1").unwrap();
+        assert!(!reproduction.can_reproduce);
+        assert!(!reproduction.environment_match);
+        assert!(reproduction.data_available && reproduction.strategy_available);
+        assert_eq!(reproduction.issues, vec!["CPU features not available on this machine: quantum-fpu".to_string()]);
+
+        fs::remove_dir_all(&storage).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_csv_export_writes_manifest_sidecar() {
+        use crate::export::{manifest_sidecar_path, ExportConfig, ExportMetadata, TradeTableExporter};
+
+        let manager = ManifestManager::new("/tmp".to_string());
+        let manifest = manager.calculate_audit_hashes(manifest(HashMap::new())).unwrap();
+        let output_path = std::env::temp_dir()
+            .join(format!("backtest_export_{}.csv", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+
+        let config = ExportConfig {
+            output_path: Some(output_path.clone()),
+            export_manifest_alongside_results: true,
+            ..ExportConfig::default()
+        };
+        let result = crate::trade_table::TradeTableGenerator::new().generate_result();
+
+        // Asking for the sidecar without a manifest is a configuration error
+        assert!(TradeTableExporter::new(config.clone()).export(&result).await.is_err());
+
+        TradeTableExporter::new(config).with_manifest(&manifest).export(&result).await.unwrap();
+        let sidecar_path = manifest_sidecar_path(&output_path);
+        let sidecar: ExportMetadata = serde_json::from_str(&fs::read_to_string(&sidecar_path).unwrap()).unwrap();
+        fs::remove_file(&output_path).unwrap();
+        fs::remove_file(&sidecar_path).unwrap();
+
+        assert_eq!(sidecar.run_id, "run-1");
+        assert_eq!(sidecar, ExportMetadata::from(&manifest));
+        assert_eq!(sidecar.strategy_hash, manifest.audit_chain.strategy_hash);
+        assert_eq!(sidecar.config_hash, manifest.audit_chain.config_hash);
+        assert!(!sidecar.config_hash.is_empty());
+    }
+}
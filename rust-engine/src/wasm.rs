@@ -2,27 +2,81 @@
 //! 
 //! Provides a WebAssembly runtime for executing trading strategies with
 //! deterministic behavior and sandboxed execution environment.
+//! 
+//! Strategies talk to the engine through JSON documents in their linear memory. A
+//! module exports `memory`, [`METADATA_EXPORT`] and
+//! 
+//! - [`ON_BAR_EXPORT`] `(handle: i32, input_ptr: i32, input_len: i32) -> i64`: react to one
+//!   bar and return the location of a JSON array of [`StrategySignal`]s
+//! 
+//! and optionally:
+//! 
+//! - [`ALLOC_EXPORT`] `(len: i32) -> i32`: reserve `len` bytes for the engine to write into
+//! - [`INIT_EXPORT`] `(config_ptr: i32, config_len: i32) -> i32`: build an instance from the
+//!   job's strategy parameters and return its handle
+//! 
+//! Exports returning `i64` pack a location as `(ptr << 32) | len`.
 
 use std::collections::HashMap;
-use anyhow::Result;
+use std::sync::Mutex;
+use anyhow::{Context, Result};
 use wasmtime::*;
 use rust_decimal::prelude::*;
-use tracing::{debug, warn, error};
+use serde::Deserialize;
+use tracing::debug;
 
+use crate::indicators::IndicatorRegistry;
 use crate::types::*;
 
 /// WASM runtime for strategy execution
 pub struct WasmRuntime {
     engine: Engine,
-    store: Store<()>,
 }
 
 /// Strategy interface for WASM execution
+/// 
+/// Each strategy owns its store, so strategies loaded with different parameters never
+/// share state. The compiled module and parameters are kept so [`Strategy::reset`] can
+/// start over from a fresh instance.
 pub struct Strategy {
-    instance: Instance,
+    engine: Engine,
+    module: Module,
+    params: serde_json::Value,
     memory: Memory,
+    metadata: StrategyMetadata,
+    alloc: Option<TypedFunc<i32, i32>>,
+    on_bar: TypedFunc<(i32, i32, i32), i64>,
+    /// Behind a lock because the simulator holds the strategy by shared reference
+    state: Mutex<StrategyState>,
+}
+
+/// Mutable side of a [`Strategy`]
+struct StrategyState {
+    store: Store<()>,
+    /// Handle returned by [`INIT_EXPORT`], 0 if the module has none
+    handle: i32,
+    /// Buffer reused for every [`ON_BAR_EXPORT`] input: (ptr, capacity)
+    input_buffer: Option<(i32, usize)>,
 }
 
+/// Export every strategy module must provide to describe itself
+/// 
+/// Takes no arguments and returns `(ptr << 32) | len` of a UTF-8 JSON document in the
+/// module's memory; see [`abi::WasmStrategyMetadata`] for its shape.
+pub const METADATA_EXPORT: &str = "strategy_get_metadata";
+
+/// Export reserving memory for engine-written inputs
+pub const ALLOC_EXPORT: &str = "strategy_alloc";
+
+/// Export receiving the job's `strategy_params` as JSON
+pub const INIT_EXPORT: &str = "strategy_init";
+
+/// Export called once per bar for signals
+pub const ON_BAR_EXPORT: &str = "strategy_on_bar";
+
+/// Fuel each call into a strategy starts with; the meter is reset, not topped up
+const CALL_FUEL: u64 = 10_000_000;
+
 impl WasmRuntime {
     pub fn new() -> Result<Self> {
         // Configure WASM engine for deterministic execution
@@ -31,67 +85,197 @@ impl WasmRuntime {
         config.consume_fuel(true); // Enable fuel for deterministic execution
         
         let engine = Engine::new(&config)?;
-        let store = Store::new(&engine, ());
         
-        Ok(Self { engine, store })
+        Ok(Self { engine })
     }
     
-    /// Load a strategy from WASM bytecode
-    pub async fn load_strategy(&mut self, wasm_hash: &str) -> Result<Strategy> {
+    /// Load a strategy from WASM bytecode, initialized with `params`
+    pub async fn load_strategy(&mut self, wasm_hash: &str, params: &serde_json::Value) -> Result<Strategy> {
         debug!("Loading strategy with hash: {}", wasm_hash);
         
         // In a real implementation, this would load WASM bytecode from storage
         // For now, create a mock strategy
         let wasm_bytes = self.create_mock_strategy()?;
         
-        let module = Module::new(&self.engine, &wasm_bytes)?;
-        let instance = Instance::new(&mut self.store, &module, &[])?;
-        
-        let memory = instance.get_memory(&mut self.store, "memory")
-            .ok_or_else(|| anyhow::anyhow!("Strategy must export memory"))?;
-        
-        Ok(Strategy { instance, memory })
+        self.load_strategy_bytes(&wasm_bytes, params)
+    }
+    
+    /// Instantiate a compiled strategy module, read its metadata and initialize it
+    /// 
+    /// `params` is passed to [`INIT_EXPORT`] as JSON, so one module can serve a whole
+    /// parameter sweep. Modules without the export only accept `null` parameters.
+    pub fn load_strategy_bytes(&mut self, wasm_bytes: &[u8], params: &serde_json::Value) -> Result<Strategy> {
+        let module = Module::new(&self.engine, wasm_bytes)?;
+        Strategy::instantiate(&self.engine, module, params.clone())
     }
     
     /// Create a mock strategy for testing
     fn create_mock_strategy(&self) -> Result<Vec<u8>> {
         // This would compile a Rust or TypeScript strategy to WASM
-        // For now, return empty bytes
-        Ok(Vec::new())
+        // For now, return a minimal module that only describes itself
+        let metadata = serde_json::json!({
+            "name": "mock_strategy",
+            "version": "1.0.0",
+            "description": "Mock strategy for testing",
+            "author": "system",
+            "required_indicators": ["ema", "rsi"],
+        });
+        Ok(abi::metadata_module(&serde_json::to_vec(&metadata)?))
     }
 }
 
 impl Strategy {
-    /// Get required indicators for this strategy
+    /// Instantiate `module` in a new store, read its metadata and initialize it with `params`
+    fn instantiate(engine: &Engine, module: Module, params: serde_json::Value) -> Result<Self> {
+        let mut store = Store::new(engine, ());
+        let instance = Instance::new(&mut store, &module, &[])?;
+        
+        let memory = instance.get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow::anyhow!("Strategy must export memory"))?;
+        let metadata = read_metadata(&mut store, &instance, &memory)?;
+        debug!("Loaded strategy {} requiring {:?}", metadata.name, metadata.required_indicators);
+        
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, ALLOC_EXPORT).ok();
+        let on_bar = instance.get_typed_func::<(i32, i32, i32), i64>(&mut store, ON_BAR_EXPORT)
+            .with_context(|| format!("Strategy {} must export {}", metadata.name, ON_BAR_EXPORT))?;
+        let init = instance.get_typed_func::<(i32, i32), i32>(&mut store, INIT_EXPORT).ok();
+        
+        let mut strategy = Strategy {
+            engine: engine.clone(),
+            module,
+            params,
+            memory,
+            metadata,
+            alloc,
+            on_bar,
+            state: Mutex::new(StrategyState { store, handle: 0, input_buffer: None }),
+        };
+        
+        match init {
+            Some(init) => {
+                let config = serde_json::to_vec(&strategy.params)?;
+                let state = strategy.state.get_mut().map_err(|_| anyhow::anyhow!("Strategy state poisoned"))?;
+                let ptr = write_input(state, strategy.alloc.as_ref(), &strategy.memory, &config)?;
+                state.store.set_fuel(CALL_FUEL)?;
+                state.handle = init.call(&mut state.store, (ptr, config.len() as i32))?;
+            }
+            None if !strategy.params.is_null() => {
+                return Err(anyhow::anyhow!(
+                    "Strategy {} takes no parameters (it does not export {})", strategy.metadata.name, INIT_EXPORT
+                ));
+            }
+            None => {}
+        }
+        
+        Ok(strategy)
+    }
+    
+    /// Drop the instance and everything the module kept in its memory and globals
+    /// 
+    /// The module is instantiated again and [`INIT_EXPORT`] rerun with the same
+    /// parameters, so the next run sees the strategy exactly as it was when loaded.
+    pub fn reset(&mut self) -> Result<()> {
+        *self = Self::instantiate(&self.engine, self.module.clone(), self.params.clone())?;
+        Ok(())
+    }
+}
+
+/// Call [`METADATA_EXPORT`] and decode the JSON it points at
+fn read_metadata(store: &mut Store<()>, instance: &Instance, memory: &Memory) -> Result<StrategyMetadata> {
+    let get_metadata = instance.get_typed_func::<(), i64>(&mut *store, METADATA_EXPORT)
+        .with_context(|| format!("Strategy must export {}", METADATA_EXPORT))?;
+    
+    store.set_fuel(CALL_FUEL)?;
+    let packed = get_metadata.call(&mut *store, ())?;
+    let metadata: abi::WasmStrategyMetadata = serde_json::from_slice(&read_output(store, memory, packed)?)
+        .context("Strategy metadata is not valid JSON")?;
+    
+    Ok(metadata.into())
+}
+
+/// Copy `bytes` into strategy memory, reusing the input buffer when it is large enough
+fn write_input(
+    state: &mut StrategyState,
+    alloc: Option<&TypedFunc<i32, i32>>,
+    memory: &Memory,
+    bytes: &[u8],
+) -> Result<i32> {
+    let ptr = match state.input_buffer {
+        Some((ptr, capacity)) if capacity >= bytes.len() => ptr,
+        _ => {
+            let alloc = alloc.with_context(|| format!("Strategy must export {} to receive input", ALLOC_EXPORT))?;
+            state.store.set_fuel(CALL_FUEL)?;
+            let ptr = alloc.call(&mut state.store, bytes.len() as i32)?;
+            state.input_buffer = Some((ptr, bytes.len()));
+            ptr
+        }
+    };
+    
+    memory.write(&mut state.store, ptr as u32 as usize, bytes)
+        .with_context(|| format!("Strategy input buffer at {} is outside its memory", ptr))?;
+    Ok(ptr)
+}
+
+/// Read the `(ptr << 32) | len` location a strategy returned
+fn read_output(store: &Store<()>, memory: &Memory, packed: i64) -> Result<Vec<u8>> {
+    let packed = packed as u64;
+    let (ptr, len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+    
+    let mut bytes = vec![0; len];
+    memory.read(store, ptr, &mut bytes)
+        .with_context(|| format!("Strategy output at {}..{} is outside its memory", ptr, ptr + len))?;
+    Ok(bytes)
+}
+
+impl Strategy {
+    /// Get required indicators for this strategy, as declared by its metadata
     pub fn get_required_indicators(&self) -> Vec<String> {
-        // This would query the WASM strategy for required indicators
-        vec!["ema".to_string(), "rsi".to_string()]
+        self.metadata.required_indicators.clone()
     }
     
     /// Execute strategy logic for a given bar
-    pub async fn execute(
-        &mut self,
+    /// 
+    /// The strategy receives the bar, its index, the value of each indicator at the bar
+    /// (`null` during warmup) and the open position.
+    pub fn execute_current(
+        &self,
         bar: &Bar,
-        indicator_values: &HashMap<String, Vec<IndicatorValue>>,
+        bar_index: usize,
+        indicators: &HashMap<String, Option<Decimal>>,
+        current_position: Option<&Position>,
+    ) -> Result<Vec<StrategySignal>> {
+        self.call_on_bar(bar, bar_index, serde_json::to_value(indicators)?, current_position)
+    }
+    
+    fn call_on_bar(
+        &self,
+        bar: &Bar,
+        bar_index: usize,
+        indicators: serde_json::Value,
         current_position: Option<&Position>,
     ) -> Result<Vec<StrategySignal>> {
         debug!("Executing strategy for bar at {}", bar.timestamp);
         
-        // This would call the WASM strategy with current market state
-        // For now, return empty signals
-        Ok(Vec::new())
+        let input = serde_json::to_vec(&serde_json::json!({
+            "bar_index": bar_index,
+            "bar": bar,
+            "indicators": indicators,
+            "position": current_position,
+        }))?;
+        
+        let mut state = self.state.lock().map_err(|_| anyhow::anyhow!("Strategy state poisoned"))?;
+        let ptr = write_input(&mut state, self.alloc.as_ref(), &self.memory, &input)?;
+        state.store.set_fuel(CALL_FUEL)?;
+        let handle = state.handle;
+        let packed = self.on_bar.call(&mut state.store, (handle, ptr, input.len() as i32))?;
+        
+        serde_json::from_slice(&read_output(&state.store, &self.memory, packed)?)
+            .context("Strategy signals are not a valid JSON array")
     }
     
     /// Get strategy metadata
     pub fn get_metadata(&self) -> Result<StrategyMetadata> {
-        Ok(StrategyMetadata {
-            name: "mock_strategy".to_string(),
-            version: "1.0.0".to_string(),
-            description: "Mock strategy for testing".to_string(),
-            author: "system".to_string(),
-            required_indicators: self.get_required_indicators(),
-            parameters: HashMap::new(),
-        })
+        Ok(self.metadata.clone())
     }
 }
 
@@ -103,13 +287,342 @@ pub struct StrategyMetadata {
     pub description: String,
     pub author: String,
     pub required_indicators: Vec<String>,
+    /// Parameter overrides declared for required indicators; the rest use the registry defaults
+    pub indicator_params: HashMap<String, IndicatorOverrides>,
     pub parameters: HashMap<String, String>,
+    /// Declared bounds for numeric parameters, keyed by parameter name
+    pub parameter_bounds: HashMap<String, ParameterBounds>,
+}
+
+/// Indicator parameters a strategy sets explicitly
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct IndicatorOverrides {
+    pub period: Option<usize>,
+    pub alpha: Option<Decimal>,
+    pub threshold: Option<Decimal>,
+}
+
+impl IndicatorOverrides {
+    /// Apply the overrides on top of `params`
+    pub fn apply(&self, mut params: IndicatorParams) -> IndicatorParams {
+        if let Some(period) = self.period {
+            params.period = period;
+        }
+        if self.alpha.is_some() {
+            params.alpha = self.alpha;
+        }
+        if self.threshold.is_some() {
+            params.threshold = self.threshold;
+        }
+        params
+    }
+}
+
+/// Inclusive bounds for a numeric strategy parameter
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ParameterBounds {
+    pub min: Decimal,
+    pub max: Decimal,
+}
+
+impl StrategyMetadata {
+    /// Parameters to compute each required indicator with
+    /// 
+    /// Registry defaults with the strategy's overrides applied; indicators the registry
+    /// does not know are left out, [`StrategyMetadata::validate`] reports them.
+    pub fn resolved_indicator_params(&self, registry: &IndicatorRegistry) -> HashMap<String, IndicatorParams> {
+        self.required_indicators.iter()
+            .filter_map(|name| {
+                let defaults = registry.default_params(name)?;
+                let params = match self.indicator_params.get(name) {
+                    Some(overrides) => overrides.apply(defaults),
+                    None => defaults,
+                };
+                Some((name.clone(), params))
+            })
+            .collect()
+    }
+}
+
+impl StrategyMetadata {
+    /// Check the strategy can run on this engine before any data is loaded
+    /// 
+    /// Every required indicator must be registered and every bounded parameter must
+    /// parse as a number within its bounds. All problems are collected into one error
+    /// rather than failing on the first.
+    pub fn validate(&self, registry: &IndicatorRegistry) -> std::result::Result<(), StrategyValidationError> {
+        let mut unsupported_indicators: Vec<String> = self.required_indicators.iter()
+            .filter(|name| !registry.is_supported(name))
+            .cloned()
+            .collect();
+        unsupported_indicators.sort();
+        unsupported_indicators.dedup();
+        
+        let mut invalid_parameters = Vec::new();
+        let mut bounded: Vec<_> = self.parameter_bounds.iter().collect();
+        bounded.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, bounds) in bounded {
+            match self.parameters.get(name).map(|raw| raw.parse::<Decimal>()) {
+                None => invalid_parameters.push(format!("{}: missing", name)),
+                Some(Err(_)) => invalid_parameters.push(format!(
+                    "{}: '{}' is not a number", name, self.parameters[name]
+                )),
+                Some(Ok(value)) if value < bounds.min || value > bounds.max => {
+                    invalid_parameters.push(format!(
+                        "{}: {} outside [{}, {}]", name, value, bounds.min, bounds.max
+                    ));
+                }
+                Some(Ok(_)) => {}
+            }
+        }
+        
+        if unsupported_indicators.is_empty() && invalid_parameters.is_empty() {
+            Ok(())
+        } else {
+            Err(StrategyValidationError {
+                strategy: self.name.clone(),
+                unsupported_indicators,
+                invalid_parameters,
+            })
+        }
+    }
+}
+
+/// Strategy rejected by [`StrategyMetadata::validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyValidationError {
+    pub strategy: String,
+    /// Required indicators the engine does not register, sorted
+    pub unsupported_indicators: Vec<String>,
+    /// One message per missing, malformed or out-of-bounds parameter
+    pub invalid_parameters: Vec<String>,
+}
+
+impl std::fmt::Display for StrategyValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Strategy '{}' failed validation", self.strategy)?;
+        if !self.unsupported_indicators.is_empty() {
+            write!(f, "; unsupported indicators: {}", self.unsupported_indicators.join(", "))?;
+        }
+        if !self.invalid_parameters.is_empty() {
+            write!(f, "; invalid parameters: {}", self.invalid_parameters.join(", "))?;
+        }
+        Ok(())
+    }
 }
 
+impl std::error::Error for StrategyValidationError {}
+
 /// Strategy ABI for WASM communication
 pub mod abi {
     use super::*;
     
+    /// JSON document returned through [`METADATA_EXPORT`]
+    /// 
+    /// Required indicators are either a bare name or an object naming the indicator
+    /// alongside its parameter overrides, e.g. `{"name": "atr", "period": 10}`.
+    #[derive(Debug, Clone, Deserialize)]
+    pub struct WasmStrategyMetadata {
+        pub name: String,
+        #[serde(default)]
+        pub version: String,
+        #[serde(default)]
+        pub description: String,
+        #[serde(default)]
+        pub author: String,
+        #[serde(default)]
+        pub required_indicators: Vec<IndicatorDeclaration>,
+        #[serde(default)]
+        pub parameters: HashMap<String, String>,
+        #[serde(default)]
+        pub parameter_bounds: HashMap<String, ParameterBounds>,
+    }
+    
+    /// One entry of [`WasmStrategyMetadata::required_indicators`]
+    #[derive(Debug, Clone, Deserialize)]
+    #[serde(untagged)]
+    pub enum IndicatorDeclaration {
+        Name(String),
+        WithParams {
+            name: String,
+            #[serde(flatten)]
+            params: IndicatorOverrides,
+        },
+    }
+    
+    impl From<WasmStrategyMetadata> for StrategyMetadata {
+        fn from(metadata: WasmStrategyMetadata) -> Self {
+            let mut required_indicators = Vec::new();
+            let mut indicator_params = HashMap::new();
+            for declaration in metadata.required_indicators {
+                match declaration {
+                    IndicatorDeclaration::Name(name) => required_indicators.push(name),
+                    IndicatorDeclaration::WithParams { name, params } => {
+                        indicator_params.insert(name.clone(), params);
+                        required_indicators.push(name);
+                    }
+                }
+            }
+            
+            Self {
+                name: metadata.name,
+                version: metadata.version,
+                description: metadata.description,
+                author: metadata.author,
+                required_indicators,
+                indicator_params,
+                parameters: metadata.parameters,
+                parameter_bounds: metadata.parameter_bounds,
+            }
+        }
+    }
+    
+    /// Offset of the metadata JSON in modules built by [`assemble_module`]
+    pub(crate) const METADATA_OFFSET: u32 = 1024;
+    
+    /// Offset of an empty JSON array in modules built by [`assemble_module`]
+    pub(crate) const EMPTY_SIGNALS_OFFSET: u32 = 8;
+    
+    /// Exported function of a module built by [`assemble_module`]
+    pub(crate) struct FunctionDef<'a> {
+        pub export: &'a str,
+        /// Number of `i32` parameters
+        pub params: usize,
+        /// Result value type (`0x7f` i32, `0x7e` i64)
+        pub result: u8,
+        /// Instructions, without locals or the final `end`
+        pub body: Vec<u8>,
+    }
+    
+    /// Assemble a module that exports one page of memory holding `metadata_json`, a
+    /// [`METADATA_EXPORT`] returning its location and an [`ON_BAR_EXPORT`] that never signals
+    /// 
+    /// Lets the engine be driven by declared metadata without a strategy toolchain.
+    pub fn metadata_module(metadata_json: &[u8]) -> Vec<u8> {
+        // Bump allocator past the metadata for the engine's inputs
+        let heap_start = (METADATA_OFFSET as usize + metadata_json.len()).next_multiple_of(8) as i32;
+        let alloc = vec![
+            0x23, 0x00, // global.get top, the result
+            0x23, 0x00, 0x20, 0x00, 0x6a, 0x24, 0x00, // top += len
+        ];
+        let mut on_bar = vec![0x42]; // i64.const
+        on_bar.extend(sleb128(((EMPTY_SIGNALS_OFFSET as i64) << 32) | 2));
+        
+        assemble_module(metadata_json, &[heap_start], &[
+            FunctionDef { export: ALLOC_EXPORT, params: 1, result: 0x7f, body: alloc },
+            FunctionDef { export: ON_BAR_EXPORT, params: 3, result: 0x7e, body: on_bar },
+        ])
+    }
+    
+    /// Module exporting one page of memory holding `metadata_json` (and an empty JSON
+    /// array at [`EMPTY_SIGNALS_OFFSET`]), a [`METADATA_EXPORT`] returning its location,
+    /// mutable `i32` globals (given their initial values) and further exported functions
+    pub(crate) fn assemble_module(metadata_json: &[u8], globals: &[i32], functions: &[FunctionDef]) -> Vec<u8> {
+        assert!(
+            METADATA_OFFSET as usize + metadata_json.len() <= 65536,
+            "metadata must fit in one page"
+        );
+        let packed = ((METADATA_OFFSET as i64) << 32) | metadata_json.len() as i64;
+        let mut get_metadata = vec![0x42]; // i64.const
+        get_metadata.extend(sleb128(packed));
+        let metadata_fn = FunctionDef { export: METADATA_EXPORT, params: 0, result: 0x7e, body: get_metadata };
+        let functions: Vec<&FunctionDef> = std::iter::once(&metadata_fn).chain(functions).collect();
+        
+        // One type per function keeps the indices trivially aligned
+        let mut types = uleb128(functions.len() as u64);
+        let mut function_types = uleb128(functions.len() as u64);
+        let mut code = uleb128(functions.len() as u64);
+        for (index, function) in functions.iter().enumerate() {
+            types.push(0x60);
+            types.extend(uleb128(function.params as u64));
+            types.extend(vec![0x7f; function.params]);
+            types.extend([0x01, function.result]);
+            function_types.extend(uleb128(index as u64));
+            
+            let mut body = vec![0x00]; // no locals
+            body.extend_from_slice(&function.body);
+            body.push(0x0b); // end
+            code.extend(uleb128(body.len() as u64));
+            code.extend(body);
+        }
+        
+        let mut global_section = uleb128(globals.len() as u64);
+        for value in globals {
+            global_section.extend([0x7f, 0x01, 0x41]); // mutable i32 = i32.const
+            global_section.extend(sleb128(*value as i64));
+            global_section.push(0x0b);
+        }
+        
+        let mut exports = uleb128(functions.len() as u64 + 1);
+        let named = std::iter::once(("memory", 0x02, 0)).chain(
+            functions.iter().enumerate().map(|(index, function)| (function.export, 0x00, index as u64))
+        );
+        for (name, kind, index) in named {
+            exports.extend(uleb128(name.len() as u64));
+            exports.extend_from_slice(name.as_bytes());
+            exports.push(kind);
+            exports.extend(uleb128(index));
+        }
+        
+        // Two active segments in memory 0, each at an i32.const offset
+        let mut data = vec![0x02];
+        for (offset, bytes) in [(EMPTY_SIGNALS_OFFSET, &b"[]"[..]), (METADATA_OFFSET, metadata_json)] {
+            data.extend([0x00, 0x41]);
+            data.extend(sleb128(offset as i64));
+            data.push(0x0b);
+            data.extend(uleb128(bytes.len() as u64));
+            data.extend_from_slice(bytes);
+        }
+        
+        let mut module = vec![
+            0x00, 0x61, 0x73, 0x6d, // magic: \0asm
+            0x01, 0x00, 0x00, 0x00, // version 1
+        ];
+        push_section(&mut module, 0x01, &types);
+        push_section(&mut module, 0x03, &function_types);
+        push_section(&mut module, 0x05, &[0x01, 0x00, 0x01]); // one memory, min 1 page
+        if !globals.is_empty() {
+            push_section(&mut module, 0x06, &global_section);
+        }
+        push_section(&mut module, 0x07, &exports);
+        push_section(&mut module, 0x0a, &code);
+        push_section(&mut module, 0x0b, &data);
+        module
+    }
+    
+    fn push_section(module: &mut Vec<u8>, id: u8, contents: &[u8]) {
+        module.push(id);
+        module.extend(uleb128(contents.len() as u64));
+        module.extend_from_slice(contents);
+    }
+    
+    fn uleb128(mut value: u64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(byte);
+                return bytes;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+    
+    fn sleb128(mut value: i64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+            if done {
+                bytes.push(byte);
+                return bytes;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+    
     /// Market data structure passed to WASM
     #[repr(C)]
     pub struct WasmBar {
@@ -185,12 +698,86 @@ pub mod abi {
                     None
                 },
                 time_to_live: if signal.time_to_live > 0 {
-                    Some(signal.time_to_live)
+                    Some(TimeToLive::Duration(signal.time_to_live))
                 } else {
                     None
                 },
+                rule_tag: None,
+                tags: Vec::new(),
+                symbol: String::new(),
             }
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn metadata(required_indicators: &[&str]) -> StrategyMetadata {
+        StrategyMetadata {
+            name: "trend_follower".to_string(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            author: "tests".to_string(),
+            required_indicators: required_indicators.iter().map(|s| s.to_string()).collect(),
+            indicator_params: HashMap::new(),
+            parameters: HashMap::new(),
+            parameter_bounds: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_validation_rejects_unregistered_indicator() {
+        let registry = IndicatorRegistry::new(false).unwrap();
+        let err = metadata(&["ema", "ichimoku", "rsi"]).validate(&registry).unwrap_err();
+
+        assert_eq!(err.unsupported_indicators, vec!["ichimoku".to_string()]);
+        assert!(err.invalid_parameters.is_empty());
+        assert!(err.to_string().contains("unsupported indicators: ichimoku"));
+
+        assert!(metadata(&["ema", "rsi"]).validate(&registry).is_ok());
+    }
+
+    #[test]
+    fn test_validation_checks_parameter_bounds() {
+        let registry = IndicatorRegistry::new(false).unwrap();
+        let mut meta = metadata(&["ema"]);
+        for name in ["fast", "slow", "risk"] {
+            meta.parameter_bounds.insert(name.to_string(), ParameterBounds { min: dec!(1), max: dec!(200) });
+        }
+        meta.parameters.insert("fast".to_string(), "12".to_string());
+        meta.parameters.insert("slow".to_string(), "500".to_string());
+
+        let err = meta.validate(&registry).unwrap_err();
+        assert!(err.unsupported_indicators.is_empty());
+        assert_eq!(err.invalid_parameters, vec![
+            "risk: missing".to_string(),
+            "slow: 500 outside [1, 200]".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_load_requires_on_bar_export() {
+        let mut runtime = WasmRuntime::new().unwrap();
+        let metadata = serde_json::to_vec(&serde_json::json!({"name": "silent"})).unwrap();
+
+        let without_on_bar = abi::assemble_module(&metadata, &[], &[]);
+        let err = runtime.load_strategy_bytes(&without_on_bar, &serde_json::Value::Null).err().unwrap();
+        assert!(err.to_string().contains(ON_BAR_EXPORT), "{}", err);
+
+        // The metadata-only module still loads, and never signals
+        let strategy = runtime.load_strategy_bytes(&abi::metadata_module(&metadata), &serde_json::Value::Null).unwrap();
+        let bar = Bar {
+            timestamp: 1609459200000,
+            open: dec!(100),
+            high: dec!(101),
+            low: dec!(99),
+            close: dec!(100),
+            volume: dec!(1),
+            trade_count: 1,
+        };
+        assert!(strategy.execute_current(&bar, 0, &HashMap::new(), None).unwrap().is_empty());
+    }
+}
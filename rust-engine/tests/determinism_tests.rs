@@ -0,0 +1,823 @@
+//! Determinism tests for the backtesting engine
+//! 
+//! Ensures that all calculations are deterministic and reproducible across runs.
+//! Tests include golden dataset validation and TradingView parity checks.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use anyhow::Result;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use tracing::info;
+
+use backtest_engine::EngineConfig;
+use backtest_engine::canonical::to_canonical_json;
+use backtest_engine::data::MarketDataBuilder;
+use backtest_engine::types::*;
+use backtest_engine::indicators::IndicatorRegistry;
+use backtest_engine::simulator::ExchangeSimulator;
+use backtest_engine::precision::validate_fp_determinism;
+use backtest_engine::test_support::{SyntheticDataConfig, SyntheticDataGenerator};
+use backtest_engine::timeframe::Timeframe;
+
+/// Test configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeterminismTestConfig {
+    pub golden_datasets: Vec<GoldenDataset>,
+    pub tv_parity_tests: Vec<TradingViewParityTest>,
+    pub tolerance: Decimal,
+    pub max_iterations: usize,
+}
+
+/// Golden dataset for deterministic validation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldenDataset {
+    pub name: String,
+    pub description: String,
+    pub symbol: String,
+    pub timeframe: String,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub expected_hash: String,
+    pub expected_trades: usize,
+    pub expected_final_equity: Decimal,
+    /// Seed for a synthetic GBM series spanning `start_time..end_time`; datasets without
+    /// one are loaded from the market data store
+    #[serde(default)]
+    pub synthetic_seed: Option<u64>,
+}
+
+/// TradingView parity test configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradingViewParityTest {
+    pub name: String,
+    pub symbol: String,
+    pub timeframe: String,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub tv_csv_path: String,
+    pub tolerance: Decimal,
+}
+
+/// Determinism test runner
+pub struct DeterminismTester {
+    config: DeterminismTestConfig,
+    engine_config: EngineConfig,
+}
+
+impl DeterminismTester {
+    pub fn new(config: DeterminismTestConfig) -> Result<Self> {
+        // Validate floating-point determinism
+        validate_fp_determinism()?;
+        
+        Ok(Self {
+            config,
+            engine_config: EngineConfig::default(),
+        })
+    }
+    
+    /// Run all determinism tests
+    pub async fn run_all_tests(&mut self) -> Result<TestResults> {
+        info!("Starting determinism tests");
+        
+        let mut results = TestResults::new();
+        
+        // Test 1: Floating-point determinism
+        results.add_test(self.test_fp_determinism().await?);
+        
+        // Test 2: Indicator determinism
+        results.add_test(self.test_indicator_determinism().await?);
+        
+        // Test 3: Golden dataset validation
+        for dataset in &self.config.golden_datasets {
+            results.add_test(self.test_golden_dataset(dataset).await?);
+        }
+        
+        // Test 4: TradingView parity tests
+        for tv_test in &self.config.tv_parity_tests {
+            results.add_test(self.test_tv_parity(tv_test).await?);
+        }
+        
+        // Test 5: Multi-run consistency
+        results.add_test(self.test_multi_run_consistency().await?);
+        
+        info!("Determinism tests completed: {} passed, {} failed", 
+              results.passed_count(), results.failed_count());
+        
+        Ok(results)
+    }
+    
+    /// Test floating-point determinism
+    async fn test_fp_determinism(&self) -> Result<TestCase> {
+        info!("Testing floating-point determinism");
+        
+        let test_name = "fp_determinism";
+        let mut errors = Vec::new();
+        
+        // Test multiple runs produce identical results
+        for run in 0..self.config.max_iterations {
+            let result1 = self.calculate_deterministic_math();
+            let result2 = self.calculate_deterministic_math();
+            
+            if result1 != result2 {
+                errors.push(format!("Run {}: FP calculations not deterministic", run));
+            }
+        }
+        
+        // Test specific FP operations
+        let test_cases = vec![
+            (0.1 + 0.2, 0.3),
+            (1.0 / 3.0, 0.3333333333333333),
+            (0.1 * 3.0, 0.30000000000000004_f64),
+        ];
+        
+        for (actual, expected) in test_cases {
+            if (actual - expected).abs() > 1e-15 {
+                errors.push(format!("FP operation failed: {} != {}", actual, expected));
+            }
+        }
+        
+        Ok(TestCase {
+            name: test_name.to_string(),
+            passed: errors.is_empty(),
+            errors,
+            execution_time_ms: 0, // Would measure actual time
+        })
+    }
+    
+    /// Test indicator calculation determinism
+    async fn test_indicator_determinism(&self) -> Result<TestCase> {
+        info!("Testing indicator determinism");
+        
+        let test_name = "indicator_determinism";
+        let mut errors = Vec::new();
+        
+        // Create test data
+        let test_bars = self.create_test_bars();
+        
+        // Test each indicator multiple times
+        let indicators = vec!["ema", "sma", "rsi", "atr", "vwap"];
+        
+        for indicator_name in indicators {
+            let mut registry = IndicatorRegistry::new(true)?;
+            
+            // Calculate indicator multiple times
+            let mut results = Vec::new();
+            for _ in 0..self.config.max_iterations {
+                let result = registry.calculate(indicator_name, &test_bars)?;
+                results.push(result);
+            }
+            
+            // Check all results are identical
+            for i in 1..results.len() {
+                if !self.compare_indicator_results(&results[0], &results[i]) {
+                    errors.push(format!("Indicator {} not deterministic", indicator_name));
+                    break;
+                }
+            }
+        }
+        
+        Ok(TestCase {
+            name: test_name.to_string(),
+            passed: errors.is_empty(),
+            errors,
+            execution_time_ms: 0,
+        })
+    }
+    
+    /// Test golden dataset validation
+    async fn test_golden_dataset(&self, dataset: &GoldenDataset) -> Result<TestCase> {
+        info!("Testing golden dataset: {}", dataset.name);
+        
+        let test_name = format!("golden_dataset_{}", dataset.name);
+        let mut errors = Vec::new();
+        
+        // Load test data
+        let market_data = self.load_test_data(dataset).await?;
+        
+        // Run backtest
+        let result = self.run_backtest(&market_data).await?;
+        
+        // Calculate result hash
+        let result_hash = self.calculate_result_hash(&result)?;
+        
+        // Validate hash
+        if result_hash != dataset.expected_hash {
+            errors.push(format!(
+                "Hash mismatch: expected {}, got {}", 
+                dataset.expected_hash, 
+                result_hash
+            ));
+        }
+        
+        // Validate trade count
+        if result.trades.len() != dataset.expected_trades {
+            errors.push(format!(
+                "Trade count mismatch: expected {}, got {}", 
+                dataset.expected_trades, 
+                result.trades.len()
+            ));
+        }
+        
+        // Validate final equity
+        if let Some(final_equity) = result.equity_curve.last() {
+            let equity_diff = (final_equity.equity - dataset.expected_final_equity).abs();
+            if equity_diff > self.config.tolerance {
+                errors.push(format!(
+                    "Final equity mismatch: expected {}, got {}, diff {}", 
+                    dataset.expected_final_equity, 
+                    final_equity.equity,
+                    equity_diff
+                ));
+            }
+        }
+        
+        Ok(TestCase {
+            name: test_name,
+            passed: errors.is_empty(),
+            errors,
+            execution_time_ms: 0,
+        })
+    }
+    
+    /// Test TradingView parity
+    async fn test_tv_parity(&self, tv_test: &TradingViewParityTest) -> Result<TestCase> {
+        info!("Testing TradingView parity: {}", tv_test.name);
+        
+        let test_name = format!("tv_parity_{}", tv_test.name);
+        let mut errors = Vec::new();
+        
+        // Load TradingView CSV data
+        let tv_data = self.load_tv_csv(&tv_test.tv_csv_path)?;
+        
+        // Load our test data
+        let market_data = self.load_test_data_for_period(
+            &tv_test.symbol,
+            tv_test.start_time,
+            tv_test.end_time,
+        ).await?;
+        
+        // Run backtest
+        let result = self.run_backtest(&market_data).await?;
+        
+        // Compare with TradingView data
+        errors.extend(compare_tv_trades(&result.trades, &tv_data.trades, tv_test.tolerance));
+        
+        Ok(TestCase {
+            name: test_name,
+            passed: errors.is_empty(),
+            errors,
+            execution_time_ms: 0,
+        })
+    }
+    
+    /// Test multi-run consistency
+    async fn test_multi_run_consistency(&self) -> Result<TestCase> {
+        info!("Testing multi-run consistency");
+        
+        let test_name = "multi_run_consistency";
+        let mut errors = Vec::new();
+        
+        // Use first golden dataset for consistency test
+        if let Some(dataset) = self.config.golden_datasets.first() {
+            let market_data = self.load_test_data(dataset).await?;
+            
+            // Run backtest multiple times
+            let mut results = Vec::new();
+            for _ in 0..self.config.max_iterations {
+                let result = self.run_backtest(&market_data).await?;
+                results.push(result);
+            }
+            
+            // Check all results are identical
+            for i in 1..results.len() {
+                if !self.compare_backtest_results(&results[0], &results[i]) {
+                    errors.push(format!("Backtest run {} not consistent with run 0", i));
+                }
+            }
+        }
+        
+        Ok(TestCase {
+            name: test_name.to_string(),
+            passed: errors.is_empty(),
+            errors,
+            execution_time_ms: 0,
+        })
+    }
+    
+    // Helper methods
+    
+    fn calculate_deterministic_math(&self) -> f64 {
+        let mut result = 0.0;
+        for i in 1..=1000 {
+            result += 1.0 / (i as f64);
+        }
+        result
+    }
+    
+    fn create_test_bars(&self) -> MarketData {
+        // 1000 one-minute bars, seeded from the engine config so every run sees the same series
+        let config = SyntheticDataConfig {
+            bar_count: 1000,
+            ..SyntheticDataConfig::default()
+        };
+        let bars = SyntheticDataGenerator::new(config, self.engine_config.random_seed).generate_bars();
+        
+        MarketDataBuilder::new("BTCUSDT")
+            .timeframe("1m")
+            .bars(bars)
+            .build()
+            .expect("synthetic bars are ordered, consistent and one minute apart")
+    }
+    
+    async fn load_test_data(&self, dataset: &GoldenDataset) -> Result<MarketData> {
+        if let Some(seed) = dataset.synthetic_seed {
+            let interval_ms = dataset.timeframe.parse::<Timeframe>()?.as_millis();
+            
+            let config = SyntheticDataConfig {
+                start_time: dataset.start_time,
+                interval_ms,
+                bar_count: ((dataset.end_time - dataset.start_time) / interval_ms) as usize,
+                ..SyntheticDataConfig::default()
+            };
+            let bars = SyntheticDataGenerator::new(config, seed).generate_bars();
+            
+            // The builder checks the bars against the dataset's timeframe
+            return Ok(MarketDataBuilder::new(dataset.symbol.clone())
+                .timeframe(dataset.timeframe.clone())
+                .bars(bars)
+                .build()?);
+        }
+        
+        // This would load actual market data from ClickHouse
+        // For now, return test data
+        Ok(self.create_test_bars())
+    }
+    
+    async fn load_test_data_for_period(
+        &self,
+        _symbol: &str,
+        _start_time: u64,
+        _end_time: u64,
+    ) -> Result<MarketData> {
+        // This would load data for the specific period
+        Ok(self.create_test_bars())
+    }
+    
+    /// Run the reference strategy through a fresh simulator
+    /// 
+    /// The reference strategy is an SMA(20) cross: go long when the close crosses above
+    /// the SMA and short when it crosses below, each with a 1% take-profit and stop-loss.
+    /// It stands in for a WASM strategy so the indicator, fill, trade-table and equity
+    /// paths are all exercised end to end.
+    async fn run_backtest(&self, market_data: &MarketData) -> Result<SimulationResult> {
+        let mut registry = IndicatorRegistry::new(self.engine_config.enable_simd)?;
+        let sma: HashMap<u64, Decimal> = registry.calculate("sma", market_data)?
+            .into_iter()
+            .map(|v| (v.timestamp, v.value))
+            .collect();
+        
+        let mut simulator = ExchangeSimulator::with_capacity(market_data.bars.len())?;
+        let mut prev_above: Option<bool> = None;
+        
+        simulator.simulate_with_signals(
+            market_data,
+            |_, bar| {
+                let Some(&average) = sma.get(&bar.timestamp) else {
+                    return Vec::new();
+                };
+                let above = bar.close > average;
+                let crossed = prev_above.is_some_and(|was_above| was_above != above);
+                prev_above = Some(above);
+                
+                if !crossed {
+                    return Vec::new();
+                }
+                
+                let (side, take_profit, stop_loss) = if above {
+                    (TradeSide::Buy, bar.close * dec!(1.01), bar.close * dec!(0.99))
+                } else {
+                    (TradeSide::Sell, bar.close * dec!(0.99), bar.close * dec!(1.01))
+                };
+                
+                vec![StrategySignal {
+                    side,
+                    size: dec!(100.0),
+                    entry_price: None,
+                    take_profit: Some(take_profit),
+                    stop_loss: Some(stop_loss),
+                    time_to_live: None,
+                    rule_tag: Some("sma_cross".to_string()),
+                    tags: Vec::new(),
+                    symbol: String::new(),
+                }]
+            },
+            &IntrabarPolicy::ExactTrades,
+            &SlippageMode::TradeSweep,
+        ).await
+    }
+    
+    /// SHA-256 over the canonical serialization (see `canonical` for the format)
+    fn calculate_result_hash(&self, result: &SimulationResult) -> Result<String> {
+        let serialized = to_canonical_json(result)?;
+        let mut hasher = Sha256::new();
+        hasher.update(serialized.as_bytes());
+        let hash = hasher.finalize();
+        Ok(format!("{:x}", hash))
+    }
+    
+    fn load_tv_csv(&self, path: &str) -> Result<TradingViewData> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read TradingView CSV {}: {}", path, e))?;
+        parse_tv_csv(&content)
+    }
+    
+    fn compare_indicator_results(&self, a: &[IndicatorValue], b: &[IndicatorValue]) -> bool {
+        if a.len() != b.len() {
+            return false;
+        }
+        
+        for (va, vb) in a.iter().zip(b.iter()) {
+            if va.timestamp != vb.timestamp {
+                return false;
+            }
+            if (va.value - vb.value).abs() > self.config.tolerance {
+                return false;
+            }
+        }
+        
+        true
+    }
+    
+    fn compare_backtest_results(&self, a: &SimulationResult, b: &SimulationResult) -> bool {
+        // Compare trades
+        if a.trades.len() != b.trades.len() {
+            return false;
+        }
+        
+        for (ta, tb) in a.trades.iter().zip(b.trades.iter()) {
+            if ta.timestamp != tb.timestamp {
+                return false;
+            }
+            if (ta.price - tb.price).abs() > self.config.tolerance {
+                return false;
+            }
+        }
+        
+        // Compare equity curve
+        if a.equity_curve.len() != b.equity_curve.len() {
+            return false;
+        }
+        
+        for (ea, eb) in a.equity_curve.iter().zip(b.equity_curve.iter()) {
+            if ea.timestamp != eb.timestamp {
+                return false;
+            }
+            if (ea.equity - eb.equity).abs() > self.config.tolerance {
+                return false;
+            }
+        }
+        
+        true
+    }
+}
+
+/// Test case result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCase {
+    pub name: String,
+    pub passed: bool,
+    pub errors: Vec<String>,
+    pub execution_time_ms: u64,
+}
+
+/// Test results container
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestResults {
+    pub test_cases: Vec<TestCase>,
+    pub total_execution_time_ms: u64,
+}
+
+impl TestResults {
+    fn new() -> Self {
+        Self {
+            test_cases: Vec::new(),
+            total_execution_time_ms: 0,
+        }
+    }
+    
+    fn add_test(&mut self, test_case: TestCase) {
+        self.test_cases.push(test_case);
+    }
+    
+    fn passed_count(&self) -> usize {
+        self.test_cases.iter().filter(|t| t.passed).count()
+    }
+    
+    fn failed_count(&self) -> usize {
+        self.test_cases.iter().filter(|t| !t.passed).count()
+    }
+    
+    fn all_passed(&self) -> bool {
+        self.test_cases.iter().all(|t| t.passed)
+    }
+}
+
+/// TradingView data structure
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradingViewData {
+    /// Fills from a strategy-tester "List of Trades" export, in time order
+    pub trades: Vec<TradingViewTrade>,
+    /// Indicator columns from a chart data export, keyed by column name
+    pub indicators: BTreeMap<String, Vec<IndicatorValue>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradingViewTrade {
+    pub timestamp: u64,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub side: TradeSide,
+}
+
+/// Parse a TradingView CSV export
+/// 
+/// Two layouts are recognised from the header:
+/// - Strategy tester "List of Trades": `Type`, `Date/Time`, `Price <ccy>`, `Contracts`
+///   columns, one row per entry/exit. Each fill becomes a `TradingViewTrade`.
+/// - Chart data export: a `time` column plus OHLC and one column per plotted
+///   indicator. Every non-OHLC column becomes an indicator series; `NaN` cells
+///   (warmup) are skipped.
+/// 
+/// Timestamps may be unix seconds, unix milliseconds, RFC 3339, or `YYYY-MM-DD HH:MM[:SS]`
+/// (TradingView's default, taken as UTC; export with the chart set to UTC).
+pub fn parse_tv_csv(content: &str) -> Result<TradingViewData> {
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let header = lines.next()
+        .ok_or_else(|| anyhow::anyhow!("TradingView CSV is empty"))?;
+    let columns: Vec<String> = split_csv_line(header).iter()
+        .map(|c| c.trim().trim_start_matches('\u{feff}').to_string())
+        .collect();
+    let find = |pred: &dyn Fn(&str) -> bool| columns.iter().position(|c| pred(&c.to_lowercase()));
+    
+    let mut data = TradingViewData::default();
+    
+    if let (Some(type_idx), Some(time_idx)) = (
+        find(&|c| c == "type"),
+        find(&|c| c == "date/time" || c == "date and time"),
+    ) {
+        let price_idx = find(&|c| c == "price" || c.starts_with("price "))
+            .ok_or_else(|| anyhow::anyhow!("TradingView trade export has no Price column"))?;
+        let qty_idx = find(&|c| c == "contracts" || c == "quantity" || c.starts_with("size"));
+        
+        for (line_no, line) in lines.enumerate() {
+            let fields = split_csv_line(line);
+            let field = |idx: usize| fields.get(idx).map(|f| f.trim()).unwrap_or("");
+            let context = |what: &str| anyhow::anyhow!("Row {}: invalid {}", line_no + 2, what);
+            
+            let side = match field(type_idx).to_lowercase().as_str() {
+                "entry long" | "exit short" => TradeSide::Buy,
+                "exit long" | "entry short" => TradeSide::Sell,
+                other => return Err(anyhow::anyhow!("Row {}: unknown trade type '{}'", line_no + 2, other)),
+            };
+            
+            data.trades.push(TradingViewTrade {
+                timestamp: parse_tv_timestamp(field(time_idx)).ok_or_else(|| context("timestamp"))?,
+                price: parse_tv_decimal(field(price_idx)).ok_or_else(|| context("price"))?,
+                quantity: qty_idx.and_then(|idx| parse_tv_decimal(field(idx))).unwrap_or(Decimal::ZERO),
+                side,
+            });
+        }
+        
+        // Exports list the newest trade first, exit above entry
+        data.trades.sort_by_key(|t| t.timestamp);
+    } else if let Some(time_idx) = find(&|c| c == "time") {
+        const OHLCV: [&str; 5] = ["open", "high", "low", "close", "volume"];
+        
+        for (line_no, line) in lines.enumerate() {
+            let fields = split_csv_line(line);
+            let timestamp = fields.get(time_idx)
+                .and_then(|f| parse_tv_timestamp(f.trim()))
+                .ok_or_else(|| anyhow::anyhow!("Row {}: invalid timestamp", line_no + 2))?;
+            
+            for (idx, name) in columns.iter().enumerate() {
+                if idx == time_idx || OHLCV.contains(&name.to_lowercase().as_str()) {
+                    continue;
+                }
+                if let Some(value) = fields.get(idx).and_then(|f| parse_tv_decimal(f.trim())) {
+                    data.indicators.entry(name.clone()).or_default()
+                        .push(IndicatorValue { timestamp, value, warm: true });
+                }
+            }
+        }
+    } else {
+        return Err(anyhow::anyhow!("Unrecognised TradingView CSV header: {}", header));
+    }
+    
+    Ok(data)
+}
+
+/// Compare engine fills against TradingView fills, returning one message per mismatch
+pub fn compare_tv_trades(
+    ours: &[ExecutedTrade],
+    tv_trades: &[TradingViewTrade],
+    tolerance: Decimal,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    
+    if ours.len() != tv_trades.len() {
+        errors.push(format!(
+            "Trade count mismatch: our {}, TV {}",
+            ours.len(),
+            tv_trades.len()
+        ));
+    }
+    
+    for (our_trade, tv_trade) in ours.iter().zip(tv_trades.iter()) {
+        let price_diff = (our_trade.price - tv_trade.price).abs();
+        if price_diff > tolerance {
+            errors.push(format!(
+                "Price mismatch at {}: our {}, TV {}, diff {}", 
+                our_trade.timestamp,
+                our_trade.price,
+                tv_trade.price,
+                price_diff
+            ));
+        }
+    }
+    
+    errors
+}
+
+/// Split one CSV line, honouring double-quoted fields and `""` escapes
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    fields.push(current);
+    
+    fields
+}
+
+fn parse_tv_timestamp(value: &str) -> Option<u64> {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+        let raw: u64 = value.parse().ok()?;
+        // Unix seconds have at most 10 digits until the year 2286
+        return Some(if value.len() > 10 { raw } else { raw * 1000 });
+    }
+    
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(value) {
+        return u64::try_from(dt.timestamp_millis()).ok();
+    }
+    
+    ["%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S"].iter()
+        .find_map(|fmt| chrono::NaiveDateTime::parse_from_str(value, fmt).ok())
+        .and_then(|dt| u64::try_from(dt.and_utc().timestamp_millis()).ok())
+}
+
+fn parse_tv_decimal(value: &str) -> Option<Decimal> {
+    let cleaned: String = value.chars().filter(|c| *c != ',' && !c.is_whitespace()).collect();
+    if cleaned.is_empty() || cleaned.eq_ignore_ascii_case("nan") {
+        return None;
+    }
+    
+    cleaned.parse::<Decimal>().ok()
+        .or_else(|| Decimal::from_scientific(&cleaned).ok())
+}
+
+/// Load test configuration from file
+pub fn load_test_config(path: &Path) -> Result<DeterminismTestConfig> {
+    let content = fs::read_to_string(path)?;
+    let config: DeterminismTestConfig = serde_json::from_str(&content)?;
+    Ok(config)
+}
+
+/// Save test results to file
+pub fn save_test_results(path: &Path, results: &TestResults) -> Result<()> {
+    let content = serde_json::to_string_pretty(results)?;
+    fs::write(path, content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TV_TRADES_CSV: &str = include_str!("data/tv_parity/btc_ema_rsi_2023_q1.csv");
+
+    fn engine_fills(tv: &TradingViewData) -> Vec<ExecutedTrade> {
+        tv.trades.iter().map(|t| ExecutedTrade {
+            timestamp: t.timestamp,
+            symbol: "BTCUSDT".into(),
+            side: t.side.clone(),
+            quantity: t.quantity,
+            price: t.price,
+            fee: dec!(0.0),
+            slippage: dec!(0.0),
+            reason_code: ReasonCode::BarClose,
+            rule_tag: None,
+            fill_outside_bar: false,
+        }).collect()
+    }
+
+    fn golden_dataset(name: &str) -> (DeterminismTestConfig, GoldenDataset) {
+        let config: DeterminismTestConfig =
+            serde_json::from_str(include_str!("config/determinism_tests.json")).unwrap();
+        let dataset = config.golden_datasets.iter()
+            .find(|d| d.name == name)
+            .cloned()
+            .unwrap();
+        (config, dataset)
+    }
+
+    #[tokio::test]
+    async fn test_real_engine_reproduces_synthetic_golden_dataset() {
+        let (config, dataset) = golden_dataset("synthetic_gbm_btc_1m");
+        let tester = DeterminismTester::new(config).unwrap();
+
+        let market_data = tester.load_test_data(&dataset).await.unwrap();
+        let result = tester.run_backtest(&market_data).await.unwrap();
+        assert!(!result.trades.is_empty());
+        assert_eq!(result.equity_curve.len(), market_data.bars.len());
+
+        let case = tester.test_golden_dataset(&dataset).await.unwrap();
+        assert!(case.passed, "{:?}", case.errors);
+
+        let case = tester.test_multi_run_consistency().await.unwrap();
+        assert!(case.passed, "{:?}", case.errors);
+    }
+
+    #[tokio::test]
+    async fn test_run_all_tests_passes_on_the_synthetic_golden_dataset() {
+        let (mut config, dataset) = golden_dataset("synthetic_gbm_btc_1m");
+        config.golden_datasets = vec![dataset];
+        config.tv_parity_tests.clear();
+        config.max_iterations = 2;
+        let mut tester = DeterminismTester::new(config).unwrap();
+
+        let results = tester.run_all_tests().await.unwrap();
+        assert_eq!(results.passed_count(), 4);
+        assert!(results.all_passed(), "{:?}", results.test_cases);
+    }
+
+    #[test]
+    fn test_parse_tv_strategy_tester_export() {
+        let tv = parse_tv_csv(TV_TRADES_CSV).unwrap();
+
+        assert_eq!(tv.trades.len(), 6);
+        // 2023-01-02 05:00 UTC, sorted oldest first despite the export's ordering
+        assert_eq!(tv.trades[0].timestamp, 1672635600000);
+        assert_eq!(tv.trades[0].price, dec!(16640.3));
+        assert_eq!(tv.trades[0].quantity, dec!(0.06));
+        assert!(matches!(tv.trades[0].side, TradeSide::Buy));
+        // Entry Short sells, Exit Short buys
+        assert!(matches!(tv.trades[2].side, TradeSide::Sell));
+        assert!(matches!(tv.trades[3].side, TradeSide::Buy));
+    }
+
+    #[test]
+    fn test_parse_tv_indicator_export() {
+        let csv = "time,open,high,low,close,EMA,RSI\n\
+                   1672531200,16541.8,16545.2,16520.0,16533.1,NaN,NaN\n\
+                   1672534800,16533.1,16560.4,16530.2,16555.7,16544.4,61.25\n";
+        let tv = parse_tv_csv(csv).unwrap();
+
+        assert!(tv.trades.is_empty());
+        assert_eq!(tv.indicators.len(), 2);
+        assert_eq!(tv.indicators["EMA"].len(), 1);
+        assert_eq!(tv.indicators["EMA"][0].timestamp, 1672534800000);
+        assert_eq!(tv.indicators["RSI"][0].value, dec!(61.25));
+    }
+
+    #[test]
+    fn test_tv_parity_detects_wrong_engine_value() {
+        let tv = parse_tv_csv(TV_TRADES_CSV).unwrap();
+        let mut ours = engine_fills(&tv);
+        assert!(compare_tv_trades(&ours, &tv.trades, dec!(0.0001)).is_empty());
+
+        ours[3].price += dec!(0.5);
+        let errors = compare_tv_trades(&ours, &tv.trades, dec!(0.0001));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("Price mismatch"));
+
+        ours.pop();
+        assert!(compare_tv_trades(&ours, &tv.trades, dec!(0.0001)).iter()
+            .any(|e| e.contains("Trade count mismatch")));
+    }
+}
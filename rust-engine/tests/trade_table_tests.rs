@@ -5,12 +5,41 @@ use rust_decimal_macros::dec;
 use anyhow::Result;
 
 use backtest_engine::types::*;
-use backtest_engine::trade_table::TradeTableGenerator;
+use backtest_engine::trade_table::{BreakevenBand, EntryCooldown, FillRangePolicy, IntrabarPath, OverlappingEntryPolicy, PositionMode, RiskLimits, TradeTableConfig, TradeTableGenerator, TradingSession};
+use chrono::Weekday;
+
+/// Bar with the given prices, 1000 volume and 100 trades
+fn bar(timestamp: u64, open: Decimal, high: Decimal, low: Decimal, close: Decimal) -> Bar {
+    Bar {
+        timestamp,
+        open,
+        high,
+        low,
+        close,
+        volume: dec!(1000.0),
+        trade_count: 100,
+    }
+}
+
+/// $1000 market entry on BTCUSDT with the given brackets, no time to live and no tags
+fn signal(side: TradeSide, take_profit: Option<Decimal>, stop_loss: Option<Decimal>) -> StrategySignal {
+    StrategySignal {
+        side,
+        size: dec!(1000.0),
+        entry_price: None,
+        take_profit,
+        stop_loss,
+        time_to_live: None,
+        rule_tag: None,
+        tags: Vec::new(),
+        symbol: "BTCUSDT".to_string(),
+    }
+}
 
 #[test]
 fn test_trade_table_generator_creation() {
     let generator = TradeTableGenerator::new();
-    assert_eq!(generator.default_size_usd, dec!(1000.0));
+    assert_eq!(generator.default_size_usd(), dec!(1000.0));
 }
 
 #[test]
@@ -34,11 +63,15 @@ fn test_long_trade_execution() -> Result<()> {
         entry_price: Some(bar.close),
         take_profit: Some(dec!(53000.0)), // 5% TP
         stop_loss: Some(dec!(48000.0)),   // 5% SL
-        time_to_live: Some(3600000), // 1 hour
+        time_to_live: Some(TimeToLive::Duration(3600000)), // 1 hour
+        rule_tag: None,
+        tags: Vec::new(),
+        symbol: "BTCUSDT".to_string(),
     }];
     
     // Process entry
     generator.process_bar(
+        "BTCUSDT",
         &bar,
         &signals,
         &IntrabarPolicy::ExactTrades,
@@ -59,6 +92,7 @@ fn test_long_trade_execution() -> Result<()> {
     
     // Process exit
     generator.process_bar(
+        "BTCUSDT",
         &exit_bar,
         &[], // No new signals
         &IntrabarPolicy::ExactTrades,
@@ -112,11 +146,15 @@ fn test_short_trade_execution() -> Result<()> {
         entry_price: Some(bar.close),
         take_profit: Some(dec!(48000.0)), // 5% TP for short
         stop_loss: Some(dec!(53000.0)),   // 5% SL for short
-        time_to_live: Some(3600000), // 1 hour
+        time_to_live: Some(TimeToLive::Duration(3600000)), // 1 hour
+        rule_tag: None,
+        tags: Vec::new(),
+        symbol: "BTCUSDT".to_string(),
     }];
     
     // Process entry
     generator.process_bar(
+        "BTCUSDT",
         &bar,
         &signals,
         &IntrabarPolicy::ExactTrades,
@@ -137,6 +175,7 @@ fn test_short_trade_execution() -> Result<()> {
     
     // Process exit
     generator.process_bar(
+        "BTCUSDT",
         &exit_bar,
         &[], // No new signals
         &IntrabarPolicy::ExactTrades,
@@ -173,8 +212,10 @@ fn test_trade_rejection_min_notional() -> Result<()> {
     let mut generator = TradeTableGenerator::new();
     
     // Create rules with high minimum notional
-    let mut rules = ExchangeRules::default();
-    rules.min_notional = dec!(2000.0); // Higher than default $1000
+    let rules = ExchangeRules {
+        min_notional: dec!(2000.0), // Higher than default $1000
+        ..ExchangeRules::default()
+    };
     
     let bar = Bar {
         timestamp: 1609459200000,
@@ -192,11 +233,15 @@ fn test_trade_rejection_min_notional() -> Result<()> {
         entry_price: Some(bar.close),
         take_profit: Some(dec!(52500.0)),
         stop_loss: Some(dec!(47500.0)),
-        time_to_live: Some(3600000),
+        time_to_live: Some(TimeToLive::Duration(3600000)),
+        rule_tag: None,
+        tags: Vec::new(),
+        symbol: "BTCUSDT".to_string(),
     }];
     
     // Process bar
     generator.process_bar(
+        "BTCUSDT",
         &bar,
         &signals,
         &IntrabarPolicy::ExactTrades,
@@ -211,7 +256,7 @@ fn test_trade_rejection_min_notional() -> Result<()> {
     
     // Verify rejection was recorded
     assert_eq!(result.rejected_trades.len(), 1);
-    assert_eq!(result.rejected_trades[0].reason, "Rejected – NotionalMin");
+    assert_eq!(result.rejected_trades[0].reason, ReasonCode::NotionalMin);
     
     Ok(())
 }
@@ -221,7 +266,7 @@ fn test_multiple_trades_summary() -> Result<()> {
     let mut generator = TradeTableGenerator::new();
     
     // Create multiple bars with trades
-    let bars = vec![
+    let bars = [
         // Bar 1: Long entry
         Bar {
             timestamp: 1609459200000,
@@ -264,7 +309,7 @@ fn test_multiple_trades_summary() -> Result<()> {
         },
     ];
     
-    let signals_sequence = vec![
+    let signals_sequence = [
         // Bar 1: Long entry
         vec![StrategySignal {
             side: TradeSide::Buy,
@@ -272,7 +317,10 @@ fn test_multiple_trades_summary() -> Result<()> {
             entry_price: Some(dec!(50500.0)),
             take_profit: Some(dec!(53000.0)),
             stop_loss: Some(dec!(48000.0)),
-            time_to_live: Some(3600000),
+            time_to_live: Some(TimeToLive::Duration(3600000)),
+            rule_tag: None,
+            tags: Vec::new(),
+            symbol: "BTCUSDT".to_string(),
         }],
         // Bar 2: No signals (exit happens)
         vec![],
@@ -283,7 +331,10 @@ fn test_multiple_trades_summary() -> Result<()> {
             entry_price: Some(dec!(52000.0)),
             take_profit: Some(dec!(49000.0)),
             stop_loss: Some(dec!(54000.0)),
-            time_to_live: Some(3600000),
+            time_to_live: Some(TimeToLive::Duration(3600000)),
+            rule_tag: None,
+            tags: Vec::new(),
+            symbol: "BTCUSDT".to_string(),
         }],
         // Bar 4: No signals (exit happens)
         vec![],
@@ -292,6 +343,7 @@ fn test_multiple_trades_summary() -> Result<()> {
     // Process all bars
     for (i, bar) in bars.iter().enumerate() {
         generator.process_bar(
+            "BTCUSDT",
             bar,
             &signals_sequence[i],
             &IntrabarPolicy::ExactTrades,
@@ -309,15 +361,13 @@ fn test_multiple_trades_summary() -> Result<()> {
     assert_eq!(result.summary.total_trades, 2);
     assert_eq!(result.summary.wins, 1);
     assert_eq!(result.summary.losses, 1);
-    assert_eq!(result.summary.win_rate, dec!(50.0)); // 50% win rate
+    assert_eq!(result.summary.win_rate, Percent::new(dec!(50.0))); // 50% win rate
     
     Ok(())
 }
 
 #[test]
 fn test_slippage_calculation() -> Result<()> {
-    let mut generator = TradeTableGenerator::new();
-    
     let bar = Bar {
         timestamp: 1609459200000,
         open: dec!(50000.0),
@@ -334,7 +384,10 @@ fn test_slippage_calculation() -> Result<()> {
         entry_price: Some(bar.close),
         take_profit: Some(dec!(53000.0)),
         stop_loss: Some(dec!(48000.0)),
-        time_to_live: Some(3600000),
+        time_to_live: Some(TimeToLive::Duration(3600000)),
+        rule_tag: None,
+        tags: Vec::new(),
+        symbol: "BTCUSDT".to_string(),
     }];
     
     // Test with different slippage modes
@@ -348,6 +401,7 @@ fn test_slippage_calculation() -> Result<()> {
         let mut test_generator = TradeTableGenerator::new();
         
         test_generator.process_bar(
+            "BTCUSDT",
             &bar,
             &signals,
             &IntrabarPolicy::ExactTrades,
@@ -356,7 +410,7 @@ fn test_slippage_calculation() -> Result<()> {
         )?;
         
         // Verify the generator was created (basic functionality test)
-        assert_eq!(test_generator.default_size_usd, dec!(1000.0));
+        assert_eq!(test_generator.default_size_usd(), dec!(1000.0));
     }
     
     Ok(())
@@ -365,21 +419,1220 @@ fn test_slippage_calculation() -> Result<()> {
 #[test]
 fn test_fee_calculation() -> Result<()> {
     let generator = TradeTableGenerator::new();
+    let mut rules = ExchangeRules::default();
+    
+    // $1,000 notional at the 0.01% taker fee
+    let fee = generator.calculate_fee(dec!(0.02), dec!(50000.0), Liquidity::Taker, &rules)?;
+    assert_eq!(fee, dec!(0.10));
+    
+    // Fees are quantized to the price precision
+    rules.fee_rounding = RoundingMode::NearestEven;
+    rules.precision_price = 2;
+    let fee = generator.calculate_fee(dec!(0.0123), dec!(50000.0), Liquidity::Taker, &rules)?;
+    assert_eq!(fee, dec!(0.06)); // 615 * 0.0001 = 0.0615
+    rules.precision_price = 3;
+    let fee = generator.calculate_fee(dec!(0.0123), dec!(50000.0), Liquidity::Taker, &rules)?;
+    assert_eq!(fee, dec!(0.062));
+    
+    Ok(())
+}
+
+#[test]
+fn test_fee_basis() -> Result<()> {
+    let generator = TradeTableGenerator::new();
+    let rules = |fee_basis: FeeBasis| ExchangeRules {
+        maker_fee: dec!(-0.0001),
+        taker_fee: dec!(0.0004),
+        fee_basis,
+        ..ExchangeRules::default()
+    };
+    // 3 contracts at 2000: $6,000 notional
+    let fee = |basis: FeeBasis, quantity: Decimal, liquidity: Liquidity| {
+        generator.calculate_fee(quantity, dec!(2000.0), liquidity, &rules(basis))
+    };
+    
+    assert_eq!(fee(FeeBasis::Notional, dec!(3.0), Liquidity::Taker)?, dec!(2.4));
+    assert_eq!(fee(FeeBasis::Notional, dec!(3.0), Liquidity::Maker)?, dec!(-0.6));
+    
+    // Per contract, whatever the price or side of the book
+    assert_eq!(fee(FeeBasis::PerUnit(dec!(0.25)), dec!(3.0), Liquidity::Taker)?, dec!(0.75));
+    assert_eq!(fee(FeeBasis::PerUnit(dec!(0.25)), dec!(3.0), Liquidity::Maker)?, dec!(0.75));
+    
+    // Flat per order: a hundredth of the size costs the same
+    assert_eq!(fee(FeeBasis::PerOrder(dec!(1.5)), dec!(3.0), Liquidity::Taker)?, dec!(1.5));
+    assert_eq!(fee(FeeBasis::PerOrder(dec!(1.5)), dec!(0.03), Liquidity::Maker)?, dec!(1.5));
+    
+    Ok(())
+}
+
+#[test]
+fn test_fee_rounding_direction() -> Result<()> {
+    let generator = TradeTableGenerator::new();
+    let mut rules = ExchangeRules {
+        precision_price: 2,
+        ..ExchangeRules::default()
+    };
+    assert_eq!(rules.fee_rounding, RoundingMode::TowardPositive);
+    
+    // 0.0615 rounds up to the next cent by default, as exchanges charge it
+    let fee = generator.calculate_fee(dec!(0.0123), dec!(50000.0), Liquidity::Taker, &rules)?;
+    assert_eq!(fee, dec!(0.07));
+    
+    // Nearest rounding under-reports the same fee
+    rules.fee_rounding = RoundingMode::NearestEven;
+    let fee = generator.calculate_fee(dec!(0.0123), dec!(50000.0), Liquidity::Taker, &rules)?;
+    assert_eq!(fee, dec!(0.06));
+    
+    // Fees already on the grid are unchanged either way
+    rules.fee_rounding = RoundingMode::TowardPositive;
+    let fee = generator.calculate_fee(dec!(0.02), dec!(50000.0), Liquidity::Taker, &rules)?;
+    assert_eq!(fee, dec!(0.10));
+    
+    Ok(())
+}
+
+#[test]
+fn test_slippage_sign_per_side() -> Result<()> {
+    let mut generator = TradeTableGenerator::new();
+    let rules = ExchangeRules { tick_size: dec!(0.01), ..ExchangeRules::default() };
+    let price = dec!(50000.0);
+    
+    // No slippage leaves the price untouched on both sides
+    assert_eq!(generator.apply_slippage(price, &TradeSide::Buy, &SlippageMode::None, &rules)?, price);
+    assert_eq!(generator.apply_slippage(price, &TradeSide::Sell, &SlippageMode::None, &rules)?, price);
+    
+    // Buys pay up and sells give up the same 0.01% (TradeSweep) / 0.05% (SyntheticBook)
+    let buy = generator.apply_slippage(price, &TradeSide::Buy, &SlippageMode::TradeSweep, &rules)?;
+    let sell = generator.apply_slippage(price, &TradeSide::Sell, &SlippageMode::TradeSweep, &rules)?;
+    assert_eq!(buy, dec!(50005.0));
+    assert_eq!(sell, dec!(49995.0));
+    
+    let buy = generator.apply_slippage(price, &TradeSide::Buy, &SlippageMode::SyntheticBook, &rules)?;
+    let sell = generator.apply_slippage(price, &TradeSide::Sell, &SlippageMode::SyntheticBook, &rules)?;
+    assert_eq!(buy, dec!(50025.0));
+    assert_eq!(sell, dec!(49975.0));
+    
+    // Results land on the tick grid
+    let buy = generator.apply_slippage(dec!(123.4567), &TradeSide::Buy, &SlippageMode::TradeSweep, &rules)?;
+    assert_eq!(buy, dec!(123.47)); // 123.46904567 rounds to the nearest cent
+    
+    Ok(())
+}
+
+#[test]
+fn test_fixed_tick_slippage() -> Result<()> {
+    let mut generator = TradeTableGenerator::new();
+    let rules = ExchangeRules { tick_size: dec!(0.05), ..ExchangeRules::default() };
+    let price = dec!(1.25);
+    
+    // Two ticks against the fill regardless of price level
+    let buy = generator.apply_slippage(price, &TradeSide::Buy, &SlippageMode::FixedTicks(2), &rules)?;
+    let sell = generator.apply_slippage(price, &TradeSide::Sell, &SlippageMode::FixedTicks(2), &rules)?;
+    assert_eq!(buy, price + dec!(2) * rules.tick_size);
+    assert_eq!(sell, price - dec!(2) * rules.tick_size);
+    
+    // Zero ticks is the same as no slippage
+    assert_eq!(generator.apply_slippage(price, &TradeSide::Buy, &SlippageMode::FixedTicks(0), &rules)?, price);
+    
+    Ok(())
+}
+
+#[test]
+fn test_stochastic_slippage_is_seeded() -> Result<()> {
+    let rules = ExchangeRules { tick_size: dec!(0.01), ..ExchangeRules::default() };
+    let price = dec!(10000.0);
+    let mode = SlippageMode::Stochastic { mean: dec!(0.001), stddev: dec!(0.0003) };
+    let draws = |seed: u64| -> Result<Vec<Decimal>> {
+        let mut generator = TradeTableGenerator::new();
+        generator.set_random_seed(seed);
+        (0..2000).map(|_| generator.apply_slippage(price, &TradeSide::Buy, &mode, &rules)).collect()
+    };
+    
+    let first = draws(7)?;
+    assert_eq!(first, draws(7)?);
+    assert_ne!(first, draws(8)?);
+    
+    // Always adverse, and centered on the configured mean
+    assert!(first.iter().all(|fill| *fill >= price));
+    let mean_rate = first.iter().map(|fill| (fill - price) / price).sum::<Decimal>() / Decimal::from(first.len());
+    assert!(mean_rate > dec!(0.00097) && mean_rate < dec!(0.00103), "mean rate {}", mean_rate);
+    
+    Ok(())
+}
+
+#[test]
+fn test_thin_bars_slip_more_in_synthetic_book() -> Result<()> {
+    let mut generator = TradeTableGenerator::new();
+    let rules = ExchangeRules { tick_size: dec!(0.01), ..ExchangeRules::default() };
+    let price = dec!(50000.0);
+    // Wide enough that the slipped fills stay inside the bar
+    let bar = |trade_count: u32| Bar {
+        trade_count,
+        ..bar(1609459200000, price, price + dec!(100.0), price - dec!(100.0), price)
+    };
+    
+    let thin = generator.apply_bar_slippage(price, &TradeSide::Buy, &SlippageMode::SyntheticBook, &bar(10), &rules)?;
+    let deep = generator.apply_bar_slippage(price, &TradeSide::Buy, &SlippageMode::SyntheticBook, &bar(1000), &rules)?;
+    assert!(thin - price > deep - price, "thin {} vs deep {}", thin, deep);
+    
+    // The reference trade count, or an unreported one, charges the base rate
+    let base = generator.apply_slippage(price, &TradeSide::Buy, &SlippageMode::SyntheticBook, &rules)?;
+    assert_eq!(generator.apply_bar_slippage(price, &TradeSide::Buy, &SlippageMode::SyntheticBook, &bar(100), &rules)?, base);
+    assert_eq!(generator.apply_bar_slippage(price, &TradeSide::Buy, &SlippageMode::SyntheticBook, &bar(0), &rules)?, base);
+    
+    Ok(())
+}
+
+#[test]
+fn test_symbol_filters_snap_to_lot_size() -> Result<()> {
+    let generator = TradeTableGenerator::new();
+    let mut rules = ExchangeRules { lot_size: dec!(0.001), ..ExchangeRules::default() };
+    
+    assert_eq!(generator.apply_symbol_filters(dec!(0.0199), &rules)?, dec!(0.019));
+    
+    // Oversized orders are capped to whole lots under the default policy
+    rules.max_qty = dec!(0.0155);
+    assert_eq!(generator.apply_symbol_filters(dec!(0.0199), &rules)?, dec!(0.015));
+    
+    Ok(())
+}
+
+/// Open a 10x short at 50,000 and feed it a spike bar under the given policy
+fn run_short_spike(stop_loss: Decimal, intrabar_policy: IntrabarPolicy) -> Result<TradeRecord> {
+    let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+        leverage: dec!(10.0),
+        ..TradeTableConfig::default()
+    });
+    
+    let entry_bar = bar(1609459200000, dec!(50000.0), dec!(50100.0), dec!(49900.0), dec!(50000.0));
+    let signals = vec![StrategySignal {
+        entry_price: Some(entry_bar.close),
+        ..signal(TradeSide::Sell, Some(dec!(45000.0)), Some(stop_loss))
+    }];
+    
+    generator.process_bar("BTCUSDT", &entry_bar, &signals, &intrabar_policy, &SlippageMode::None, &ExchangeRules::default())?;
+    
+    // Upward wick through both the liquidation price and the stop
+    let spike_bar = bar(1609459260000, dec!(50000.0), dec!(57000.0), dec!(49900.0), dec!(50100.0));
+    
+    generator.process_bar("BTCUSDT", &spike_bar, &[], &intrabar_policy, &SlippageMode::None, &ExchangeRules::default())?;
+    
+    let result = generator.generate_result();
+    assert_eq!(result.trades.len(), 1);
+    Ok(result.trades[0].clone())
+}
+
+#[test]
+fn test_short_liquidated_on_spike_before_stop() -> Result<()> {
+    // 10x short at 50,000 with 0.5% maintenance margin liquidates at 54,750,
+    // closer to entry than the 56,000 stop
+    for policy in [IntrabarPolicy::ExactTrades, IntrabarPolicy::LinearInterpolation] {
+        let trade = run_short_spike(dec!(56000.0), policy)?;
+        assert_eq!(trade.exit_reason, ExitReason::Liquidation);
+        assert_eq!(trade.exit_price, dec!(54750.0));
+        assert_eq!(trade.hit_tp_sl, HitTpSl::None);
+    }
+    
+    Ok(())
+}
+
+#[test]
+fn test_short_stop_before_liquidation_when_closer() -> Result<()> {
+    for policy in [IntrabarPolicy::ExactTrades, IntrabarPolicy::LinearInterpolation] {
+        let trade = run_short_spike(dec!(52000.0), policy)?;
+        assert_eq!(trade.exit_reason, ExitReason::StopLoss);
+        assert_eq!(trade.exit_price, dec!(52000.0));
+    }
+    
+    Ok(())
+}
+
+#[test]
+fn test_bar_ttl_ignores_gaps_between_bars() -> Result<()> {
+    let mut generator = TradeTableGenerator::new();
+    let flat_bar = |timestamp: u64| bar(timestamp, dec!(50000.0), dec!(50100.0), dec!(49900.0), dec!(50000.0));
+    
+    // Minute bars with multi-hour holes; a duration TTL would have expired long ago
+    let timestamps = [
+        1609459200000,
+        1609459260000,
+        1609466400000,
+        1609466460000,
+        1609488000000,
+        1609488060000,
+        1609488120000,
+    ];
+    
+    let signals = vec![StrategySignal {
+        time_to_live: Some(TimeToLive::Bars(5)),
+        ..signal(TradeSide::Buy, None, None)
+    }];
+    
+    for (i, &timestamp) in timestamps.iter().enumerate() {
+        let bar_signals: &[StrategySignal] = if i == 0 { &signals } else { &[] };
+        generator.process_bar(
+            "BTCUSDT",
+            &flat_bar(timestamp),
+            bar_signals,
+            &IntrabarPolicy::ExactTrades,
+            &SlippageMode::None,
+            &ExchangeRules::default(),
+        )?;
+        
+        let closed = generator.generate_result().trades.len();
+        assert_eq!(closed, usize::from(i >= 5), "bar {}", i);
+    }
+    
+    let trade = &generator.generate_result().trades[0];
+    assert_eq!(trade.exit_reason, ExitReason::Timeout);
+    assert_eq!(trade.exit_time_utc, "2021-01-01T08:01:00.000Z");
+    
+    Ok(())
+}
+
+fn run_timeout_result(slippage_mode: SlippageMode) -> Result<TradeTableResult> {
+    let mut generator = TradeTableGenerator::new();
+    let rules = ExchangeRules::default();
+    let bar = |timestamp: u64, close: Decimal| bar(timestamp, close, close + dec!(50.0), close - dec!(50.0), close);
+    let signals = vec![StrategySignal {
+        time_to_live: Some(TimeToLive::Bars(1)),
+        ..signal(TradeSide::Buy, None, None)
+    }];
+    
+    generator.process_bar("BTCUSDT", &bar(1609459200000, dec!(50000.0)), &signals, &IntrabarPolicy::ExactTrades, &slippage_mode, &rules)?;
+    generator.process_bar("BTCUSDT", &bar(1609459260000, dec!(51000.0)), &[], &IntrabarPolicy::ExactTrades, &slippage_mode, &rules)?;
+    
+    let result = generator.generate_result();
+    assert_eq!(result.trades.len(), 1);
+    assert_eq!(result.trades[0].exit_reason, ExitReason::Timeout);
+    Ok(result)
+}
+
+fn run_timeout_exit(slippage_mode: SlippageMode) -> Result<TradeRecord> {
+    Ok(run_timeout_result(slippage_mode)?.trades[0].clone())
+}
+
+#[test]
+fn test_timeout_exit_pays_slippage() -> Result<()> {
+    let mut generator = TradeTableGenerator::new();
+    let rules = ExchangeRules::default();
+    
+    let frictionless = run_timeout_exit(SlippageMode::None)?;
+    let swept = run_timeout_exit(SlippageMode::TradeSweep)?;
+    
+    // Closing a long sells into the bid at the bar close
+    assert_eq!(frictionless.exit_price, dec!(51000.0));
+    let expected_exit = generator.apply_slippage(dec!(51000.0), &TradeSide::Sell, &SlippageMode::TradeSweep, &rules)?;
+    assert_eq!(swept.exit_price, expected_exit);
+    assert!(swept.exit_price < frictionless.exit_price);
+    
+    // Exit fee is charged on the slipped price
+    let entry_fee = generator.calculate_fee(swept.qty, swept.entry_price, Liquidity::Taker, &rules)?;
+    let exit_fee = generator.calculate_fee(swept.qty, swept.exit_price, Liquidity::Taker, &rules)?;
+    assert_eq!(swept.fees_usd, entry_fee + exit_fee);
+    
+    Ok(())
+}
+
+/// Short held one bar and closed on timeout at a close that is also the bar's high,
+/// with slippage of 500 ticks
+fn run_short_timeout_at_high(fill_range_policy: FillRangePolicy) -> Result<TradeRecord> {
+    let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+        fill_range_policy,
+        ..TradeTableConfig::default()
+    });
+    let rules = ExchangeRules { tick_size: dec!(0.01), ..ExchangeRules::default() };
+    let slippage = SlippageMode::FixedTicks(500);
+    let entry_bar = bar(1609459200000, dec!(50000.0), dec!(50100.0), dec!(49900.0), dec!(50000.0));
+    let exit_bar = bar(1609459260000, dec!(50000.0), dec!(50020.0), dec!(49990.0), dec!(50020.0));
+    let signals = vec![StrategySignal {
+        time_to_live: Some(TimeToLive::Bars(1)),
+        ..signal(TradeSide::Sell, None, None)
+    }];
+    
+    generator.process_bar("BTCUSDT", &entry_bar, &signals, &IntrabarPolicy::ExactTrades, &slippage, &rules)?;
+    generator.process_bar("BTCUSDT", &exit_bar, &[], &IntrabarPolicy::ExactTrades, &slippage, &rules)?;
+    
+    let result = generator.generate_result();
+    assert_eq!(result.trades.len(), 1);
+    assert_eq!(result.trades[0].exit_reason, ExitReason::Timeout);
+    Ok(result.trades[0].clone())
+}
+
+#[test]
+fn test_slipped_exit_is_clamped_to_bar_high() -> Result<()> {
+    // Buying back at 50020 plus 5.00 of slippage would fill above anything that traded
+    let clamped = run_short_timeout_at_high(FillRangePolicy::Clamp)?;
+    assert_eq!(clamped.exit_price, dec!(50020.0));
+    assert_eq!(clamped.entry_price, dec!(49995.0));
+    assert!(!clamped.fill_outside_bar);
+    
+    let flagged = run_short_timeout_at_high(FillRangePolicy::Flag)?;
+    assert_eq!(flagged.exit_price, dec!(50025.0));
+    assert!(flagged.fill_outside_bar);
+    
+    Ok(())
+}
+
+#[test]
+fn test_summary_separates_slippage_from_fees() -> Result<()> {
+    let summary = |slippage_mode: SlippageMode| -> Result<(TradeRecord, TradeSummary)> {
+        let result = run_timeout_result(slippage_mode)?;
+        Ok((result.trades[0].clone(), result.summary))
+    };
+    
+    let (frictionless_trade, frictionless) = summary(SlippageMode::None)?;
+    assert_eq!(frictionless_trade.slippage_usd, dec!(0.0));
+    assert_eq!(frictionless.total_slippage_usd, dec!(0.0));
+    assert_eq!(frictionless.total_fees_usd, frictionless_trade.fees_usd);
+    
+    // 5 bps against the fill on the way in (at 50000) and on the way out (at 51000)
+    let (booked_trade, booked) = summary(SlippageMode::SyntheticBook)?;
+    let expected = (dec!(50000.0) + dec!(51000.0)) * dec!(0.0005) * booked_trade.qty;
+    assert_eq!(booked_trade.slippage_usd, expected);
+    assert_eq!(booked.total_slippage_usd, expected);
+    assert_eq!(booked.total_fees_usd, booked_trade.fees_usd);
+    
+    // Slippage is already in the prices: frictionless PnL minus both costs
+    let gross = (booked_trade.exit_price - booked_trade.entry_price) * booked_trade.qty + booked.total_slippage_usd;
+    assert_eq!(gross, (frictionless_trade.exit_price - frictionless_trade.entry_price) * booked_trade.qty);
+    
+    Ok(())
+}
+
+fn run_deep_wick(intrabar_path: IntrabarPath) -> Result<Vec<TradeRecord>> {
+    let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+        intrabar_path,
+        ..TradeTableConfig::default()
+    });
+    let rules = ExchangeRules::default();
+    let policy = IntrabarPolicy::LinearInterpolation;
+    
+    let entry_bar = bar(1609459200000, dec!(50000.0), dec!(50100.0), dec!(49900.0), dec!(50000.0));
+    let signals = vec![signal(TradeSide::Buy, Some(dec!(55000.0)), Some(dec!(48000.0)))];
+    generator.process_bar("BTCUSDT", &entry_bar, &signals, &policy, &SlippageMode::None, &rules)?;
+    
+    // Opens and closes near the entry but wicks down through the stop
+    let wick_bar = bar(1609459260000, dec!(50000.0), dec!(50200.0), dec!(47000.0), dec!(50100.0));
+    generator.process_bar("BTCUSDT", &wick_bar, &[], &policy, &SlippageMode::None, &rules)?;
+    
+    Ok(generator.generate_result().trades)
+}
+
+#[test]
+fn test_wick_path_triggers_stop_missed_by_linear_path() -> Result<()> {
+    assert!(run_deep_wick(IntrabarPath::OpenClose)?.is_empty());
+    
+    let trades = run_deep_wick(IntrabarPath::NearestExtremeFirst)?;
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].exit_reason, ExitReason::StopLoss);
+    assert_eq!(trades[0].exit_price, dec!(48000.0));
+    
+    Ok(())
+}
+
+#[test]
+fn test_hedge_mode_closes_legs_independently() -> Result<()> {
+    let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+        position_mode: PositionMode::Hedge,
+        ..TradeTableConfig::default()
+    });
+    let rules = ExchangeRules::default();
+    let policy = IntrabarPolicy::ExactTrades;
+    let bar = |timestamp: u64, high: Decimal, low: Decimal| bar(timestamp, dec!(50000.0), high, low, dec!(50000.0));
+    let leg = |side: TradeSide, take_profit: Decimal, stop_loss: Decimal| signal(side, Some(take_profit), Some(stop_loss));
+    
+    // Long and short opened together at 50000
+    let signals = vec![
+        leg(TradeSide::Buy, dec!(51000.0), dec!(45000.0)),
+        leg(TradeSide::Sell, dec!(49000.0), dec!(55000.0)),
+    ];
+    generator.process_bar("BTCUSDT", &bar(1609459200000, dec!(50100.0), dec!(49900.0)), &signals, &policy, &SlippageMode::None, &rules)?;
+    let (gross, net) = generator.open_notional();
+    assert_eq!(gross, dec!(2000.0));
+    assert_eq!(net, dec!(0.0));
+    
+    // Rally takes profit on the long only
+    generator.process_bar("BTCUSDT", &bar(1609459260000, dec!(51200.0), dec!(49900.0)), &[], &policy, &SlippageMode::None, &rules)?;
+    assert_eq!(generator.generate_result().trades.len(), 1);
+    assert_eq!(generator.open_notional(), (dec!(1000.0), dec!(-1000.0)));
+    
+    // Drop takes profit on the short
+    generator.process_bar("BTCUSDT", &bar(1609459320000, dec!(50100.0), dec!(48800.0)), &[], &policy, &SlippageMode::None, &rules)?;
+    
+    let result = generator.generate_result();
+    assert_eq!(result.trades.len(), 2);
+    let (long, short) = (&result.trades[0], &result.trades[1]);
+    assert_eq!((long.trade_type.clone(), long.exit_price), (TradeType::Long, dec!(51000.0)));
+    assert_eq!((short.trade_type.clone(), short.exit_price), (TradeType::Short, dec!(49000.0)));
+    
+    // Each leg earns 1000 * 0.02 = 20 before fees
+    let fees = long.fees_usd + short.fees_usd;
+    assert_eq!(result.summary.net_pnl_usd, dec!(40.0) - fees);
+    assert_eq!(long.pnl_usd + short.pnl_usd, result.summary.net_pnl_usd);
+    
+    Ok(())
+}
+
+/// Run one long round trip per outcome: entry at 100, then a bar hitting TP (win) or SL (loss)
+fn summary_for_outcomes(outcomes: &[bool]) -> Result<TradeSummary> {
+    let mut generator = TradeTableGenerator::new();
+    let flat = |timestamp: u64, high: Decimal, low: Decimal| bar(timestamp, dec!(100.0), high, low, dec!(100.0));
+    
+    for (i, &win) in outcomes.iter().enumerate() {
+        let entry_time = 1609459200000 + i as u64 * 120_000;
+        let signals = vec![signal(TradeSide::Buy, Some(dec!(102.0)), Some(dec!(98.0)))];
+        generator.process_bar(
+            "BTCUSDT",
+            &flat(entry_time, dec!(100.5), dec!(99.5)),
+            &signals,
+            &IntrabarPolicy::ExactTrades,
+            &SlippageMode::None,
+            &ExchangeRules::default(),
+        )?;
+        
+        let exit_bar = if win {
+            flat(entry_time + 60_000, dec!(103.0), dec!(99.5))
+        } else {
+            flat(entry_time + 60_000, dec!(100.5), dec!(97.0))
+        };
+        generator.process_bar(
+            "BTCUSDT",
+            &exit_bar,
+            &[],
+            &IntrabarPolicy::ExactTrades,
+            &SlippageMode::None,
+            &ExchangeRules::default(),
+        )?;
+    }
+    
+    Ok(generator.generate_result().summary)
+}
+
+#[test]
+fn test_summary_all_wins() -> Result<()> {
+    let summary = summary_for_outcomes(&[true, true, true])?;
+    
+    assert_eq!(summary.total_trades, 3);
+    assert_eq!(summary.losses, 0);
+    assert_eq!(summary.win_rate, Percent::new(dec!(100.0)));
+    assert_eq!(summary.avg_loss_usd, dec!(0.0));
+    // No losses: the documented sentinel, not 0
+    assert_eq!(summary.profit_factor, PROFIT_FACTOR_NO_LOSSES);
+    assert_eq!(summary.expectancy, summary.avg_win_usd);
+    assert!(summary.expectancy > dec!(0.0));
+    
+    Ok(())
+}
+
+#[test]
+fn test_summary_all_losses() -> Result<()> {
+    let summary = summary_for_outcomes(&[false, false])?;
+    
+    assert_eq!(summary.total_trades, 2);
+    assert_eq!(summary.wins, 0);
+    assert_eq!(summary.win_rate, Percent::new(dec!(0.0)));
+    assert_eq!(summary.avg_win_usd, dec!(0.0));
+    assert_eq!(summary.profit_factor, dec!(0.0));
+    assert_eq!(summary.expectancy, summary.avg_loss_usd);
+    assert!(summary.expectancy < dec!(0.0));
+    
+    Ok(())
+}
+
+#[test]
+fn test_summary_zero_trades_and_mixed_expectancy() -> Result<()> {
+    let summary = summary_for_outcomes(&[])?;
+    
+    assert_eq!(summary.total_trades, 0);
+    assert_eq!(summary.win_rate, Percent::new(dec!(0.0)));
+    assert_eq!(summary.expectancy, dec!(0.0));
+    assert_eq!(summary.profit_factor, dec!(0.0));
+    assert_eq!(summary.avg_holding_time_hours, dec!(0.0));
+    
+    // Expectancy is net PnL per trade, consistent with the win/loss averages
+    let summary = summary_for_outcomes(&[true, false, false])?;
+    assert_eq!(summary.expectancy * dec!(3), summary.net_pnl_usd);
+    let weighted = (summary.avg_win_usd + dec!(2) * summary.avg_loss_usd) / dec!(3);
+    assert!((summary.expectancy - weighted).abs() < dec!(0.0000000001));
+    assert!(summary.profit_factor > dec!(0.0) && summary.profit_factor < PROFIT_FACTOR_NO_LOSSES);
+    
+    Ok(())
+}
+
+/// Round trips from 100 that exit on the next bar at (high, low); a bar inside the
+/// 98/102 bracket times out flat at 100
+fn summary_for_exits(config: TradeTableConfig, rules: &ExchangeRules, exits: &[(Decimal, Decimal)]) -> Result<TradeSummary> {
+    let mut generator = TradeTableGenerator::with_config(config);
+    let flat = |timestamp: u64, high: Decimal, low: Decimal| bar(timestamp, dec!(100.0), high, low, dec!(100.0));
+    
+    for (i, &(high, low)) in exits.iter().enumerate() {
+        let entry_time = 1609459200000 + i as u64 * 120_000;
+        let signals = vec![StrategySignal {
+            time_to_live: Some(TimeToLive::Bars(1)),
+            ..signal(TradeSide::Buy, Some(dec!(102.0)), Some(dec!(98.0)))
+        }];
+        generator.process_bar("BTCUSDT", &flat(entry_time, dec!(100.5), dec!(99.5)), &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, rules)?;
+        generator.process_bar("BTCUSDT", &flat(entry_time + 60_000, high, low), &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, rules)?;
+    }
+    
+    Ok(generator.generate_result().summary)
+}
+
+#[test]
+fn test_breakeven_trades_are_scratches() -> Result<()> {
+    let win = (dec!(103.0), dec!(99.5));
+    let loss = (dec!(100.5), dec!(97.0));
+    let flat = (dec!(100.5), dec!(99.5));
+    
+    // Without fees a flat exit nets exactly zero: a scratch, not a loss
+    let fee_free = ExchangeRules {
+        maker_fee: dec!(0.0),
+        taker_fee: dec!(0.0),
+        ..ExchangeRules::default()
+    };
+    let summary = summary_for_exits(TradeTableConfig::default(), &fee_free, &[win, flat, loss])?;
+    assert_eq!((summary.wins, summary.losses, summary.scratches), (1, 1, 1));
+    assert_eq!(summary.win_rate, Percent::new(dec!(50.0)));
+    assert_eq!(summary.avg_loss_usd, dec!(-20.0));
+    
+    // With fees the flat exit loses exactly its fees: a loss under the default band,
+    // a scratch when the band covers fees
+    let summary = summary_for_exits(TradeTableConfig::default(), &ExchangeRules::default(), &[win, flat])?;
+    assert_eq!((summary.wins, summary.losses, summary.scratches), (1, 1, 0));
+    
+    let fee_band = TradeTableConfig {
+        breakeven_band: BreakevenBand::Fees,
+        ..TradeTableConfig::default()
+    };
+    let summary = summary_for_exits(fee_band, &ExchangeRules::default(), &[win, flat])?;
+    assert_eq!((summary.wins, summary.losses, summary.scratches), (1, 0, 1));
+    assert_eq!(summary.win_rate, Percent::new(dec!(100.0)));
+    assert!(summary.net_pnl_usd < summary.avg_win_usd);
+    
+    Ok(())
+}
+
+#[test]
+fn test_percent_fields_are_in_percent() -> Result<()> {
+    let mut generator = TradeTableGenerator::new();
+    let rules = ExchangeRules::default();
+    let flat = |timestamp: u64, high: Decimal, low: Decimal| bar(timestamp, dec!(100.0), high, low, dec!(100.0));
+    let signals = vec![signal(TradeSide::Buy, Some(dec!(102.0)), Some(dec!(98.0)))];
+    
+    // +2% then -2% on $1000 against $10,000 of equity
+    for (i, (high, low)) in [(dec!(103.0), dec!(99.5)), (dec!(100.5), dec!(97.0))].into_iter().enumerate() {
+        let entry_time = 1609459200000 + i as u64 * 120_000;
+        generator.process_bar("BTCUSDT", &flat(entry_time, dec!(100.5), dec!(99.5)), &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+        generator.process_bar("BTCUSDT", &flat(entry_time + 60_000, high, low), &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    }
+    let result = generator.generate_result();
+    
+    // pnl_pct: about 2 (percent of size_usd), not 0.02
+    let win = &result.trades[0];
+    assert_eq!(win.pnl_pct, Percent::from_ratio(win.pnl_usd / win.size_usd));
+    assert!(win.pnl_pct.value() > dec!(1.9) && win.pnl_pct.value() < dec!(2.0));
+    
+    // win_rate: 50 for one win in two
+    assert_eq!(result.summary.win_rate.value(), dec!(50));
+    
+    // max_drawdown: the $20 loss from a ~$10,020 peak is about 0.2%, not 0.002
+    let drawdown = result.summary.max_drawdown.value();
+    assert!(drawdown > dec!(0.19) && drawdown < dec!(0.21), "{}", drawdown);
+    
+    Ok(())
+}
+
+#[test]
+fn test_drawdown_limit_blocks_new_entries() -> Result<()> {
+    let config = TradeTableConfig {
+        risk_limits: RiskLimits {
+            max_drawdown_pct: Some(Percent::new(dec!(0.3))),
+            ..RiskLimits::default()
+        },
+        ..TradeTableConfig::default()
+    };
+    let mut generator = TradeTableGenerator::with_config(config);
+    let rules = ExchangeRules::default();
+    let flat = |timestamp: u64, high: Decimal, low: Decimal| bar(timestamp, dec!(100.0), high, low, dec!(100.0));
+    let signals = vec![signal(TradeSide::Buy, Some(dec!(102.0)), Some(dec!(98.0)))];
+    
+    // Each stop-out loses ~$20 (0.2%) of $10,000; the second one crosses the 0.3% cap
+    for i in 0..4u64 {
+        let entry_time = 1609459200000 + i * 120_000;
+        generator.process_bar("BTCUSDT", &flat(entry_time, dec!(100.5), dec!(99.5)), &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+        generator.process_bar("BTCUSDT", &flat(entry_time + 60_000, dec!(100.5), dec!(97.0)), &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    }
+    let result = generator.generate_result();
+    
+    assert_eq!(result.trades.len(), 2);
+    assert_eq!(result.rejected_trades.len(), 2);
+    for rejected in &result.rejected_trades {
+        assert_eq!(rejected.reason, ReasonCode::RiskLimit);
+        assert_eq!(rejected.notional, dec!(1000.0));
+    }
+    assert_eq!(result.rejected_trades[0].timestamp, 1609459200000 + 240_000);
+    
+    Ok(())
+}
+
+#[test]
+fn test_daily_loss_limit_resets_at_utc_midnight() -> Result<()> {
+    let config = TradeTableConfig {
+        risk_limits: RiskLimits {
+            daily_loss_limit: Some(dec!(30.0)),
+            ..RiskLimits::default()
+        },
+        ..TradeTableConfig::default()
+    };
+    let mut generator = TradeTableGenerator::with_config(config);
+    let rules = ExchangeRules::default();
+    let flat = |timestamp: u64, high: Decimal, low: Decimal| bar(timestamp, dec!(100.0), high, low, dec!(100.0));
+    let signals = vec![signal(TradeSide::Buy, Some(dec!(102.0)), Some(dec!(98.0)))];
+    let stop_out = |generator: &mut TradeTableGenerator, entry_time: u64| -> Result<()> {
+        generator.process_bar("BTCUSDT", &flat(entry_time, dec!(100.5), dec!(99.5)), &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+        generator.process_bar("BTCUSDT", &flat(entry_time + 60_000, dec!(100.5), dec!(97.0)), &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)
+    };
+    
+    // A losing morning on 2021-01-01: the second ~$20 stop-out crosses the $30 limit
+    let midnight = 1609459200000;
+    for i in 0..4u64 {
+        stop_out(&mut generator, midnight + 8 * 3_600_000 + i * 120_000)?;
+    }
+    let result = generator.generate_result();
+    assert_eq!(result.trades.len(), 2);
+    assert_eq!(result.rejected_trades.len(), 2);
+    assert!(result.rejected_trades.iter().all(|r| r.reason == ReasonCode::RiskLimit));
+    
+    // Still blocked late in the day, trading again right after the next midnight
+    stop_out(&mut generator, midnight + 86_400_000 - 120_000)?;
+    assert_eq!(generator.rejected_trades().len(), 3);
+    stop_out(&mut generator, midnight + 86_400_000)?;
+    assert_eq!(generator.rejected_trades().len(), 3);
+    assert_eq!(generator.trade_records().len(), 3);
+    
+    Ok(())
+}
+
+fn run_open_at_end(close_at_end_of_data: bool) -> Result<TradeTableGenerator> {
+    let config = TradeTableConfig {
+        close_at_end_of_data,
+        ..TradeTableConfig::default()
+    };
+    let mut generator = TradeTableGenerator::with_config(config);
+    let rules = ExchangeRules::default();
+    let bar = |timestamp: u64, close: Decimal| bar(timestamp, dec!(100.0), dec!(101.0), dec!(99.5), close);
+    let signals = vec![signal(TradeSide::Buy, Some(dec!(105.0)), Some(dec!(95.0)))];
+    
+    let last_bar = bar(1609459260000, dec!(100.5));
+    generator.process_bar("BTCUSDT", &bar(1609459200000, dec!(100.0)), &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    generator.process_bar("BTCUSDT", &last_bar, &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    generator.finalize(&last_bar, &SlippageMode::None, &rules)?;
+    
+    Ok(generator)
+}
+
+#[test]
+fn test_open_position_closed_at_end_of_data() -> Result<()> {
+    let generator = run_open_at_end(true)?;
+    let result = generator.generate_result();
+    
+    assert_eq!(result.trades.len(), 1);
+    let trade = &result.trades[0];
+    assert_eq!(trade.exit_reason, ExitReason::EndOfData);
+    assert_eq!(trade.hit_tp_sl, HitTpSl::None);
+    assert_eq!(trade.exit_price, dec!(100.5));
+    assert_eq!(trade.exit_time_utc, "2021-01-01T00:01:00.000Z");
+    assert!(trade.pnl_usd > dec!(0.0));
+    assert_eq!(result.summary.net_pnl_usd, trade.pnl_usd);
+    assert!(generator.active_positions().is_empty());
+    
+    // Opting out leaves the position open and out of the table
+    let generator = run_open_at_end(false)?;
+    assert!(generator.generate_result().trades.is_empty());
+    assert_eq!(generator.active_positions().len(), 1);
+    
+    Ok(())
+}
+
+#[test]
+fn test_maker_rebate_on_take_profit() -> Result<()> {
+    let run = |maker_fee: Decimal| -> Result<TradeRecord> {
+        let mut generator = TradeTableGenerator::new();
+        let rules = ExchangeRules {
+            maker_fee,
+            ..ExchangeRules::default()
+        };
+        let flat = |timestamp: u64, high: Decimal| bar(timestamp, dec!(100.0), high, dec!(99.5), dec!(100.0));
+        let signals = vec![signal(TradeSide::Buy, Some(dec!(102.0)), Some(dec!(98.0)))];
+        generator.process_bar("BTCUSDT", &flat(1609459200000, dec!(100.5)), &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+        generator.process_bar("BTCUSDT", &flat(1609459260000, dec!(103.0)), &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+        Ok(generator.generate_result().trades[0].clone())
+    };
+    
+    // Taker entry pays 0.01% of $1000; the maker take profit earns 0.02% of $1020 back
+    let rebated = run(dec!(-0.0002))?;
+    assert_eq!(rebated.exit_reason, ExitReason::TakeProfit);
+    assert_eq!(rebated.fees_usd, dec!(0.10) - dec!(0.204));
+    assert!(rebated.fees_usd < dec!(0.0));
+    assert_eq!(rebated.pnl_usd, dec!(20.104));
+    
+    let fee_free_exit = run(dec!(0.0))?;
+    assert!(rebated.pnl_usd > fee_free_exit.pnl_usd);
+    assert_eq!(fee_free_exit.pnl_usd, dec!(19.90));
+    
+    Ok(())
+}
+
+#[test]
+fn test_entry_cooldown_rejects_back_to_back_entries() -> Result<()> {
+    let config = TradeTableConfig {
+        entry_cooldown: Some(EntryCooldown::Duration(300_000)),
+        ..TradeTableConfig::default()
+    };
+    let mut generator = TradeTableGenerator::with_config(config);
+    let rules = ExchangeRules::default();
+    let flat = |timestamp: u64| bar(timestamp, dec!(100.0), dec!(100.5), dec!(99.5), dec!(100.0));
+    let signals = vec![StrategySignal {
+        time_to_live: Some(TimeToLive::Bars(1)),
+        ..signal(TradeSide::Buy, None, None)
+    }];
+    
+    // Minute bars: enter at 0, flat again after bar 1, signal again at 2 and at 5
+    let start = 1609459200000;
+    for minute in 0..6u64 {
+        let bar_signals: &[StrategySignal] = if minute % 2 == 0 || minute == 5 { &signals } else { &[] };
+        generator.process_bar("BTCUSDT", &flat(start + minute * 60_000), bar_signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+        if minute == 3 {
+            // One trade so far and the minute-2 signal rejected inside the 5-minute window
+            assert_eq!(generator.trade_records().len(), 1);
+            assert_eq!(generator.rejected_trades().len(), 1);
+            assert_eq!(generator.rejected_trades()[0].reason, ReasonCode::Cooldown);
+            assert_eq!(generator.rejected_trades()[0].timestamp, start + 120_000);
+        }
+    }
+    
+    // Minute 4 is still cooling down; minute 5 is exactly five minutes after the entry
+    let result = generator.generate_result();
+    assert_eq!(result.rejected_trades.len(), 2);
+    assert_eq!(generator.active_positions().len(), 1);
+    
+    Ok(())
+}
+
+#[test]
+fn test_exit_at_breakeven_price_nets_zero() -> Result<()> {
+    let mut generator = TradeTableGenerator::new();
+    let rules = ExchangeRules::default();
+    
+    for slippage_mode in [SlippageMode::None, SlippageMode::TradeSweep] {
+        let trade = run_timeout_exit(slippage_mode.clone())?;
+        assert!(trade.breakeven_price > trade.entry_price);
+        
+        // Close the same long at the breakeven market price instead
+        let exit_fill = generator.apply_slippage(trade.breakeven_price, &TradeSide::Sell, &slippage_mode, &rules)?;
+        let entry_fee = generator.calculate_fee(trade.qty, trade.entry_price, Liquidity::Taker, &rules)?;
+        let exit_fee = generator.calculate_fee(trade.qty, exit_fill, Liquidity::Taker, &rules)?;
+        let pnl = (exit_fill - trade.entry_price) * trade.qty - entry_fee - exit_fee;
+        
+        // Off only by tick rounding of the fill and the fee on the price difference
+        assert!(pnl.abs() < dec!(0.01), "{:?}: {}", slippage_mode, pnl);
+    }
+    
+    Ok(())
+}
+
+#[test]
+fn test_short_breakeven_price_is_below_entry() {
+    let breakeven = breakeven_price(&TradeType::Short, dec!(100.0), dec!(10.0), dec!(2.0), dec!(0.05));
+    assert_eq!(breakeven, dec!(99.75));
+    assert_eq!(breakeven_price(&TradeType::Long, dec!(100.0), Decimal::ZERO, dec!(2.0), dec!(0.05)), dec!(100.0));
+}
+
+#[test]
+fn test_scarce_capital_goes_to_the_same_symbol_every_run() -> Result<()> {
+    let bar = bar(1609459200000, dec!(100.0), dec!(100.5), dec!(99.5), dec!(100.0));
+    let signal = |symbol: &str| StrategySignal {
+        symbol: symbol.to_string(),
+        ..signal(TradeSide::Buy, None, None)
+    };
+    
+    // Room for one $1000 entry; both symbols signal on the same bar, in either order
+    for run in 0..10 {
+        let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+            capital_limit: Some(dec!(1500.0)),
+            ..TradeTableConfig::default()
+        });
+        let signals = if run % 2 == 0 {
+            vec![signal("ETHUSDT"), signal("BTCUSDT")]
+        } else {
+            vec![signal("BTCUSDT"), signal("ETHUSDT")]
+        };
+        generator.process_bar("BTCUSDT", &bar, &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &ExchangeRules::default())?;
+        
+        let open: Vec<&String> = generator.active_positions().keys().collect();
+        assert_eq!(open, vec!["BTCUSDT"]);
+        assert_eq!(generator.rejected_trades().len(), 1);
+        assert_eq!(generator.rejected_trades()[0].symbol, "ETHUSDT");
+        assert_eq!(generator.rejected_trades()[0].reason, ReasonCode::InsufficientCapital);
+    }
+    
+    Ok(())
+}
+
+
+#[test]
+fn test_short_rejected_on_spot_but_opens_on_perp() -> Result<()> {
+    let bar = bar(1609459200000, dec!(100.0), dec!(100.5), dec!(99.5), dec!(100.0));
+    let signals = vec![signal(TradeSide::Sell, None, None)];
+    let spot = ExchangeRules {
+        allow_shorts: false,
+        ..ExchangeRules::default()
+    };
+    
+    let mut generator = TradeTableGenerator::new();
+    generator.process_bar("BTCUSDT", &bar, &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &spot)?;
+    assert!(generator.active_positions().is_empty());
+    assert_eq!(generator.rejected_trades().len(), 1);
+    assert_eq!(generator.rejected_trades()[0].reason, ReasonCode::ShortsNotAllowed);
+    
+    let mut generator = TradeTableGenerator::new();
+    generator.process_bar("BTCUSDT", &bar, &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &ExchangeRules::default())?;
+    assert!(generator.rejected_trades().is_empty());
+    assert!(matches!(generator.active_positions()["BTCUSDT"].trade_type, TradeType::Short));
+    
+    Ok(())
+}
+
+#[test]
+fn test_flatten_closes_every_open_position() -> Result<()> {
+    let mut generator = TradeTableGenerator::new();
     let rules = ExchangeRules::default();
+    let bar = |timestamp: u64, close: Decimal| bar(timestamp, close, close + dec!(0.5), close - dec!(0.5), close);
+    let signal = |symbol: &str, side: TradeSide, take_profit: Decimal, stop_loss: Decimal| StrategySignal {
+        symbol: symbol.to_string(),
+        ..signal(side, Some(take_profit), Some(stop_loss))
+    };
     
-    // Test fee calculation
-    let quantity = dec!(0.02); // 0.02 BTC
-    let price = dec!(50000.0); // $50,000 per BTC
-    let notional = quantity * price; // $1,000
+    let signals = vec![
+        signal("BTCUSDT", TradeSide::Buy, dec!(150.0), dec!(50.0)),
+        signal("ETHUSDT", TradeSide::Sell, dec!(50.0), dec!(150.0)),
+    ];
+    generator.process_bar("BTCUSDT", &bar(1609459200000, dec!(100.0)), &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    assert_eq!(generator.active_positions().len(), 2);
     
-    // Expected fee: $1,000 * 0.0001 = $0.10
-    let expected_fee = notional * rules.taker_fee;
+    // Neither TP nor SL is anywhere near; the halt closes both anyway
+    let halt = bar(1609459260000, dec!(101.0));
+    generator.flatten_all(&halt, ExitReason::Flatten, &SlippageMode::None, &rules)?;
     
-    // This is a basic test - in a real implementation, we'd need to expose the fee calculation method
-    assert_eq!(expected_fee, dec!(0.10));
+    assert!(generator.active_positions().is_empty());
+    let trades = generator.trade_records();
+    assert_eq!(trades.len(), 2);
+    assert!(trades.iter().all(|t| t.exit_reason == ExitReason::Flatten && t.exit_price == dec!(101.0)));
+    assert_eq!(trades.iter().map(|t| t.symbol.as_str()).collect::<Vec<_>>(), vec!["BTCUSDT", "ETHUSDT"]);
     
     Ok(())
 }
 
+#[test]
+fn test_entries_only_inside_trading_session() -> Result<()> {
+    let hour = 3_600_000;
+    let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+        session: Some(TradingSession {
+            start_ms_of_day: 9 * hour,
+            end_ms_of_day: 17 * hour,
+            days_of_week: Vec::new(),
+            flatten_at_close: true,
+        }),
+        ..TradeTableConfig::default()
+    });
+    let rules = ExchangeRules::default();
+    let bar = |timestamp: u64| bar(timestamp, dec!(100.0), dec!(100.5), dec!(99.5), dec!(100.0));
+    let signals = vec![signal(TradeSide::Buy, None, None)];
+    
+    // 2021-01-01 (a Friday): 08:00 is before the open, 10:00 inside
+    let midnight = 1609459200000;
+    generator.process_bar("BTCUSDT", &bar(midnight + 8 * hour), &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    assert!(generator.active_positions().is_empty());
+    assert_eq!(generator.rejected_trades().len(), 1);
+    assert_eq!(generator.rejected_trades()[0].reason, ReasonCode::OutsideSession);
+    
+    generator.process_bar("BTCUSDT", &bar(midnight + 10 * hour), &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    assert_eq!(generator.active_positions().len(), 1);
+    
+    // The first bar after the close flattens at the session's last bar
+    generator.process_bar("BTCUSDT", &bar(midnight + 16 * hour), &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    generator.process_bar("BTCUSDT", &bar(midnight + 17 * hour), &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    assert!(generator.active_positions().is_empty());
+    assert_eq!(generator.trade_records().len(), 1);
+    assert_eq!(generator.trade_records()[0].exit_reason, ExitReason::Flatten);
+    assert_eq!(generator.trade_records()[0].exit_time_utc, "2021-01-01T16:00:00.000Z");
+    
+    // Weekend days can be left out entirely
+    let weekdays = TradingSession {
+        start_ms_of_day: 0,
+        end_ms_of_day: 24 * hour,
+        days_of_week: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+        flatten_at_close: false,
+    };
+    assert!(weekdays.contains(midnight + 10 * hour));
+    assert!(!weekdays.contains(midnight + 24 * hour + 10 * hour));
+    
+    Ok(())
+}
 
+#[test]
+fn test_flatten_at_close_exits_at_last_session_bar_close() -> Result<()> {
+    let hour = 3_600_000;
+    let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+        session: Some(TradingSession {
+            start_ms_of_day: 9 * hour,
+            end_ms_of_day: 17 * hour,
+            days_of_week: Vec::new(),
+            flatten_at_close: true,
+        }),
+        ..TradeTableConfig::default()
+    });
+    let rules = ExchangeRules::default();
+    let bar = |timestamp: u64, close: Decimal| bar(timestamp, dec!(100.0), close.max(dec!(100.0)), close.min(dec!(100.0)), close);
+    
+    let midnight = 1609459200000;
+    generator.process_bar("BTCUSDT", &bar(midnight + 10 * hour, dec!(100.0)), &[signal(TradeSide::Buy, None, None)], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    generator.process_bar("BTCUSDT", &bar(midnight + 16 * hour, dec!(103.0)), &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    
+    // Still inside the session: nothing closes yet
+    assert_eq!(generator.active_positions().len(), 1);
+    
+    // The after-hours bar gaps down; the exit takes the session's last close instead
+    generator.process_bar("BTCUSDT", &bar(midnight + 18 * hour, dec!(90.0)), &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    assert!(generator.active_positions().is_empty());
+    let trades = generator.trade_records();
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].exit_reason, ExitReason::Flatten);
+    assert_eq!(trades[0].exit_price, dec!(103.0));
+    assert_eq!(trades[0].exit_time_utc, "2021-01-01T16:00:00.000Z");
+    assert!(trades[0].pnl_usd > dec!(0.0));
+    
+    Ok(())
+}
 
+#[test]
+fn test_summary_by_tag_splits_win_rates() -> Result<()> {
+    let mut generator = TradeTableGenerator::new();
+    let rules = ExchangeRules::default();
+    let bar = |timestamp: u64, high: Decimal, low: Decimal| bar(timestamp, dec!(100.0), high, low, dec!(100.0));
+    let signal = |setup: &str| StrategySignal {
+        tags: vec![
+            ("setup".to_string(), setup.to_string()),
+            ("regime".to_string(), "trend".to_string()),
+        ],
+        ..signal(TradeSide::Buy, Some(dec!(110.0)), Some(dec!(90.0)))
+    };
+    
+    // Breakouts win twice; reversals win once and lose once
+    let trades = [("breakout", true), ("reversal", false), ("breakout", true), ("reversal", true)];
+    for (i, (setup, wins)) in trades.iter().enumerate() {
+        let entry_time = 1609459200000 + i as u64 * 120_000;
+        generator.process_bar("BTCUSDT", &bar(entry_time, dec!(100.5), dec!(99.5)), &[signal(setup)], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+        let exit_bar = if *wins { bar(entry_time + 60_000, dec!(111.0), dec!(99.5)) } else { bar(entry_time + 60_000, dec!(100.5), dec!(89.0)) };
+        generator.process_bar("BTCUSDT", &exit_bar, &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    }
+    
+    let result = generator.generate_result();
+    assert_eq!(result.trades.len(), 4);
+    assert_eq!(result.trades[0].tags[0], ("setup".to_string(), "breakout".to_string()));
+    
+    let by_tag = &result.summary_by_tag;
+    assert_eq!(by_tag.keys().collect::<Vec<_>>(), vec!["regime=trend", "setup=breakout", "setup=reversal"]);
+    assert_eq!(by_tag["setup=breakout"].total_trades, 2);
+    assert_eq!(by_tag["setup=breakout"].win_rate, Percent::new(dec!(100)));
+    assert_eq!(by_tag["setup=reversal"].total_trades, 2);
+    assert_eq!(by_tag["setup=reversal"].win_rate, Percent::new(dec!(50)));
+    // A tag on every trade reproduces the overall summary
+    assert_eq!(by_tag["regime=trend"].net_pnl_usd, result.summary.net_pnl_usd);
+    assert_eq!(by_tag["regime=trend"].win_rate, result.summary.win_rate);
+    
+    Ok(())
+}
+
+#[test]
+fn test_max_open_positions_caps_portfolio() -> Result<()> {
+    let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+        risk_limits: RiskLimits {
+            max_open_positions: Some(5),
+            ..RiskLimits::default()
+        },
+        ..TradeTableConfig::default()
+    });
+    let rules = ExchangeRules::default();
+    let bar = |timestamp: u64, high: Decimal| bar(timestamp, dec!(100.0), high, dec!(99.5), dec!(100.0));
+    let signal = |symbol: &str| StrategySignal {
+        symbol: symbol.to_string(),
+        ..signal(TradeSide::Buy, Some(dec!(110.0)), Some(dec!(90.0)))
+    };
+    let symbols = ["ADAUSDT", "BNBUSDT", "BTCUSDT", "DOGEUSDT", "ETHUSDT", "SOLUSDT"];
+    let signals: Vec<StrategySignal> = symbols.iter().map(|symbol| signal(symbol)).collect();
+    
+    // Six simultaneous entries: the sixth by symbol order is over the cap
+    generator.process_bar("BTCUSDT", &bar(1609459200000, dec!(100.5)), &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    assert_eq!(generator.active_positions().len(), 5);
+    assert_eq!(generator.rejected_trades().len(), 1);
+    assert_eq!(generator.rejected_trades()[0].symbol, "SOLUSDT");
+    assert_eq!(generator.rejected_trades()[0].reason, ReasonCode::MaxOpenPositions);
+    
+    // Once positions close the slots free up
+    for symbol in &symbols[..5] {
+        generator.process_bar(symbol, &bar(1609459260000, dec!(111.0)), &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    }
+    assert!(generator.active_positions().is_empty());
+    generator.process_bar("BTCUSDT", &bar(1609459320000, dec!(100.5)), &[signal("SOLUSDT")], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    assert_eq!(generator.active_positions().len(), 1);
+    
+    Ok(())
+}
+
+#[test]
+fn test_exits_only_check_the_bars_own_symbol() -> Result<()> {
+    let mut generator = TradeTableGenerator::new();
+    let rules = ExchangeRules::default();
+    let signal = |symbol: &str| StrategySignal {
+        symbol: symbol.to_string(),
+        time_to_live: Some(TimeToLive::Bars(2)),
+        ..signal(TradeSide::Buy, Some(dec!(110.0)), Some(dec!(90.0)))
+    };
+    let quiet = |timestamp: u64| bar(timestamp, dec!(100.0), dec!(100.5), dec!(99.5), dec!(100.0));
+    
+    generator.process_bar("BTCUSDT", &quiet(1609459200000), &[signal("BTCUSDT")], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    generator.process_bar("ETHUSDT", &quiet(1609459200000), &[signal("ETHUSDT")], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    
+    // An ETH bar through BTC's take profit and stop neither closes BTC nor ages it
+    let wide = bar(1609459260000, dec!(100.0), dec!(120.0), dec!(80.0), dec!(100.0));
+    generator.process_bar("ETHUSDT", &wide, &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    assert_eq!(generator.trade_records().len(), 1);
+    assert_eq!(generator.trade_records()[0].symbol, "ETHUSDT");
+    assert_eq!(generator.active_positions()["BTCUSDT"].bars_held, 0);
+    
+    // Its own bars still count toward its time to live
+    generator.process_bar("BTCUSDT", &quiet(1609459260000), &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    assert_eq!(generator.active_positions()["BTCUSDT"].bars_held, 1);
+    generator.process_bar("BTCUSDT", &quiet(1609459320000), &[], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    assert!(generator.active_positions().is_empty());
+    assert_eq!(generator.trade_records()[1].exit_reason, ExitReason::Timeout);
+    
+    Ok(())
+}
+
+#[test]
+fn test_overlapping_entry_policies() -> Result<()> {
+    let rules = ExchangeRules::default();
+    let bar = bar(1609459200000, dec!(100.0), dec!(100.5), dec!(99.5), dec!(100.0));
+    let buy = |take_profit: Decimal| StrategySignal {
+        size: dec!(1.0),
+        ..signal(TradeSide::Buy, Some(take_profit), None)
+    };
+    // Two same-bar buys, told apart by their take profit
+    let signals = vec![buy(dec!(110.0)), buy(dec!(120.0))];
+    let run = |overlapping_entries: OverlappingEntryPolicy| -> Result<TradeTableGenerator> {
+        let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+            overlapping_entries,
+            ..TradeTableConfig::default()
+        });
+        generator.process_bar("BTCUSDT", &bar, &signals, &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+        Ok(generator)
+    };
+    let overlap_rejections = |generator: &TradeTableGenerator| generator.rejected_trades().iter()
+        .filter(|rejected| rejected.reason == ReasonCode::OverlappingEntry)
+        .count();
+    
+    let first = run(OverlappingEntryPolicy::FirstWins)?;
+    assert_eq!(first.active_positions()["BTCUSDT"].take_profit, Some(dec!(110.0)));
+    assert_eq!(first.active_positions()["BTCUSDT"].size_usd, dec!(1000.0));
+    assert_eq!(overlap_rejections(&first), 1);
+    
+    let last = run(OverlappingEntryPolicy::LastWins)?;
+    assert_eq!(last.active_positions()["BTCUSDT"].take_profit, Some(dec!(120.0)));
+    assert_eq!(overlap_rejections(&last), 1);
+    
+    // One position carrying both entries' notional and the first one's brackets
+    let aggregate = run(OverlappingEntryPolicy::Aggregate)?;
+    let position = &aggregate.active_positions()["BTCUSDT"];
+    assert_eq!(position.size_usd, dec!(2000.0));
+    assert_eq!(position.quantity, dec!(20.0));
+    assert_eq!(position.take_profit, Some(dec!(110.0)));
+    assert!(aggregate.rejected_trades().is_empty());
+    
+    let rejected = run(OverlappingEntryPolicy::Reject)?;
+    assert!(rejected.active_positions().is_empty());
+    assert_eq!(overlap_rejections(&rejected), 2);
+    
+    // A lone signal is untouched by any policy
+    let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+        overlapping_entries: OverlappingEntryPolicy::Reject,
+        ..TradeTableConfig::default()
+    });
+    generator.process_bar("BTCUSDT", &bar, &signals[..1], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    assert_eq!(generator.active_positions().len(), 1);
+    
+    Ok(())
+}
+
+#[test]
+fn test_entry_onto_an_open_position_is_rejected() -> Result<()> {
+    let rules = ExchangeRules::default();
+    let first = bar(1609459200000, dec!(100.0), dec!(100.5), dec!(99.5), dec!(100.0));
+    let mut generator = TradeTableGenerator::new();
+    generator.process_bar("BTCUSDT", &first, &[signal(TradeSide::Buy, Some(dec!(110.0)), None)], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    
+    // An opposite entry on the next bar neither flips nor closes the long
+    let next = bar(1609459260000, dec!(100.0), dec!(100.5), dec!(99.5), dec!(100.0));
+    generator.process_bar("BTCUSDT", &next, &[signal(TradeSide::Sell, Some(dec!(90.0)), None)], &IntrabarPolicy::ExactTrades, &SlippageMode::None, &rules)?;
+    assert!(matches!(generator.active_positions()["BTCUSDT"].trade_type, TradeType::Long));
+    assert_eq!(generator.rejected_trades().len(), 1);
+    assert_eq!(generator.rejected_trades()[0].reason, ReasonCode::PositionOpen);
+    assert_eq!(generator.rejected_trades()[0].timestamp, 1609459260000);
+    
+    Ok(())
+}
+
+#[test]
+fn test_same_bar_entry_and_exit_follow_path_from_entry() -> Result<()> {
+    let rules = ExchangeRules::default();
+    let bar = |high: Decimal, low: Decimal| bar(1609459200000, dec!(100.0), high, low, dec!(100.0));
+    // Long from 100 with TP 105 and SL 95, both inside every bar's range below
+    let signals = vec![StrategySignal {
+        size: dec!(1.0),
+        ..signal(TradeSide::Buy, Some(dec!(105.0)), Some(dec!(95.0)))
+    }];
+    let run = |bar: &Bar, intrabar_policy: IntrabarPolicy, intrabar_path: IntrabarPath| -> Result<TradeTableGenerator> {
+        let mut generator = TradeTableGenerator::with_config(TradeTableConfig {
+            intrabar_path,
+            ..TradeTableConfig::default()
+        });
+        generator.process_bar("BTCUSDT", bar, &signals, &intrabar_policy, &SlippageMode::None, &rules)?;
+        Ok(generator)
+    };
+    let exit_of = |generator: &TradeTableGenerator| {
+        assert!(generator.active_positions().is_empty());
+        assert_eq!(generator.trade_records().len(), 1);
+        let trade = &generator.trade_records()[0];
+        assert_eq!(trade.entry_time_utc, trade.exit_time_utc);
+        trade.exit_reason.clone()
+    };
+    
+    // Entered at the open, the nearer extreme is reached first
+    let high_first = bar(dec!(106.0), dec!(93.0));
+    let low_first = bar(dec!(107.0), dec!(94.0));
+    assert_eq!(exit_of(&run(&high_first, IntrabarPolicy::OneSecondBars, IntrabarPath::Range)?), ExitReason::TakeProfit);
+    assert_eq!(exit_of(&run(&low_first, IntrabarPolicy::OneSecondBars, IntrabarPath::Range)?), ExitReason::StopLoss);
+    assert_eq!(exit_of(&run(&high_first, IntrabarPolicy::LinearInterpolation, IntrabarPath::NearestExtremeFirst)?), ExitReason::TakeProfit);
+    
+    // With only the range known, the stop is assumed to come first
+    assert_eq!(exit_of(&run(&high_first, IntrabarPolicy::LinearInterpolation, IntrabarPath::Range)?), ExitReason::StopLoss);
+    
+    // Entered at the close, nothing after the entry is left to touch either level
+    let generator = run(&high_first, IntrabarPolicy::ExactTrades, IntrabarPath::Range)?;
+    assert!(generator.trade_records().is_empty());
+    assert_eq!(generator.active_positions().len(), 1);
+    
+    Ok(())
+}
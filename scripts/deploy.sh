@@ -140,13 +140,10 @@ build_wasm_strategies() {
 run_determinism_tests() {
     log_info "Running determinism tests..."
     
-    cd tests
-    
-    # Run Rust determinism tests
-    cargo test --release determinism_tests
+    cd rust-engine
     
-    # Run golden dataset validation
-    cargo run --release --bin determinism_tests -- --config config/determinism_tests.json
+    # Run Rust determinism tests, including golden dataset validation
+    cargo test --release --test determinism_tests
     
     cd ..
     
@@ -2,12 +2,19 @@
 //! 
 //! This demonstrates how to write a deterministic trading strategy that compiles to WASM
 //! for safe execution in the backtesting engine.
+//! 
+//! [`EmaRsiStrategy`] implements [`Strategy`], a copy of the engine's
+//! `backtest_engine::strategy::Strategy` trait over the plain types below. This crate
+//! cannot depend on the engine itself, which links wasmtime and does not build for
+//! wasm32, so the `strategy_*` exports at the end are a thin shim: they decode what the
+//! engine sends into a [`BarContext`] and call the trait.
 
 use std::collections::HashMap;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-/// Strategy configuration
+/// Strategy configuration; fields left out take their defaults
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct StrategyConfig {
     pub ema_period: usize,
     pub rsi_period: usize,
@@ -16,6 +23,8 @@ pub struct StrategyConfig {
     pub position_size: f64,
     pub stop_loss_pct: f64,
     pub take_profit_pct: f64,
+    /// Minimum time between signals, in milliseconds
+    pub min_signal_interval_ms: u64,
 }
 
 impl Default for StrategyConfig {
@@ -28,178 +37,177 @@ impl Default for StrategyConfig {
             position_size: 0.1, // 10% of equity
             stop_loss_pct: 0.02, // 2% stop loss
             take_profit_pct: 0.04, // 4% take profit
+            min_signal_interval_ms: 300_000, // 5 minutes
         }
     }
 }
 
 /// Market data structure passed to strategy
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct MarketBar {
     pub timestamp: u64,
+    #[serde(deserialize_with = "decimal")]
     pub open: f64,
+    #[serde(deserialize_with = "decimal")]
     pub high: f64,
+    #[serde(deserialize_with = "decimal")]
     pub low: f64,
+    #[serde(deserialize_with = "decimal")]
     pub close: f64,
+    #[serde(deserialize_with = "decimal")]
     pub volume: f64,
 }
 
-/// Indicator values
-#[derive(Debug, Clone)]
-pub struct IndicatorValue {
-    pub timestamp: u64,
-    pub value: f64,
+/// What the engine writes for `strategy_on_bar`
+#[derive(Debug, Deserialize)]
+struct BarInput {
+    bar_index: usize,
+    bar: MarketBar,
+    /// Each required indicator's value at the bar, `null` during its warmup
+    indicators: HashMap<String, Option<Decimal>>,
+    position: Option<Position>,
 }
 
-/// Trading signal
-#[derive(Debug, Clone)]
+/// Engine decimal, sent as a string
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Decimal(#[serde(deserialize_with = "decimal")] f64);
+
+/// Read an engine decimal, which serializes as a string, or a plain number
+fn decimal<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Number {
+        Text(String),
+        Float(f64),
+    }
+    match Number::deserialize(deserializer)? {
+        Number::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        Number::Float(value) => Ok(value),
+    }
+}
+
+/// Trading signal, serialized as the engine's `StrategySignal`
+#[derive(Debug, Clone, Serialize)]
 pub struct TradingSignal {
     pub side: TradeSide,
     pub size: f64,
     pub entry_price: Option<f64>,
     pub stop_loss: Option<f64>,
     pub take_profit: Option<f64>,
-    pub time_to_live: Option<u64>,
+    pub time_to_live: Option<TimeToLive>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum TradeSide {
     Buy,
     Sell,
 }
 
+/// How long a position may stay open
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum TimeToLive {
+    /// Milliseconds since entry
+    Duration(u64),
+}
+
 /// Current position state
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Position {
     pub symbol: String,
+    #[serde(deserialize_with = "decimal")]
     pub quantity: f64,
+    #[serde(deserialize_with = "decimal")]
     pub avg_price: f64,
+    #[serde(deserialize_with = "decimal")]
     pub unrealized_pnl: f64,
+    #[serde(deserialize_with = "decimal")]
     pub realized_pnl: f64,
 }
 
-/// Main strategy implementation
+/// Everything a strategy sees for one bar, as in `backtest_engine::strategy::BarContext`
+#[derive(Debug, Clone, Copy)]
+pub struct BarContext<'a> {
+    pub bar_index: usize,
+    pub bar: &'a MarketBar,
+    /// Each required indicator's value at the bar, `None` during its warmup
+    pub indicators: &'a HashMap<String, Option<f64>>,
+    /// The engine's position in the symbol, if it has traded
+    pub position: Option<&'a Position>,
+}
+
+impl BarContext<'_> {
+    /// Value of `indicator_name` at the bar, `None` during warmup or if not required
+    pub fn indicator(&self, indicator_name: &str) -> Option<f64> {
+        self.indicators.get(indicator_name).copied().flatten()
+    }
+}
+
+/// Signal logic the engine runs bar by bar, as in `backtest_engine::strategy::Strategy`
+pub trait Strategy {
+    /// Indicators the engine computes for [`Strategy::on_bar`]
+    fn required_indicators(&self) -> Vec<String>;
+
+    /// Signals for the bar in `ctx`
+    fn on_bar(&mut self, ctx: &BarContext) -> Vec<TradingSignal>;
+
+    /// Forget per-symbol state
+    fn reset(&mut self) {}
+}
+
+/// EMA trend filter with RSI entries and exits
+/// 
+/// While flat, enters long when RSI is below `rsi_oversold` while the EMA is rising and
+/// the close is above it, and short on the mirror image. While holding, exits on the
+/// stop loss, the take profit or RSI reaching the opposite extreme. Signals are at
+/// least `min_signal_interval_ms` apart.
 pub struct EmaRsiStrategy {
     config: StrategyConfig,
-    position: Option<Position>,
-    ema_values: Vec<IndicatorValue>,
-    rsi_values: Vec<IndicatorValue>,
-    last_signal_time: u64,
+    previous_ema: Option<f64>,
+    last_signal_time: Option<u64>,
 }
 
 impl EmaRsiStrategy {
     pub fn new(config: StrategyConfig) -> Self {
         Self {
             config,
-            position: None,
-            ema_values: Vec::new(),
-            rsi_values: Vec::new(),
-            last_signal_time: 0,
+            previous_ema: None,
+            last_signal_time: None,
         }
     }
     
-    /// Process a new bar and generate signals
-    pub fn process_bar(
-        &mut self,
-        bar: &MarketBar,
-        ema_values: &[IndicatorValue],
-        rsi_values: &[IndicatorValue],
-    ) -> Vec<TradingSignal> {
-        let mut signals = Vec::new();
-        
-        // Update indicator values
-        self.ema_values = ema_values.to_vec();
-        self.rsi_values = rsi_values.to_vec();
-        
-        // Need at least 2 values for trend analysis
-        if self.ema_values.len() < 2 || self.rsi_values.len() < 2 {
-            return signals;
-        }
-        
-        let current_ema = self.ema_values.last().unwrap().value;
-        let previous_ema = self.ema_values[self.ema_values.len() - 2].value;
-        let current_rsi = self.rsi_values.last().unwrap().value;
-        
-        // Calculate trend direction
-        let ema_trend_up = current_ema > previous_ema;
-        let price_above_ema = bar.close > current_ema;
-        
-        // Generate signals based on strategy logic
-        match &self.position {
-            None => {
-                // No position - look for entry signals
-                if self.should_enter_long(bar, current_rsi, ema_trend_up, price_above_ema) {
-                    signals.push(self.create_long_signal(bar));
-                } else if self.should_enter_short(bar, current_rsi, ema_trend_up, price_above_ema) {
-                    signals.push(self.create_short_signal(bar));
-                }
-            },
-            Some(position) => {
-                // Have position - check for exit signals
-                if self.should_exit_position(bar, position) {
-                    signals.push(self.create_exit_signal(bar, position));
-                }
-            }
-        }
-        
-        self.last_signal_time = bar.timestamp;
-        signals
+    /// Whether a signal on `bar` would come too soon after the last one
+    fn too_soon(&self, bar: &MarketBar) -> bool {
+        self.last_signal_time
+            .is_some_and(|last| bar.timestamp.saturating_sub(last) <= self.config.min_signal_interval_ms)
     }
     
     /// Determine if we should enter a long position
-    fn should_enter_long(
-        &self,
-        bar: &MarketBar,
-        rsi: f64,
-        ema_trend_up: bool,
-        price_above_ema: bool,
-    ) -> bool {
+    fn should_enter_long(&self, rsi: f64, ema_trend_up: bool, price_above_ema: bool) -> bool {
         // Long entry conditions:
         // 1. RSI is oversold (below threshold)
         // 2. EMA trend is up
         // 3. Price is above EMA (momentum)
-        // 4. Haven't signaled recently (avoid overtrading)
-        
-        let time_since_last_signal = bar.timestamp - self.last_signal_time;
-        let min_time_between_signals = 300_000; // 5 minutes in milliseconds
-        
-        rsi < self.config.rsi_oversold
-            && ema_trend_up
-            && price_above_ema
-            && time_since_last_signal > min_time_between_signals
+        rsi < self.config.rsi_oversold && ema_trend_up && price_above_ema
     }
     
     /// Determine if we should enter a short position
-    fn should_enter_short(
-        &self,
-        bar: &MarketBar,
-        rsi: f64,
-        ema_trend_up: bool,
-        price_above_ema: bool,
-    ) -> bool {
+    fn should_enter_short(&self, rsi: f64, ema_trend_up: bool, price_above_ema: bool) -> bool {
         // Short entry conditions:
         // 1. RSI is overbought (above threshold)
         // 2. EMA trend is down
         // 3. Price is below EMA (momentum)
-        // 4. Haven't signaled recently
-        
-        let time_since_last_signal = bar.timestamp - self.last_signal_time;
-        let min_time_between_signals = 300_000; // 5 minutes
-        
-        rsi > self.config.rsi_overbought
-            && !ema_trend_up
-            && !price_above_ema
-            && time_since_last_signal > min_time_between_signals
+        rsi > self.config.rsi_overbought && !ema_trend_up && !price_above_ema
     }
     
     /// Determine if we should exit current position
-    fn should_exit_position(&self, bar: &MarketBar, position: &Position) -> bool {
+    fn should_exit_position(&self, bar: &MarketBar, rsi: f64, position: &Position) -> bool {
         // Exit conditions:
         // 1. Stop loss hit
         // 2. Take profit hit
         // 3. RSI reversal (for mean reversion)
         
-        let current_rsi = self.rsi_values.last().unwrap().value;
-        let pnl_pct = (bar.close - position.avg_price) / position.avg_price;
+        // Signed so a short gains as the price falls
+        let pnl_pct = (bar.close - position.avg_price) / position.avg_price * position.quantity.signum();
         
         // Stop loss
         if pnl_pct <= -self.config.stop_loss_pct {
@@ -212,10 +220,10 @@ impl EmaRsiStrategy {
         }
         
         // RSI reversal (for mean reversion strategies)
-        if position.quantity > 0.0 && current_rsi > self.config.rsi_overbought {
+        if position.quantity > 0.0 && rsi > self.config.rsi_overbought {
             return true;
         }
-        if position.quantity < 0.0 && current_rsi < self.config.rsi_oversold {
+        if position.quantity < 0.0 && rsi < self.config.rsi_oversold {
             return true;
         }
         
@@ -233,7 +241,7 @@ impl EmaRsiStrategy {
             entry_price: Some(bar.close),
             stop_loss: Some(stop_loss),
             take_profit: Some(take_profit),
-            time_to_live: Some(3600_000), // 1 hour
+            time_to_live: Some(TimeToLive::Duration(3_600_000)), // 1 hour
         }
     }
     
@@ -248,7 +256,7 @@ impl EmaRsiStrategy {
             entry_price: Some(bar.close),
             stop_loss: Some(stop_loss),
             take_profit: Some(take_profit),
-            time_to_live: Some(3600_000), // 1 hour
+            time_to_live: Some(TimeToLive::Duration(3_600_000)), // 1 hour
         }
     }
     
@@ -260,15 +268,10 @@ impl EmaRsiStrategy {
             entry_price: Some(bar.close),
             stop_loss: None,
             take_profit: None,
-            time_to_live: Some(60_000), // 1 minute
+            time_to_live: Some(TimeToLive::Duration(60_000)), // 1 minute
         }
     }
     
-    /// Update position after trade execution
-    pub fn update_position(&mut self, position: Option<Position>) {
-        self.position = position;
-    }
-    
     /// Get strategy metadata
     pub fn get_metadata(&self) -> StrategyMetadata {
         StrategyMetadata {
@@ -276,7 +279,7 @@ impl EmaRsiStrategy {
             version: "1.0.0".to_string(),
             description: "EMA trend following with RSI mean reversion".to_string(),
             author: "backtest-engine".to_string(),
-            required_indicators: vec!["ema".to_string(), "rsi".to_string()],
+            required_indicators: self.required_indicators(),
             parameters: self.config_to_params(),
         }
     }
@@ -291,12 +294,57 @@ impl EmaRsiStrategy {
         params.insert("position_size".to_string(), self.config.position_size.to_string());
         params.insert("stop_loss_pct".to_string(), self.config.stop_loss_pct.to_string());
         params.insert("take_profit_pct".to_string(), self.config.take_profit_pct.to_string());
+        params.insert("min_signal_interval_ms".to_string(), self.config.min_signal_interval_ms.to_string());
         params
     }
 }
 
+impl Strategy for EmaRsiStrategy {
+    fn required_indicators(&self) -> Vec<String> {
+        vec!["ema".to_string(), "rsi".to_string()]
+    }
+    
+    fn on_bar(&mut self, ctx: &BarContext) -> Vec<TradingSignal> {
+        let (Some(ema), Some(rsi)) = (ctx.indicator("ema"), ctx.indicator("rsi")) else {
+            return Vec::new();
+        };
+        // The trend needs two EMA values
+        let Some(previous_ema) = self.previous_ema.replace(ema) else {
+            return Vec::new();
+        };
+        
+        let bar = ctx.bar;
+        if self.too_soon(bar) {
+            return Vec::new();
+        }
+        
+        // Calculate trend direction
+        let ema_trend_up = ema > previous_ema;
+        let price_above_ema = bar.close > ema;
+        
+        // Entries while flat, exits while holding
+        let signal = match ctx.position.filter(|position| position.quantity != 0.0) {
+            None if self.should_enter_long(rsi, ema_trend_up, price_above_ema) => Some(self.create_long_signal(bar)),
+            None if self.should_enter_short(rsi, ema_trend_up, price_above_ema) => Some(self.create_short_signal(bar)),
+            None => None,
+            Some(position) => self.should_exit_position(bar, rsi, position)
+                .then(|| self.create_exit_signal(bar, position)),
+        };
+        
+        if signal.is_some() {
+            self.last_signal_time = Some(bar.timestamp);
+        }
+        signal.into_iter().collect()
+    }
+    
+    fn reset(&mut self) {
+        self.previous_ema = None;
+        self.last_signal_time = None;
+    }
+}
+
 /// Strategy metadata
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct StrategyMetadata {
     pub name: String,
     pub version: String,
@@ -307,6 +355,13 @@ pub struct StrategyMetadata {
 }
 
 /// WASM ABI functions that the engine will call
+/// 
+/// Reserves `len` bytes for the engine to write the config (and later inputs) into
+#[no_mangle]
+pub extern "C" fn strategy_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+}
+
 #[no_mangle]
 pub extern "C" fn strategy_init(config_ptr: *const u8, config_len: usize) -> *mut EmaRsiStrategy {
     let config_bytes = unsafe { std::slice::from_raw_parts(config_ptr, config_len) };
@@ -317,37 +372,47 @@ pub extern "C" fn strategy_init(config_ptr: *const u8, config_len: usize) -> *mu
     Box::into_raw(Box::new(strategy))
 }
 
+/// Called by the engine once per bar with the JSON described by `BarInput`
+/// 
+/// Decodes it into a [`BarContext`] for [`Strategy::on_bar`] and returns
+/// `(ptr << 32) | len` of the JSON array of signals.
 #[no_mangle]
-pub extern "C" fn strategy_process_bar(
+pub extern "C" fn strategy_on_bar(
     strategy_ptr: *mut EmaRsiStrategy,
-    bar_ptr: *const u8,
-    bar_len: usize,
-    ema_ptr: *const u8,
-    ema_len: usize,
-    rsi_ptr: *const u8,
-    rsi_len: usize,
-) -> *mut u8 {
+    input_ptr: *const u8,
+    input_len: usize,
+) -> u64 {
     let strategy = unsafe { &mut *strategy_ptr };
-    let bar_bytes = unsafe { std::slice::from_raw_parts(bar_ptr, bar_len) };
-    let ema_bytes = unsafe { std::slice::from_raw_parts(ema_ptr, ema_len) };
-    let rsi_bytes = unsafe { std::slice::from_raw_parts(rsi_ptr, rsi_len) };
+    let input_bytes = unsafe { std::slice::from_raw_parts(input_ptr, input_len) };
+    let input: BarInput = serde_json::from_slice(input_bytes).unwrap();
     
-    let bar: MarketBar = serde_json::from_slice(bar_bytes).unwrap();
-    let ema_values: Vec<IndicatorValue> = serde_json::from_slice(ema_bytes).unwrap();
-    let rsi_values: Vec<IndicatorValue> = serde_json::from_slice(rsi_bytes).unwrap();
-    
-    let signals = strategy.process_bar(&bar, &ema_values, &rsi_values);
+    let indicators: HashMap<String, Option<f64>> = input.indicators.into_iter()
+        .map(|(name, value)| (name, value.map(|Decimal(value)| value)))
+        .collect();
+    let ctx = BarContext {
+        bar_index: input.bar_index,
+        bar: &input.bar,
+        indicators: &indicators,
+        position: input.position.as_ref(),
+    };
+    let signals = strategy.on_bar(&ctx);
     let signals_json = serde_json::to_vec(&signals).unwrap();
-    
-    Box::into_raw(signals_json.into_boxed_slice()) as *mut u8
+    let len = signals_json.len() as u64;
+    let ptr = Box::into_raw(signals_json.into_boxed_slice()) as *mut u8 as u64;
+    (ptr << 32) | len
 }
 
+/// Returns `(ptr << 32) | len` of the metadata JSON, as the engine expects
+/// 
+/// Metadata describes the module rather than one instance, so it is built from the
+/// default config and needs no prior `strategy_init`.
 #[no_mangle]
-pub extern "C" fn strategy_get_metadata(strategy_ptr: *mut EmaRsiStrategy) -> *mut u8 {
-    let strategy = unsafe { &*strategy_ptr };
-    let metadata = strategy.get_metadata();
+pub extern "C" fn strategy_get_metadata() -> u64 {
+    let metadata = EmaRsiStrategy::new(StrategyConfig::default()).get_metadata();
     let metadata_json = serde_json::to_vec(&metadata).unwrap();
-    Box::into_raw(metadata_json.into_boxed_slice()) as *mut u8
+    let len = metadata_json.len() as u64;
+    let ptr = Box::into_raw(metadata_json.into_boxed_slice()) as *mut u8 as u64;
+    (ptr << 32) | len
 }
 
 #[no_mangle]
@@ -369,33 +434,73 @@ mod tests {
         assert_eq!(strategy.config.rsi_period, 14);
     }
     
+    fn bar(timestamp: u64, close: f64) -> MarketBar {
+        MarketBar {
+            timestamp,
+            open: 100.0,
+            high: 105.0,
+            low: 95.0,
+            close,
+            volume: 1000.0,
+        }
+    }
+    
+    fn on_bar(strategy: &mut EmaRsiStrategy, bar: &MarketBar, ema: f64, rsi: f64, position: Option<&Position>) -> Vec<TradingSignal> {
+        let indicators = HashMap::from([("ema".to_string(), Some(ema)), ("rsi".to_string(), Some(rsi))]);
+        strategy.on_bar(&BarContext { bar_index: 0, bar, indicators: &indicators, position })
+    }
+    
     #[test]
     fn test_long_entry_conditions() {
         let config = StrategyConfig::default();
         let mut strategy = EmaRsiStrategy::new(config);
         
-        let bar = MarketBar {
-            timestamp: 1000,
-            open: 100.0,
-            high: 105.0,
-            low: 95.0,
-            close: 102.0,
-            volume: 1000.0,
-        };
+        // The first value only starts the trend
+        assert!(on_bar(&mut strategy, &bar(540_000, 101.0), 100.0, 35.0, None).is_empty());
         
-        let ema_values = vec![
-            IndicatorValue { timestamp: 999, value: 100.0 },
-            IndicatorValue { timestamp: 1000, value: 101.0 },
-        ];
+        // Rising EMA, close above it and RSI oversold
+        let signals = on_bar(&mut strategy, &bar(600_000, 102.0), 101.0, 25.0, None);
+        assert_eq!(signals.len(), 1);
+        assert!(matches!(signals[0].side, TradeSide::Buy));
         
-        let rsi_values = vec![
-            IndicatorValue { timestamp: 999, value: 35.0 },
-            IndicatorValue { timestamp: 1000, value: 25.0 }, // Oversold
-        ];
+        // Starting over forgets the trend
+        strategy.reset();
+        assert!(on_bar(&mut strategy, &bar(1_200_000, 103.0), 102.0, 25.0, None).is_empty());
+    }
+    
+    #[test]
+    fn test_exits_long_on_take_profit() {
+        let mut strategy = EmaRsiStrategy::new(StrategyConfig::default());
+        let position = Position {
+            symbol: "BTCUSDT".to_string(),
+            quantity: 0.1,
+            avg_price: 100.0,
+            unrealized_pnl: 0.0,
+            realized_pnl: 0.0,
+        };
         
-        let signals = strategy.process_bar(&bar, &ema_values, &rsi_values);
+        on_bar(&mut strategy, &bar(540_000, 104.0), 100.0, 50.0, Some(&position));
+        let signals = on_bar(&mut strategy, &bar(600_000, 105.0), 101.0, 50.0, Some(&position));
         assert_eq!(signals.len(), 1);
-        assert!(matches!(signals[0].side, TradeSide::Buy));
+        assert!(matches!(signals[0].side, TradeSide::Sell));
+        assert_eq!(signals[0].size, 0.1);
+    }
+    
+    #[test]
+    fn test_reads_engine_bar_input() {
+        // As the engine sends it: decimals as strings, indicators `null` during warmup
+        let input: BarInput = serde_json::from_str(r#"{
+            "bar_index": 3,
+            "bar": {"timestamp": 1000, "open": "100.5", "high": "105", "low": "95", "close": "102", "volume": "1000", "trade_count": 7},
+            "indicators": {"ema": "101.25", "rsi": null},
+            "position": null
+        }"#).unwrap();
+        
+        assert_eq!(input.bar_index, 3);
+        assert_eq!(input.bar.close, 102.0);
+        assert_eq!(input.indicators["ema"].map(|Decimal(value)| value), Some(101.25));
+        assert!(input.indicators["rsi"].is_none());
+        assert!(input.position.is_none());
     }
 }
 